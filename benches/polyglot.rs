@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+use polyglot_ast::util::Language;
+use polyglot_ast::{PolyglotTree, PolyglotZipper, TreePrinter};
+
+/// Representative polyglot programs, generated so that their size can be scaled.
+mod corpus {
+    /// A JavaScript host evaluating `n` Python snippets of a few statements each, half of them inline and half from a file.
+    pub fn js_host(n: usize) -> String {
+        (0..n)
+            .map(|i| match i % 2 {
+                0 => format!(
+                    "let r{i} = Polyglot.eval('python', 'def f{i}(x):\\n    return x * {i}\\nprint(f{i}(42))')\n"
+                ),
+                _ => format!(
+                    "Polyglot.evalFile('python', 'TestSamples/pyprint.py')\nconsole.log({i})\n"
+                ),
+            })
+            .collect()
+    }
+
+    /// A Python host evaluating `n` JavaScript snippets, exporting and importing values through the polyglot bindings.
+    pub fn python_host(n: usize) -> String {
+        let mut code = String::from("import polyglot\n");
+        for i in 0..n {
+            code.push_str(&format!(
+                "polyglot.export_value(name=\"x{i}\", value={i})\npolyglot.eval(language=\"js\", string=\"let y = Polyglot.import('x{i}'); console.log(y * 2)\")\n"
+            ));
+        }
+        code
+    }
+
+    /// A JavaScript host whose code crosses `depth` language boundaries, alternating between Python and JavaScript.
+    pub fn nested(depth: usize) -> String {
+        let mut code = String::from("print(0)");
+        for level in (0..depth).rev() {
+            let escaped = code.replace('\\', "\\\\").replace('\'', "\\'");
+            code = match level % 2 {
+                0 => format!("console.log({level})\nPolyglot.eval('python', '{escaped}')"),
+                _ => format!("print({level})\npolyglot.eval(language='js', string='{escaped}')"),
+            };
+        }
+        code
+    }
+}
+
+fn construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construction");
+    for n in [10, 100, 1000] {
+        let code = corpus::js_host(n);
+        group.throughput(Throughput::Bytes(code.len() as u64));
+        group.bench_with_input(BenchmarkId::new("js_host", n), &code, |b, code| {
+            b.iter(|| PolyglotTree::from(black_box(code.as_str()), Language::JavaScript))
+        });
+    }
+    let code = corpus::js_host(1000);
+    let options = BuildOptions::new().threads(4);
+    group.bench_function("js_host_threaded/1000", |b| {
+        b.iter(|| {
+            PolyglotTree::from_with_options(
+                black_box(code.as_str()),
+                Language::JavaScript,
+                &options,
+            )
+        })
+    });
+    let code = corpus::python_host(100);
+    group.bench_function("python_host/100", |b| {
+        b.iter(|| PolyglotTree::from(black_box(code.as_str()), Language::Python))
+    });
+    let code = corpus::nested(8);
+    group.bench_function("nested/8", |b| {
+        b.iter(|| PolyglotTree::from(black_box(code.as_str()), Language::JavaScript))
+    });
+    group.bench_function("from_path/export_x", |b| {
+        b.iter(|| {
+            PolyglotTree::from_path(PathBuf::from("TestSamples/export_x.py"), Language::Python)
+        })
+    });
+    group.finish();
+}
+
+fn traversal(c: &mut Criterion) {
+    let tree = PolyglotTree::from(corpus::js_host(100), Language::JavaScript).unwrap();
+    let mut group = c.benchmark_group("traversal");
+    group.bench_function("nodes", |b| b.iter(|| black_box(&tree).nodes().count()));
+    group.bench_function("walk_events", |b| {
+        b.iter(|| black_box(&tree).walk_events().count())
+    });
+    group.bench_function("zipper_find_all", |b| {
+        b.iter(|| {
+            PolyglotZipper::from(black_box(&tree))
+                .find_all("identifier")
+                .len()
+        })
+    });
+    group.bench_function("eval_sites", |b| b.iter(|| black_box(&tree).eval_sites()));
+    group.finish();
+}
+
+fn export(c: &mut Criterion) {
+    let tree = PolyglotTree::from(corpus::js_host(100), Language::JavaScript).unwrap();
+    let mut group = c.benchmark_group("export");
+    group.bench_function("tree_printer", |b| {
+        b.iter(|| {
+            let mut printer = TreePrinter::new();
+            black_box(&tree).apply(&mut printer);
+            printer.get_result().len()
+        })
+    });
+    group.bench_function("sexp", |b| b.iter(|| black_box(&tree).to_string()));
+    group.bench_function("to_source", |b| b.iter(|| black_box(&tree).to_source()));
+    group.finish();
+}
+
+criterion_group!(benches, construction, traversal, export);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+/*
+ * The C API of polyglot_ast, exported by the shared library built with the `ffi` feature:
+ *
+ *     cargo build --release --no-default-features --features ffi,python,javascript,java
+ *
+ * Trees, call sites and strings returned by this API are owned by the caller, and must be freed with the matching
+ * `_free` function. Every function accepts null pointers, returning null, 0 or doing nothing.
+ * No function unwinds into the caller: a function failing unexpectedly returns null or 0 as well.
+ */
+#ifndef POLYGLOT_AST_H
+#define POLYGLOT_AST_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The values of languages. */
+#define POLYGLOT_PYTHON 0
+#define POLYGLOT_JAVASCRIPT 1
+#define POLYGLOT_JAVA 2
+/* The guest language of call sites whose evaluated language is missing or not supported, or that are not eval calls. */
+#define POLYGLOT_NO_LANGUAGE (-1)
+
+/* The kinds of call sites. */
+#define POLYGLOT_CALL_EVAL 0
+#define POLYGLOT_CALL_IMPORT 1
+#define POLYGLOT_CALL_EXPORT 2
+
+/* A polyglot AST. */
+typedef struct PolyglotTree PolyglotTree;
+
+/* The polyglot calls of a tree. */
+typedef struct PolyglotCallSites PolyglotCallSites;
+
+/*
+ * A polyglot eval, import or export call. Its strings are owned by the PolyglotCallSites it comes from,
+ * and are null when there is no such value. Positions are zero-based, and byte offsets are in the coordinates
+ * of the file the call physically lives in.
+ */
+typedef struct PolyglotCallSite {
+    int32_t kind;
+    int32_t language;
+    int32_t guest_language;
+    size_t start_row;
+    size_t start_column;
+    size_t start_byte;
+    size_t end_byte;
+    /* The file containing the call, if its code comes from one. */
+    const char *file;
+    /* The path of the evaluated file, for eval calls evaluating a file. */
+    const char *path;
+    /* The name of the binding, for import and export calls naming it with a string literal. */
+    const char *name;
+    bool is_inline;
+    bool has_subtree;
+} PolyglotCallSite;
+
+/* Builds the tree of the given UTF-8 code written in the given language, returning null if it cannot be built,
+ * as when the grammar of the language is not compiled in. */
+PolyglotTree *polyglot_tree_from_code(const char *code, int32_t language);
+
+/* Builds the tree of the file at the given path, its language being detected from its extension. */
+PolyglotTree *polyglot_tree_from_path(const char *path);
+
+void polyglot_tree_free(PolyglotTree *tree);
+
+/* Returns the tree as JSON text, to be freed with polyglot_string_free. */
+char *polyglot_tree_to_json(const PolyglotTree *tree);
+
+void polyglot_string_free(char *string);
+
+/* Returns the polyglot calls of the tree and of its subtrees, the eval calls first. They can outlive the tree. */
+PolyglotCallSites *polyglot_call_sites(const PolyglotTree *tree);
+
+size_t polyglot_call_sites_len(const PolyglotCallSites *sites);
+
+/* Returns the call site at the given index, valid until the call sites are freed, or null if the index is out of bounds. */
+const PolyglotCallSite *polyglot_call_sites_get(const PolyglotCallSites *sites, size_t index);
+
+void polyglot_call_sites_free(PolyglotCallSites *sites);
+
+/* Returns the name of the given language, which must not be freed, or null if it is unknown. */
+const char *polyglot_language_name(int32_t language);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
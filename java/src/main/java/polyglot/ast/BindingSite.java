@@ -0,0 +1,19 @@
+package polyglot.ast;
+
+/**
+ * A polyglot import or export call. Rows and columns are zero-based, within the code of the tree containing the call,
+ * and byte offsets are in the coordinates of the file the call physically lives in.
+ *
+ * @param name the name of the binding, or null if it is not given as a string literal
+ * @param export whether the call exports the binding, rather than importing it
+ * @param file the file containing the call, or null if its code does not come from one
+ */
+public record BindingSite(
+        String name,
+        boolean export,
+        String language,
+        int row,
+        int column,
+        int startByte,
+        int endByte,
+        String file) {}
@@ -0,0 +1,21 @@
+package polyglot.ast;
+
+/**
+ * A polyglot eval call. Rows and columns are zero-based, within the code of the tree containing the call,
+ * and byte offsets are in the coordinates of the file the call physically lives in.
+ *
+ * @param guestLanguage the language of the evaluated code, or null if it is missing or not supported
+ * @param file the file containing the call, or null if its code does not come from one
+ * @param path the path of the evaluated file, or null if the code is inline
+ */
+public record EvalSite(
+        String hostLanguage,
+        String guestLanguage,
+        int row,
+        int column,
+        int startByte,
+        int endByte,
+        String file,
+        String path,
+        boolean inline,
+        boolean hasSubtree) {}
@@ -0,0 +1,83 @@
+package polyglot.ast;
+
+/**
+ * A polyglot AST, built by the polyglot_ast native library, which must be on the {@code java.library.path}.
+ *
+ * <p>Trees hold native memory, and must be closed once they are no longer needed.
+ */
+public final class PolyglotTree implements AutoCloseable {
+    static {
+        System.loadLibrary("polyglot_ast");
+    }
+
+    private long handle;
+
+    private PolyglotTree(long handle) {
+        this.handle = handle;
+    }
+
+    /**
+     * Builds the tree of the given code, written in the given language (python, js or java).
+     *
+     * @throws IllegalArgumentException if the language is not supported, or no tree can be built
+     */
+    public static PolyglotTree fromCode(String code, String language) {
+        return new PolyglotTree(fromCode0(code, language));
+    }
+
+    /**
+     * Builds the tree of the file at the given path, its language being detected from its extension.
+     *
+     * @throws IllegalArgumentException if no tree can be built
+     */
+    public static PolyglotTree fromPath(String path) {
+        return new PolyglotTree(fromPath0(path));
+    }
+
+    /** Returns the language of the code of the root of the tree: python, js or java. */
+    public String language() {
+        return language0(handle());
+    }
+
+    /** Returns the polyglot eval calls of the tree and of its subtrees, in document order. */
+    public EvalSite[] evalSites() {
+        return evalSites0(handle());
+    }
+
+    /** Returns the polyglot import and export calls of the tree and of its subtrees, in document order. */
+    public BindingSite[] bindings() {
+        return bindings0(handle());
+    }
+
+    /** Returns the tree as JSON text, every node having a kind, a language, start and end positions, and either text or children. */
+    public String toJson() {
+        return toJson0(handle());
+    }
+
+    @Override
+    public void close() {
+        free0(handle);
+        handle = 0;
+    }
+
+    private long handle() {
+        if (handle == 0) {
+            throw new IllegalStateException("the tree is closed");
+        }
+        return handle;
+    }
+
+    private static native long fromCode0(String code, String language);
+
+    private static native long fromPath0(String path);
+
+    private static native void free0(long handle);
+
+    private static native String language0(long handle);
+
+    private static native EvalSite[] evalSites0(long handle);
+
+    private static native BindingSite[] bindings0(long handle);
+
+    private static native String toJson0(long handle);
+}
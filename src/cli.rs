@@ -0,0 +1,312 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::batch::BatchDiagnosticKind;
+use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+use polyglot_ast::polyglot_tree::exporter::{
+    write_csv_record, write_json_members, write_yaml_mapping,
+};
+use polyglot_ast::polyglot_tree::file_filter::FileFilter;
+use polyglot_ast::polyglot_tree::project::PolyglotProject;
+use polyglot_ast::util;
+use polyglot_ast::{PolyglotTree, PolyglotZipper};
+use thiserror::Error;
+
+#[cfg(feature = "tui")]
+mod browse;
+mod calls;
+mod diff;
+mod extract;
+mod json;
+mod lint;
+mod lsp;
+mod parse;
+mod print;
+mod query;
+mod serve;
+
+#[cfg(feature = "tui")]
+pub use browse::browse;
+pub use calls::calls;
+pub use diff::diff;
+pub use extract::extract;
+pub use lint::lint;
+pub use lsp::lsp;
+pub use parse::parse;
+pub use print::print;
+pub use query::query;
+pub use serve::serve;
+
+/// The problems stopping a command from running at all, as opposed to the problems it reports about the code it analyzes.
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("unable to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("unsupported language {0}, expected one of python, js or java")]
+    Language(String),
+    #[error("the language of the standard input must be given with --language")]
+    StdinLanguage,
+    #[error("unable to build a tree for {0}")]
+    Unbuilt(PathBuf),
+    #[error("invalid query: {0}")]
+    Query(String),
+    #[error("unsupported severity {0}, expected one of info, warning or error")]
+    Severity(String),
+    #[error("the {0} command does not support the {1} format")]
+    UnsupportedFormat(&'static str, &'static str),
+    #[error("unable to serve requests: {0}")]
+    Serve(std::io::Error),
+    #[cfg(feature = "tui")]
+    #[error("terminal error: {0}")]
+    Terminal(std::io::Error),
+}
+
+impl CliError {
+    pub fn unsupported(command: &'static str, format: Format) -> CliError {
+        CliError::UnsupportedFormat(command, format.as_str())
+    }
+}
+
+/// How the results of a command are written to the standard output.
+///
+/// Every command writes text, JSON, YAML and CSV, whereas SARIF is only written by `lint` and DOT by `print`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Yaml,
+    Csv,
+    /// The Static Analysis Results Interchange Format, only written by `lint`.
+    Sarif,
+    /// The Graphviz graph description language, only written by `print`.
+    Dot,
+}
+
+impl Format {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Csv => "csv",
+            Format::Sarif => "sarif",
+            Format::Dot => "dot",
+        }
+    }
+}
+
+/// The flags shared by every command.
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    pub format: Format,
+    /// Whether syntax errors and eval calls left without a subtree make the command fail.
+    pub strict: bool,
+}
+
+impl Settings {
+    pub fn from_matches(matches: &ArgMatches) -> Settings {
+        let format = match matches.get_one::<String>("format").map(String::as_str) {
+            Some("json") => Format::Json,
+            Some("yaml") => Format::Yaml,
+            Some("csv") => Format::Csv,
+            Some("sarif") => Format::Sarif,
+            Some("dot") => Format::Dot,
+            _ => Format::Text,
+        };
+        Settings {
+            format,
+            strict: matches.get_flag("strict"),
+        }
+    }
+}
+
+/// The path standing for the standard input, so that code can be piped to the commands.
+pub const STDIN: &str = "-";
+
+/// Builds the tree of the file at the given path, written in the given language or else in the one detected from its extension or shebang.
+///
+/// Code is read from the standard input if the path is `-`, in which case its language must be given unless it starts with a shebang,
+/// and the paths it evaluates are resolved relative to the current directory.
+pub fn load_tree(path: &Path, language: Option<&String>) -> Result<PolyglotTree, CliError> {
+    let language = language
+        .map(|language| {
+            util::language_string_to_enum(language)
+                .map_err(|_| CliError::Language(language.clone()))
+        })
+        .transpose()?;
+    let tree = if path == Path::new(STDIN) {
+        let code = std::io::read_to_string(std::io::stdin())
+            .map_err(|e| CliError::Io(path.to_path_buf(), e))?;
+        let language = language
+            .or_else(|| util::language_from_shebang(&code))
+            .ok_or(CliError::StdinLanguage)?;
+        PolyglotTree::from(code, language)
+    } else {
+        match language {
+            Some(language) => {
+                if let Err(e) = std::fs::metadata(path) {
+                    return Err(CliError::Io(path.to_path_buf(), e));
+                }
+                PolyglotTree::from_path(path.to_path_buf(), language)
+            }
+            None => PolyglotTree::from_path_auto(path.to_path_buf()),
+        }
+    };
+    tree.ok_or_else(|| CliError::Unbuilt(path.to_path_buf()))
+}
+
+/// The trees a command runs on: the one of the given file, or the ones of the files of the given directory.
+pub struct Inputs {
+    /// Whether a directory was given, in which case the results of its files are written together.
+    pub directory: bool,
+    /// The files and their trees, in alphabetical order.
+    pub trees: Vec<(PathBuf, PolyglotTree)>,
+    /// Whether some files of the directory could not be built, or were built with problems.
+    problems: bool,
+}
+
+impl Inputs {
+    /// Builds the trees of the `file` argument of a command, as `load_tree` does, or if it is a directory,
+    /// of its files kept by `file_filter`.
+    /// The files of a directory are built together by a PolyglotProject, and their language is detected from their extension.
+    pub fn load(matches: &ArgMatches) -> Result<Inputs, CliError> {
+        let path = PathBuf::from(matches.get_one::<String>("file").expect("file is required"));
+        if !path.is_dir() {
+            let tree = load_tree(&path, matches.get_one::<String>("language"))?;
+            return Ok(Inputs {
+                directory: false,
+                trees: vec![(path, tree)],
+                problems: false,
+            });
+        }
+
+        let project =
+            PolyglotProject::with_filter(&path, file_filter(matches), &BuildOptions::default())
+                .map_err(|e| CliError::Io(path.clone(), e))?;
+        for diagnostic in project.diagnostics() {
+            if diagnostic.kind == BatchDiagnosticKind::Unbuilt {
+                eprintln!(
+                    "warning: unable to build a tree for {}",
+                    diagnostic.path.display()
+                );
+            }
+        }
+        Ok(Inputs {
+            directory: true,
+            trees: project
+                .trees()
+                .map(|(file, tree)| (file.to_path_buf(), tree.clone()))
+                .collect(),
+            problems: !project.diagnostics().is_empty(),
+        })
+    }
+
+    /// Returns whether the trees have problems making a strict command fail, as described by `has_problems`,
+    /// or whether some files of the directory could not be built.
+    pub fn has_problems(&self) -> bool {
+        self.problems || self.trees.iter().any(|(_, tree)| has_problems(tree))
+    }
+}
+
+/// Returns the filter of the files of a directory given by the `--include` and `--exclude` globs, also leaving out the files ignored by its `.gitignore` files.
+pub fn file_filter(matches: &ArgMatches) -> FileFilter {
+    let globs = |id| {
+        matches
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<String>>()
+    };
+    FileFilter::new()
+        .include(globs("include"))
+        .exclude(globs("exclude"))
+        .gitignore(true)
+}
+
+/// Returns whether the tree has problems making a strict command fail: syntax errors, or eval calls left without a subtree.
+pub fn has_problems(tree: &PolyglotTree) -> bool {
+    PolyglotZipper::from(tree).has_error() || tree.eval_sites().iter().any(|s| !s.has_subtree)
+}
+
+pub fn exit_code(settings: Settings, has_problems: bool) -> ExitCode {
+    match settings.strict && has_problems {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// A value of a record written by a command, serialized the same way as the values written by the exporters.
+pub use polyglot_ast::polyglot_tree::exporter::ExportValue as Value;
+
+/// The records written by a command in the structured formats, one per row, with a value per column.
+pub struct Table {
+    pub columns: &'static [&'static str],
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// Writes the table as JSON, YAML or CSV, failing for the other formats.
+    ///
+    /// A table of a single record is written as a lone JSON object or YAML mapping if `single` is true, as a list otherwise.
+    pub fn write(
+        &self,
+        command: &'static str,
+        format: Format,
+        single: bool,
+    ) -> Result<String, CliError> {
+        let mut result = String::new();
+        match format {
+            Format::Json => {
+                let records: Vec<String> =
+                    self.rows.iter().map(|row| self.json_record(row)).collect();
+                match single && records.len() == 1 {
+                    true => result.push_str(&records[0]),
+                    false => result = format!("[{}]", records.join(",")),
+                }
+            }
+            Format::Yaml => {
+                let (first, indent) = match single && self.rows.len() == 1 {
+                    true => ("", ""),
+                    false => ("- ", "  "),
+                };
+                for row in &self.rows {
+                    write_yaml_mapping(&mut result, &self.fields(row), first, indent);
+                }
+                if self.rows.is_empty() {
+                    result.push_str("[]");
+                }
+            }
+            Format::Csv => {
+                let header: Vec<Value> = self
+                    .columns
+                    .iter()
+                    .map(|column| Value::Str(column.to_string()))
+                    .collect();
+                write_csv_record(&mut result, &header);
+                for row in &self.rows {
+                    write_csv_record(&mut result, row);
+                }
+            }
+            format => return Err(CliError::unsupported(command, format)),
+        }
+        Ok(result.trim_end().to_string())
+    }
+
+    fn json_record(&self, row: &[Value]) -> String {
+        let mut result = String::from("{");
+        write_json_members(&mut result, &self.fields(row));
+        result.push('}');
+        result
+    }
+
+    /// Returns the values of a row along with the names of their columns.
+    fn fields(&self, row: &[Value]) -> Vec<(&str, Value)> {
+        self.columns
+            .iter()
+            .copied()
+            .zip(row.iter().cloned())
+            .collect()
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::io::{self, Stdout};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::util::{self, Language};
+use polyglot_ast::{PolyglotTree, PolyglotZipper};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::crossterm::ExecutableCommand;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+
+use super::{exit_code, has_problems, load_tree, CliError, Format, Settings};
+
+const HELP: &str =
+    " ↑↓ move  → expand  ← collapse  space toggle  n/N next/previous boundary  q quit ";
+
+/// A node of the browsed tree, with the nodes of its subtrees as children of its eval calls.
+struct Entry<'a> {
+    zip: PolyglotZipper<'a>,
+    depth: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Whether the node is the root of a subtree, whose code is evaluated by its parent.
+    boundary: bool,
+}
+
+/// The state of `browse`: the nodes of the tree in depth-first order, which of them are expanded, and the selected one.
+struct Browser<'a> {
+    tree: &'a PolyglotTree,
+    entries: Vec<Entry<'a>>,
+    expanded: Vec<bool>,
+    /// The indices of the entries shown, the ones whose ancestors are all expanded.
+    visible: Vec<usize>,
+    list: ListState,
+    /// The code of the files nodes physically live in, read when one of their nodes is first selected.
+    sources: HashMap<PathBuf, String>,
+}
+
+impl<'a> Browser<'a> {
+    fn new(tree: &'a PolyglotTree) -> Browser<'a> {
+        let mut entries = Vec::new();
+        let mut stack = vec![(PolyglotZipper::from(tree), 0, None)];
+        while let Some((zip, depth, parent)) = stack.pop() {
+            let index = entries.len();
+            if let Some(parent) = parent {
+                let parent: &mut Entry = &mut entries[parent];
+                parent.children.push(index);
+            }
+            let children: Vec<PolyglotZipper> =
+                std::iter::successors(zip.child(0), |child| child.next_sibling()).collect();
+            // pushed in reverse, so that children are numbered in order
+            stack.extend(
+                children
+                    .into_iter()
+                    .rev()
+                    .map(|c| (c, depth + 1, Some(index))),
+            );
+            entries.push(Entry {
+                boundary: zip.parent().is_some_and(|p| p.is_polyglot_eval_call()),
+                zip,
+                depth,
+                parent,
+                children: Vec::new(),
+            });
+        }
+
+        // the top-level nodes are expanded, so that the browser does not open on a single line
+        let expanded = entries.iter().map(|e| e.depth == 0).collect();
+        let mut browser = Browser {
+            tree,
+            entries,
+            expanded,
+            visible: Vec::new(),
+            list: ListState::default(),
+            sources: HashMap::new(),
+        };
+        browser.refresh();
+        browser.list.select(Some(0));
+        browser
+    }
+
+    fn refresh(&mut self) {
+        self.visible.clear();
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            self.visible.push(index);
+            if self.expanded[index] {
+                stack.extend(self.entries[index].children.iter().rev());
+            }
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.visible[self.list.selected().unwrap_or_default()]
+    }
+
+    fn select(&mut self, index: usize) {
+        let mut ancestor = self.entries[index].parent;
+        while let Some(parent) = ancestor {
+            self.expanded[parent] = true;
+            ancestor = self.entries[parent].parent;
+        }
+        self.refresh();
+        self.list
+            .select(self.visible.iter().position(|&visible| visible == index));
+    }
+
+    fn step(&mut self, forward: bool) {
+        let position = self.list.selected().unwrap_or_default();
+        let position = match forward {
+            true => (position + 1).min(self.visible.len() - 1),
+            false => position.saturating_sub(1),
+        };
+        self.list.select(Some(position));
+    }
+
+    fn set_expanded(&mut self, expanded: bool) {
+        let index = self.selected();
+        match (expanded, self.entries[index].parent) {
+            // collapsing a collapsed node, or a leaf, goes up to its parent instead
+            (false, Some(parent))
+                if !self.expanded[index] || self.entries[index].children.is_empty() =>
+            {
+                self.select(parent)
+            }
+            _ => {
+                self.expanded[index] = expanded;
+                self.refresh();
+            }
+        }
+    }
+
+    /// Selects the next root of a subtree after the selected node, or the previous one before it, expanding its ancestors.
+    fn jump_to_boundary(&mut self, forward: bool) {
+        let current = self.selected();
+        let found = match forward {
+            true => (current + 1..self.entries.len()).find(|&i| self.entries[i].boundary),
+            false => (0..current).rev().find(|&i| self.entries[i].boundary),
+        };
+        if let Some(index) = found {
+            self.select(index);
+        }
+    }
+
+    /// Returns the code of the file the selected node physically lives in, which the positions of the node refer to.
+    fn source(&mut self) -> &str {
+        let zip = &self.entries[self.selected()].zip;
+        match zip.origin().file() {
+            None => self.tree.source(),
+            Some(file) => self
+                .sources
+                .entry(file.clone())
+                .or_insert_with(|| std::fs::read_to_string(file).unwrap_or_default()),
+        }
+    }
+}
+
+fn language_color(language: &Language) -> Color {
+    match language {
+        Language::Python => Color::Blue,
+        Language::JavaScript => Color::Yellow,
+        Language::Java => Color::Red,
+    }
+}
+
+/// Runs `browse`, opening an interactive explorer of the tree of a file: its nodes, colored after their language,
+/// are listed on the left and can be collapsed, and the code of the selected node is highlighted on the right.
+pub fn browse(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let path = PathBuf::from(matches.get_one::<String>("file").expect("file is required"));
+    if settings.format != Format::Text {
+        return Err(CliError::unsupported("browse", settings.format));
+    }
+    let tree = load_tree(&path, matches.get_one::<String>("language"))?;
+    let mut browser = Browser::new(&tree);
+
+    enable_raw_mode().map_err(CliError::Terminal)?;
+    io::stdout()
+        .execute(EnterAlternateScreen)
+        .map_err(CliError::Terminal)?;
+    let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+        .and_then(|mut terminal| run(&mut terminal, &mut browser));
+    // the terminal is restored even if drawing failed, so that the shell is left usable
+    let _ = io::stdout().execute(LeaveAlternateScreen);
+    let _ = disable_raw_mode();
+    result.map_err(CliError::Terminal)?;
+
+    Ok(exit_code(settings, has_problems(&tree)))
+}
+
+fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, browser: &mut Browser) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, browser))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => browser.step(true),
+            KeyCode::Up | KeyCode::Char('k') => browser.step(false),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => browser.set_expanded(true),
+            KeyCode::Left | KeyCode::Char('h') => browser.set_expanded(false),
+            KeyCode::Char(' ') => {
+                let index = browser.selected();
+                browser.set_expanded(!browser.expanded[index]);
+            }
+            KeyCode::Char('n') => browser.jump_to_boundary(true),
+            KeyCode::Char('N') => browser.jump_to_boundary(false),
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, browser: &mut Browser) {
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(frame.size());
+
+    let items: Vec<ListItem> = browser
+        .visible
+        .iter()
+        .map(|&index| {
+            let entry = &browser.entries[index];
+            let marker = match (entry.children.is_empty(), browser.expanded[index]) {
+                (true, _) => "  ",
+                (false, true) => "▾ ",
+                (false, false) => "▸ ",
+            };
+            let mut spans = vec![
+                Span::raw("  ".repeat(entry.depth)),
+                Span::raw(marker),
+                Span::styled(
+                    entry.zip.kind().to_string(),
+                    Style::default().fg(language_color(entry.zip.get_lang())),
+                ),
+            ];
+            if entry.boundary {
+                let language = util::language_enum_to_string(entry.zip.get_lang());
+                spans.push(Span::styled(
+                    format!("  [{language}]"),
+                    Style::default().add_modifier(Modifier::DIM),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Tree ")
+                .title_bottom(HELP),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, areas[0], &mut browser.list);
+
+    let zip = browser.entries[browser.selected()].zip.clone();
+    let (start, end) = zip.host_positions();
+    let title = match zip.origin().file() {
+        Some(file) => format!(" {} ", file.display()),
+        None => " <source> ".to_string(),
+    };
+    let highlight = Style::default()
+        .bg(language_color(zip.get_lang()))
+        .fg(Color::Black);
+    let lines: Vec<Line> = browser
+        .source()
+        .lines()
+        .enumerate()
+        .map(|(row, line)| {
+            // the selected node is highlighted on the lines it spans, with columns counted in bytes as tree-sitter does
+            let from = if row == start.row { start.column } else { 0 };
+            let to = if row == end.row {
+                end.column
+            } else {
+                line.len()
+            };
+            if row < start.row
+                || row > end.row
+                || !line.is_char_boundary(from.min(line.len()))
+                || !line.is_char_boundary(to.min(line.len()))
+            {
+                return Line::raw(line.to_string());
+            }
+            let (from, to) = (from.min(line.len()), to.min(line.len()));
+            Line::from(vec![
+                Span::raw(line[..from].to_string()),
+                Span::styled(line[from..to].to_string(), highlight),
+                Span::raw(line[to..].to_string()),
+            ])
+        })
+        .collect();
+    // the selected node is kept on screen, a few lines below the top of the pane
+    let scroll = start.row.saturating_sub(3).min(u16::MAX as usize) as u16;
+    let source = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((scroll, 0));
+    frame.render_widget(source, areas[1]);
+}
@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::call_sites::{BindingDirection, EvalKind};
+use polyglot_ast::util;
+use polyglot_ast::{NodeId, PolyglotTree};
+
+use super::{exit_code, CliError, Format, Inputs, Settings, Table, Value};
+
+/// A polyglot call found by `calls`, with a 1-based position in the file the call physically lives in.
+#[derive(PartialEq, Eq)]
+struct CallRow {
+    file: PathBuf,
+    line: usize,
+    column: usize,
+    call: &'static str,
+    host: &'static str,
+    guest: Option<&'static str>,
+    payload: Option<&'static str>,
+    /// The evaluated file for eval calls, or the name of the binding for import and export calls.
+    target: Option<String>,
+}
+
+/// Runs `calls`, listing the polyglot eval, import and export calls of the files and of the code they evaluate.
+/// A call reached from several files of a directory, which evaluate the file it lives in, is only listed once.
+pub fn calls(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let inputs = Inputs::load(matches)?;
+    let rows = collect_rows(
+        inputs
+            .trees
+            .iter()
+            .map(|(path, tree)| (path.as_path(), tree)),
+    );
+
+    match settings.format {
+        Format::Text => print_table(&rows),
+        format => println!("{}", calls_table(&rows).write("calls", format, false)?),
+    }
+    Ok(exit_code(settings, inputs.has_problems()))
+}
+
+/// Returns the calls of the given files in document order, a call reached from several files being listed once.
+fn collect_rows<'t>(trees: impl IntoIterator<Item = (&'t Path, &'t PolyglotTree)>) -> Vec<CallRow> {
+    let mut rows: Vec<CallRow> = trees
+        .into_iter()
+        .flat_map(|(path, tree)| call_rows(tree, path))
+        .collect();
+    rows.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    rows.dedup();
+    rows
+}
+
+/// Returns the table of the calls of the given files, as written by `calls` in the structured formats.
+pub(super) fn table<'t>(trees: impl IntoIterator<Item = (&'t Path, &'t PolyglotTree)>) -> Table {
+    calls_table(&collect_rows(trees))
+}
+
+fn call_rows(tree: &PolyglotTree, path: &Path) -> Vec<CallRow> {
+    let row = |id: &NodeId, file: Option<&PathBuf>, call, host| {
+        let (start, _) = tree
+            .zipper_at(id)
+            .expect("call sites are nodes of the tree")
+            .host_positions();
+        CallRow {
+            file: file.cloned().unwrap_or_else(|| path.to_path_buf()),
+            line: start.row + 1,
+            column: start.column + 1,
+            call,
+            host,
+            guest: None,
+            payload: None,
+            target: None,
+        }
+    };
+
+    let mut rows: Vec<CallRow> = tree
+        .eval_sites()
+        .iter()
+        .map(|site| CallRow {
+            guest: site
+                .guest_language
+                .as_ref()
+                .map(util::language_enum_to_string),
+            payload: site.kind.map(|kind| match kind {
+                EvalKind::Inline => "inline",
+                EvalKind::File => "file",
+            }),
+            target: site.path.as_ref().map(|p| p.display().to_string()),
+            ..row(
+                &site.id,
+                site.origin.file(),
+                "eval",
+                util::language_enum_to_string(&site.host_language),
+            )
+        })
+        .collect();
+    rows.extend(tree.bindings().iter().map(|site| CallRow {
+        target: site.name.clone(),
+        ..row(
+            &site.id,
+            site.origin.file(),
+            match site.direction {
+                BindingDirection::Import => "import",
+                BindingDirection::Export => "export",
+            },
+            util::language_enum_to_string(&site.language),
+        )
+    }));
+    rows
+}
+
+fn print_table(rows: &[CallRow]) {
+    let cells: Vec<[String; 6]> = rows
+        .iter()
+        .map(|row| {
+            [
+                format!("{}:{}:{}", row.file.display(), row.line, row.column),
+                row.call.to_string(),
+                row.host.to_string(),
+                row.guest.unwrap_or("-").to_string(),
+                row.payload.unwrap_or("-").to_string(),
+                row.target.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    let header = ["LOCATION", "CALL", "HOST", "GUEST", "PAYLOAD", "TARGET"].map(String::from);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+fn calls_table(rows: &[CallRow]) -> Table {
+    Table {
+        columns: &[
+            "file", "line", "column", "call", "host", "guest", "payload", "target",
+        ],
+        rows: rows
+            .iter()
+            .map(|row| {
+                vec![
+                    Value::Str(row.file.to_string_lossy().into_owned()),
+                    Value::Int(row.line),
+                    Value::Int(row.column),
+                    Value::Str(row.call.to_string()),
+                    Value::Str(row.host.to_string()),
+                    Value::optional(row.guest),
+                    Value::optional(row.payload),
+                    Value::optional(row.target.as_deref()),
+                ]
+            })
+            .collect(),
+    }
+}
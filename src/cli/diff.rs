@@ -0,0 +1,185 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::tree_diff::{DiffNode, TreeChange};
+use polyglot_ast::util;
+use polyglot_ast::{PolyglotTree, PolyglotZipper};
+
+use super::{exit_code, has_problems, load_tree, CliError, Format, Settings, Table, Value};
+
+/// A 1-based position in the file some code physically lives in.
+struct Location {
+    file: PathBuf,
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    fn of(zip: &PolyglotZipper, path: &Path) -> Location {
+        let (start, _) = zip.host_positions();
+        Location {
+            file: zip
+                .origin()
+                .file()
+                .cloned()
+                .unwrap_or_else(|| path.to_path_buf()),
+            line: start.row + 1,
+            column: start.column + 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+/// A change found by `diff`, with the location of the node in each version having it.
+struct ChangeRow {
+    change: &'static str,
+    kind: &'static str,
+    language: &'static str,
+    old: Option<Location>,
+    new: Option<Location>,
+    /// The eval call evaluating the embedded snippet the change is in, or None if it is in the code of the file itself.
+    snippet: Option<Location>,
+    /// Whether the change adds, removes or replaces code containing a polyglot eval call, or changes the language of some code.
+    boundary: bool,
+}
+
+/// Runs `diff`, reporting the structural changes between two versions of a file, including the changes of the snippets they evaluate,
+/// as found by `PolyglotTree::diff`. Changes adding, removing or replacing eval calls are reported as boundary changes.
+pub fn diff(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let old_path = PathBuf::from(matches.get_one::<String>("old").expect("old is required"));
+    let new_path = PathBuf::from(matches.get_one::<String>("new").expect("new is required"));
+    if matches!(settings.format, Format::Sarif | Format::Dot) {
+        return Err(CliError::unsupported("diff", settings.format));
+    }
+    let language = matches.get_one::<String>("language");
+    let old = load_tree(&old_path, language)?;
+    let new = load_tree(&new_path, language)?;
+
+    let located = |tree: &PolyglotTree, node: &DiffNode, path: &Path| {
+        let zip = tree
+            .zipper_at(&node.id)
+            .expect("changed nodes are nodes of their tree");
+        let snippet = zip
+            .ancestors()
+            .find(|ancestor| ancestor.is_polyglot_eval_call())
+            .map(|call| Location::of(&call, path));
+        let evaluates = !zip
+            .find_where(|descendant| descendant.is_polyglot_eval_call())
+            .is_empty();
+        (Location::of(&zip, path), snippet, evaluates)
+    };
+    let rows: Vec<ChangeRow> = old
+        .diff(&new)
+        .iter()
+        .map(|change| match change {
+            TreeChange::Added(node) => {
+                let (location, snippet, evaluates) = located(&new, node, &new_path);
+                ChangeRow {
+                    change: "added",
+                    kind: node.kind,
+                    language: util::language_enum_to_string(&node.language),
+                    old: None,
+                    new: Some(location),
+                    snippet,
+                    boundary: evaluates,
+                }
+            }
+            TreeChange::Removed(node) => {
+                let (location, snippet, evaluates) = located(&old, node, &old_path);
+                ChangeRow {
+                    change: "removed",
+                    kind: node.kind,
+                    language: util::language_enum_to_string(&node.language),
+                    old: Some(location),
+                    new: None,
+                    snippet,
+                    boundary: evaluates,
+                }
+            }
+            TreeChange::Changed {
+                old: before,
+                new: after,
+            } => {
+                let (old_location, _, old_evaluates) = located(&old, before, &old_path);
+                let (new_location, snippet, new_evaluates) = located(&new, after, &new_path);
+                ChangeRow {
+                    change: "changed",
+                    kind: after.kind,
+                    language: util::language_enum_to_string(&after.language),
+                    old: Some(old_location),
+                    new: Some(new_location),
+                    snippet,
+                    boundary: before.language != after.language || old_evaluates || new_evaluates,
+                }
+            }
+        })
+        .collect();
+
+    match settings.format {
+        Format::Text => print_changes(&rows),
+        format => {
+            let table = Table {
+                columns: &[
+                    "change", "kind", "language", "old", "new", "snippet", "boundary",
+                ],
+                rows: rows.iter().map(change_record).collect(),
+            };
+            println!("{}", table.write("diff", format, false)?);
+        }
+    }
+    Ok(exit_code(
+        settings,
+        has_problems(&old) || has_problems(&new),
+    ))
+}
+
+fn print_changes(rows: &[ChangeRow]) {
+    for row in rows {
+        let location = match (&row.old, &row.new) {
+            (Some(old), Some(new)) => format!("{old} -> {new}"),
+            (Some(location), None) | (None, Some(location)) => location.to_string(),
+            (None, None) => unreachable!("changes have at least one version"),
+        };
+        let scope = match (&row.snippet, row.boundary) {
+            (_, true) => "  (boundary)".to_string(),
+            (Some(snippet), false) => format!("  (in the snippet evaluated at {snippet})"),
+            (None, false) => String::new(),
+        };
+        println!(
+            "{:<7}  {:<6}  {}  {location}{scope}",
+            row.change, row.language, row.kind
+        );
+    }
+
+    let in_snippets = rows.iter().filter(|row| row.snippet.is_some()).count();
+    let boundaries = rows.iter().filter(|row| row.boundary).count();
+    println!(
+        "{} changes: {} in host code, {in_snippets} in embedded snippets, {boundaries} to boundaries",
+        rows.len(),
+        rows.len() - in_snippets
+    );
+}
+
+fn change_record(row: &ChangeRow) -> Vec<Value> {
+    let location = |location: &Option<Location>| {
+        location
+            .as_ref()
+            .map_or(Value::Null, |l| Value::Str(l.to_string()))
+    };
+    vec![
+        Value::Str(row.change.to_string()),
+        Value::Str(row.kind.to_string()),
+        Value::Str(row.language.to_string()),
+        location(&row.old),
+        location(&row.new),
+        location(&row.snippet),
+        Value::Bool(row.boundary),
+    ]
+}
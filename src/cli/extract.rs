@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::call_sites::EvalKind;
+use polyglot_ast::util::{self, Language};
+
+use super::{exit_code, CliError, Format, Inputs, Settings, Table, Value, STDIN};
+
+/// A snippet written by `extract`, with the 1-based position of its eval call in the file the call physically lives in.
+struct Extracted {
+    file: PathBuf,
+    line: usize,
+    column: usize,
+    language: Language,
+    output: PathBuf,
+}
+
+/// Runs `extract`, writing the code of every inline snippet evaluated by the files, however deep, to its own file of the output directory.
+///
+/// Snippets are named after the file and position of their eval call and the extension of their language, eg. `main.js.3_5.py`,
+/// so external formatters and linters can run on them. Snippets of unsupported languages are left out.
+pub fn extract(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let out = PathBuf::from(matches.get_one::<String>("out").expect("out is required"));
+    if matches!(settings.format, Format::Sarif | Format::Dot) {
+        return Err(CliError::unsupported("extract", settings.format));
+    }
+    let inputs = Inputs::load(matches)?;
+    std::fs::create_dir_all(&out).map_err(|e| CliError::Io(out.clone(), e))?;
+
+    let mut extracted: Vec<Extracted> = Vec::new();
+    for (path, tree) in &inputs.trees {
+        for site in tree.eval_sites() {
+            let (Some(EvalKind::Inline), Some(language)) = (site.kind, site.guest_language) else {
+                continue;
+            };
+            let Some(call) = tree.zipper_at(&site.id) else {
+                continue;
+            };
+            let Some(snippet) = call.child(0) else {
+                continue;
+            };
+            let (start, _) = call.host_positions();
+            let file = site.origin.file().cloned().unwrap_or_else(|| path.clone());
+            let (line, column) = (start.row + 1, start.column + 1);
+            let name = match file.file_name() {
+                Some(name) if file != Path::new(STDIN) => name.to_string_lossy().into_owned(),
+                _ => "stdin".to_string(),
+            };
+            let output = out.join(format!("{name}.{line}_{column}.{}", extension(language)));
+            // a snippet reached from several files of a directory, which evaluate the file it lives in, is written once
+            if extracted.iter().any(|e| e.output == output) {
+                continue;
+            }
+            // snippets are parsed with their escape sequences as written, whereas tools expect the code that is actually evaluated
+            let code = util::unescape_string_literal(snippet.code(), &site.host_language);
+            std::fs::write(&output, code.as_ref()).map_err(|e| CliError::Io(output.clone(), e))?;
+            extracted.push(Extracted {
+                file,
+                line,
+                column,
+                language,
+                output,
+            });
+        }
+    }
+
+    match settings.format {
+        Format::Text => {
+            for snippet in &extracted {
+                println!(
+                    "{}:{}:{} -> {}",
+                    snippet.file.display(),
+                    snippet.line,
+                    snippet.column,
+                    snippet.output.display()
+                );
+            }
+        }
+        format => {
+            let table = Table {
+                columns: &["file", "line", "column", "language", "output"],
+                rows: extracted.iter().map(extracted_record).collect(),
+            };
+            println!("{}", table.write("extract", format, false)?);
+        }
+    }
+    Ok(exit_code(settings, inputs.has_problems()))
+}
+
+fn extension(language: Language) -> &'static str {
+    match language {
+        Language::Python => "py",
+        Language::JavaScript => "js",
+        Language::Java => "java",
+    }
+}
+
+fn extracted_record(snippet: &Extracted) -> Vec<Value> {
+    let path = |path: &Path| Value::Str(path.to_string_lossy().into_owned());
+    vec![
+        path(&snippet.file),
+        Value::Int(snippet.line),
+        Value::Int(snippet.column),
+        Value::Str(util::language_enum_to_string(&snippet.language).to_string()),
+        path(&snippet.output),
+    ]
+}
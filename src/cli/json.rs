@@ -0,0 +1,276 @@
+use std::fmt;
+
+use polyglot_ast::util;
+
+/// A JSON value, as read from the requests of `serve`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// The members of an object, in the order they were written.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON text, returning a message describing the problem if it is not valid JSON.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: text.char_indices().peekable(),
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        match parser.chars.next() {
+            None => Ok(value),
+            Some((i, c)) => Err(format!("unexpected `{c}` at byte {i}")),
+        }
+    }
+
+    /// Returns an object with the given members.
+    pub fn object<'k>(members: impl IntoIterator<Item = (&'k str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Returns the value of the given member, if this is an object having it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a non-negative integer.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Json {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+/// Writes the value as compact JSON.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => f.write_str(&util::json_string(s)),
+            Json::Array(values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(members) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}:{value}", util::json_string(key))?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| c.is_ascii_whitespace())
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((i, c)) => Err(format!("expected `{expected}` at byte {i}, found `{c}`")),
+            None => Err(format!("expected `{expected}`, found the end of the text")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        keyword.chars().try_for_each(|c| self.expect(c))?;
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.chars.peek().copied() {
+            None => Err("expected a value, found the end of the text".to_string()),
+            Some((_, 'n')) => self.keyword("null", Json::Null),
+            Some((_, 't')) => self.keyword("true", Json::Bool(true)),
+            Some((_, 'f')) => self.keyword("false", Json::Bool(false)),
+            Some((_, '"')) => self.string().map(Json::String),
+            Some((_, '[')) => self.array(),
+            Some((_, '{')) => self.object(),
+            Some((_, c)) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some((i, c)) => Err(format!("unexpected `{c}` at byte {i}")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let mut text = String::new();
+        while let Some((_, c)) = self
+            .chars
+            .next_if(|(_, c)| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            text.push(c);
+        }
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("invalid number {text}"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err("unterminated string".to_string()),
+                Some((_, '"')) => return Ok(result),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => result.push('"'),
+                    Some((_, '\\')) => result.push('\\'),
+                    Some((_, '/')) => result.push('/'),
+                    Some((_, 'b')) => result.push('\u{8}'),
+                    Some((_, 'f')) => result.push('\u{c}'),
+                    Some((_, 'n')) => result.push('\n'),
+                    Some((_, 'r')) => result.push('\r'),
+                    Some((_, 't')) => result.push('\t'),
+                    Some((_, 'u')) => {
+                        let unit = self.code_unit()?;
+                        // characters outside of the basic multilingual plane are written as surrogate pairs
+                        let c = match unit {
+                            0xd800..=0xdbff => {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.code_unit()?;
+                                char::from_u32(
+                                    0x10000 + ((unit - 0xd800) << 10) + (low.wrapping_sub(0xdc00)),
+                                )
+                            }
+                            unit => char::from_u32(unit),
+                        };
+                        result.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some((i, c)) => return Err(format!("invalid escape `\\{c}` at byte {i}")),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some((_, c)) => result.push(c),
+            }
+        }
+    }
+
+    fn code_unit(&mut self) -> Result<u32, String> {
+        let digits: String = (0..4)
+            .filter_map(|_| self.chars.next().map(|(_, c)| c))
+            .collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("invalid unicode escape \\u{digits}"))
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Json::Array(values)),
+                Some((i, c)) => {
+                    return Err(format!("expected `,` or `]` at byte {i}, found `{c}`"))
+                }
+                None => return Err("unterminated array".to_string()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Json::Object(members)),
+                Some((i, c)) => {
+                    return Err(format!("expected `,` or `}}` at byte {i}, found `{c}`"))
+                }
+                None => return Err("unterminated object".to_string()),
+            }
+        }
+    }
+}
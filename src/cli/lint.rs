@@ -0,0 +1,257 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::binding_graph::unmatched_bindings;
+use polyglot_ast::polyglot_tree::call_sites::{BindingDirection, BindingSite};
+use polyglot_ast::polyglot_tree::lint::{LintFinding, Severity};
+use polyglot_ast::util;
+use polyglot_ast::PolyglotTree;
+
+use super::{CliError, Format, Inputs, Settings, Table, Value};
+
+/// A finding of `lint`, along with the input it was found from and its code.
+type Located<'a> = (LintFinding, &'a Path, &'a str);
+
+/// Runs `lint`, reporting the problems found by `PolyglotTree::lint` and failing if any is at least as serious as `--fail-on`.
+/// A problem reached from several files of a directory, which evaluate the file it lives in, is only reported once.
+pub fn lint(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let fail_on = matches
+        .get_one::<String>("fail-on")
+        .map_or("error", String::as_str);
+    let fail_on: Severity = fail_on
+        .parse()
+        .map_err(|_| CliError::Severity(fail_on.to_string()))?;
+    let inputs = Inputs::load(matches)?;
+    let findings = collect_findings(
+        inputs
+            .trees
+            .iter()
+            .map(|(path, tree)| (path.as_path(), tree)),
+    );
+
+    match settings.format {
+        Format::Text => {
+            for (finding, path, source) in &findings {
+                print!("{}", render(finding, path, source));
+            }
+            println!("{} problems found", findings.len());
+        }
+        Format::Sarif => println!("{}", sarif(&findings)),
+        format => println!(
+            "{}",
+            findings_table(&findings).write("lint", format, false)?
+        ),
+    }
+
+    // strict runs fail on warnings as well
+    let fail_on = match settings.strict {
+        true => fail_on.min(Severity::Warning),
+        false => fail_on,
+    };
+    match findings.iter().any(|(f, _, _)| f.severity >= fail_on) {
+        true => Ok(ExitCode::FAILURE),
+        false => Ok(ExitCode::SUCCESS),
+    }
+}
+
+/// Returns the findings of the given files, a problem reached from several files being reported once.
+fn collect_findings<'t>(
+    trees: impl IntoIterator<Item = (&'t Path, &'t PolyglotTree)>,
+) -> Vec<Located<'t>> {
+    let mut findings: Vec<Located> = Vec::new();
+    let mut sites = Vec::new();
+    for (path, tree) in trees {
+        for finding in tree.lint() {
+            let duplicate =
+                finding.file.is_some() && findings.iter().any(|(f, _, _)| *f == finding);
+            if !duplicate {
+                findings.push((finding, path, tree.source()));
+            }
+        }
+        sites.extend(tree.bindings());
+    }
+    let unmatched = unmatched(&sites);
+    findings.retain(|(finding, _, _)| !is_resolved_elsewhere(finding, &unmatched));
+    findings
+}
+
+/// Returns the import and export calls among `sites` whose binding no other call uses.
+pub(super) fn unmatched(sites: &[BindingSite]) -> Vec<&BindingSite> {
+    let mut result = unmatched_bindings(sites, BindingDirection::Import);
+    result.extend(unmatched_bindings(sites, BindingDirection::Export));
+    result
+}
+
+/// Returns true if the finding is about an import or export call that is not among the `unmatched` calls of all the code analyzed,
+/// as the other files can use the bindings of a file without it evaluating them or being evaluated by them.
+pub(super) fn is_resolved_elsewhere(finding: &LintFinding, unmatched: &[&BindingSite]) -> bool {
+    matches!(finding.rule, "unresolved-import" | "unused-export")
+        && !unmatched
+            .iter()
+            .any(|site| site.origin.file() == finding.file.as_ref() && site.span == finding.span)
+}
+
+/// Returns the table of the findings of the given files, as written by `lint` in the structured formats.
+pub(super) fn table<'t>(trees: impl IntoIterator<Item = (&'t Path, &'t PolyglotTree)>) -> Table {
+    findings_table(&collect_findings(trees))
+}
+
+fn findings_table(findings: &[Located]) -> Table {
+    Table {
+        columns: &[
+            "rule", "severity", "message", "file", "start", "end", "related",
+        ],
+        rows: findings
+            .iter()
+            .map(|(finding, path, _)| finding_record(finding, path))
+            .collect(),
+    }
+}
+
+/// Renders a finding with the line it starts on and a marker under the problem, the way compilers do.
+/// The code of findings that are not in a file, such as code read from the standard input, is taken from `source`.
+fn render(finding: &LintFinding, path: &Path, source: &str) -> String {
+    let file = finding.file.as_deref().unwrap_or(path);
+    let line = finding.start.row + 1;
+    let mut result = format!(
+        "{}[{}]: {}\n  --> {}:{}:{}\n",
+        finding.severity,
+        finding.rule,
+        finding.message,
+        file.display(),
+        line,
+        finding.start.column + 1
+    );
+    let code = match &finding.file {
+        Some(file) => std::fs::read_to_string(file).ok(),
+        None => Some(source.to_string()),
+    };
+    let notes: String = finding
+        .related
+        .iter()
+        .map(|related| {
+            let file = related.file.as_deref().unwrap_or(path);
+            let (line, column) = (related.start.row + 1, related.start.column + 1);
+            format!(
+                "  = note: {}: {}:{line}:{column}\n",
+                related.message,
+                file.display()
+            )
+        })
+        .collect();
+    let Some(code) = code.and_then(|code| code.lines().nth(finding.start.row).map(str::to_string))
+    else {
+        return result + &notes;
+    };
+
+    let gutter = " ".repeat(line.to_string().len());
+    let start = code[..finding.start.column.min(code.len())].chars().count();
+    let width = match finding.end.row == finding.start.row {
+        true => finding.end.column.saturating_sub(finding.start.column),
+        false => code.len().saturating_sub(finding.start.column),
+    };
+    let _ = writeln!(result, "{gutter} |");
+    let _ = writeln!(result, "{line} | {code}");
+    let _ = writeln!(
+        result,
+        "{gutter} | {}{}",
+        " ".repeat(start),
+        "^".repeat(width.max(1))
+    );
+    let _ = writeln!(result, "{notes}");
+    result
+}
+
+fn finding_record(finding: &LintFinding, path: &Path) -> Vec<Value> {
+    vec![
+        Value::Str(finding.rule.to_string()),
+        Value::Str(finding.severity.to_string()),
+        Value::Str(finding.message.clone()),
+        Value::Str(
+            finding
+                .file
+                .as_deref()
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        Value::Position(finding.start.row + 1, finding.start.column + 1),
+        Value::Position(finding.end.row + 1, finding.end.column + 1),
+        // the other places of the problem, as `message: file:line:column`
+        match finding.related.is_empty() {
+            true => Value::Null,
+            false => Value::Str(
+                finding
+                    .related
+                    .iter()
+                    .map(|related| {
+                        let file = related.file.as_deref().unwrap_or(path).display();
+                        let (line, column) = (related.start.row + 1, related.start.column + 1);
+                        format!("{}: {file}:{line}:{column}", related.message)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+        },
+    ]
+}
+
+/// Writes the findings as a SARIF 2.1.0 log, understood by code scanning tools.
+fn sarif(findings: &[Located]) -> String {
+    let rules: BTreeSet<&str> = findings.iter().map(|(f, _, _)| f.rule).collect();
+    let rules: Vec<String> = rules
+        .iter()
+        .map(|rule| format!("{{\"id\":{}}}", util::json_string(rule)))
+        .collect();
+    let results: Vec<String> = findings
+        .iter()
+        .map(|(finding, path, _)| {
+            let level = match finding.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "note",
+            };
+            let file = finding.file.as_deref().unwrap_or(path).to_string_lossy().replace('\\', "/");
+            let related: Vec<String> = finding
+                .related
+                .iter()
+                .enumerate()
+                .map(|(id, related)| {
+                    let file = related.file.as_deref().unwrap_or(path).to_string_lossy().replace('\\', "/");
+                    format!(
+                        "{{\"id\":{id},\"message\":{{\"text\":{}}},\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}},\
+                         \"region\":{{\"startLine\":{},\"startColumn\":{},\"endLine\":{},\"endColumn\":{}}}}}}}",
+                        util::json_string(&related.message),
+                        util::json_string(&file),
+                        related.start.row + 1,
+                        related.start.column + 1,
+                        related.end.row + 1,
+                        related.end.column + 1
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"ruleId\":{},\"level\":\"{level}\",\"message\":{{\"text\":{}}},\"locations\":[{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}},\
+                 \"region\":{{\"startLine\":{},\"startColumn\":{},\"endLine\":{},\"endColumn\":{}}}}}}}],\"relatedLocations\":[{}]}}",
+                util::json_string(finding.rule),
+                util::json_string(&finding.message),
+                util::json_string(&file),
+                finding.start.row + 1,
+                finding.start.column + 1,
+                finding.end.row + 1,
+                finding.end.column + 1,
+                related.join(",")
+            )
+        })
+        .collect();
+    format!(
+        "{{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"polyglot-ast\",\
+         \"version\":\"{}\",\"rules\":[{}]}}}},\"results\":[{}]}}]}}",
+        env!("CARGO_PKG_VERSION"),
+        rules.join(","),
+        results.join(",")
+    )
+}
@@ -0,0 +1,831 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+use polyglot_ast::polyglot_tree::call_sites::{BindingDirection, BindingSite};
+use polyglot_ast::polyglot_tree::file_filter::FileFilter;
+use polyglot_ast::polyglot_tree::file_provider::{FileProvider, OsFileProvider};
+use polyglot_ast::polyglot_tree::lint::Severity;
+use polyglot_ast::polyglot_tree::project::PolyglotProject;
+use polyglot_ast::polyglot_tree::references::{Reference, ReferenceKind};
+use polyglot_ast::util::{self, Language, LineIndex};
+use polyglot_ast::{PolyglotTree, PolyglotZipper};
+use tree_sitter::{InputEdit, Point};
+
+use super::json::Json;
+use super::lint;
+use super::{CliError, Settings};
+
+/// The kinds of the definitions listed as document symbols, with the LSP SymbolKind of each.
+const DEFINITIONS: [(&str, usize); 6] = [
+    ("class_definition", 5),
+    ("class_declaration", 5),
+    ("function_definition", 12),
+    ("function_declaration", 12),
+    ("method_definition", 6),
+    ("method_declaration", 6),
+];
+const MODULE: usize = 2;
+const VARIABLE: usize = 13;
+
+/// A file open in the editor, with the tree of its unsaved code.
+struct Document {
+    path: PathBuf,
+    code: String,
+    tree: PolyglotTree,
+}
+
+/// The state of `lsp`: the project of the workspace, built from the files on disk, and the documents open in the editor.
+struct LanguageServer {
+    project: Option<PolyglotProject>,
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+/// Reads files from the unsaved code of the documents open in the editor, and from the file system otherwise,
+/// so that the code they evaluate is the one being edited.
+#[derive(Debug)]
+struct Overlay {
+    open: HashMap<PathBuf, String>,
+}
+
+impl FileProvider for Overlay {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        match self.open.get(&canonical(path)) {
+            Some(code) => Ok(code.clone()),
+            None => OsFileProvider.read(path),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.open.contains_key(&canonical(path)) || OsFileProvider.exists(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.open.contains_key(&canonical(path)) {
+            true => Ok(canonical(path)),
+            false => OsFileProvider.canonicalize(path),
+        }
+    }
+}
+
+/// Runs `lsp`, a language server speaking the Language Server Protocol on the standard input and output.
+///
+/// It publishes the problems found by `PolyglotTree::lint` in the open documents, embedded syntax errors included,
+/// lists their definitions, eval calls and bindings as document symbols, the ones of the snippets they evaluate nested in their eval calls,
+/// goes from polyglot import calls to the export calls of the same binding in the workspace, finds the references to bindings across languages, describes the code under the cursor when hovering it, and completes the names of bindings in import calls and member accesses.
+/// Documents are reparsed incrementally as they are edited, and the workspace is brought up to date when they are saved.
+pub fn lsp(_matches: &ArgMatches, _settings: Settings) -> Result<ExitCode, CliError> {
+    let mut server = LanguageServer {
+        project: None,
+        documents: HashMap::new(),
+        shutdown: false,
+    };
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    while let Some(message) = read_message(&mut input).map_err(CliError::Serve)? {
+        let Ok(message) = Json::parse(&message) else {
+            eprintln!("warning: ignoring a message that is not valid JSON");
+            continue;
+        };
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        if method == "exit" {
+            return Ok(match server.shutdown {
+                true => ExitCode::SUCCESS,
+                false => ExitCode::FAILURE,
+            });
+        }
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let (result, notifications) = server.handle(method, params);
+        for (method, params) in notifications {
+            let notification = Json::object([
+                ("jsonrpc", Json::from("2.0")),
+                ("method", Json::from(method)),
+                ("params", params),
+            ]);
+            write_message(&mut output, &notification).map_err(CliError::Serve)?;
+        }
+        if let Some(id) = message.get("id") {
+            let response = match result {
+                Some(result) => Json::object([
+                    ("jsonrpc", Json::from("2.0")),
+                    ("id", id.clone()),
+                    ("result", result),
+                ]),
+                None => Json::object([
+                    ("jsonrpc", Json::from("2.0")),
+                    ("id", id.clone()),
+                    (
+                        "error",
+                        Json::object([
+                            ("code", Json::Number(-32601.0)),
+                            (
+                                "message",
+                                Json::from(format!("unknown method {method}").as_str()),
+                            ),
+                        ]),
+                    ),
+                ]),
+            };
+            write_message(&mut output, &response).map_err(CliError::Serve)?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reads the content of the next message, or None once the input is closed.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "a message has no Content-Length",
+        )
+    })?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+impl LanguageServer {
+    /// Handles a request or a notification, returning its result, or None if the method is unknown,
+    /// along with the notifications to send back to the editor.
+    fn handle(&mut self, method: &str, params: &Json) -> (Option<Json>, Vec<(&'static str, Json)>) {
+        let mut notifications = Vec::new();
+        let result = match method {
+            "initialize" => {
+                let root = params
+                    .get("rootUri")
+                    .and_then(Json::as_str)
+                    .and_then(uri_to_path)
+                    .or_else(|| {
+                        params
+                            .get("rootPath")
+                            .and_then(Json::as_str)
+                            .map(PathBuf::from)
+                    });
+                if let Some(root) = root {
+                    let filter = FileFilter::new().gitignore(true);
+                    match PolyglotProject::with_filter(&root, filter, &BuildOptions::default()) {
+                        Ok(project) => self.project = Some(project),
+                        Err(e) => eprintln!("warning: unable to read {}: {e}", root.display()),
+                    }
+                }
+                Json::object([
+                    (
+                        "capabilities",
+                        Json::object([
+                            (
+                                "textDocumentSync",
+                                Json::object([
+                                    ("openClose", Json::from(true)),
+                                    // incremental
+                                    ("change", Json::from(2)),
+                                    ("save", Json::from(true)),
+                                ]),
+                            ),
+                            ("documentSymbolProvider", Json::from(true)),
+                            ("definitionProvider", Json::from(true)),
+                            ("referencesProvider", Json::from(true)),
+                            ("hoverProvider", Json::from(true)),
+                            (
+                                "completionProvider",
+                                Json::object([(
+                                    "triggerCharacters",
+                                    Json::Array(vec![Json::from("'"), Json::from("\"")]),
+                                )]),
+                            ),
+                        ]),
+                    ),
+                    (
+                        "serverInfo",
+                        Json::object([
+                            ("name", Json::from("polyglot-ast")),
+                            ("version", Json::from(env!("CARGO_PKG_VERSION"))),
+                        ]),
+                    ),
+                ])
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Json::Null
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument").unwrap_or(&Json::Null);
+                if let (Some(uri), Some(code)) = (
+                    document.get("uri").and_then(Json::as_str),
+                    document.get("text").and_then(Json::as_str),
+                ) {
+                    let language = document
+                        .get("languageId")
+                        .and_then(Json::as_str)
+                        .and_then(|id| util::language_string_to_enum(id).ok());
+                    self.open(uri, code.to_string(), language);
+                    notifications.extend(self.diagnostics(uri));
+                }
+                Json::Null
+            }
+            "textDocument/didChange" => {
+                let uri = document_uri(params);
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                if let (Some(uri), Some(changes)) = (uri, changes) {
+                    self.change(uri, changes);
+                    notifications.extend(self.diagnostics(uri));
+                }
+                Json::Null
+            }
+            "textDocument/didSave" => {
+                if let (Some(project), Some(document)) = (
+                    &mut self.project,
+                    document_uri(params).and_then(|uri| self.documents.get(uri)),
+                ) {
+                    if let Err(e) = project.update(std::slice::from_ref(&document.path)) {
+                        eprintln!("warning: unable to update the workspace: {e}");
+                    }
+                }
+                Json::Null
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = document_uri(params) {
+                    self.documents.remove(uri);
+                    notifications.push((
+                        "textDocument/publishDiagnostics",
+                        Json::object([
+                            ("uri", Json::from(uri)),
+                            ("diagnostics", Json::Array(Vec::new())),
+                        ]),
+                    ));
+                }
+                Json::Null
+            }
+            "textDocument/documentSymbol" => {
+                match document_uri(params).and_then(|uri| self.documents.get(uri)) {
+                    Some(document) => {
+                        let index = LineIndex::new(&document.code);
+                        Json::Array(symbols(
+                            &PolyglotZipper::from(&document.tree),
+                            &document.code,
+                            &index,
+                        ))
+                    }
+                    None => Json::Null,
+                }
+            }
+            "textDocument/definition" => self.definition(params).unwrap_or(Json::Null),
+            "textDocument/references" => self.references(params).unwrap_or(Json::Null),
+            "textDocument/hover" => self.hover(params).unwrap_or(Json::Null),
+            "textDocument/completion" => self.completion(params).unwrap_or(Json::Null),
+            // unknown notifications are ignored, as no response is sent for them
+            _ => return (None, notifications),
+        };
+        (Some(result), notifications)
+    }
+
+    fn overlay(&self) -> Overlay {
+        let open = self
+            .documents
+            .values()
+            .map(|document| (canonical(&document.path), document.code.clone()))
+            .collect();
+        Overlay { open }
+    }
+
+    /// Returns the options the trees of the documents are built with, reading the files they evaluate from the open documents first.
+    fn options(&self) -> BuildOptions {
+        BuildOptions::new().file_provider(Arc::new(self.overlay()))
+    }
+
+    fn open(&mut self, uri: &str, code: String, language: Option<Language>) {
+        let Some(path) = uri_to_path(uri) else {
+            eprintln!("warning: ignoring {uri}, which is not a file");
+            return;
+        };
+        let Some(language) = language.or_else(|| util::language_from_path(&path)) else {
+            eprintln!("warning: ignoring {uri}, whose language is not supported");
+            return;
+        };
+        let mut overlay = self.overlay();
+        overlay.open.insert(canonical(&path), code.clone());
+        let options = BuildOptions::new().file_provider(Arc::new(overlay));
+        match PolyglotTree::from_path_with_options(path.clone(), language, &options) {
+            Some(tree) => {
+                self.documents
+                    .insert(uri.to_string(), Document { path, code, tree });
+            }
+            None => eprintln!("warning: unable to build a tree for {uri}"),
+        }
+    }
+
+    /// Applies the changes of a document to its code, reparsing its tree incrementally for each of them.
+    fn change(&mut self, uri: &str, changes: &[Json]) {
+        for change in changes {
+            let Some(text) = change.get("text").and_then(Json::as_str) else {
+                continue;
+            };
+            let Some(document) = self.documents.get(uri) else {
+                return;
+            };
+            let language = document.tree.language();
+            let Some(range) = change.get("range") else {
+                // the whole document is replaced
+                self.open(uri, text.to_string(), Some(language));
+                continue;
+            };
+            let index = LineIndex::new(&document.code);
+            let (Some(start), Some(end)) = (
+                range
+                    .get("start")
+                    .and_then(|p| offset(&document.code, &index, p)),
+                range
+                    .get("end")
+                    .and_then(|p| offset(&document.code, &index, p)),
+            ) else {
+                continue;
+            };
+            let mut code = document.code.clone();
+            code.replace_range(start..end, text);
+            let new_end = start + text.len();
+            let edit = InputEdit {
+                start_byte: start,
+                old_end_byte: end,
+                new_end_byte: new_end,
+                start_position: index.point(start),
+                old_end_position: index.point(end),
+                new_end_position: LineIndex::new(&code).point(new_end),
+            };
+            let options = self.options();
+            let document = self
+                .documents
+                .get_mut(uri)
+                .expect("the document was just found");
+            if document
+                .tree
+                .apply_edit_with_options(&edit, code.as_str(), &options)
+                .is_err()
+            {
+                eprintln!("warning: unable to reparse {uri}");
+            }
+            document.code = code;
+        }
+    }
+
+    /// Returns the notification publishing the problems found in the code of a document.
+    fn diagnostics(&self, uri: &str) -> Option<(&'static str, Json)> {
+        let document = self.documents.get(uri)?;
+        let index = LineIndex::new(&document.code);
+        let path = canonical(&document.path);
+        // the bindings of the document can be used by the other files of the workspace, the saved version of the document left out
+        let sites: Vec<BindingSite> = self
+            .documents
+            .values()
+            .flat_map(|document| document.tree.bindings())
+            .chain(
+                self.project
+                    .iter()
+                    .flat_map(PolyglotProject::bindings)
+                    .filter(|site| site.origin.file().map(|f| canonical(f)) != Some(path.clone())),
+            )
+            .collect();
+        let unmatched = lint::unmatched(&sites);
+        let diagnostics = document
+            .tree
+            .lint()
+            .into_iter()
+            // problems in the files the document evaluates are published when these files are open
+            .filter(|finding| finding.file.as_deref().map(canonical) == Some(path.clone()))
+            .filter(|finding| !lint::is_resolved_elsewhere(finding, &unmatched))
+            .map(|finding| {
+                let related = finding
+                    .related
+                    .iter()
+                    .filter_map(|related| {
+                        let location = self.location(
+                            related.file.as_deref()?,
+                            related.span.start,
+                            related.span.end,
+                        )?;
+                        Some(Json::object([
+                            ("location", location),
+                            ("message", Json::from(related.message.as_str())),
+                        ]))
+                    })
+                    .collect();
+                Json::object([
+                    (
+                        "range",
+                        range(&document.code, &index, finding.span.start, finding.span.end),
+                    ),
+                    (
+                        "severity",
+                        Json::from(match finding.severity {
+                            Severity::Error => 1,
+                            Severity::Warning => 2,
+                            Severity::Info => 3,
+                        }),
+                    ),
+                    ("code", Json::from(finding.rule)),
+                    ("source", Json::from("polyglot-ast")),
+                    ("message", Json::from(finding.message.as_str())),
+                    ("relatedInformation", Json::Array(related)),
+                ])
+            })
+            .collect();
+        Some((
+            "textDocument/publishDiagnostics",
+            Json::object([
+                ("uri", Json::from(uri)),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ))
+    }
+
+    /// Returns the locations of the export calls of the binding imported by the call at the given position,
+    /// found in the open documents and in the files of the workspace.
+    fn definition(&self, params: &Json) -> Option<Json> {
+        let document = self.documents.get(document_uri(params)?)?;
+        let index = LineIndex::new(&document.code);
+        let position = offset(&document.code, &index, params.get("position")?)?;
+        let zip = document.tree.zipper_at_position(index.point(position))?;
+        let import = std::iter::once(zip.clone())
+            .chain(zip.ancestors())
+            .find(|ancestor| ancestor.is_polyglot_import_call())?;
+        let name = import.get_binding_name().ok()?;
+
+        let mut exports: Vec<BindingSite> = self
+            .documents
+            .values()
+            .flat_map(|document| document.tree.bindings())
+            .chain(self.project.iter().flat_map(PolyglotProject::bindings))
+            .filter(|site| {
+                site.direction == BindingDirection::Export && site.name.as_ref() == Some(&name)
+            })
+            .collect();
+        exports
+            .sort_by(|a, b| (a.origin.file(), a.span.start).cmp(&(b.origin.file(), b.span.start)));
+        exports.dedup_by(|a, b| a.origin.file() == b.origin.file() && a.span == b.span);
+        let locations = exports
+            .iter()
+            .filter_map(|site| self.location(site.origin.file()?, site.span.start, site.span.end))
+            .collect();
+        Some(Json::Array(locations))
+    }
+
+    /// Returns the locations of the references to the binding the node at the given position refers to, whatever their language,
+    /// found in the document and the files it evaluates, in the other open documents and in the files of the workspace.
+    fn references(&self, params: &Json) -> Option<Json> {
+        let document = self.documents.get(document_uri(params)?)?;
+        let index = LineIndex::new(&document.code);
+        let position = offset(&document.code, &index, params.get("position")?)?;
+        let zip = document.tree.zipper_at_position(index.point(position))?;
+        let name = zip
+            .binding()
+            .or_else(|| self.project.as_ref()?.binding(&zip))?;
+        let declarations = params
+            .get("context")
+            .and_then(|context| context.get("includeDeclaration"))
+            .and_then(Json::as_bool)
+            .unwrap_or(true);
+
+        // the saved versions of the open documents are out of date once they are edited
+        let edited: Vec<PathBuf> = self
+            .documents
+            .values()
+            .filter(|document| {
+                let path = canonical(&document.path);
+                let saved = self
+                    .project
+                    .iter()
+                    .flat_map(PolyglotProject::trees)
+                    .find(|(p, _)| canonical(p) == path);
+                saved.is_none_or(|(_, tree)| tree.source() != document.code)
+            })
+            .map(|document| canonical(&document.path))
+            .collect();
+        let mut references: Vec<Reference> = self
+            .documents
+            .values()
+            .flat_map(|document| document.tree.binding_references(&name))
+            .chain(
+                self.project
+                    .iter()
+                    .flat_map(|project| project.binding_references(&name))
+                    .filter(|reference| {
+                        !reference
+                            .origin
+                            .file()
+                            .is_some_and(|f| edited.contains(&canonical(f)))
+                    }),
+            )
+            .filter(|reference| declarations || reference.kind != ReferenceKind::Definition)
+            .collect();
+        references
+            .sort_by(|a, b| (a.origin.file(), a.span.start).cmp(&(b.origin.file(), b.span.start)));
+        references.dedup_by(|a, b| a.origin.file() == b.origin.file() && a.span == b.span);
+        let locations = references
+            .iter()
+            .filter_map(|r| self.location(r.origin.file()?, r.span.start, r.span.end))
+            .collect();
+        Some(Json::Array(locations))
+    }
+
+    /// Returns the hover of the node at the given position, the counterparts of its binding being looked for
+    /// in the other open documents and in the files of the workspace as well.
+    fn hover(&self, params: &Json) -> Option<Json> {
+        let uri = document_uri(params)?;
+        let document = self.documents.get(uri)?;
+        let index = LineIndex::new(&document.code);
+        let position = offset(&document.code, &index, params.get("position")?)?;
+        let point = index.point(position);
+        let mut hover = document.tree.hover(point)?;
+        // identifiers holding imported values may only be recognized by the trees of the files evaluating the document
+        if let (None, Some(project)) = (&hover.binding, &self.project) {
+            let zip = document.tree.zipper_at_position(point)?;
+            hover.binding = project.binding(&zip);
+            hover.direction = project.binding_direction(&zip);
+        }
+
+        if let Some(name) = &hover.binding {
+            let path = canonical(&document.path);
+            let others = self
+                .documents
+                .iter()
+                .filter(|(other, _)| other.as_str() != uri)
+                .flat_map(|(_, document)| document.tree.bindings())
+                .chain(
+                    self.project
+                        .iter()
+                        .flat_map(PolyglotProject::bindings)
+                        .filter(|site| {
+                            site.origin.file().map(|f| canonical(f)) != Some(path.clone())
+                        }),
+                )
+                .filter(|site| site.name.as_ref() == Some(name))
+                .filter(|site| hover.direction != Some(site.direction));
+            hover.counterparts.extend(others);
+            hover.counterparts.sort_by(|a, b| {
+                (a.origin.file(), a.span.start).cmp(&(b.origin.file(), b.span.start))
+            });
+            hover
+                .counterparts
+                .dedup_by(|a, b| a.origin.file() == b.origin.file() && a.span == b.span);
+        }
+
+        Some(Json::object([
+            (
+                "contents",
+                Json::object([
+                    ("kind", Json::from("markdown")),
+                    ("value", Json::from(hover.markdown().as_str())),
+                ]),
+            ),
+            (
+                "range",
+                range(&document.code, &index, hover.span.start, hover.span.end),
+            ),
+        ]))
+    }
+
+    /// Returns the names of the bindings that can be imported at the given position, when it is in the string literal naming the binding
+    /// of an import call or of a Java member access: the bindings exported before it by the document and the files it evaluates,
+    /// and the bindings exported by the other open documents and the files of the workspace.
+    fn completion(&self, params: &Json) -> Option<Json> {
+        let uri = document_uri(params)?;
+        let document = self.documents.get(uri)?;
+        let index = LineIndex::new(&document.code);
+        let position = offset(&document.code, &index, params.get("position")?)?;
+        let point = index.point(position);
+        let zip = document.tree.zipper_at_position(point)?;
+        let literal = std::iter::once(zip.clone())
+            .chain(zip.ancestors())
+            .take(2)
+            .find(|z| matches!(z.kind(), "string" | "string_literal" | "template_string"))?;
+        // Python names bindings with a keyword argument, one level deeper than positional arguments
+        literal
+            .ancestors()
+            .take(3)
+            .find(|call| call.is_polyglot_import_call() || is_member_access(call))?;
+
+        let path = canonical(&document.path);
+        let mut items: Vec<(String, Language)> = document
+            .tree
+            .bindings_visible_at(point)
+            .into_iter()
+            .map(|binding| (binding.name, binding.language))
+            .collect();
+        let others = self
+            .documents
+            .iter()
+            .filter(|(other, _)| other.as_str() != uri)
+            .flat_map(|(_, document)| document.tree.bindings())
+            .chain(
+                self.project
+                    .iter()
+                    .flat_map(PolyglotProject::bindings)
+                    .filter(|site| site.origin.file().map(|f| canonical(f)) != Some(path.clone())),
+            )
+            .filter(|site| site.direction == BindingDirection::Export)
+            .filter_map(|site| Some((site.name?, site.language)));
+        items.extend(others);
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+        items.dedup_by(|a, b| a.0 == b.0);
+
+        let items = items
+            .into_iter()
+            .map(|(name, language)| {
+                Json::object([
+                    ("label", Json::from(name.as_str())),
+                    // Value
+                    ("kind", Json::from(12)),
+                    (
+                        "detail",
+                        Json::from(
+                            format!("exported by {}", util::language_enum_to_string(&language))
+                                .as_str(),
+                        ),
+                    ),
+                ])
+            })
+            .collect();
+        Some(Json::Array(items))
+    }
+
+    /// Returns the location of a byte range of a file, read from its open document if there is one, and from the file system otherwise.
+    fn location(&self, file: &Path, start: usize, end: usize) -> Option<Json> {
+        let file = canonical(file);
+        let code = match self
+            .documents
+            .values()
+            .find(|document| canonical(&document.path) == file)
+        {
+            Some(document) => document.code.clone(),
+            None => std::fs::read_to_string(&file).ok()?,
+        };
+        let index = LineIndex::new(&code);
+        Some(Json::object([
+            ("uri", Json::from(path_to_uri(&file).as_str())),
+            ("range", range(&code, &index, start, end)),
+        ]))
+    }
+}
+
+/// Returns true if the node is a call to a method of a Java Value taking the name of a member, which is a binding for the bindings of a Context.
+fn is_member_access(zip: &PolyglotZipper) -> bool {
+    zip.kind() == "method_invocation"
+        && std::iter::successors(zip.child(0), |c| c.next_sibling())
+            .find(|c| c.field_name() == Some("name"))
+            .is_some_and(|name| {
+                matches!(
+                    name.code(),
+                    "getMember" | "hasMember" | "removeMember" | "putMember"
+                )
+            })
+}
+
+/// Returns the document symbols of the descendants of a node, the symbols of the snippets evaluated by its eval calls nested in them.
+fn symbols(zip: &PolyglotZipper, code: &str, index: &LineIndex) -> Vec<Json> {
+    let mut result = Vec::new();
+    for child in std::iter::successors(zip.child(0), |c| c.next_sibling()) {
+        // subtrees of other files are listed in the symbols of these files
+        if child.origin().file() != zip.origin().file() {
+            continue;
+        }
+        let (name, kind) = if child.is_polyglot_eval_call() {
+            let language = match child.child(0) {
+                Some(root)
+                    if root.get_lang() != child.get_lang() || root.origin() != child.origin() =>
+                {
+                    util::language_enum_to_string(root.get_lang())
+                }
+                _ => "unknown",
+            };
+            (format!("eval {language}"), MODULE)
+        } else if child.is_polyglot_import_call() || child.is_polyglot_export_call() {
+            let direction = match child.is_polyglot_import_call() {
+                true => "import",
+                false => "export",
+            };
+            let name = child.get_binding_name().unwrap_or_else(|_| "?".to_string());
+            (format!("{direction} {name}"), VARIABLE)
+        } else if let Some((_, kind)) = DEFINITIONS.iter().find(|(k, _)| *k == child.kind()) {
+            let name = std::iter::successors(child.child(0), |c| c.next_sibling())
+                .find(|c| c.field_name() == Some("name"))
+                .map_or_else(|| "?".to_string(), |name| name.code().to_string());
+            (name, *kind)
+        } else {
+            result.extend(symbols(&child, code, index));
+            continue;
+        };
+
+        let byte_range = child.host_byte_range();
+        let range = range(code, index, byte_range.start, byte_range.end);
+        result.push(Json::object([
+            ("name", Json::from(name.as_str())),
+            ("kind", Json::from(kind)),
+            ("range", range.clone()),
+            ("selectionRange", range),
+            ("children", Json::Array(symbols(&child, code, index))),
+        ]));
+    }
+    result
+}
+
+/// Returns the uri of the document a request or a notification is about.
+fn document_uri(params: &Json) -> Option<&str> {
+    params.get("textDocument")?.get("uri")?.as_str()
+}
+
+/// Returns the byte offset of an LSP position, whose character counts UTF-16 code units from the start of its line.
+fn offset(code: &str, index: &LineIndex, position: &Json) -> Option<usize> {
+    let line = position.get("line")?.as_usize()?;
+    let character = position.get("character")?.as_usize()?;
+    let start = index.offset(Point::new(line, 0))?;
+    let text = code[start..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= character {
+            return Some(start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(start + text.len())
+}
+
+/// Returns the LSP position of a byte offset.
+fn position(code: &str, index: &LineIndex, offset: usize) -> Json {
+    let point = index.point(offset);
+    let line_start = offset.min(code.len()) - point.column;
+    let character = code
+        .get(line_start..offset.min(code.len()))
+        .map_or(point.column, |text| text.encode_utf16().count());
+    Json::object([
+        ("line", Json::from(point.row)),
+        ("character", Json::from(character)),
+    ])
+}
+
+fn range(code: &str, index: &LineIndex, start: usize, end: usize) -> Json {
+    Json::object([
+        ("start", position(code, index, start)),
+        ("end", position(code, index, end)),
+    ])
+}
+
+/// Returns the canonical form of a path, or the path itself if there is no file at that path.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let path = canonical(path);
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(byte as char)
+            }
+            byte => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::util;
+use polyglot_ast::{PolyglotTree, PolyglotZipper};
+
+use super::{exit_code, CliError, Format, Inputs, Settings, Table, Value};
+
+/// Runs `parse`, reporting the shape of the tree of each file and whether it contains errors.
+pub fn parse(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let inputs = Inputs::load(matches)?;
+    match settings.format {
+        Format::Text => {
+            for (path, tree) in &inputs.trees {
+                print_summary(path, tree);
+            }
+        }
+        format => {
+            let table = table(
+                inputs
+                    .trees
+                    .iter()
+                    .map(|(path, tree)| (path.as_path(), tree)),
+            );
+            println!("{}", table.write("parse", format, !inputs.directory)?);
+        }
+    }
+    Ok(exit_code(settings, inputs.has_problems()))
+}
+
+fn print_summary(path: &Path, tree: &PolyglotTree) {
+    let stats = tree.stats();
+    println!(
+        "{}: {} tree with {} nodes, {} subtrees and a depth of {}",
+        path.display(),
+        util::language_enum_to_string(&tree.language()),
+        stats.node_count,
+        stats.subtree_count,
+        stats.depth
+    );
+    if PolyglotZipper::from(tree).has_error() {
+        println!("{}: contains syntax errors", path.display());
+    }
+    let unresolved = tree.eval_sites().iter().filter(|s| !s.has_subtree).count();
+    if unresolved > 0 {
+        println!(
+            "{}: {unresolved} eval calls without a subtree",
+            path.display()
+        );
+    }
+}
+
+/// Returns the table of the shapes of the given files, as written by `parse` in the structured formats.
+pub(super) fn table<'t>(trees: impl IntoIterator<Item = (&'t Path, &'t PolyglotTree)>) -> Table {
+    Table {
+        columns: &[
+            "path",
+            "language",
+            "nodes",
+            "subtrees",
+            "depth",
+            "syntax_errors",
+            "unresolved_evals",
+        ],
+        rows: trees
+            .into_iter()
+            .map(|(path, tree)| record(path, tree))
+            .collect(),
+    }
+}
+
+fn record(path: &Path, tree: &PolyglotTree) -> Vec<Value> {
+    let stats = tree.stats();
+    vec![
+        Value::Str(path.to_string_lossy().into_owned()),
+        Value::Str(util::language_enum_to_string(&tree.language()).to_string()),
+        Value::Int(stats.node_count),
+        Value::Int(stats.subtree_count),
+        Value::Int(stats.depth),
+        Value::Bool(PolyglotZipper::from(tree).has_error()),
+        Value::Int(tree.eval_sites().iter().filter(|s| !s.has_subtree).count()),
+    ]
+}
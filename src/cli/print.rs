@@ -0,0 +1,103 @@
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::exporter::{CsvExporter, DotExporter, JsonExporter, YamlExporter};
+use polyglot_ast::util;
+use polyglot_ast::{PolyglotTree, TreePrinter};
+
+use super::{exit_code, CliError, Format, Inputs, Settings};
+
+/// Runs `print`, writing the whole tree of each file, subtrees included, with the exporter of the requested format.
+///
+/// The trees of the files of a directory are written one after the other in text and DOT, as a list of `file` and `tree` pairs
+/// in JSON and YAML, and as a single CSV table whose first column is the file.
+pub fn print(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let inputs = Inputs::load(matches)?;
+    if settings.format == Format::Sarif {
+        return Err(CliError::unsupported("print", settings.format));
+    }
+    if !inputs.directory {
+        let (_, tree) = &inputs.trees[0];
+        print!("{}", export(tree, settings.format));
+        return Ok(exit_code(settings, inputs.has_problems()));
+    }
+
+    let trees = inputs
+        .trees
+        .iter()
+        .map(|(path, tree)| (path.to_string_lossy(), export(tree, settings.format)));
+    match settings.format {
+        Format::Json => {
+            let trees: Vec<String> = trees
+                .map(|(file, tree)| {
+                    format!(
+                        "{{\"file\":{},\"tree\":{}}}",
+                        util::json_string(&file),
+                        tree.trim_end()
+                    )
+                })
+                .collect();
+            println!("[{}]", trees.join(","));
+        }
+        Format::Yaml => {
+            for (file, tree) in trees {
+                println!("- file: {}\n  tree:", util::json_string(&file));
+                tree.lines().for_each(|line| println!("    {line}"));
+            }
+        }
+        Format::Csv => {
+            println!("file,{}", CsvExporter::HEADER);
+            for (file, tree) in trees {
+                let file = util::csv_field(&file);
+                tree.lines()
+                    .skip(1)
+                    .for_each(|line| println!("{file},{line}"));
+            }
+        }
+        _ => {
+            for (path, tree) in &inputs.trees {
+                if settings.format == Format::Text {
+                    println!("{}:", path.display());
+                }
+                print!("{}", export(tree, settings.format));
+            }
+        }
+    }
+    Ok(exit_code(settings, inputs.has_problems()))
+}
+
+/// Returns the tree written in the given format, which must not be SARIF, ending with a line break.
+fn export(tree: &PolyglotTree, format: Format) -> String {
+    let result = match format {
+        Format::Text => {
+            let mut printer = TreePrinter::new();
+            tree.apply(&mut printer);
+            printer.get_result().to_string()
+        }
+        Format::Json => {
+            let mut exporter = JsonExporter::new();
+            tree.apply(&mut exporter);
+            exporter.get_result().to_string()
+        }
+        Format::Yaml => {
+            let mut exporter = YamlExporter::new();
+            tree.apply(&mut exporter);
+            exporter.get_result().to_string()
+        }
+        Format::Csv => {
+            let mut exporter = CsvExporter::new();
+            tree.apply(&mut exporter);
+            exporter.get_result().to_string()
+        }
+        Format::Dot => {
+            let mut exporter = DotExporter::new();
+            tree.apply(&mut exporter);
+            exporter.get_result().to_string()
+        }
+        Format::Sarif => unreachable!("print does not write SARIF"),
+    };
+    match result.ends_with('\n') {
+        true => result,
+        false => result + "\n",
+    }
+}
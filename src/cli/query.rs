@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::util::{self, Language};
+use polyglot_ast::{PolyglotTree, PolyglotZipper};
+use tree_sitter::Point;
+
+use super::{exit_code, CliError, Format, Inputs, Settings, Table, Value};
+
+/// A node captured by `query`, with its position in the file it physically lives in.
+#[derive(PartialEq, Eq)]
+struct CaptureRow {
+    file: PathBuf,
+    start: Point,
+    end: Point,
+    name: String,
+    kind: String,
+    text: String,
+}
+
+/// Runs `query`, running a tree-sitter query over the tree of each file and every subtree written in the requested language,
+/// and listing the captures with their position in the file they physically live in.
+/// A node reached from several files of a directory, which evaluate the file it lives in, is only listed once.
+pub fn query(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let lang = matches.get_one::<String>("lang").expect("lang is required");
+    let language =
+        util::language_string_to_enum(lang).map_err(|_| CliError::Language(lang.clone()))?;
+    let source = matches
+        .get_one::<String>("query")
+        .expect("query is required");
+    if matches!(settings.format, Format::Sarif | Format::Dot) {
+        return Err(CliError::unsupported("query", settings.format));
+    }
+    let inputs = Inputs::load(matches)?;
+
+    let mut rows: Vec<CaptureRow> = Vec::new();
+    for (path, tree) in &inputs.trees {
+        for row in capture_rows(tree, path, language, source)? {
+            if !rows.contains(&row) {
+                rows.push(row);
+            }
+        }
+    }
+
+    match settings.format {
+        Format::Text => {
+            for row in &rows {
+                println!(
+                    "{}:{}:{}  @{}  {}  {}",
+                    row.file.display(),
+                    row.start.row + 1,
+                    row.start.column + 1,
+                    row.name,
+                    row.kind,
+                    row.text.lines().next().unwrap_or_default()
+                );
+            }
+        }
+        format => {
+            let table = Table {
+                columns: &["file", "start", "end", "capture", "kind", "text"],
+                rows: rows.iter().map(capture_record).collect(),
+            };
+            println!("{}", table.write("query", format, false)?);
+        }
+    }
+    Ok(exit_code(settings, inputs.has_problems()))
+}
+
+fn capture_rows(
+    tree: &PolyglotTree,
+    path: &Path,
+    language: Language,
+    source: &str,
+) -> Result<Vec<CaptureRow>, CliError> {
+    // every tree is queried on its own, as the nodes of a subtree are not part of the tree-sitter tree of its host
+    let root = PolyglotZipper::from(tree);
+    let roots = std::iter::once(root.clone()).chain(
+        root.find_where(|zip| zip.is_polyglot_eval_call())
+            .into_iter()
+            .filter_map(|call| call.child(0)),
+    );
+    let mut rows = Vec::new();
+    for root in roots.filter(|root| *root.get_lang() == language) {
+        let captures = root
+            .query(source, false)
+            .map_err(|e| CliError::Query(format!("{e:?}")))?;
+        rows.extend(captures.into_iter().map(|capture| {
+            let zip = capture.zipper;
+            let (start, end) = zip.host_positions();
+            CaptureRow {
+                file: zip
+                    .origin()
+                    .file()
+                    .cloned()
+                    .unwrap_or_else(|| path.to_path_buf()),
+                start,
+                end,
+                name: capture.name,
+                kind: zip.kind().to_string(),
+                text: zip.code().to_string(),
+            }
+        }));
+    }
+    Ok(rows)
+}
+
+fn capture_record(row: &CaptureRow) -> Vec<Value> {
+    vec![
+        Value::Str(row.file.to_string_lossy().into_owned()),
+        Value::Position(row.start.row + 1, row.start.column + 1),
+        Value::Position(row.end.row + 1, row.end.column + 1),
+        Value::Str(row.name.clone()),
+        Value::Str(row.kind.clone()),
+        Value::Str(row.text.clone()),
+    ]
+}
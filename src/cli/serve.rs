@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::ArgMatches;
+use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+use polyglot_ast::polyglot_tree::project::PolyglotProject;
+use polyglot_ast::util;
+use polyglot_ast::PolyglotTree;
+use tree_sitter::Point;
+
+use super::json::Json;
+use super::{calls, file_filter, lint, load_tree, parse, CliError, Format, Settings};
+
+/// The JSON-RPC error codes used by `serve`.
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+/// The code of the errors of the methods themselves, such as files that cannot be built.
+const SERVER_ERROR: i32 = -32000;
+
+/// A JSON-RPC error, with its code and message.
+struct RpcError(i32, String);
+
+/// The warm state of `serve`: the project of the served directory, and the trees of the files outside of it that were asked about.
+struct Server {
+    project: PolyglotProject,
+    others: HashMap<PathBuf, PolyglotTree>,
+    shutdown: bool,
+}
+
+/// Runs `serve`, keeping the trees of the files of a directory in memory and answering JSON-RPC 2.0 requests about them,
+/// one per line, on the standard input or on the connections to the given TCP address.
+///
+/// The methods are `parse`, `calls` and `diagnostics`, which take a `path` and answer as the commands of the same name do in JSON,
+/// `diagnostics` reporting the findings of every file of the project if no path is given; `nodeAtPosition`, which takes a `path`,
+/// and a 1-based `line` and `column`; `update`, which takes the `paths` of changed files and rebuilds their trees; and `shutdown`.
+pub fn serve(matches: &ArgMatches, settings: Settings) -> Result<ExitCode, CliError> {
+    let root = PathBuf::from(
+        matches
+            .get_one::<String>("root")
+            .expect("root has a default"),
+    );
+    if !matches!(settings.format, Format::Text | Format::Json) {
+        return Err(CliError::unsupported("serve", settings.format));
+    }
+    let project =
+        PolyglotProject::with_filter(&root, file_filter(matches), &BuildOptions::default())
+            .map_err(|e| CliError::Io(root.clone(), e))?;
+    let mut server = Server {
+        project,
+        others: HashMap::new(),
+        shutdown: false,
+    };
+
+    match matches.get_one::<String>("listen") {
+        None => server
+            .run(io::stdin().lock(), io::stdout().lock())
+            .map_err(CliError::Serve)?,
+        Some(address) => {
+            let listener = TcpListener::bind(address).map_err(CliError::Serve)?;
+            eprintln!("listening on {address}");
+            // connections are served one after the other, sharing the state of the server
+            for stream in listener.incoming() {
+                let stream = stream.map_err(CliError::Serve)?;
+                let reader = BufReader::new(stream.try_clone().map_err(CliError::Serve)?);
+                if let Err(e) = server.run(reader, stream) {
+                    eprintln!("warning: connection closed: {e}");
+                }
+                if server.shutdown {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+impl Server {
+    /// Answers the requests read from the given reader until it is closed or a shutdown is requested.
+    fn run(&mut self, reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.respond(&line) {
+                writeln!(writer, "{response}")?;
+                writer.flush()?;
+            }
+            if self.shutdown {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the response to a request, or None if it is a notification, which has no id.
+    fn respond(&mut self, line: &str) -> Option<String> {
+        let request = match Json::parse(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error(&Json::Null, RpcError(PARSE_ERROR, e))),
+        };
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(Json::as_str) {
+            None => Err(RpcError(
+                INVALID_REQUEST,
+                "the request has no method".to_string(),
+            )),
+            Some(method) => self.call(method, request.get("params").unwrap_or(&Json::Null)),
+        };
+        let id = id?;
+        Some(match result {
+            Ok(result) => format!("{{\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{result}}}"),
+            Err(e) => error(&id, e),
+        })
+    }
+
+    /// Runs a method, returning its result as JSON text.
+    fn call(&mut self, method: &str, params: &Json) -> Result<String, RpcError> {
+        let written = |result: Result<String, CliError>| {
+            result.map_err(|e| RpcError(SERVER_ERROR, e.to_string()))
+        };
+        match method {
+            "parse" => {
+                let (path, tree) = self.tree(params)?;
+                written(parse::table([(path.as_path(), tree)]).write("serve", Format::Json, true))
+            }
+            "calls" => {
+                let (path, tree) = self.tree(params)?;
+                written(calls::table([(path.as_path(), tree)]).write("serve", Format::Json, false))
+            }
+            "diagnostics" => match params.get("path") {
+                Some(_) => {
+                    let (path, tree) = self.tree(params)?;
+                    written(lint::table([(path.as_path(), tree)]).write(
+                        "serve",
+                        Format::Json,
+                        false,
+                    ))
+                }
+                None => {
+                    written(lint::table(self.project.trees()).write("serve", Format::Json, false))
+                }
+            },
+            "nodeAtPosition" => {
+                let position = |name| {
+                    params
+                        .get(name)
+                        .and_then(Json::as_usize)
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| {
+                            RpcError(INVALID_PARAMS, format!("missing or invalid {name}"))
+                        })
+                };
+                let point = Point::new(position("line")? - 1, position("column")? - 1);
+                let (_, tree) = self.tree(params)?;
+                Ok(node_at(tree, point))
+            }
+            "update" => {
+                let paths: Vec<PathBuf> = params
+                    .get("paths")
+                    .and_then(Json::as_array)
+                    .ok_or_else(|| RpcError(INVALID_PARAMS, "missing paths".to_string()))?
+                    .iter()
+                    .filter_map(|path| path.as_str().map(PathBuf::from))
+                    .collect();
+                for path in &paths {
+                    self.others.remove(path);
+                }
+                let update = self
+                    .project
+                    .update(&paths)
+                    .map_err(|e| RpcError(SERVER_ERROR, e.to_string()))?;
+                let list = |paths: &[PathBuf]| {
+                    Json::Array(
+                        paths
+                            .iter()
+                            .map(|p| Json::String(p.to_string_lossy().into_owned()))
+                            .collect(),
+                    )
+                };
+                Ok(format!(
+                    "{{\"rebuilt\":{},\"removed\":{}}}",
+                    list(&update.rebuilt),
+                    list(&update.removed)
+                ))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok("null".to_string())
+            }
+            _ => Err(RpcError(
+                METHOD_NOT_FOUND,
+                format!("unknown method {method}"),
+            )),
+        }
+    }
+
+    /// Returns the tree of the file at the `path` of the params, which is either a file of the project,
+    /// relative to the current directory or to the root of the project, or a file built when first asked about.
+    fn tree(&mut self, params: &Json) -> Result<(PathBuf, &PolyglotTree), RpcError> {
+        let path = params
+            .get("path")
+            .and_then(Json::as_str)
+            .map(PathBuf::from)
+            .ok_or_else(|| RpcError(INVALID_PARAMS, "missing path".to_string()))?;
+        let in_project = [path.clone(), self.project.root().join(&path)]
+            .into_iter()
+            .find(|candidate| self.project.tree(candidate).is_some());
+        if let Some(path) = in_project {
+            let tree = self.project.tree(&path).expect("the tree was just found");
+            return Ok((path, tree));
+        }
+        if !self.others.contains_key(&path) {
+            let tree = load_tree(&path, None).map_err(|e| RpcError(SERVER_ERROR, e.to_string()))?;
+            self.others.insert(path.clone(), tree);
+        }
+        let tree = &self.others[&path];
+        Ok((path, tree))
+    }
+}
+
+/// Returns the node at the given position of a tree as a JSON object, or null if the position is outside of its code.
+fn node_at(tree: &PolyglotTree, point: Point) -> String {
+    let Some(zip) = tree.zipper_at_position(point) else {
+        return "null".to_string();
+    };
+    let (start, end) = zip.host_positions();
+    format!(
+        "{{\"id\":{},\"kind\":{},\"language\":{},\"start\":[{},{}],\"end\":[{},{}],\"text\":{}}}",
+        util::json_string(&zip.node_id().to_string()),
+        util::json_string(zip.kind()),
+        util::json_string(util::language_enum_to_string(zip.get_lang())),
+        start.row + 1,
+        start.column + 1,
+        end.row + 1,
+        end.column + 1,
+        util::json_string(zip.code())
+    )
+}
+
+fn error(id: &Json, RpcError(code, message): RpcError) -> String {
+    format!(
+        "{{\"jsonrpc\":\"2.0\",\"id\":{id},\"error\":{{\"code\":{code},\"message\":{}}}}}",
+        util::json_string(&message)
+    )
+}
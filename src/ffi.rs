@@ -0,0 +1,330 @@
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use crate::polyglot_tree::call_sites::{BindingDirection, EvalKind};
+use crate::polyglot_tree::exporter::JsonExporter;
+use crate::util::{self, Language};
+use crate::PolyglotTree;
+
+/// The value standing for Python in the `language` fields and arguments of the C API.
+pub const POLYGLOT_PYTHON: i32 = 0;
+/// The value standing for JavaScript in the `language` fields and arguments of the C API.
+pub const POLYGLOT_JAVASCRIPT: i32 = 1;
+/// The value standing for Java in the `language` fields and arguments of the C API.
+pub const POLYGLOT_JAVA: i32 = 2;
+/// The value of the `guest_language` field of call sites whose evaluated language is missing or not supported.
+pub const POLYGLOT_NO_LANGUAGE: i32 = -1;
+
+/// The value of the `kind` field of the call sites of polyglot eval calls.
+pub const POLYGLOT_CALL_EVAL: i32 = 0;
+/// The value of the `kind` field of the call sites of polyglot import calls.
+pub const POLYGLOT_CALL_IMPORT: i32 = 1;
+/// The value of the `kind` field of the call sites of polyglot export calls.
+pub const POLYGLOT_CALL_EXPORT: i32 = 2;
+
+/// A polyglot eval, import or export call, as seen from C.
+///
+/// Its strings are owned by the PolyglotCallSites it comes from, and are null when there is no such value.
+/// Positions are zero-based, and byte offsets are in the coordinates of the file the call physically lives in.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PolyglotCallSite {
+    /// One of the `POLYGLOT_CALL_` values.
+    pub kind: i32,
+    /// The language the call is written in, one of the `POLYGLOT_` language values.
+    pub language: i32,
+    /// The language of the evaluated code for eval calls, `POLYGLOT_NO_LANGUAGE` otherwise.
+    pub guest_language: i32,
+    pub start_row: usize,
+    pub start_column: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    /// The file containing the call, if its code comes from one.
+    pub file: *const c_char,
+    /// The path of the evaluated file, for eval calls evaluating a file.
+    pub path: *const c_char,
+    /// The name of the binding, for import and export calls naming it with a string literal.
+    pub name: *const c_char,
+    /// Whether the evaluated code is written inline, for eval calls.
+    pub is_inline: bool,
+    /// Whether a subtree could be built for the evaluated code, for eval calls.
+    pub has_subtree: bool,
+}
+
+/// The polyglot calls of a tree, as returned by `polyglot_call_sites`.
+pub struct PolyglotCallSites {
+    sites: Vec<PolyglotCallSite>,
+    /// The strings the sites point to.
+    strings: Vec<CString>,
+}
+
+impl PolyglotCallSites {
+    fn string(&mut self, value: Option<String>) -> *const c_char {
+        // strings with an interior nul byte cannot be given to C, and are left out
+        match value.and_then(|value| CString::new(value).ok()) {
+            Some(value) => {
+                // the pointer stays valid as the CString is moved, its buffer being on the heap
+                let pointer = value.as_ptr();
+                self.strings.push(value);
+                pointer
+            }
+            None => ptr::null(),
+        }
+    }
+}
+
+fn language_from_c(language: i32) -> Option<Language> {
+    match language {
+        POLYGLOT_PYTHON => Some(Language::Python),
+        POLYGLOT_JAVASCRIPT => Some(Language::JavaScript),
+        POLYGLOT_JAVA => Some(Language::Java),
+        _ => None,
+    }
+}
+
+fn language_to_c(language: &Language) -> i32 {
+    match language {
+        Language::Python => POLYGLOT_PYTHON,
+        Language::JavaScript => POLYGLOT_JAVASCRIPT,
+        Language::Java => POLYGLOT_JAVA,
+    }
+}
+
+/// Returns the string a C string points to, or None if it is null or not valid UTF-8.
+///
+/// # Safety
+///
+/// `string` must be null or point to a nul-terminated string.
+unsafe fn str_from_c<'a>(string: *const c_char) -> Option<&'a str> {
+    match string.is_null() {
+        true => None,
+        false => CStr::from_ptr(string).to_str().ok(),
+    }
+}
+
+/// Returns what `f` returns, or `default` if it panics, as a panic cannot unwind through the functions of the C API and would abort the process.
+fn catch_panic<T>(default: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+/// Builds the tree of the given nul-terminated UTF-8 code, written in the given language, one of the `POLYGLOT_` language values.
+///
+/// Returns null if the language is unknown or its grammar is not compiled in, the code is not valid UTF-8 or no tree can be built.
+/// The tree must be freed with `polyglot_tree_free`.
+///
+/// # Safety
+///
+/// `code` must be null or point to a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_tree_from_code(
+    code: *const c_char,
+    language: i32,
+) -> *mut PolyglotTree {
+    catch_panic(ptr::null_mut(), || {
+        let language = language_from_c(language).filter(|l| util::try_grammar(l).is_some());
+        let tree = str_from_c(code)
+            .zip(language)
+            .and_then(|(code, language)| PolyglotTree::from(code, language));
+        tree.map_or(ptr::null_mut(), |tree| Box::into_raw(Box::new(tree)))
+    })
+}
+
+/// Builds the tree of the file at the given nul-terminated UTF-8 path, its language being detected from its extension.
+///
+/// Returns null if the path is not valid UTF-8, its language cannot be detected or no tree can be built.
+/// The tree must be freed with `polyglot_tree_free`.
+///
+/// # Safety
+///
+/// `path` must be null or point to a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_tree_from_path(path: *const c_char) -> *mut PolyglotTree {
+    catch_panic(ptr::null_mut(), || {
+        let tree =
+            str_from_c(path).and_then(|path| PolyglotTree::from_path_auto(PathBuf::from(path)));
+        tree.map_or(ptr::null_mut(), |tree| Box::into_raw(Box::new(tree)))
+    })
+}
+
+/// Frees a tree returned by `polyglot_tree_from_code` or `polyglot_tree_from_path`. Does nothing if `tree` is null.
+///
+/// # Safety
+///
+/// `tree` must be null or a tree that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_tree_free(tree: *mut PolyglotTree) {
+    if !tree.is_null() {
+        catch_panic((), || drop(Box::from_raw(tree)));
+    }
+}
+
+/// Returns the tree as nul-terminated JSON text, as written by `JsonExporter`,
+/// or null if `tree` is null or its code contains a nul byte, which C strings cannot hold.
+/// The text must be freed with `polyglot_string_free`.
+///
+/// # Safety
+///
+/// `tree` must be null or a tree that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_tree_to_json(tree: *const PolyglotTree) -> *mut c_char {
+    let Some(tree) = tree.as_ref() else {
+        return ptr::null_mut();
+    };
+    catch_panic(ptr::null_mut(), || {
+        let mut exporter = JsonExporter::new();
+        tree.apply(&mut exporter);
+        CString::new(exporter.get_result()).map_or(ptr::null_mut(), CString::into_raw)
+    })
+}
+
+/// Frees a string returned by this API. Does nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this API that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        catch_panic((), || drop(CString::from_raw(string)));
+    }
+}
+
+/// Returns the polyglot eval, import and export calls of the tree and of its subtrees, the eval calls first,
+/// or null if `tree` is null. They must be freed with `polyglot_call_sites_free`, and can outlive the tree.
+///
+/// # Examples
+///
+/// ```
+/// use std::ffi::CStr;
+/// use polyglot_ast::ffi::*;
+///
+/// unsafe {
+///     let tree = polyglot_tree_from_code(c"import polyglot\npolyglot.eval(language=\"js\", string=\"1\")".as_ptr(), POLYGLOT_PYTHON);
+///     let sites = polyglot_call_sites(tree);
+///     polyglot_tree_free(tree);
+///
+///     assert_eq!(polyglot_call_sites_len(sites), 1);
+///     let site = &*polyglot_call_sites_get(sites, 0);
+///     assert_eq!(site.kind, POLYGLOT_CALL_EVAL);
+///     assert_eq!(CStr::from_ptr(polyglot_language_name(site.language)).to_str(), Ok("python"));
+///     assert_eq!(site.guest_language, POLYGLOT_JAVASCRIPT);
+///     assert_eq!((site.start_row, site.start_column), (1, 0));
+///     assert!(site.is_inline && site.has_subtree);
+///     assert!(polyglot_call_sites_get(sites, 1).is_null());
+///     polyglot_call_sites_free(sites);
+/// }
+/// ```
+///
+/// # Safety
+///
+/// `tree` must be null or a tree that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_call_sites(tree: *const PolyglotTree) -> *mut PolyglotCallSites {
+    let Some(tree) = tree.as_ref() else {
+        return ptr::null_mut();
+    };
+    catch_panic(ptr::null_mut(), || {
+        let mut result = PolyglotCallSites {
+            sites: Vec::new(),
+            strings: Vec::new(),
+        };
+        for site in tree.eval_sites() {
+            let file = result.string(site.origin.file().map(|f| f.to_string_lossy().into_owned()));
+            let path = result.string(site.path.map(|p| p.to_string_lossy().into_owned()));
+            result.sites.push(PolyglotCallSite {
+                kind: POLYGLOT_CALL_EVAL,
+                language: language_to_c(&site.host_language),
+                guest_language: site
+                    .guest_language
+                    .as_ref()
+                    .map_or(POLYGLOT_NO_LANGUAGE, language_to_c),
+                start_row: site.start_position.row,
+                start_column: site.start_position.column,
+                start_byte: site.span.start,
+                end_byte: site.span.end,
+                file,
+                path,
+                name: ptr::null(),
+                is_inline: site.kind == Some(EvalKind::Inline),
+                has_subtree: site.has_subtree,
+            });
+        }
+        for site in tree.bindings() {
+            let file = result.string(site.origin.file().map(|f| f.to_string_lossy().into_owned()));
+            let name = result.string(site.name);
+            result.sites.push(PolyglotCallSite {
+                kind: match site.direction {
+                    BindingDirection::Import => POLYGLOT_CALL_IMPORT,
+                    BindingDirection::Export => POLYGLOT_CALL_EXPORT,
+                },
+                language: language_to_c(&site.language),
+                guest_language: POLYGLOT_NO_LANGUAGE,
+                start_row: site.start_position.row,
+                start_column: site.start_position.column,
+                start_byte: site.span.start,
+                end_byte: site.span.end,
+                file,
+                path: ptr::null(),
+                name,
+                is_inline: false,
+                has_subtree: false,
+            });
+        }
+        Box::into_raw(Box::new(result))
+    })
+}
+
+/// Returns the number of call sites, or 0 if `sites` is null.
+///
+/// # Safety
+///
+/// `sites` must be null or call sites that were not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_call_sites_len(sites: *const PolyglotCallSites) -> usize {
+    catch_panic(0, || sites.as_ref().map_or(0, |sites| sites.sites.len()))
+}
+
+/// Returns the call site at the given index, or null if `sites` is null or the index is out of bounds.
+/// The call site is valid until the call sites are freed.
+///
+/// # Safety
+///
+/// `sites` must be null or call sites that were not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_call_sites_get(
+    sites: *const PolyglotCallSites,
+    index: usize,
+) -> *const PolyglotCallSite {
+    catch_panic(ptr::null(), || {
+        sites
+            .as_ref()
+            .and_then(|sites| sites.sites.get(index))
+            .map_or(ptr::null(), |site| site as *const PolyglotCallSite)
+    })
+}
+
+/// Frees call sites returned by `polyglot_call_sites`. Does nothing if `sites` is null.
+///
+/// # Safety
+///
+/// `sites` must be null or call sites that were not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn polyglot_call_sites_free(sites: *mut PolyglotCallSites) {
+    if !sites.is_null() {
+        catch_panic((), || drop(Box::from_raw(sites)));
+    }
+}
+
+/// Returns the nul-terminated name of the given language value, as given by `util::language_enum_to_string`,
+/// or null if it is unknown. The name must not be freed.
+#[no_mangle]
+pub extern "C" fn polyglot_language_name(language: i32) -> *const c_char {
+    catch_panic(ptr::null(), || match language_from_c(language) {
+        Some(Language::Python) => c"python".as_ptr(),
+        Some(Language::JavaScript) => c"js".as_ptr(),
+        Some(Language::Java) => c"java".as_ptr(),
+        None => ptr::null(),
+    })
+}
@@ -0,0 +1,232 @@
+use std::path::PathBuf;
+
+use jni::objects::{JClass, JObject, JObjectArray, JString, JValue};
+use jni::sys::{jlong, jobjectArray, jstring};
+use jni::JNIEnv;
+use thiserror::Error;
+
+use crate::polyglot_tree::call_sites::{BindingDirection, EvalKind};
+use crate::polyglot_tree::exporter::JsonExporter;
+use crate::util;
+use crate::PolyglotTree;
+
+/// The problems a native method of `polyglot.ast.PolyglotTree` can run into, thrown to the JVM as exceptions.
+#[derive(Debug, Error)]
+enum JvmError {
+    #[error(transparent)]
+    Jni(#[from] jni::errors::Error),
+    /// An argument the tree cannot be built from, thrown as an IllegalArgumentException.
+    #[error("{0}")]
+    Argument(String),
+}
+
+/// Runs `f`, throwing its error to the JVM if it fails, in which case `default` is returned to the JVM, which ignores it.
+fn throwing<'local, T>(
+    env: &mut JNIEnv<'local>,
+    default: T,
+    f: impl FnOnce(&mut JNIEnv<'local>) -> Result<T, JvmError>,
+) -> T {
+    match f(env) {
+        Ok(value) => value,
+        Err(e) => {
+            let class = match e {
+                // the exception thrown by the JVM is already pending
+                JvmError::Jni(jni::errors::Error::JavaException) => return default,
+                JvmError::Jni(_) => "java/lang/IllegalStateException",
+                JvmError::Argument(_) => "java/lang/IllegalArgumentException",
+            };
+            let _ = env.throw_new(class, e.to_string());
+            default
+        }
+    }
+}
+
+/// Returns the tree a handle held by a `polyglot.ast.PolyglotTree` stands for.
+///
+/// # Safety
+///
+/// The handle must have been returned by `into_handle`, and not freed yet.
+unsafe fn tree<'a>(handle: jlong) -> &'a PolyglotTree {
+    &*(handle as *const PolyglotTree)
+}
+
+fn into_handle(tree: PolyglotTree) -> jlong {
+    Box::into_raw(Box::new(tree)) as jlong
+}
+
+/// Returns a Java string, or null if there is no value.
+fn optional_string<'local>(
+    env: &mut JNIEnv<'local>,
+    value: Option<&str>,
+) -> Result<JObject<'local>, JvmError> {
+    Ok(match value {
+        Some(value) => env.new_string(value)?.into(),
+        None => JObject::null(),
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_fromCode0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    code: JString<'local>,
+    language: JString<'local>,
+) -> jlong {
+    throwing(&mut env, 0, |env| {
+        let code: String = env.get_string(&code)?.into();
+        let language: String = env.get_string(&language)?.into();
+        let language = util::language_string_to_enum(&language).map_err(|_| {
+            JvmError::Argument(format!(
+                "unsupported language {language}, expected one of python, js or java"
+            ))
+        })?;
+        PolyglotTree::from(code, language)
+            .map(into_handle)
+            .ok_or_else(|| JvmError::Argument("unable to build a tree for the code".to_string()))
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_fromPath0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    path: JString<'local>,
+) -> jlong {
+    throwing(&mut env, 0, |env| {
+        let path: String = env.get_string(&path)?.into();
+        PolyglotTree::from_path_auto(PathBuf::from(&path))
+            .map(into_handle)
+            .ok_or_else(|| JvmError::Argument(format!("unable to build a tree for {path}")))
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_free0<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) {
+    if handle != 0 {
+        // SAFETY: the Java class frees its handle once, when it is closed
+        drop(unsafe { Box::from_raw(handle as *mut PolyglotTree) });
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_language0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) -> jstring {
+    // SAFETY: the Java class only calls native methods with the handle of a tree that is not closed
+    let tree = unsafe { tree(handle) };
+    throwing(&mut env, std::ptr::null_mut(), |env| {
+        let language = util::language_enum_to_string(&tree.language());
+        Ok(env.new_string(language)?.into_raw())
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_toJson0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) -> jstring {
+    // SAFETY: the Java class only calls native methods with the handle of a tree that is not closed
+    let tree = unsafe { tree(handle) };
+    throwing(&mut env, std::ptr::null_mut(), |env| {
+        let mut exporter = JsonExporter::new();
+        tree.apply(&mut exporter);
+        Ok(env.new_string(exporter.get_result())?.into_raw())
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_evalSites0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) -> jobjectArray {
+    // SAFETY: the Java class only calls native methods with the handle of a tree that is not closed
+    let tree = unsafe { tree(handle) };
+    throwing(&mut env, std::ptr::null_mut(), |env| {
+        let sites = tree.eval_sites();
+        let array: JObjectArray =
+            env.new_object_array(sites.len() as i32, "polyglot/ast/EvalSite", JObject::null())?;
+        for (i, site) in sites.iter().enumerate() {
+            let host = env.new_string(util::language_enum_to_string(&site.host_language))?;
+            let guest = optional_string(
+                env,
+                site.guest_language
+                    .as_ref()
+                    .map(util::language_enum_to_string),
+            )?;
+            let file = optional_string(env, site.origin.file().and_then(|f| f.to_str()))?;
+            let path = optional_string(env, site.path.as_ref().and_then(|p| p.to_str()))?;
+            let object = env.new_object(
+                "polyglot/ast/EvalSite",
+                "(Ljava/lang/String;Ljava/lang/String;IIIILjava/lang/String;Ljava/lang/String;ZZ)V",
+                &[
+                    JValue::Object(&host),
+                    JValue::Object(&guest),
+                    JValue::Int(site.start_position.row as i32),
+                    JValue::Int(site.start_position.column as i32),
+                    JValue::Int(site.span.start as i32),
+                    JValue::Int(site.span.end as i32),
+                    JValue::Object(&file),
+                    JValue::Object(&path),
+                    JValue::Bool((site.kind == Some(EvalKind::Inline)).into()),
+                    JValue::Bool(site.has_subtree.into()),
+                ],
+            )?;
+            env.set_object_array_element(&array, i as i32, &object)?;
+            // local references are only released when the native method returns, and the JVM only guarantees a few of them
+            for local in [object, host.into(), guest, file, path] {
+                env.delete_local_ref(local)?;
+            }
+        }
+        Ok(array.into_raw())
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_polyglot_ast_PolyglotTree_bindings0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) -> jobjectArray {
+    // SAFETY: the Java class only calls native methods with the handle of a tree that is not closed
+    let tree = unsafe { tree(handle) };
+    throwing(&mut env, std::ptr::null_mut(), |env| {
+        let sites = tree.bindings();
+        let array: JObjectArray = env.new_object_array(
+            sites.len() as i32,
+            "polyglot/ast/BindingSite",
+            JObject::null(),
+        )?;
+        for (i, site) in sites.iter().enumerate() {
+            let name = optional_string(env, site.name.as_deref())?;
+            let language = env.new_string(util::language_enum_to_string(&site.language))?;
+            let file = optional_string(env, site.origin.file().and_then(|f| f.to_str()))?;
+            let object = env.new_object(
+                "polyglot/ast/BindingSite",
+                "(Ljava/lang/String;ZLjava/lang/String;IIIILjava/lang/String;)V",
+                &[
+                    JValue::Object(&name),
+                    JValue::Bool((site.direction == BindingDirection::Export).into()),
+                    JValue::Object(&language),
+                    JValue::Int(site.start_position.row as i32),
+                    JValue::Int(site.start_position.column as i32),
+                    JValue::Int(site.span.start as i32),
+                    JValue::Int(site.span.end as i32),
+                    JValue::Object(&file),
+                ],
+            )?;
+            env.set_object_array_element(&array, i as i32, &object)?;
+            for local in [object, name, language.into(), file] {
+                env.delete_local_ref(local)?;
+            }
+        }
+        Ok(array.into_raw())
+    })
+}
@@ -4,12 +4,35 @@
 pub mod util;
 
 /// The main module of the project.
-/// 
+///
 /// This module contains the PolyglotTree struct, which is the main object used to build and interact with polyglot ASTs.
 pub mod polyglot_tree;
+
+/// The JavaScript API of the WebAssembly build, built with the `wasm` feature.
+///
+/// This module exposes the construction of trees, the enumeration of their polyglot calls and their JSON export through `wasm-bindgen`.
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// The C API of the shared library, built with the `ffi` feature.
+///
+/// This module exposes the construction of trees, the iteration over their polyglot calls and their JSON export as `extern "C"` functions,
+/// declared for C and C++ in `include/polyglot_ast.h`.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+/// The native methods of the `polyglot.ast.PolyglotTree` Java class, built with the `jni` feature.
+///
+/// The Java classes exposing tree construction and the eval and binding sites of trees to the JVM live in the `java` directory.
+#[cfg(feature = "jni")]
+mod jvm;
+pub use polyglot_tree::node_id::NodeId;
 pub use polyglot_tree::polyglot_processor::{PolygotProcessor, TreePrinter};
-pub use polyglot_tree::polyglot_zipper::PolyglotZipper;
-pub use polyglot_tree::PolyglotTree;
+pub use polyglot_tree::polyglot_zipper::{DetachedNode, PolyglotZipper, QueryCapture};
+pub use polyglot_tree::query_set::LanguageQuerySet;
+pub use polyglot_tree::syntax_node::SyntaxNode;
+pub use polyglot_tree::zipper_set::ZipperSet;
+pub use polyglot_tree::{Origin, PolyglotTree};
 
 #[cfg(test)]
 mod tests;
@@ -1,13 +1,190 @@
-use std::path::PathBuf;
-
-use polyglot_ast::util::Language;
-use polyglot_ast::{PolyglotTree, TreePrinter};
-
-fn main() {
-    let file = PathBuf::from("TestSamples/JavaTest.java");
-    let tree =
-        PolyglotTree::from_path(file, Language::Java).expect("Should not have parsing issues");
-    let mut tp = TreePrinter::new();
-    tree.apply(&mut tp);
-    println!("{}", tp.get_result())
+use std::process::ExitCode;
+
+use clap::{Arg, ArgAction, Command};
+
+mod cli;
+
+fn command() -> Command {
+    let file = Arg::new("file")
+        .required(true)
+        .value_name("FILE")
+        .help("The file to analyze, a directory to analyze its files, or - to read code from the standard input");
+    let language = Arg::new("language")
+        .long("language")
+        .short('l')
+        .value_name("LANGUAGE")
+        .help("The language of the file (python, js or java), detected from its extension or shebang by default, and ignored for directories");
+
+    let command = Command::new("polyglot-ast")
+        .about("Builds and analyzes the polyglot ASTs of GraalVM programs mixing Python, JavaScript and Java")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .global(true)
+                .value_parser(["text", "json", "yaml", "csv", "sarif", "dot"])
+                .default_value("text")
+                .help("How to write the results: sarif is only written by lint, dot by print"),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Exit with a failure code if the code contains syntax errors or eval calls that cannot be followed"),
+        )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .global(true)
+                .action(ArgAction::Append)
+                .value_name("GLOB")
+                .help("Only analyze the files of a directory matching one of these globs, eg. '**/*.py'"),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .global(true)
+                .action(ArgAction::Append)
+                .value_name("GLOB")
+                .help("Leave out the files of a directory matching one of these .gitignore patterns, eg. 'vendor/'"),
+        )
+        .subcommand(
+            Command::new("parse")
+                .about("Builds the tree of a file and reports its shape and errors")
+                .arg(file.clone())
+                .arg(language.clone()),
+        )
+        .subcommand(
+            Command::new("print")
+                .about("Prints the tree of a file, including the trees of the code it evaluates")
+                .arg(file.clone())
+                .arg(language.clone()),
+        )
+        .subcommand(
+            Command::new("calls")
+                .about("Lists the polyglot eval, import and export calls of a file and of the code it evaluates")
+                .arg(file.clone())
+                .arg(language.clone()),
+        )
+        .subcommand(
+            Command::new("extract")
+                .about("Writes every inline snippet evaluated by a file to its own file, named after its location and language")
+                .arg(file.clone())
+                .arg(language.clone())
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .short('o')
+                        .value_name("DIR")
+                        .required(true)
+                        .help("The directory to write the snippets to, created if needed"),
+                ),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Runs a tree-sitter query over every tree of a file written in the given language, subtrees included")
+                .arg(file.clone())
+                .arg(language.clone())
+                .arg(
+                    Arg::new("lang")
+                        .long("lang")
+                        .value_name("LANGUAGE")
+                        .required(true)
+                        .help("The language of the trees to query (python, js or java)"),
+                )
+                .arg(
+                    Arg::new("query")
+                        .long("query")
+                        .short('q')
+                        .value_name("QUERY")
+                        .required(true)
+                        .help("The tree-sitter query, eg. '(call_expression) @call'"),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Reports the structural changes between two versions of a file, including the snippets they evaluate")
+                .arg(
+                    Arg::new("old")
+                        .required(true)
+                        .value_name("OLD")
+                        .help("The old version of the file"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .required(true)
+                        .value_name("NEW")
+                        .help("The new version of the file"),
+                )
+                .arg(language.clone()),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Keeps the trees of the files of a directory in memory and answers JSON-RPC requests about them, one per line")
+                .arg(
+                    Arg::new("root")
+                        .value_name("DIR")
+                        .default_value(".")
+                        .help("The directory whose files are built up front"),
+                )
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .value_name("ADDRESS")
+                        .help("Answer the requests of the connections to this TCP address, eg. 127.0.0.1:7878, instead of the standard input"),
+                ),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Checks a file and the code it evaluates for common problems of polyglot code")
+                .arg(file.clone())
+                .arg(language.clone())
+                .arg(
+                    Arg::new("fail-on")
+                        .long("fail-on")
+                        .value_name("SEVERITY")
+                        .value_parser(["info", "warning", "error"])
+                        .default_value("error")
+                        .help("Exit with a failure code if a problem at least this serious is found"),
+                ),
+        )
+        .subcommand(
+            Command::new("lsp")
+                .about("Runs a language server on the standard input and output, publishing the problems of the open files, their symbols and the definitions of their polyglot imports"),
+        );
+    #[cfg(feature = "tui")]
+    let command = command.subcommand(
+        Command::new("browse")
+            .about("Opens an interactive explorer of the tree of a file, with its code next to it")
+            .arg(file.help("The file to browse"))
+            .arg(language),
+    );
+    command
+}
+
+fn main() -> ExitCode {
+    let matches = command().get_matches();
+    let settings = cli::Settings::from_matches(&matches);
+    let result = match matches.subcommand() {
+        Some(("parse", matches)) => cli::parse(matches, settings),
+        Some(("print", matches)) => cli::print(matches, settings),
+        Some(("calls", matches)) => cli::calls(matches, settings),
+        Some(("extract", matches)) => cli::extract(matches, settings),
+        Some(("diff", matches)) => cli::diff(matches, settings),
+        Some(("lint", matches)) => cli::lint(matches, settings),
+        Some(("query", matches)) => cli::query(matches, settings),
+        Some(("serve", matches)) => cli::serve(matches, settings),
+        Some(("lsp", matches)) => cli::lsp(matches, settings),
+        #[cfg(feature = "tui")]
+        Some(("browse", matches)) => cli::browse(matches, settings),
+        _ => unreachable!("a subcommand is required"),
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        ExitCode::from(2)
+    })
 }
@@ -1,22 +1,119 @@
 use super::util;
 use super::util::Language;
+use build_options::{BuildContext, BuildOptions};
+use node_map::NodeMap;
+use polyglot_node::{EvalPayload, PolyglotNode};
+use source_text::SourceText;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tree_sitter::{Node, Parser, Tree};
+use std::sync::{Arc, Mutex, OnceLock};
+use tree_sitter::{Node, Point, Tree};
 
+pub mod batch;
+pub mod binding_graph;
+pub mod bounded_tree;
+pub mod build_options;
+pub mod call_graph;
+pub mod call_sites;
+pub mod completion;
+pub mod conflicts;
+pub mod data_flow;
+pub mod dependency_graph;
+pub mod disk_cache;
+pub mod edit;
+mod encoding;
+pub mod exporter;
+pub mod file_cache;
+pub mod file_filter;
+pub mod file_provider;
+pub mod fingerprint;
+pub mod graft;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+#[cfg(feature = "graph-dsl")]
+pub mod graph_dsl;
+pub mod hover;
+pub mod lint;
+pub mod memory;
+pub mod node_data;
+pub mod node_id;
+mod node_map;
+pub mod owned_tree;
+mod parser_pool;
+pub mod polyglot_node;
 pub mod polyglot_processor;
+pub mod polyglot_walker;
 pub mod polyglot_zipper;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod project;
+pub mod project_state;
+pub mod query_set;
+pub mod references;
+pub mod rename;
+mod scheduler;
+mod sharded_map;
+pub mod source;
+mod source_text;
+pub mod stats;
+pub mod stream;
+pub mod summary;
+pub mod symbols;
+pub mod syntax_node;
+pub mod tree_diff;
+#[cfg(feature = "watch")]
+pub mod watch;
+pub mod zipper_set;
 
 /// An Abstract Syntax Tree (AST) spanning across multiple languages.
 ///
-///
+/// Cloning a PolyglotTree is cheap, as its syntax trees and code are reference counted.
+#[derive(Clone)]
 pub struct PolyglotTree {
     tree: Tree,
-    code: String,
+    code: SourceText,
     working_dir: PathBuf,
     language: Language,
-    node_to_subtrees_map: HashMap<usize, PolyglotTree>,
+    node_to_subtrees_map: NodeMap<Arc<PolyglotTree>>,
+    origin: Origin,
+    polyglot_nodes: NodeMap<PolyglotNode>,
+    /// The polyglot import and export calls, which are only looked for when first needed.
+    bindings: OnceLock<NodeMap<PolyglotNode>>,
+    stats: OnceLock<stats::TreeStats>,
+    #[cfg(feature = "profile")]
+    profile: profile::BuildProfile,
+}
+
+/// Describes where the code of a PolyglotTree physically comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Origin {
+    /// The code was provided directly as a string, and is not associated with any file.
+    Snippet,
+    /// The code was read from the file at the given path.
+    File(PathBuf),
+    /// The code was taken from a string literal passed to a polyglot eval call in a host tree.
+    ///
+    /// `file` is the physical file containing the literal, if any,
+    /// and `range` is the byte range of the literal's content in that file (or in the top-level snippet if there is no file).
+    Literal {
+        file: Option<PathBuf>,
+        range: Range<usize>,
+    },
+}
+
+impl Origin {
+    /// Returns the path of the physical file the code lives in, if there is one.
+    pub fn file(&self) -> Option<&PathBuf> {
+        match self {
+            Origin::Snippet => None,
+            Origin::File(path) => Some(path),
+            Origin::Literal { file, .. } => file.as_ref(),
+        }
+    }
 }
 
 impl PolyglotTree {
@@ -51,29 +148,36 @@ impl PolyglotTree {
     /// This can only happen if tree_sitter and the grammars are of incompatible versions;
     /// either refer to the `tree_sitter::Parser::set_language()` documentation or directly contact polyglot_ast maintainers if this method keeps panicking.
     pub fn from(code: impl ToString, language: Language) -> Option<PolyglotTree> {
-        let code = code.to_string();
-
-        let mut parser = Parser::new();
-        let ts_lang = util::language_enum_to_treesitter(&language);
-
-        parser
-            .set_language(ts_lang)
-            .expect("Error loading the language grammar into the parser; if this error persists, consider reporting it to the library maintainers.");
-
-        let tree = parser.parse(code.as_str(), None)?;
+        Self::from_with_options(code, language, &BuildOptions::default())
+    }
 
-        let mut result = PolyglotTree {
-            tree,
-            code,
-            working_dir: PathBuf::new(),
+    /// Same as `from`, except that subtrees are only built as allowed by the given BuildOptions.
+    pub fn from_with_options(
+        code: impl ToString,
+        language: Language,
+        options: &BuildOptions,
+    ) -> Option<PolyglotTree> {
+        Self::from_directory(
+            SourceText::from(code.to_string()),
             language,
-            node_to_subtrees_map: HashMap::new(),
-        };
+            PathBuf::new(),
+            Origin::Snippet,
+            &BuildContext::new(options),
+        )
+    }
 
-        let mut map = HashMap::new();
-        result.build_polyglot_tree(&mut map); // traverse the tree to build the subtrees
-        result.node_to_subtrees_map = map; // set the map after its built
-        Some(result)
+    /// Same as `from_with_options`, except that a Cancelled error is returned if the build was cancelled
+    /// through the CancellationToken of the options, rather than None or a partially built tree.
+    pub fn try_from_with_options(
+        code: impl ToString,
+        language: Language,
+        options: &BuildOptions,
+    ) -> Result<Option<PolyglotTree>, util::Cancelled> {
+        let tree = Self::from_with_options(code, language, options);
+        match options.is_cancelled() {
+            true => Err(util::Cancelled),
+            false => Ok(tree),
+        }
     }
 
     /// Given a path to a file and a Language, returns a PolyglotTree instance that represents the program written in the file.
@@ -113,39 +217,118 @@ impl PolyglotTree {
     /// This can only happen if tree_sitter and the grammars are of incompatible versions;
     /// either refer to the `tree_sitter::Parser::set_language()` documentation or directly contact polyglot_ast maintainers if this method keeps panicking.
     pub fn from_path(path: PathBuf, language: Language) -> Option<PolyglotTree> {
-        let file = path.clone();
-        let code = match std::fs::read_to_string(path) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!(
-                    "Warning: unable to create tree for file {} due to the following error: {e}",
-                    file.to_str()?
-                );
-                return None;
-            }
-        };
+        Self::from_path_with_options(path, language, &BuildOptions::default())
+    }
 
-        let mut parser = Parser::new();
-        let ts_lang = util::language_enum_to_treesitter(&language);
+    /// Same as `from_path`, except that subtrees are only built as allowed by the given BuildOptions.
+    pub fn from_path_with_options(
+        path: PathBuf,
+        language: Language,
+        options: &BuildOptions,
+    ) -> Option<PolyglotTree> {
+        Self::from_path_impl(path, language, &BuildContext::new(options))
+    }
 
-        parser
-            .set_language(ts_lang)
-            .expect("Error loading the language grammar into the parser; consider verifying your versions of the grammar and tree-sitter are compatible.");
+    /// Same as `from_path_with_options`, except that a Cancelled error is returned if the build was cancelled
+    /// through the CancellationToken of the options, rather than None or a partially built tree.
+    pub fn try_from_path_with_options(
+        path: PathBuf,
+        language: Language,
+        options: &BuildOptions,
+    ) -> Result<Option<PolyglotTree>, util::Cancelled> {
+        let tree = Self::from_path_with_options(path, language, options);
+        match options.is_cancelled() {
+            true => Err(util::Cancelled),
+            false => Ok(tree),
+        }
+    }
 
-        let tree = parser.parse(code.as_str(), None)?;
+    /// Same as `from_path`, except that the Language of the file is detected from the extension of its path,
+    /// or else from the shebang line of its code, as with `util::detect_language`.
+    ///
+    /// Returns None if the file cannot be read, if its Language cannot be detected or if it cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from_path_auto(PathBuf::from("TestSamples/test_pyprint_file.js")).unwrap();
+    /// assert_eq!(tree.language(), Language::JavaScript);
+    /// assert_eq!(tree.subtrees().next().unwrap().1.language(), Language::Python);
+    /// ```
+    pub fn from_path_auto(path: PathBuf) -> Option<PolyglotTree> {
+        Self::from_path_auto_with_options(path, &BuildOptions::default())
+    }
 
-        let mut result = PolyglotTree {
-            tree,
-            code,
-            working_dir: file.parent()?.to_path_buf(),
-            language,
-            node_to_subtrees_map: HashMap::new(),
+    /// Same as `from_path_auto`, except that subtrees are only built as allowed by the given BuildOptions.
+    pub fn from_path_auto_with_options(
+        path: PathBuf,
+        options: &BuildOptions,
+    ) -> Option<PolyglotTree> {
+        let code = Self::read_file(&path, options)?;
+        let Some(language) = util::detect_language(&path, &code) else {
+            eprintln!(
+                "Warning: unable to detect the language of file {}",
+                path.display()
+            );
+            return None;
         };
+        Self::from_file_code(code, path, language, &BuildContext::new(options))
+    }
 
-        let mut map = HashMap::new();
-        result.build_polyglot_tree(&mut map);
-        result.node_to_subtrees_map = map;
-        Some(result)
+    /// Internal function to build a polyglot tree from a file, as part of the tree being built with the given context.
+    fn from_path_impl(
+        path: PathBuf,
+        language: Language,
+        ctx: &BuildContext,
+    ) -> Option<PolyglotTree> {
+        let code = Self::read_file(&path, ctx.options)?;
+        Self::from_file_code(code, path, language, ctx)
+    }
+
+    /// Internal function to build a polyglot tree from the code read from the file at `path`.
+    fn from_file_code(
+        code: SourceText,
+        path: PathBuf,
+        language: Language,
+        ctx: &BuildContext,
+    ) -> Option<PolyglotTree> {
+        let working_dir = path.parent()?.to_path_buf();
+        let ctx = ctx.in_file(&path);
+        Self::from_directory(code, language, working_dir, Origin::File(path), &ctx)
+    }
+
+    /// Internal function to read the code of a file, printing a warning if it cannot be read.
+    fn read_file(path: &Path, options: &BuildOptions) -> Option<SourceText> {
+        match Self::load_file(path, options) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                eprintln!(
+                    "Warning: unable to create tree for file {} due to the following error: {e}",
+                    path.to_str()?
+                );
+                None
+            }
+        }
+    }
+
+    /// Internal function to read the code of a file.
+    /// Files at least as big as the threshold set with `BuildOptions::mmap_threshold` are memory-mapped rather than read.
+    fn load_file(path: &Path, options: &BuildOptions) -> std::io::Result<SourceText> {
+        if let Some(provider) = &options.file_provider {
+            return provider.read(path).map(SourceText::from);
+        }
+        #[cfg(feature = "mmap")]
+        if options
+            .mmap_threshold
+            .is_some_and(|threshold| std::fs::metadata(path).is_ok_and(|m| m.len() >= threshold))
+        {
+            return SourceText::map(path);
+        }
+        std::fs::read_to_string(path).map(SourceText::from)
     }
 
     /// Internal function to build a polyglot tree, which sets a specific working directory for the built subtree.
@@ -157,6 +340,8 @@ impl PolyglotTree {
     /// For proper use, ensure that `code.to_string()` would provide a syntactically correct code snippet.
     /// - `language` The Language variant that the file at `path` is written in.
     /// - `working_dir` a PathBuf of the parent directory of the file currently being processed.
+    /// - `origin` the Origin of `code`.
+    /// - `ctx` the BuildContext of the tree being built, which `code` is part of.
     ///
     /// # Panics
     ///
@@ -165,33 +350,63 @@ impl PolyglotTree {
     /// This can only happen if tree_sitter and the grammars are of incompatible versions;
     /// either refer to the `tree_sitter::Parser::set_language()` documentation or directly contact polyglot_ast maintainers if this method keeps panicking.
     fn from_directory(
-        code: impl ToString,
+        code: SourceText,
         language: Language,
         working_dir: PathBuf,
+        origin: Origin,
+        ctx: &BuildContext,
     ) -> Option<PolyglotTree> {
-        let code = code.to_string();
+        #[cfg(feature = "profile")]
+        let start = std::time::Instant::now();
+        let mut result = Self::parse_only(code, language, working_dir, origin, ctx.options)?;
+        #[cfg(feature = "profile")]
+        let parsed = std::time::Instant::now();
 
-        let mut parser = Parser::new();
-        let ts_lang = util::language_enum_to_treesitter(&language);
-
-        parser
-            .set_language(ts_lang)
-            .expect("Error loading the language grammar into the parser; consider verifying your versions of the grammar and tree-sitter are compatible.");
+        // traverse the tree to build the subtrees, and set the maps after they are built
+        let (mut map, nodes, jobs) = result.collect_polyglot_links(ctx);
+        result.polyglot_nodes = nodes;
+        #[cfg(feature = "profile")]
+        let assembled = std::time::Instant::now();
+        if let Some(stream) = ctx.stream {
+            // the tree is streamed before its subtrees, which it does not keep
+            (stream.borrow_mut())(&result);
+        }
+        result.build_subtrees(&mut map, jobs, ctx);
+        result.node_to_subtrees_map = map;
 
-        let tree = parser.parse(code.as_str(), None)?;
+        #[cfg(feature = "profile")]
+        {
+            result.profile = profile::BuildProfile {
+                parse: parsed - start,
+                assembly: assembled - parsed,
+                subtrees: assembled.elapsed(),
+            };
+        }
+        Some(result)
+    }
 
-        let mut result = PolyglotTree {
+    /// Internal function to parse the given code into a tree without looking for polyglot calls, leaving it without polyglot nodes nor subtrees.
+    fn parse_only(
+        code: SourceText,
+        language: Language,
+        working_dir: PathBuf,
+        origin: Origin,
+        options: &BuildOptions,
+    ) -> Option<PolyglotTree> {
+        let tree = parser_pool::parse(language, code.as_str(), None, options)?;
+        Some(PolyglotTree {
             tree,
             code,
             working_dir,
             language,
-            node_to_subtrees_map: HashMap::new(),
-        };
-
-        let mut map = HashMap::new();
-        result.build_polyglot_tree(&mut map);
-        result.node_to_subtrees_map = map;
-        Some(result)
+            node_to_subtrees_map: NodeMap::default(),
+            origin,
+            polyglot_nodes: NodeMap::default(),
+            bindings: OnceLock::new(),
+            stats: OnceLock::new(),
+            #[cfg(feature = "profile")]
+            profile: profile::BuildProfile::default(),
+        })
     }
 
     /// Applies the given processor to the tree, starting from the root of the tree.
@@ -200,6 +415,306 @@ impl PolyglotTree {
         processor.process(polyglot_zipper::PolyglotZipper::from(self))
     }
 
+    /// Returns the Language of the tree's root, which is the language its own code is written in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from_path(PathBuf::from("TestSamples/pyprint.py"), Language::Python).unwrap();
+    /// assert_eq!(tree.language(), Language::Python);
+    /// assert_eq!(tree.source(), std::fs::read_to_string("TestSamples/pyprint.py").unwrap());
+    /// assert_eq!(tree.working_dir(), Path::new("TestSamples"));
+    /// ```
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Returns the code the tree was built from, without the code of its subtrees when they were read from other files.
+    pub fn source(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the directory relative to which the paths passed to polyglot eval calls are resolved.
+    ///
+    /// This is the parent directory of the file the tree was read from, or an empty path for trees built from a snippet.
+    pub fn working_dir(&self) -> &Path {
+        &self.working_dir
+    }
+
+    /// Converts a byte offset in the code of this tree to the coordinates of the file the code physically lives in.
+    ///
+    /// For trees built from a string literal, this is the offset in the file (or top-level snippet) containing the literal;
+    /// for other trees, the offset is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'print(42)')", Language::JavaScript).unwrap();
+    /// let (_, subtree) = tree.subtrees().next().unwrap();
+    ///
+    /// assert_eq!(subtree.to_host_offset(6), 31);
+    /// assert_eq!(subtree.to_local_offset(31), Some(6));
+    /// assert_eq!(subtree.to_local_offset(3), None);
+    /// ```
+    pub fn to_host_offset(&self, offset: usize) -> usize {
+        match &self.origin {
+            Origin::Literal { range, .. } => range.start + offset,
+            _ => offset,
+        }
+    }
+
+    /// Converts a byte offset in the file the code of this tree physically lives in to an offset in the code of this tree.
+    ///
+    /// Returns None if the offset is outside of the string literal this tree was built from.
+    pub fn to_local_offset(&self, host_offset: usize) -> Option<usize> {
+        match &self.origin {
+            Origin::Literal { range, .. } => (range.start..=range.end)
+                .contains(&host_offset)
+                .then(|| host_offset - range.start),
+            _ => (host_offset <= self.code.len()).then_some(host_offset),
+        }
+    }
+
+    /// Returns a zipper on the deepest node at the given position of the code of this tree, looking into the subtrees of the string literals it evaluates.
+    ///
+    /// Subtrees of evaluated files are not looked into, as their code lives in other files.
+    /// Returns None if the position is not within the code of this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    /// use tree_sitter::Point;
+    ///
+    /// let code = "let x = 1;\nPolyglot.eval(\"python\", 'print(42)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    ///
+    /// assert_eq!(tree.zipper_at_position(Point::new(0, 4)).unwrap().code(), "x");
+    /// assert_eq!(tree.zipper_at_position(Point::new(1, 32)).unwrap().kind(), "integer");
+    /// assert!(tree.zipper_at_position(Point::new(5, 0)).is_none());
+    /// ```
+    pub fn zipper_at_position(
+        &self,
+        position: Point,
+    ) -> Option<polyglot_zipper::PolyglotZipper<'_>> {
+        let offset = self.to_host_offset(util::LineIndex::new(&self.code).offset(position)?);
+        let mut zip = polyglot_zipper::PolyglotZipper::from(self);
+        // children are compared in the coordinates of the file, which the code of the literals of this tree also lives in,
+        // unlike the code of the other files it evaluates
+        while let Some(child) =
+            std::iter::successors(zip.child(0), |c| c.next_sibling()).find(|c| {
+                c.origin().file() == self.origin.file() && c.host_byte_range().contains(&offset)
+            })
+        {
+            zip = child;
+        }
+        Some(zip)
+    }
+
+    /// Returns the underlying tree-sitter tree for this tree's own code, so it can be used with other tree-sitter based tooling without parsing the code again.
+    ///
+    /// The returned tree does not know about polyglot eval calls: the subtrees they lead to are separate trees,
+    /// which can be reached through a PolyglotZipper and its own `ts_node` method.
+    pub fn ts_tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Returns an iterator over the direct subtrees of this tree, in document order,
+    /// each paired with the byte range of the polyglot eval call it was built from.
+    ///
+    /// Like `EvalSite::span`, the range is given in the coordinates of the file the call physically lives in.
+    /// Subtrees of subtrees are not included, but can be reached by calling this method on the yielded trees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "let x = 1;\nPolyglot.eval(\"python\", 'print(42)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let subtrees: Vec<_> = tree.subtrees().collect();
+    ///
+    /// assert_eq!(subtrees.len(), 1);
+    /// assert_eq!(&code[subtrees[0].0.clone()], "Polyglot.eval(\"python\", 'print(42)')");
+    /// assert_eq!(subtrees[0].1.ts_tree().root_node().to_sexp(), "(module (expression_statement (call function: (identifier) arguments: (argument_list (integer)))))");
+    /// ```
+    pub fn subtrees(&self) -> impl Iterator<Item = (Range<usize>, &PolyglotTree)> {
+        let mut result = Vec::with_capacity(self.node_to_subtrees_map.len());
+        if !self.node_to_subtrees_map.is_empty() {
+            let mut cursor = self.root_node().walk();
+            'walk: loop {
+                let node = cursor.node();
+                if let Some(subtree) = self.node_to_subtrees_map.get(&node.id()) {
+                    result.push((self.host_range(node), subtree.as_ref()));
+                } else if cursor.goto_first_child() {
+                    continue;
+                }
+
+                while !cursor.goto_next_sibling() {
+                    if !cursor.goto_parent() {
+                        break 'walk;
+                    }
+                }
+            }
+        }
+        result.into_iter()
+    }
+
+    /// Returns the languages found anywhere in the tree, each with the number of sites written in it:
+    /// the tree itself counts as one site of its language, and every subtree however deep counts as one site of its own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(1)')\nPolyglot.eval(\"python\", 'print(2)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let languages = tree.languages();
+    ///
+    /// assert_eq!(languages.len(), 2);
+    /// assert_eq!(languages[&Language::JavaScript], 1);
+    /// assert_eq!(languages[&Language::Python], 2);
+    /// ```
+    pub fn languages(&self) -> HashMap<Language, usize> {
+        let mut result = HashMap::new();
+        self.count_languages(&mut result);
+        result
+    }
+
+    fn count_languages(&self, counts: &mut HashMap<Language, usize>) {
+        *counts.entry(self.language).or_insert(0) += 1;
+        for subtree in self.node_to_subtrees_map.values() {
+            subtree.count_languages(counts);
+        }
+    }
+
+    /// Returns an iterator over the events of a depth-first walk of the tree, starting from its root.
+    ///
+    /// Each node produces an `Enter` event, followed by the events of its descendants and a `Leave` event.
+    /// When the walk crosses a polyglot eval call into its subtree, the subtree's events are surrounded by `BoundaryEnter` and `BoundaryLeave` events.
+    /// Apart from those crossings, walking the tree does not allocate, which makes this well suited to single-pass streaming analyses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::polyglot_walker::WalkEvent;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'print(42)')", Language::JavaScript).unwrap();
+    ///
+    /// let mut depth = 0;
+    /// let mut languages = Vec::new();
+    /// for event in tree.walk_events() {
+    ///     match event {
+    ///         WalkEvent::Enter(_) => depth += 1,
+    ///         WalkEvent::Leave(_) => depth -= 1,
+    ///         WalkEvent::BoundaryEnter(lang, _) => languages.push(lang),
+    ///         WalkEvent::BoundaryLeave => (),
+    ///     }
+    /// }
+    /// assert_eq!(depth, 0);
+    /// assert_eq!(languages, vec![Language::Python]);
+    /// ```
+    pub fn walk_events(&self) -> polyglot_walker::WalkEvents<'_> {
+        polyglot_walker::WalkEvents::from(self)
+    }
+
+    /// Returns an iterator over every node of the tree and of its subtrees however deep they are, in document order,
+    /// each along with the Language it is written in and the Origin of its code.
+    ///
+    /// This visits the same nodes as the `Enter` events of `walk_events`, and is the simplest way to build indexes or statistics in a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'print(42)')", Language::JavaScript).unwrap();
+    ///
+    /// let mut identifiers: HashMap<Language, Vec<&str>> = HashMap::new();
+    /// for (node, language, _origin) in tree.nodes() {
+    ///     if node.kind() == "identifier" {
+    ///         identifiers.entry(language).or_default().push(node.code());
+    ///     }
+    /// }
+    /// assert_eq!(identifiers[&Language::Python], vec!["print"]);
+    /// assert!(!identifiers.contains_key(&Language::JavaScript));
+    /// ```
+    pub fn nodes(&self) -> polyglot_walker::Nodes<'_> {
+        polyglot_walker::Nodes::from(self)
+    }
+
+    /// Internal function to get the Origin of a subtree whose code is the `len` bytes starting at `offset` in this tree's code.
+    fn literal_origin(&self, offset: usize, len: usize) -> Origin {
+        let (file, start) = match &self.origin {
+            Origin::Snippet => (None, offset),
+            Origin::File(path) => (Some(path.clone()), offset),
+            Origin::Literal { file, range } => (file.clone(), range.start + offset),
+        };
+        Origin::Literal {
+            file,
+            range: start..start + len,
+        }
+    }
+
+    /// Internal function to get a copy of a tree built for an inline snippet, moved to the position of an identical snippet with the given Origin.
+    ///
+    /// The syntax trees are shared with the original tree, and only the coordinates that depend on the position are changed,
+    /// including those of the snippets nested in it.
+    fn relocated(&self, origin: Origin) -> PolyglotTree {
+        let start = |origin: &Origin| match origin {
+            Origin::Literal { range, .. } => range.start,
+            _ => 0,
+        };
+        let (old_start, new_start) = (start(&self.origin), start(&origin));
+        let shift = |range: &Range<usize>| {
+            range.start - old_start + new_start..range.end - old_start + new_start
+        };
+
+        let mut result = self.clone();
+        for node in result.polyglot_nodes.values_mut() {
+            if let PolyglotNode::EvalCall { span, .. } = node {
+                *span = shift(span);
+            }
+        }
+        for subtree in result.node_to_subtrees_map.values_mut() {
+            if let Origin::Literal { range, .. } = &subtree.origin {
+                let nested = Origin::Literal {
+                    file: origin.file().cloned(),
+                    range: shift(range),
+                };
+                *subtree = Arc::new(subtree.relocated(nested));
+            }
+        }
+        result.origin = origin;
+        result
+    }
+
+    /// Internal function to get a node's byte range in the coordinates of the file its code physically lives in.
+    fn host_range(&self, node: Node) -> Range<usize> {
+        let range = node.byte_range();
+        match &self.origin {
+            Origin::Literal { range: literal, .. } => {
+                range.start + literal.start..range.end + literal.start
+            }
+            _ => range,
+        }
+    }
+
     /// Internal function to get a node's source code.
     fn node_to_code(&self, node: Node) -> &str {
         &self.code[node.start_byte()..node.end_byte()]
@@ -210,16 +725,155 @@ impl PolyglotTree {
         self.tree.root_node()
     }
 
-    /// Internal function to start building the polyglot mappings and subtrees.
-    fn build_polyglot_tree(&self, node_tree_map: &mut HashMap<usize, PolyglotTree>) {
+    /// Internal function to build the polyglot mappings and subtrees, returned as the subtree map and the polyglot node map.
+    fn build_polyglot_tree(
+        &self,
+        ctx: &BuildContext,
+    ) -> (NodeMap<Arc<PolyglotTree>>, NodeMap<PolyglotNode>) {
+        let (mut node_tree_map, node_map, jobs) = self.collect_polyglot_links(ctx);
+        self.build_subtrees(&mut node_tree_map, jobs, ctx);
+        (node_tree_map, node_map)
+    }
+
+    /// Internal function to build the polyglot mappings, along with the jobs building the subtrees that are not built yet.
+    fn collect_polyglot_links(
+        &self,
+        ctx: &BuildContext,
+    ) -> (
+        NodeMap<Arc<PolyglotTree>>,
+        NodeMap<PolyglotNode>,
+        Vec<SubtreeJob>,
+    ) {
+        // when the tree is rebuilt after an edit, its previous version tells how many eval calls to expect
+        let expected = ctx.reuse.map_or(0, HashMap::len);
+        let mut node_tree_map = node_map::with_capacity(expected);
+        let mut node_map = node_map::with_capacity(expected);
+        let mut jobs = Vec::new();
         let root = self.tree.root_node();
-        self.build_polyglot_links(node_tree_map, root); // we get the root, and then call the recursive function
+        self.build_polyglot_links(&mut node_tree_map, &mut node_map, &mut jobs, ctx, root);
+        (node_tree_map, node_map, jobs)
     }
 
-    /// Internal recursive function that iterates over the nodes in the tree, and builds all subtrees as well as the polyglot link map.
-    fn build_polyglot_links(&self, node_tree_map: &mut HashMap<usize, PolyglotTree>, node: Node) {
-        if self.is_polyglot_eval_call(node) {
-            if !self.make_subtree(node_tree_map, node) {
+    /// Internal function to run the given jobs, and add the subtrees they build to the map.
+    fn build_subtrees(
+        &self,
+        node_tree_map: &mut NodeMap<Arc<PolyglotTree>>,
+        jobs: Vec<SubtreeJob>,
+        ctx: &BuildContext,
+    ) {
+        if ctx.stream.is_some() {
+            let nested = ctx.nested();
+            for job in jobs.into_iter().take_while(|_| !ctx.options.is_cancelled()) {
+                // streamed subtrees are dropped as soon as they, and their own subtrees, were processed
+                if self
+                    .make_subtree_impl(job.position, job.target, &nested)
+                    .is_none()
+                    && !ctx.options.is_cancelled()
+                {
+                    eprintln!(
+                        "Warning: unable to make subtree for polyglot call at position {}",
+                        job.position
+                    );
+                }
+            }
+            return;
+        }
+
+        // the subtrees are only added once they are all built, so the map is sized for all of them at once
+        node_tree_map.reserve(jobs.len());
+        let threads = ctx.options.threads.unwrap_or(1);
+        let built = if ctx.depth == 0 && threads > 1 && jobs.len() > 1 {
+            self.make_subtrees_parallel(jobs, ctx, threads)
+        } else {
+            let nested = ctx.nested();
+            jobs.into_iter()
+                .take_while(|_| !ctx.options.is_cancelled())
+                .map(|job| {
+                    (
+                        job.id,
+                        job.position,
+                        self.make_subtree_impl(job.position, job.target, &nested),
+                    )
+                })
+                .collect()
+        };
+        for (id, position, subtree) in built {
+            match subtree {
+                Some(subtree) => {
+                    node_tree_map.insert(id, subtree);
+                }
+                None if ctx.options.is_cancelled() => {}
+                // If building the subtree failed,
+                // we want to soft fail (eg. not panic) to avoid interrupting the tree building.
+                // Eventually, this should be made into a proper Error,
+                // but for now for debugging purposes it just prints a warning.
+                None => eprintln!(
+                    "Warning: unable to make subtree for polyglot call at position {position}"
+                ),
+            }
+        }
+    }
+
+    /// Internal function to build the given subtrees on up to `threads` threads, each thread taking the next job as soon as it is done with one.
+    fn make_subtrees_parallel(
+        &self,
+        jobs: Vec<SubtreeJob>,
+        ctx: &BuildContext,
+        threads: usize,
+    ) -> Vec<(usize, Point, Option<Arc<PolyglotTree>>)> {
+        let (options, depth, snippets, files) = (ctx.options, ctx.depth, &ctx.snippets, &ctx.files);
+        let workers = threads.min(jobs.len());
+        let queue = Mutex::new(jobs.into_iter());
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        // contexts cannot be shared between threads, so each thread builds with its own, sharing the snippets
+                        let mut ctx = BuildContext::sharing(options, depth + 1, snippets.clone());
+                        ctx.files = files.clone();
+                        let mut built = Vec::new();
+                        loop {
+                            let job = sharded_map::lock(&queue).next();
+                            let Some(job) = job else {
+                                return built;
+                            };
+                            if options.is_cancelled() {
+                                return built;
+                            }
+                            built.push((
+                                job.id,
+                                job.position,
+                                self.make_subtree_impl(job.position, job.target, &ctx),
+                            ));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("building a subtree panicked"))
+                .collect()
+        })
+    }
+
+    /// Internal function that iterates over the nodes in the tree, and builds the polyglot link map as well as the jobs building all subtrees.
+    /// Import and export calls are left for `binding_nodes`, as many uses of a tree never need them.
+    ///
+    /// The traversal uses a cursor rather than recursion, so that very deep or very wide trees cannot overflow the stack.
+    fn build_polyglot_links(
+        &self,
+        node_tree_map: &mut NodeMap<Arc<PolyglotTree>>,
+        node_map: &mut NodeMap<PolyglotNode>,
+        jobs: &mut Vec<SubtreeJob>,
+        ctx: &BuildContext,
+        node: Node,
+    ) {
+        let mut cursor = node.walk();
+        loop {
+            let node = cursor.node();
+            let is_eval = self.is_polyglot_eval_call(node);
+            if is_eval && !self.make_subtree(node_tree_map, node_map, jobs, ctx, node) {
                 // If building the subtree failed,
                 // we want to soft fail (eg. not panic) to avoid interrupting the tree building.
                 // Eventually, this should be made into a proper Error,
@@ -229,16 +883,55 @@ impl PolyglotTree {
                     node.start_position()
                 )
             }
-        } else {
-            if let Some(child) = node.child(0) {
-                self.build_polyglot_links(node_tree_map, child)
-            };
-            if let Some(sibling) = node.next_sibling() {
-                self.build_polyglot_links(node_tree_map, sibling)
-            };
+
+            // the arguments of eval calls are not visited
+            if !is_eval && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return;
+                }
+            }
         }
     }
 
+    /// Internal function returning the meaning of a node if it is a polyglot eval, import or export call whose arguments could be understood.
+    fn polyglot_node_at(&self, node: Node) -> Option<&PolyglotNode> {
+        self.polyglot_nodes
+            .get(&node.id())
+            .or_else(|| self.binding_nodes().get(&node.id()))
+    }
+
+    /// Internal function returning the polyglot import and export calls of the tree, looking for them and reading their binding names on first use.
+    fn binding_nodes(&self) -> &NodeMap<PolyglotNode> {
+        self.bindings.get_or_init(|| {
+            let mut bindings = NodeMap::default();
+            let mut cursor = self.tree.walk();
+            loop {
+                let node = cursor.node();
+                let is_eval = self.is_polyglot_eval_call(node);
+                if self.is_polyglot_import_call(node) {
+                    let name = self.binding_name(node);
+                    bindings.insert(node.id(), PolyglotNode::ImportCall { name });
+                } else if self.is_polyglot_export_call(node) {
+                    let name = self.binding_name(node);
+                    bindings.insert(node.id(), PolyglotNode::ExportCall { name });
+                }
+
+                // the arguments of eval calls are not visited
+                if !is_eval && cursor.goto_first_child() {
+                    continue;
+                }
+                while !cursor.goto_next_sibling() {
+                    if !cursor.goto_parent() {
+                        return bindings;
+                    }
+                }
+            }
+        })
+    }
+
     fn get_polyglot_call_python(&self, node: Node) -> Option<&str> {
         let child = node.child(0)?;
         if node.kind().eq("call") && child.kind().eq("attribute") {
@@ -263,6 +956,50 @@ impl PolyglotTree {
         None
     }
 
+    /// Internal function to check whether a node's subtree contains syntax errors, including in the subtrees of polyglot eval calls it contains.
+    fn has_error_within(&self, node: Node) -> bool {
+        if node.has_error() {
+            return true;
+        }
+        if self.node_to_subtrees_map.is_empty() {
+            return false;
+        }
+
+        let mut cursor = node.walk();
+        loop {
+            if let Some(subtree) = self.node_to_subtrees_map.get(&cursor.node().id()) {
+                if subtree.has_error_within(subtree.root_node()) {
+                    return true;
+                }
+            }
+
+            if cursor.goto_first_child() || cursor.goto_next_sibling() {
+                continue;
+            }
+            loop {
+                if !cursor.goto_parent() {
+                    return false;
+                }
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Internal function to get a node's type, accounting for polyglot nodes.
+    fn polyglot_kind(&self, node: Node) -> &'static str {
+        if self.is_polyglot_eval_call(node) {
+            "polyglot_eval_call"
+        } else if self.is_polyglot_import_call(node) {
+            "polyglot_import_call"
+        } else if self.is_polyglot_export_call(node) {
+            "polyglot_export_call"
+        } else {
+            node.kind()
+        }
+    }
+
     fn is_polyglot_eval_call(&self, node: Node) -> bool {
         match self.language {
             Language::Python => {
@@ -302,121 +1039,62 @@ impl PolyglotTree {
         }
     }
 
-    fn make_subtree(&self, node_tree_map: &mut HashMap<usize, PolyglotTree>, node: Node) -> bool {
-        let subtree: PolyglotTree;
-        let result: Option<PolyglotTree> = match self.language {
-            // delegate to language specific subfunction
-            Language::Python => self.make_subtree_python(&node),
-            Language::JavaScript => self.make_subtree_js(&node),
-            Language::Java => self.make_subtree_java(&node),
-        };
-
-        subtree = match result {
-            Some(t) => t,
-            None => return false,
-        };
-
-        node_tree_map.insert(node.id(), subtree);
-
-        true // signal everything went right
-    }
-
-    fn make_subtree_python(&self, node: &Node) -> Option<PolyglotTree> {
-        let arg1 = node.child(1)?.child(1)?.child(0)?;
-        let arg2 = node.child(1)?.child(3)?.child(0)?;
-
-        let mut new_code: Option<String> = None;
-        let mut new_lang: Option<String> = None;
-        let mut path: Option<PathBuf> = None;
-
-        // Python polyglot calls use a single function and differentiate by argument names, which are mandatory.
-        // We need to check both arguments for each possible case, and then check again at the end we have enough information.
-        match self.node_to_code(arg1) {
-            "path" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg1.next_sibling()?.next_sibling()?));
-                path = Some(self.working_dir.clone());
-                let new_path = match PathBuf::from_str(tmp.as_str()) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        eprintln!(
-                            "Warning: could not build subtree for {} because of error {e}",
-                            tmp.as_str()
-                        );
-                        return None;
-                    }
-                };
-                path = path.map(|mut p| {
-                    p.push(new_path);
-                    p
-                });
+    fn make_subtree(
+        &self,
+        node_tree_map: &mut NodeMap<Arc<PolyglotTree>>,
+        node_map: &mut NodeMap<PolyglotNode>,
+        jobs: &mut Vec<SubtreeJob>,
+        ctx: &BuildContext,
+        node: Node,
+    ) -> bool {
+        let target = match self.eval_target(&node, ctx.options) {
+            Ok(t) => t,
+            Err(message) => {
+                eprintln!("{message}");
+                return false;
             }
+        };
 
-            "language" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg1.next_sibling()?.next_sibling()?));
-                new_lang = Some(String::from(tmp.as_str()));
-            }
+        let lang = target
+            .language
+            .as_deref()
+            .and_then(|l| util::language_string_to_enum(l).ok());
+        node_map.insert(
+            node.id(),
+            PolyglotNode::EvalCall {
+                lang,
+                payload: target.payload.clone(),
+                span: self.host_range(node),
+            },
+        );
 
-            "string" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg1.next_sibling()?.next_sibling()?));
-                new_code = Some(String::from(tmp.as_str()));
-            }
-            other => {
-                eprintln!(
-                    "Warning: unable to handle polyglot call argument {other} at position {}",
-                    arg1.start_position()
-                );
-                return None;
-            }
+        if let Some(subtree) = ctx.reuse.and_then(|reuse| reuse.get(&node.byte_range())) {
+            node_tree_map.insert(node.id(), subtree.clone());
+            return true;
         }
 
-        match self.node_to_code(arg2) {
-            "path" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg2.next_sibling()?.next_sibling()?));
-                path = Some(self.working_dir.clone());
-                let new_path = match PathBuf::from_str(tmp.as_str()) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        eprintln!(
-                            "Warning: could not build subtree for {} because of error {e}",
-                            tmp.as_str()
-                        );
-                        return None;
-                    }
-                };
-                path = path.map(|mut p| {
-                    p.push(new_path);
-                    p
-                });
-            }
-
-            "language" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg2.next_sibling()?.next_sibling()?));
-                new_lang = Some(String::from(tmp.as_str()));
-            }
-
-            "string" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg2.next_sibling()?.next_sibling()?));
-
-                new_code = Some(String::from(tmp.as_str()));
-            }
-
-            other => {
-                eprintln!(
-                    "Warning: unable to handle polyglot call argument {other} at position {}",
-                    arg2.start_position()
-                );
-                return None;
+        if let Some(lang) = lang {
+            if !ctx.options.allows(lang, ctx.depth + 1) {
+                return true; // the subtree was left out on purpose, which is not a failure
             }
         }
 
+        jobs.push(SubtreeJob {
+            id: node.id(),
+            position: node.start_position(),
+            target,
+        });
+        true // signal everything went right
+    }
+
+    fn make_subtree_impl(
+        &self,
+        position: Point,
+        target: EvalTarget,
+        ctx: &BuildContext,
+    ) -> Option<Arc<PolyglotTree>> {
         // We convert the language, if there was one
-        let new_lang = match new_lang {
+        let new_lang = match target.language {
             Some(s) => match util::language_string_to_enum(s.as_str()) {
                 Ok(l) => l,
                 Err(e) => {
@@ -427,117 +1105,330 @@ impl PolyglotTree {
             None => {
                 eprintln!(
                     "Warning: no language argument provided for polyglot call at position {}",
-                    node.start_position()
+                    position
                 );
                 return None;
             }
         };
 
-        let subtree = match new_code {
-            Some(c) => Self::from_directory(c, new_lang, self.working_dir.clone())?,
-            None => Self::from_path(
-                // No raw code, check for a path
-                match path {
-                    Some(p) => p,
-                    None => {
-                        // No path either -> we cant build the tree
-                        eprintln!("Warning:: no path or string argument provided to Python polyglot call at position {}", node.start_position());
-                        return None;
+        match target.payload {
+            Some(EvalPayload::Inline { code, offset }) => {
+                let origin = self.literal_origin(offset, code.len());
+                let code = match self.code.slice(offset..offset + code.len()) {
+                    Some(view) if *view == *code => view,
+                    _ => SourceText::from(code), // the snippet is not written as is in the host
+                };
+                if ctx.stream.is_some() {
+                    // streamed snippets are not kept, so they cannot be shared
+                    let tree =
+                        Self::from_directory(code, new_lang, self.working_dir.clone(), origin, ctx);
+                    return tree.map(Arc::new);
+                }
+                let key = (new_lang, ctx.depth, self.working_dir.clone(), code);
+                let memoized = ctx.snippets.shard(&key).get(&key).cloned();
+                if let Some(tree) = memoized {
+                    // an identical snippet was already built, only its position may differ
+                    if tree.origin == origin {
+                        return Some(tree.clone());
                     }
-                },
-                new_lang,
-            )?,
+                    return Some(Arc::new(tree.relocated(origin)));
+                }
+
+                let tree = Arc::new(Self::from_directory(
+                    key.3.clone(),
+                    new_lang,
+                    key.2.clone(),
+                    origin,
+                    ctx,
+                )?);
+                ctx.snippets.shard(&key).insert(key, tree.clone());
+                Some(tree)
+            }
+            Some(EvalPayload::File(path)) if ctx.is_building(&path) => {
+                eprintln!(
+                    "Warning: not building file {} evaluated at position {position} again, as it evaluates itself",
+                    path.display()
+                );
+                None
+            }
+            Some(EvalPayload::File(path)) => match &ctx.options.file_cache {
+                Some(cache) if ctx.stream.is_none() => {
+                    cache.get_or_build(&path.clone(), new_lang, ctx.depth, ctx.options, |code| {
+                        Self::from_file_code(code, path, new_lang, ctx)
+                    })
+                }
+                _ => Self::from_path_impl(path, new_lang, ctx).map(Arc::new),
+            },
+            None => {
+                // No raw code nor path -> we cant build the tree
+                eprintln!(
+                    "Warning:: no path or string argument provided to polyglot call at position {}",
+                    position
+                );
+                None
+            }
+        }
+    }
+
+    /// Internal function to read what a polyglot eval call evaluates from its arguments, without building anything.
+    /// Returns an error message if the arguments could not be read at all.
+    /// Paths of evaluated files are resolved as set by the given options,
+    /// and the language of an evaluated file is detected from the file itself if the call does not give it.
+    fn eval_target(&self, node: &Node, options: &BuildOptions) -> Result<EvalTarget, String> {
+        let target = match self.language {
+            // delegate to language specific subfunction
+            Language::Python => self.eval_target_python(node, options),
+            Language::JavaScript => self.eval_target_js(node, options),
+            Language::Java => self.eval_target_java(node),
         };
-        Some(subtree)
+        let mut target = target.unwrap_or_else(|| {
+            Err(format!(
+                "Warning: unable to read the arguments of polyglot call at position {}",
+                node.start_position()
+            ))
+        })?;
+        if let (None, Some(EvalPayload::File(path))) = (&target.language, &target.payload) {
+            let language = util::language_from_path(path).or_else(|| {
+                let code = options.provider().read(path).ok()?;
+                util::language_from_shebang(&code)
+            });
+            target.language = language.map(|l| util::language_enum_to_string(&l).to_string());
+        }
+        Ok(target)
     }
 
-    fn make_subtree_js(&self, node: &Node) -> Option<PolyglotTree> {
-        let call_type = node.child(0)?.child(2)?; // function name
-        let arg1 = node.child(1)?.child(1)?; // language
-        let arg2 = node.child(1)?.child(3)?; // code
+    /// Internal function to get the name of the binding a polyglot import or export call refers to.
+    ///
+    /// Returns None if the node is not such a call, or if the name is not given as a string literal.
+    fn binding_name(&self, node: Node) -> Option<String> {
+        let literal = self.binding_name_node(node)?;
+        Some(util::strip_quotes(self.node_to_code(literal)).to_string())
+    }
 
-        // JavaScript uses a different function for evaluating raw code and files, so we have two cases
-        match self.node_to_code(call_type) {
-            "eval" => {
-                // Arguments are positional, and always at the same spot
-                let tmp_lang = util::strip_quotes(self.node_to_code(arg1));
-                let tmp_code = util::strip_quotes(self.node_to_code(arg2));
-
-                let new_lang = match util::language_string_to_enum(tmp_lang.as_str()) {
-                    Ok(l) => l,
-                    Err(e) => {
-                        eprintln!(
-                            "Could not convert argument {} to language due to error: {e}",
-                            tmp_lang.as_str()
-                        );
-                        return None;
+    /// Internal function returning the string literal naming the binding a polyglot import or export call refers to.
+    fn binding_name_node<'t>(&self, node: Node<'t>) -> Option<Node<'t>> {
+        if !self.is_polyglot_import_call(node) && !self.is_polyglot_export_call(node) {
+            return None;
+        }
+        let args = node.child_by_field_name("arguments")?;
+        let mut cursor = args.walk();
+        let mut positional = None;
+
+        for arg in args.named_children(&mut cursor) {
+            match arg.kind() {
+                // Python bindings are usually named with a keyword argument, which can appear anywhere
+                "keyword_argument" => {
+                    let name = arg.child_by_field_name("name")?;
+                    let value = arg.child_by_field_name("value")?;
+                    if self.node_to_code(name) == "name" && value.kind() == "string" {
+                        return Some(value);
                     }
-                };
+                }
+                "string" | "string_literal" if positional.is_none() => {
+                    positional = Some(arg);
+                }
+                _ => {}
+            }
+        }
+        positional
+    }
 
-                let new_code = String::from(tmp_code.as_str());
-                Self::from_directory(new_code, new_lang, self.working_dir.clone())
+    /// Internal function to resolve a path argument of a polyglot call, relative to the working directory by default,
+    /// or as set by `BuildOptions::path_resolution`.
+    fn resolve_path(&self, tmp: &str, options: &BuildOptions) -> Result<PathBuf, String> {
+        let new_path = match PathBuf::from_str(tmp) {
+            Ok(p) => p,
+            Err(e) => {
+                return Err(format!(
+                    "Warning: could not build subtree for {tmp} because of error {e}"
+                ))
             }
+        };
+        Ok(options.resolve_path(&self.working_dir, &new_path))
+    }
 
-            "evalFile" => {
-                let tmp_lang = util::strip_quotes(self.node_to_code(arg1));
-
-                let new_lang = match util::language_string_to_enum(tmp_lang.as_str()) {
-                    Ok(l) => l,
-                    Err(e) => {
-                        eprintln!(
-                            "Could not convert argument {} to language due to error: {e}",
-                            tmp_lang.as_str()
-                        );
-                        return None;
-                    }
-                };
+    fn eval_target_python(
+        &self,
+        node: &Node,
+        options: &BuildOptions,
+    ) -> Option<Result<EvalTarget, String>> {
+        let args = node.child(1)?;
+        let mut cursor = args.walk();
+        let args: Vec<Node> = args
+            .named_children(&mut cursor)
+            .filter(|arg| arg.kind() != "comment")
+            .map(|arg| arg.child(0))
+            .collect::<Option<_>>()?;
 
-                let tmp_path = util::strip_quotes(self.node_to_code(arg2));
+        let mut target = EvalTarget {
+            language: None,
+            payload: None,
+        };
 
-                let mut path = self.working_dir.clone();
+        // Python polyglot calls use a single function and differentiate by argument names, which are mandatory.
+        // We need to check every argument for each possible case, and then check again at the end we have enough information.
+        // The language can be left out when evaluating a file, in which case it is detected from the file.
+        for arg in args {
+            let value = arg.next_sibling()?.next_sibling()?;
+            let tmp = util::strip_quotes(self.node_to_code(value));
 
-                let new_path = match PathBuf::from_str(tmp_path.as_str()) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        eprintln!(
-                            "Warning: could not build subtree for {} because of error {e}",
-                            tmp_path.as_str()
-                        );
-                        return None;
+            match self.node_to_code(arg) {
+                "path" => {
+                    // raw code takes precedence over a path
+                    if target.payload.is_none() {
+                        target.payload = match self.resolve_path(tmp, options) {
+                            Ok(p) => Some(EvalPayload::File(p)),
+                            Err(e) => return Some(Err(e)),
+                        };
                     }
-                };
+                }
 
-                path.push(new_path);
+                "language" => {
+                    target.language = Some(tmp.to_string());
+                }
 
-                Self::from_path(path, new_lang)
+                "string" => {
+                    target.payload = Some(EvalPayload::Inline {
+                        code: tmp.to_string(),
+                        offset: value.start_byte() + 1, // skip the opening quote
+                    });
+                }
+
+                other => {
+                    return Some(Err(format!(
+                        "Warning: unable to handle polyglot call argument {other} at position {}",
+                        arg.start_position()
+                    )));
+                }
             }
+        }
+
+        Some(Ok(target))
+    }
+
+    fn eval_target_js(
+        &self,
+        node: &Node,
+        options: &BuildOptions,
+    ) -> Option<Result<EvalTarget, String>> {
+        let call_type = node.child(0)?.child(2)?; // function name
+        let arg1 = node.child(1)?.child(1)?; // language
+        let arg2 = node.child(1)?.child(3)?; // code
+
+        // Arguments are positional, and always at the same spot
+        let language = Some(util::strip_quotes(self.node_to_code(arg1)).to_string());
+        let tmp = util::strip_quotes(self.node_to_code(arg2));
+
+        // JavaScript uses a different function for evaluating raw code and files, so we have two cases
+        let payload = match self.node_to_code(call_type) {
+            "eval" => EvalPayload::Inline {
+                code: tmp.to_string(),
+                offset: arg2.start_byte() + 1,
+            },
+
+            "evalFile" => match self.resolve_path(tmp, options) {
+                Ok(p) => EvalPayload::File(p),
+                Err(e) => return Some(Err(e)),
+            },
 
             other => {
-                eprintln!(
+                return Some(Err(format!(
                     "Warning: unable to identify polyglot function call {other} at position {}",
                     node.start_position()
-                );
-                None
+                )));
             }
-        }
+        };
+
+        Some(Ok(EvalTarget {
+            language,
+            payload: Some(payload),
+        }))
     }
 
-    fn make_subtree_java(&self, node: &Node) -> Option<PolyglotTree> {
+    fn eval_target_java(&self, node: &Node) -> Option<Result<EvalTarget, String>> {
         // Java uses positional arguments, so they will always be accessible with the same route.
         let arg1 = node.child(3)?.child(1)?; // language
         let arg2 = node.child(3)?.child(3)?; // code
 
-        let s = util::strip_quotes(self.node_to_code(arg1));
+        Some(Ok(EvalTarget {
+            language: Some(util::strip_quotes(self.node_to_code(arg1)).to_string()),
+            payload: Some(EvalPayload::Inline {
+                code: util::strip_quotes(self.node_to_code(arg2)).to_string(),
+                offset: arg2.start_byte() + 1,
+            }),
+        }))
+    }
+}
 
-        let new_lang = match util::language_string_to_enum(&s) {
-            Ok(l) => l,
-            Err(e) => {
-                eprintln!("Could not convert argument {s} to language due to error: {e}",);
-                return None;
-            }
-        };
+/// Summarizes the structure of the tree: its language, origin and the subtrees of its polyglot eval calls, recursively.
+impl fmt::Debug for PolyglotTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolyglotTree")
+            .field("language", &self.language)
+            .field("origin", &self.origin)
+            .field("working_dir", &self.working_dir)
+            .field("root", &self.root_node().kind())
+            .field("has_error", &self.root_node().has_error())
+            .field(
+                "subtrees",
+                &self.subtrees().map(|(_, t)| t).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
 
-        let new_code = util::strip_quotes(self.node_to_code(arg2));
-        Self::from_directory(new_code, new_lang, self.working_dir.clone())
+/// Formats the tree as a compact s-expression of its named nodes, without field names.
+/// The subtree of a polyglot eval call is written inside the call's node, prefixed by its language.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x')", Language::JavaScript).unwrap();
+/// assert_eq!(
+///     tree.to_string(),
+///     "(program (expression_statement (polyglot_eval_call python: (module (expression_statement (identifier))))))"
+/// );
+/// ```
+impl fmt::Display for PolyglotTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_sexp(f, self.root_node())
     }
 }
+
+impl PolyglotTree {
+    /// Internal function writing the s-expression of a node and its named descendants for the Display implementation.
+    fn write_sexp(&self, f: &mut fmt::Formatter<'_>, node: Node) -> fmt::Result {
+        if node.is_missing() {
+            return write!(f, "(MISSING {})", node.kind());
+        }
+        write!(f, "({}", self.polyglot_kind(node))?;
+
+        if let Some(subtree) = self.node_to_subtrees_map.get(&node.id()) {
+            write!(f, " {}: ", util::language_enum_to_string(&subtree.language))?;
+            subtree.write_sexp(f, subtree.root_node())?;
+        } else if !self.is_polyglot_eval_call(node) {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                f.write_str(" ")?;
+                self.write_sexp(f, child)?;
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+/// Internal description of a subtree to build for the polyglot eval call of the given node id, located at `position`.
+struct SubtreeJob {
+    id: usize,
+    position: Point,
+    target: EvalTarget,
+}
+
+/// Internal description of what a polyglot eval call evaluates, as read from its arguments.
+struct EvalTarget {
+    language: Option<String>,
+    payload: Option<EvalPayload>,
+}
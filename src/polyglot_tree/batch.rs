@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::build_options::BuildOptions;
+use super::call_sites::EvalSite;
+use super::file_cache::FileCache;
+use super::scheduler::{self, BuiltFile};
+use super::util::Language;
+use super::PolyglotTree;
+
+/// The trees built by `PolyglotTree::from_paths`, along with what went wrong while building them.
+pub struct BatchBuild {
+    /// The tree built for each input, in the order of the inputs, or None if the file could not be read or parsed,
+    /// or was skipped as allowed by `BuildOptions::polyglot_free_files`.
+    pub trees: Vec<(PathBuf, Option<PolyglotTree>)>,
+    /// The problems found in the built trees, grouped by input in the order of the inputs.
+    pub diagnostics: Vec<BatchDiagnostic>,
+}
+
+/// A problem found while building one of the inputs of `PolyglotTree::from_paths`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchDiagnostic {
+    /// The input the problem was found in.
+    pub path: PathBuf,
+    pub kind: BatchDiagnosticKind,
+}
+
+/// The kinds of problems reported by `PolyglotTree::from_paths`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchDiagnosticKind {
+    /// The file could not be read or parsed at all.
+    Unbuilt,
+    /// The tree, or one of its subtrees, contains syntax errors.
+    SyntaxErrors,
+    /// No subtree could be built for the given polyglot eval call, though the BuildOptions allowed it.
+    MissingSubtree(EvalSite),
+    /// The file evaluates itself through the files of the given cycle, which starts and ends with it,
+    /// the evaluation closing the cycle being left without a subtree.
+    /// Only reported by `PolyglotProject`, for the first file of the cycle in alphabetical order.
+    Cycle(Vec<PathBuf>),
+}
+
+impl PolyglotTree {
+    /// Builds a tree for each of the given files, written in the given Language.
+    ///
+    /// This is faster than calling `from_path` in a loop: parsers are reused across files,
+    /// files evaluated by several inputs are only parsed once, and inputs are built in parallel when `BuildOptions::threads` allows it.
+    /// Files are scheduled along with the files they evaluate: an evaluated file is built before the files evaluating it,
+    /// and files that do not depend on each other are built at the same time.
+    /// Problems found in the trees are reported as diagnostics rather than only printed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::batch::BatchDiagnosticKind;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let inputs = vec![
+    ///     (PathBuf::from("TestSamples/test_pyprint_file.js"), Language::JavaScript),
+    ///     (PathBuf::from("TestSamples/export_x.py"), Language::Python),
+    ///     (PathBuf::from("TestSamples/missing.py"), Language::Python),
+    /// ];
+    /// let batch = PolyglotTree::from_paths(inputs);
+    ///
+    /// assert_eq!(batch.trees.len(), 3);
+    /// assert!(batch.trees[0].1.is_some());
+    /// assert!(batch.trees[2].1.is_none());
+    /// assert_eq!(batch.diagnostics.len(), 1);
+    /// assert_eq!(batch.diagnostics[0].kind, BatchDiagnosticKind::Unbuilt);
+    /// ```
+    pub fn from_paths(inputs: impl IntoIterator<Item = (PathBuf, Language)>) -> BatchBuild {
+        Self::from_paths_with_options(inputs, &BuildOptions::default())
+    }
+
+    /// Same as `from_paths`, except that trees are built as allowed by the given BuildOptions.
+    ///
+    /// If the options have no FileCache, one is created for the batch. The threads set in the options are used to build
+    /// several files at once, each file being built on a single thread.
+    pub fn from_paths_with_options(
+        inputs: impl IntoIterator<Item = (PathBuf, Language)>,
+        options: &BuildOptions,
+    ) -> BatchBuild {
+        let mut options = options.clone();
+        let threads = options.threads.take().unwrap_or(1).max(1);
+        if options.file_cache.is_none() {
+            options.file_cache = Some(Arc::new(FileCache::new()));
+        }
+
+        let inputs: Vec<(PathBuf, Language)> = inputs.into_iter().collect();
+        let paths: Vec<PathBuf> = inputs.iter().map(|(path, _)| path.clone()).collect();
+        let built = scheduler::build_files(inputs, &options, threads);
+
+        let mut trees = Vec::with_capacity(built.len());
+        let mut diagnostics = Vec::new();
+        for (path, built) in paths.into_iter().zip(built) {
+            let diagnostic = |kind| BatchDiagnostic {
+                path: path.clone(),
+                kind,
+            };
+            let tree = match built {
+                BuiltFile::Tree(tree) => *tree,
+                BuiltFile::Unbuilt => {
+                    diagnostics.push(diagnostic(BatchDiagnosticKind::Unbuilt));
+                    trees.push((path, None));
+                    continue;
+                }
+                BuiltFile::Skipped => {
+                    trees.push((path, None));
+                    continue;
+                }
+            };
+            if tree.has_error_within(tree.root_node()) {
+                diagnostics.push(diagnostic(BatchDiagnosticKind::SyntaxErrors));
+            }
+            for site in tree.eval_sites() {
+                let allowed = site
+                    .guest_language
+                    .is_some_and(|lang| options.allows(lang, site.id.paths().len()));
+                if allowed && !site.has_subtree {
+                    diagnostics.push(diagnostic(BatchDiagnosticKind::MissingSubtree(site)));
+                }
+            }
+            trees.push((path, Some(tree)));
+        }
+
+        BatchBuild { trees, diagnostics }
+    }
+}
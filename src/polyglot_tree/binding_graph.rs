@@ -0,0 +1,180 @@
+use super::call_sites::{BindingDirection, BindingSite};
+use super::node_id::NodeId;
+use super::PolyglotTree;
+
+/// How the trees of the two calls linked by a BindingEdge are related.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingScope {
+    /// Both calls belong to the same tree.
+    SameTree,
+    /// The import call belongs to code evaluated, directly or not, by the tree of the export call.
+    HostToGuest,
+    /// The export call belongs to code evaluated, directly or not, by the tree of the import call.
+    GuestToHost,
+    /// The calls belong to different trees, neither of them evaluating the other, such as two snippets evaluated by the same host.
+    Unrelated,
+}
+
+/// Why a BindingEdge links an export call to an import call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EdgeProvenance {
+    /// How the trees of the two calls are related.
+    pub scope: BindingScope,
+    /// Whether the calls are written in different languages.
+    pub cross_language: bool,
+    /// Whether the export call comes before the import call in document order, the calls of a subtree coming right after its eval call,
+    /// which is the order they run in when the code has no loops or functions called later on.
+    pub export_first: bool,
+}
+
+/// A link from an export call to an import call of the same binding, given by their indices in the sites of the BindingGraph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingEdge {
+    pub export: usize,
+    pub import: usize,
+    /// The name of the binding.
+    pub name: String,
+    pub provenance: EdgeProvenance,
+}
+
+/// The polyglot import and export calls of a tree and of its subtrees, as returned by `PolyglotTree::binding_graph`,
+/// where each export call is linked to the import calls reading the binding it writes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BindingGraph {
+    /// The import and export calls, in the order of `PolyglotTree::bindings`.
+    pub sites: Vec<BindingSite>,
+    /// The links between the calls, grouped by export call in the order of the sites, then by import call in that order.
+    pub edges: Vec<BindingEdge>,
+}
+
+impl BindingGraph {
+    /// Returns the edges going from the export call at the given index of the sites.
+    pub fn imports_of(&self, export: usize) -> impl Iterator<Item = &BindingEdge> {
+        self.edges.iter().filter(move |edge| edge.export == export)
+    }
+
+    /// Returns the edges going to the import call at the given index of the sites.
+    pub fn exports_of(&self, import: usize) -> impl Iterator<Item = &BindingEdge> {
+        self.edges.iter().filter(move |edge| edge.import == import)
+    }
+
+    /// Returns the export calls writing a binding that no import call of the graph reads, as described by `unmatched_bindings`.
+    pub fn unused_exports(&self) -> Vec<&BindingSite> {
+        unmatched_bindings(&self.sites, BindingDirection::Export)
+    }
+
+    /// Returns the import calls reading a binding that no export call of the graph writes, as described by `unmatched_bindings`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.export('x', 1)\nPolyglot.import('y')\nPolyglot.eval('python', 'polyglot.import_value(name=\"x\")')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let graph = tree.binding_graph();
+    ///
+    /// let unresolved = graph.unresolved_imports();
+    /// assert_eq!(unresolved.len(), 1);
+    /// assert_eq!(unresolved[0].name.as_deref(), Some("y"));
+    /// assert!(graph.unused_exports().is_empty());
+    /// ```
+    pub fn unresolved_imports(&self) -> Vec<&BindingSite> {
+        unmatched_bindings(&self.sites, BindingDirection::Import)
+    }
+}
+
+/// Returns the calls of the given direction among `sites` whose binding no call of the other direction uses:
+/// the export calls no import call reads from, or the import calls no export call writes to.
+///
+/// Calls whose binding name is not given as a string literal are left out. As such a call of the other direction
+/// could use any binding, no call is returned when there is one.
+pub fn unmatched_bindings(sites: &[BindingSite], direction: BindingDirection) -> Vec<&BindingSite> {
+    let others: Vec<&BindingSite> = sites
+        .iter()
+        .filter(|site| site.direction != direction)
+        .collect();
+    if others.iter().any(|site| site.name.is_none()) {
+        return Vec::new();
+    }
+    sites
+        .iter()
+        .filter(|site| site.direction == direction && site.name.is_some())
+        .filter(|site| !others.iter().any(|other| other.name == site.name))
+        .collect()
+}
+
+impl PolyglotTree {
+    /// Returns the BindingGraph of the tree and of its subtrees, linking every export call whose binding name is given as a string literal
+    /// to the import calls of the same name, whatever their language and the tree they belong to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::binding_graph::BindingScope;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\npolyglot.export_value(name=\"x\", value=42)\npolyglot.eval(language=\"js\", string=\"Polyglot.import('x')\")\npolyglot.import_value(name=\"x\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let graph = tree.binding_graph();
+    ///
+    /// assert_eq!(graph.sites.len(), 3);
+    /// assert_eq!(graph.edges.len(), 2);
+    ///
+    /// let guest = &graph.edges[0];
+    /// assert_eq!((guest.export, guest.import), (0, 1));
+    /// assert_eq!(guest.name, "x");
+    /// assert_eq!(guest.provenance.scope, BindingScope::HostToGuest);
+    /// assert!(guest.provenance.cross_language && guest.provenance.export_first);
+    ///
+    /// let host = &graph.edges[1];
+    /// assert_eq!((host.export, host.import), (0, 2));
+    /// assert_eq!(host.provenance.scope, BindingScope::SameTree);
+    /// assert!(!host.provenance.cross_language);
+    /// ```
+    pub fn binding_graph(&self) -> BindingGraph {
+        let sites = self.bindings();
+        let mut edges = Vec::new();
+        for (export, from) in sites.iter().enumerate() {
+            let Some(name) = &from.name else { continue };
+            if from.direction != BindingDirection::Export {
+                continue;
+            }
+            for (import, to) in sites.iter().enumerate() {
+                if to.direction != BindingDirection::Import || to.name.as_ref() != Some(name) {
+                    continue;
+                }
+                edges.push(BindingEdge {
+                    export,
+                    import,
+                    name: name.clone(),
+                    provenance: EdgeProvenance {
+                        scope: scope(&from.id, &to.id),
+                        cross_language: from.language != to.language,
+                        // sites are given in document order
+                        export_first: export < import,
+                    },
+                });
+            }
+        }
+        BindingGraph { sites, edges }
+    }
+}
+
+/// Internal function returning how the trees of the nodes of the given NodeIds are related.
+fn scope(export: &NodeId, import: &NodeId) -> BindingScope {
+    // the last path of a NodeId leads to the node within its tree, the previous ones to the eval calls of the trees containing it
+    let export_tree = &export.paths()[..export.paths().len() - 1];
+    let import_tree = &import.paths()[..import.paths().len() - 1];
+    if export_tree == import_tree {
+        BindingScope::SameTree
+    } else if import_tree.starts_with(export_tree) {
+        BindingScope::HostToGuest
+    } else if export_tree.starts_with(import_tree) {
+        BindingScope::GuestToHost
+    } else {
+        BindingScope::Unrelated
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::build_options::{BuildContext, BuildOptions};
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::sharded_map::lock;
+use super::util::Language;
+use super::PolyglotTree;
+
+/// A polyglot tree whose subtrees are built on demand, only keeping the most recently accessed ones.
+///
+/// Traversing an enormous polyglot program with a PolyglotTree requires all of its subtrees to be in memory.
+/// A BoundedTree only keeps its root and up to `capacity` subtrees: when more are accessed, the least recently accessed ones are dropped,
+/// and built again if they are accessed later on.
+///
+/// Each tree is built on its own, so the eval calls of the root and of the subtrees returned by a BoundedTree have no subtree:
+/// the subtree of an eval call is obtained from the BoundedTree, with the NodeId of the call.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::bounded_tree::BoundedTree;
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+/// use polyglot_ast::util::Language;
+///
+/// let code = "Polyglot.eval(\"python\", 'print(1)')\nPolyglot.eval(\"js\", 'Polyglot.eval(\"python\", \"print(2)\")')";
+/// let tree = BoundedTree::from_with_options(code, Language::JavaScript, &BuildOptions::new(), 1).unwrap();
+///
+/// let mut languages = Vec::new();
+/// tree.visit(|_, subtree| languages.push(subtree.language()));
+/// assert_eq!(languages, vec![Language::JavaScript, Language::Python, Language::JavaScript, Language::Python]);
+/// assert_eq!(tree.materialized(), 1);
+///
+/// // evicted subtrees are built again when needed
+/// let calls = tree.eval_calls(None);
+/// assert_eq!(tree.subtree(&calls[0]).unwrap().source(), "print(1)");
+/// ```
+pub struct BoundedTree {
+    root: PolyglotTree,
+    options: BuildOptions,
+    capacity: usize,
+    materialized: Mutex<Materialized>,
+}
+
+/// The subtrees kept in memory, by the NodeId of their eval call.
+#[derive(Default)]
+struct Materialized {
+    trees: HashMap<NodeId, Entry>,
+    /// The eval calls of the subtrees, by the time they were last accessed at, so that the least recently accessed one is found right away.
+    accesses: BTreeMap<u64, NodeId>,
+    clock: u64,
+}
+
+/// A subtree kept in memory.
+struct Entry {
+    tree: Arc<PolyglotTree>,
+    /// The files the subtree is part of, from the file of the root, with canonical paths, as in `BuildContext::files`.
+    files: Vec<PathBuf>,
+    /// The time the subtree was last accessed at.
+    accessed: u64,
+}
+
+impl Materialized {
+    /// Returns the next time, each access getting its own.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl BoundedTree {
+    /// Builds the root tree of the given code, keeping up to `capacity` of its subtrees in memory once they are built.
+    /// Subtrees are built as allowed by the given BuildOptions.
+    ///
+    /// Returns None if the code could not be parsed, as with `PolyglotTree::from`.
+    pub fn from_with_options(
+        code: impl ToString,
+        language: Language,
+        options: &BuildOptions,
+        capacity: usize,
+    ) -> Option<BoundedTree> {
+        let root = PolyglotTree::from_with_options(code, language, &single(options, 0))?;
+        Some(Self::with_root(root, options, capacity))
+    }
+
+    /// Same as `from_with_options`, except that the code is read from the file at the given path, as with `PolyglotTree::from_path`.
+    pub fn from_path_with_options(
+        path: PathBuf,
+        language: Language,
+        options: &BuildOptions,
+        capacity: usize,
+    ) -> Option<BoundedTree> {
+        let root = PolyglotTree::from_path_with_options(path, language, &single(options, 0))?;
+        Some(Self::with_root(root, options, capacity))
+    }
+
+    fn with_root(root: PolyglotTree, options: &BuildOptions, capacity: usize) -> BoundedTree {
+        BoundedTree {
+            root,
+            options: options.clone(),
+            capacity,
+            materialized: Mutex::new(Materialized::default()),
+        }
+    }
+
+    /// Returns the root tree, whose eval calls have no subtree.
+    pub fn root(&self) -> &PolyglotTree {
+        &self.root
+    }
+
+    /// Returns the maximum number of subtrees kept in memory.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of subtrees currently kept in memory.
+    pub fn materialized(&self) -> usize {
+        lock(&self.materialized).trees.len()
+    }
+
+    /// Returns the NodeIds of the eval calls of a tree, in document order: the root if `host` is None,
+    /// or else the subtree of the eval call whose NodeId is `host`.
+    ///
+    /// The NodeIds are relative to the root, and can be given to `subtree`. Returns an empty vector if the host has no subtree.
+    pub fn eval_calls(&self, host: Option<&NodeId>) -> Vec<NodeId> {
+        match host {
+            None => Self::eval_calls_in(&self.root, &[]),
+            Some(host) => match self.subtree(host) {
+                Some(tree) => Self::eval_calls_in(&tree, host.paths()),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    fn eval_calls_in(tree: &PolyglotTree, prefix: &[Vec<usize>]) -> Vec<NodeId> {
+        let mut calls = Vec::new();
+        tree.visit_nodes(&mut |tree, node, id| {
+            if tree.is_polyglot_eval_call(node) {
+                let mut paths = prefix.to_vec();
+                paths.extend(id.paths().iter().cloned());
+                calls.push(NodeId::from_paths(paths));
+            }
+        });
+        calls
+    }
+
+    /// Returns the subtree of the eval call whose NodeId is `call`, building it if it is not in memory,
+    /// or None if there is no such call or its subtree could not be built or is not allowed by the BuildOptions.
+    ///
+    /// A returned subtree stays valid after being dropped from the BoundedTree, for as long as it is held.
+    pub fn subtree(&self, call: &NodeId) -> Option<Arc<PolyglotTree>> {
+        self.entry(call).map(|(tree, _)| tree)
+    }
+
+    /// Internal function returning the subtree of the eval call whose NodeId is `call`, as `subtree` does,
+    /// along with the files it is part of.
+    fn entry(&self, call: &NodeId) -> Option<(Arc<PolyglotTree>, Vec<PathBuf>)> {
+        {
+            let mut materialized = lock(&self.materialized);
+            let now = materialized.tick();
+            if let Some(entry) = materialized.trees.get_mut(call) {
+                let before = std::mem::replace(&mut entry.accessed, now);
+                let found = (entry.tree.clone(), entry.files.clone());
+                materialized.accesses.remove(&before);
+                materialized.accesses.insert(now, call.clone());
+                return Some(found);
+            }
+        }
+
+        let (path, host_paths) = call.paths().split_last()?;
+        let (host, files) = match host_paths {
+            [] => (None, self.files_of(&self.root, Vec::new())),
+            _ => {
+                let (host, files) = self.entry(&NodeId::from_paths(host_paths.to_vec()))?;
+                (Some(host), files)
+            }
+        };
+        let host = host.as_deref().unwrap_or(&self.root);
+        let tree = self.build(host, path, call.paths().len(), files.clone())?;
+        let files = self.files_of(&tree, files);
+
+        let mut materialized = lock(&self.materialized);
+        let now = materialized.tick();
+        let entry = Entry {
+            tree: tree.clone(),
+            files: files.clone(),
+            accessed: now,
+        };
+        // another thread may have built the same subtree in the meantime
+        if let Some(before) = materialized.trees.insert(call.clone(), entry) {
+            materialized.accesses.remove(&before.accessed);
+        }
+        materialized.accesses.insert(now, call.clone());
+        while materialized.trees.len() > self.capacity {
+            let Some((_, oldest)) = materialized.accesses.pop_first() else {
+                break;
+            };
+            materialized.trees.remove(&oldest);
+        }
+        Some((tree, files))
+    }
+
+    /// Internal function adding the file the code of the given tree lives in to the files of its host, if it is not one of them already.
+    fn files_of(&self, tree: &PolyglotTree, mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+        if let Some(file) = tree.origin.file() {
+            let file = self.options.canonicalize(file);
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+        files
+    }
+
+    /// Calls `f` on the root and on every subtree, however deep, in document order, along with the NodeId of their eval call.
+    /// Subtrees are built as they are visited, and only the ones leading to the tree being visited are held in the meantime.
+    pub fn visit(&self, mut f: impl FnMut(Option<&NodeId>, &PolyglotTree)) {
+        f(None, &self.root);
+        self.visit_impl(None, &mut f);
+    }
+
+    fn visit_impl(
+        &self,
+        host: Option<&NodeId>,
+        f: &mut impl FnMut(Option<&NodeId>, &PolyglotTree),
+    ) {
+        for call in self.eval_calls(host) {
+            if let Some(subtree) = self.subtree(&call) {
+                f(Some(&call), &subtree);
+                self.visit_impl(Some(&call), f);
+            }
+        }
+    }
+
+    /// Internal function building the subtree of the eval call at `path` in `host`, at the given depth, without its own subtrees.
+    ///
+    /// `files` are the files the host is part of, so that a file evaluating itself, however indirectly, is not built again and again.
+    fn build(
+        &self,
+        host: &PolyglotTree,
+        path: &[usize],
+        depth: usize,
+        files: Vec<PathBuf>,
+    ) -> Option<Arc<PolyglotTree>> {
+        let mut node = host.root_node();
+        for index in path {
+            node = node.child(*index)?;
+        }
+        match host.polyglot_nodes.get(&node.id()) {
+            Some(PolyglotNode::EvalCall {
+                lang: Some(lang), ..
+            }) if self.options.allows(*lang, depth) => {}
+            _ => return None,
+        }
+
+        let target = host.eval_target(&node, &self.options).ok()?;
+        let options = single(&self.options, depth);
+        let mut ctx = BuildContext::at_depth(&options, depth);
+        ctx.files = files;
+        host.make_subtree_impl(node.start_position(), target, &ctx)
+    }
+}
+
+/// Internal function returning options building a tree at the given depth without any of its subtrees.
+/// The file cache is left out, as the trees it holds are expected to have their subtrees.
+fn single(options: &BuildOptions, depth: usize) -> BuildOptions {
+    let mut options = options.clone().max_depth(depth);
+    options.file_cache = None;
+    options
+}
@@ -0,0 +1,378 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::file_cache::FileCache;
+use super::file_provider::{FileProvider, OsFileProvider};
+use super::sharded_map::ShardedMap;
+use super::source_text::SourceText;
+use super::util::{self, Language};
+use super::PolyglotTree;
+
+/// Options controlling how a PolyglotTree is built, to be passed to `PolyglotTree::from_with_options` or `PolyglotTree::from_path_with_options`.
+///
+/// By default, subtrees are built for every polyglot eval call in a supported language, however deeply they are nested.
+/// Restricting this speeds up tools that only care about some of the code, for instance Python snippets embedded in Java.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let code = "Polyglot.eval(\"python\", 'print(1)')\nPolyglot.eval(\"js\", 'Polyglot.eval(\"python\", \"print(2)\")')";
+/// let options = BuildOptions::new().languages([Language::Python]);
+/// let tree = PolyglotTree::from_with_options(code, Language::JavaScript, &options).unwrap();
+/// assert_eq!(tree.subtrees().count(), 1);
+///
+/// let options = BuildOptions::new().max_depth(1);
+/// let tree = PolyglotTree::from_with_options(code, Language::JavaScript, &options).unwrap();
+/// assert_eq!(tree.stats().subtree_count, 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    languages: Option<Vec<Language>>,
+    max_depth: Option<usize>,
+    pub(crate) file_cache: Option<Arc<FileCache>>,
+    pub(crate) threads: Option<usize>,
+    #[cfg(feature = "mmap")]
+    pub(crate) mmap_threshold: Option<u64>,
+    pub(crate) cancellation: Option<CancellationToken>,
+    pub(crate) polyglot_free_files: PolyglotFreeFiles,
+    pub(crate) file_provider: Option<Arc<dyn FileProvider>>,
+    path_bases: Vec<PathBase>,
+}
+
+impl BuildOptions {
+    /// Returns the default options, which build every subtree that can be built.
+    pub fn new() -> BuildOptions {
+        BuildOptions::default()
+    }
+
+    /// Only builds subtrees for code written in one of the given languages.
+    /// Eval calls to other languages are left without a subtree, and the code they evaluate is not read.
+    pub fn languages(mut self, languages: impl IntoIterator<Item = Language>) -> BuildOptions {
+        self.languages = Some(languages.into_iter().collect());
+        self
+    }
+
+    /// Stops building subtrees past the given number of nested language boundaries:
+    /// with a depth of 1, subtrees are built for the eval calls of the root tree, but not for the eval calls of those subtrees.
+    /// A depth of 0 builds no subtree at all.
+    pub fn max_depth(mut self, depth: usize) -> BuildOptions {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Reuses the subtrees of evaluated files from the given cache, and stores the ones that are built into it.
+    /// The same cache can be given to many builds, so that files evaluated by several host files are only parsed once.
+    pub fn file_cache(mut self, cache: Arc<FileCache>) -> BuildOptions {
+        self.file_cache = Some(cache);
+        self
+    }
+
+    /// Builds the subtrees of the root tree on up to the given number of threads, each of them building the subtrees nested in the ones it builds.
+    /// This bounds the parallelism a single build uses, which lets servers handling many requests share their cores between them.
+    ///
+    /// By default, and with 0 or 1 thread, everything is built on the calling thread.
+    pub fn threads(mut self, threads: usize) -> BuildOptions {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Memory-maps the files that are at least `bytes` long, rather than reading them into memory, which avoids copying
+    /// multi-hundred-megabyte generated files. Their content is checked to be valid UTF-8 once, before being parsed.
+    ///
+    /// Mapped files must not be modified while the trees built from them are in use.
+    #[cfg(feature = "mmap")]
+    pub fn mmap_threshold(mut self, bytes: u64) -> BuildOptions {
+        self.mmap_threshold = Some(bytes);
+        self
+    }
+
+    /// Lets the build be aborted with the given token, from another thread.
+    /// Cancelling the token interrupts the parser of the tree or subtree being built, and no further subtree is built.
+    pub fn cancellation(mut self, token: CancellationToken) -> BuildOptions {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets how `PolyglotTree::from_paths_with_options` handles the files that contain no polyglot call,
+    /// as shown by a quick scan of their code with `util::may_contain_polyglot_calls`.
+    pub fn polyglot_free_files(mut self, handling: PolyglotFreeFiles) -> BuildOptions {
+        self.polyglot_free_files = handling;
+        self
+    }
+
+    /// Reads files through the given FileProvider rather than from the file system, including the files evaluated by polyglot eval calls.
+    /// Files are not memory-mapped when a FileProvider is set.
+    pub fn file_provider(mut self, provider: Arc<dyn FileProvider>) -> BuildOptions {
+        self.file_provider = Some(provider);
+        self
+    }
+
+    /// Resolves the paths of evaluated files from each of the given bases in turn, keeping the first path that leads to an existing file.
+    /// If none does, the path resolved from the first base is kept, and reported as missing.
+    ///
+    /// By default, paths are only resolved relative to the directory of the file containing the eval call, as with `PathBase::HostDirectory`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    /// use polyglot_ast::polyglot_tree::build_options::{BuildOptions, PathBase};
+    /// use polyglot_ast::polyglot_tree::file_provider::MemoryFileProvider;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let files = MemoryFileProvider::new()
+    ///     .with_file("app/main.js", "Polyglot.evalFile('python', 'lib/util.py')")
+    ///     .with_file("shared/lib/util.py", "print(42)");
+    /// let options = BuildOptions::new()
+    ///     .file_provider(Arc::new(files))
+    ///     .path_resolution([PathBase::HostDirectory, PathBase::SourceRoot(PathBuf::from("shared"))]);
+    ///
+    /// let tree = PolyglotTree::from_path_with_options(PathBuf::from("app/main.js"), Language::JavaScript, &options).unwrap();
+    /// assert_eq!(tree.eval_sites()[0].path, Some(PathBuf::from("shared/lib/util.py")));
+    /// assert_eq!(tree.subtrees().count(), 1);
+    /// ```
+    pub fn path_resolution(mut self, bases: impl IntoIterator<Item = PathBase>) -> BuildOptions {
+        self.path_bases = bases.into_iter().collect();
+        self
+    }
+
+    /// Internal function resolving the path of a file evaluated from code whose evaluated files are relative to `working_dir`.
+    pub(crate) fn resolve_path(&self, working_dir: &Path, path: &Path) -> PathBuf {
+        if self.path_bases.is_empty() {
+            return working_dir.join(path);
+        }
+        let candidates: Vec<PathBuf> = self
+            .path_bases
+            .iter()
+            .map(|base| match base {
+                PathBase::HostDirectory => working_dir.join(path),
+                PathBase::CurrentDirectory => path.to_path_buf(),
+                PathBase::SourceRoot(root) => root.join(path),
+            })
+            .collect();
+        candidates
+            .iter()
+            .find(|candidate| self.provider().exists(candidate))
+            .unwrap_or(&candidates[0])
+            .clone()
+    }
+
+    /// Internal function returning the FileProvider files are read through.
+    pub(crate) fn provider(&self) -> &dyn FileProvider {
+        self.file_provider.as_deref().unwrap_or(&OsFileProvider)
+    }
+
+    /// Internal function returning the canonical form of a path through the FileProvider,
+    /// or its normalized form if the provider cannot canonicalize it, for instance because there is no file at that path.
+    pub(crate) fn canonicalize(&self, path: &Path) -> PathBuf {
+        self.provider()
+            .canonicalize(path)
+            .unwrap_or_else(|_| util::normalize_path(path))
+    }
+
+    /// Internal function to check whether the build was cancelled through the options' CancellationToken.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Internal function to check whether a subtree in the given language and at the given boundary depth should be built.
+    pub(crate) fn allows(&self, language: Language, depth: usize) -> bool {
+        self.languages
+            .as_ref()
+            .is_none_or(|languages| languages.contains(&language))
+            && self.max_depth.is_none_or(|max| depth <= max)
+    }
+}
+
+/// How batch builds handle the files that contain no polyglot call, see `BuildOptions::polyglot_free_files`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use polyglot_ast::polyglot_tree::build_options::{BuildOptions, PolyglotFreeFiles};
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let inputs = vec![
+///     (PathBuf::from("TestSamples/test_pyprint_file.js"), Language::JavaScript),
+///     (PathBuf::from("TestSamples/pyprint.py"), Language::Python),
+/// ];
+/// let options = BuildOptions::new().polyglot_free_files(PolyglotFreeFiles::Skip);
+/// let batch = PolyglotTree::from_paths_with_options(inputs, &options);
+///
+/// // the evaluated file is still built as a subtree, but not as an input of its own
+/// assert_eq!(batch.trees[0].1.as_ref().unwrap().subtrees().count(), 1);
+/// assert!(batch.trees[1].1.is_none());
+/// assert!(batch.diagnostics.is_empty());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PolyglotFreeFiles {
+    /// Files are built without being scanned.
+    #[default]
+    Build,
+    /// Files whose scan finds no polyglot call are parsed without looking for polyglot calls in their syntax tree.
+    /// This builds the same trees as `Build`, faster.
+    HostOnly,
+    /// Inputs whose scan finds no polyglot call are not parsed at all, and are left without a tree nor diagnostic.
+    /// Files evaluated by other inputs are still built, as with `HostOnly`.
+    Skip,
+}
+
+/// A directory the paths of evaluated files can be resolved from, see `BuildOptions::path_resolution`.
+/// Absolute paths are kept as they are, whatever the base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathBase {
+    /// The directory of the file containing the eval call, or the current directory for code that does not come from a file.
+    HostDirectory,
+    /// The current directory of the process.
+    CurrentDirectory,
+    /// The given directory, for instance one of the source roots of a build layout.
+    SourceRoot(PathBuf),
+}
+
+/// A flag to abort builds from another thread, such as a UI thread or a server handling a cancelled request.
+///
+/// Clones of a token share the same flag. Once cancelled, a token stays cancelled, and every build using it stops as soon as possible;
+/// `PolyglotTree::try_from_with_options` and `PolyglotTree::try_from_path_with_options` then return a Cancelled error.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::build_options::{BuildOptions, CancellationToken};
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let token = CancellationToken::new();
+/// let options = BuildOptions::new().cancellation(token.clone());
+/// assert!(PolyglotTree::try_from_with_options("print(1)", Language::Python, &options).is_ok());
+///
+/// token.cancel();
+/// assert!(PolyglotTree::try_from_with_options("print(1)", Language::Python, &options).is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicUsize>,
+}
+
+impl CancellationToken {
+    /// Initializes a new token, which is not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels the builds using this token or one of its clones.
+    pub fn cancel(&self) {
+        self.flag.store(1, Ordering::Relaxed);
+    }
+
+    /// Returns true if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) != 0
+    }
+
+    /// Internal function to get the flag, in the form tree-sitter parsers check it.
+    pub(crate) fn flag(&self) -> &AtomicUsize {
+        &self.flag
+    }
+}
+
+/// Internal state shared by the recursive calls building a tree and its subtrees.
+pub(crate) struct BuildContext<'o> {
+    pub(crate) options: &'o BuildOptions,
+    /// The number of language boundaries crossed to reach the tree being built.
+    pub(crate) depth: usize,
+    /// The subtrees built for inline snippets so far, so that identical snippets are only parsed once.
+    /// They are shared with the contexts of the threads building subtrees of the same tree.
+    pub(crate) snippets: SnippetMemo,
+    /// Subtrees of a previous version of the tree being built that are still valid, by the byte range of their eval call.
+    /// Only used for the tree being built, not for its subtrees.
+    pub(crate) reuse: Option<&'o HashMap<Range<usize>, Arc<PolyglotTree>>>,
+    /// When streaming, the function each tree is given to as soon as its own code is parsed,
+    /// its subtrees being built and streamed after it, and dropped right away.
+    pub(crate) stream: Option<&'o StreamSink<'o>>,
+    /// The files the tree being built is part of, from the outermost one, with canonical paths,
+    /// so that files evaluating each other in a cycle are not built again and again.
+    pub(crate) files: Vec<PathBuf>,
+}
+
+/// Everything the subtree of an inline snippet depends on, apart from its position:
+/// its Language, its depth, the directory its evaluated files are resolved from and its code.
+pub(crate) type SnippetKey = (Language, usize, PathBuf, SourceText);
+
+/// The subtrees built for inline snippets, by everything they depend on.
+pub(crate) type SnippetMemo = Arc<ShardedMap<SnippetKey, Arc<PolyglotTree>>>;
+
+/// The function streamed trees are given to, see `PolyglotTree::stream`.
+pub(crate) type StreamSink<'o> = RefCell<dyn FnMut(&PolyglotTree) + 'o>;
+
+impl<'o> BuildContext<'o> {
+    pub(crate) fn new(options: &'o BuildOptions) -> BuildContext<'o> {
+        BuildContext::at_depth(options, 0)
+    }
+
+    /// Returns a new context for building a tree at the given boundary depth, sharing nothing with the contexts of other trees.
+    pub(crate) fn at_depth(options: &'o BuildOptions, depth: usize) -> BuildContext<'o> {
+        BuildContext::sharing(options, depth, Arc::new(ShardedMap::new()))
+    }
+
+    /// Returns a new context for building a tree at the given boundary depth, reusing the given snippet subtrees,
+    /// for instance on another thread than the context they come from.
+    pub(crate) fn sharing(
+        options: &'o BuildOptions,
+        depth: usize,
+        snippets: SnippetMemo,
+    ) -> BuildContext<'o> {
+        BuildContext {
+            options,
+            depth,
+            snippets,
+            reuse: None,
+            stream: None,
+            files: Vec::new(),
+        }
+    }
+
+    /// Returns the context for building the subtrees of the tree being built.
+    pub(crate) fn nested(&self) -> BuildContext<'o> {
+        BuildContext {
+            options: self.options,
+            depth: self.depth + 1,
+            snippets: self.snippets.clone(),
+            reuse: None,
+            stream: self.stream,
+            files: self.files.clone(),
+        }
+    }
+
+    /// Returns the context for building the tree of the code of the given file, as part of the tree being built.
+    pub(crate) fn in_file(&self, path: &Path) -> BuildContext<'o> {
+        let mut files = self.files.clone();
+        files.push(self.options.canonicalize(path));
+        BuildContext {
+            options: self.options,
+            depth: self.depth,
+            snippets: self.snippets.clone(),
+            reuse: self.reuse,
+            stream: self.stream,
+            files,
+        }
+    }
+
+    /// Returns true if the given file is one of the files the tree being built is part of, in which case it evaluates itself.
+    pub(crate) fn is_building(&self, path: &Path) -> bool {
+        self.files.contains(&self.options.canonicalize(path))
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use tree_sitter::Point;
+
+use super::node_id::NodeId;
+use super::polyglot_zipper::PolyglotZipper;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// The kinds of the nodes defining a named function or method, in any of the supported languages.
+const FUNCTIONS: [&str; 6] = [
+    "function_definition",
+    "function_declaration",
+    "generator_function_declaration",
+    "method_definition",
+    "method_declaration",
+    "constructor_declaration",
+];
+
+/// The methods of a GraalVM `Value` calling the function it holds, as used by Java hosts.
+const EXECUTE_METHODS: [&str; 2] = ["execute", "executeVoid"];
+
+/// A function of a CallGraph, or the top-level code of a file or snippet, which runs when it is evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The name of the function, or None for the top-level code of a tree.
+    pub name: Option<String>,
+    /// The Language the function is written in.
+    pub language: Language,
+    /// The Origin of the code containing the function.
+    pub origin: Origin,
+    /// The NodeId of the definition of the function, or of the root of the tree for top-level code.
+    pub id: NodeId,
+    /// The byte range of the function, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the function in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+}
+
+/// How a function of a CallEdge calls the other one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CallKind {
+    /// The caller calls the callee by its name, in the same tree.
+    Direct,
+    /// The caller evaluates the tree whose top-level code is the callee, with a polyglot eval call.
+    Eval,
+    /// The caller calls a value imported under the given binding name, to which the callee is exported, possibly from another language.
+    Polyglot(String),
+}
+
+/// A call from a function of a CallGraph to another one, given by their indices in the functions of the graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallEdge {
+    pub caller: usize,
+    pub callee: usize,
+    pub kind: CallKind,
+    /// The byte range of the call, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+}
+
+/// The functions of a tree and of its subtrees, and the calls between them across languages, as returned by `PolyglotTree::call_graph`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    /// The functions, in document order, the top-level code of each tree coming first. The function at index 0 is the top-level code of the tree itself.
+    pub functions: Vec<Function>,
+    /// The calls, grouped by kind: direct calls first, then eval calls and polyglot calls.
+    pub edges: Vec<CallEdge>,
+}
+
+impl CallGraph {
+    /// Returns the indices of the functions called, directly or not, by the function at the given index, that function included, in increasing order.
+    pub fn reachable_from(&self, function: usize) -> Vec<usize> {
+        let mut reached = HashSet::from([function]);
+        let mut pending = vec![function];
+        while let Some(caller) = pending.pop() {
+            for edge in self.edges.iter().filter(|edge| edge.caller == caller) {
+                if reached.insert(edge.callee) {
+                    pending.push(edge.callee);
+                }
+            }
+        }
+        let mut result: Vec<usize> = reached.into_iter().collect();
+        result.sort_unstable();
+        result
+    }
+
+    /// Returns the indices of the functions that the top-level code of the tree never calls, directly or not, in increasing order.
+    /// They are dead code, unless they are called by code the graph does not know about, such as the host application.
+    pub fn unreachable(&self) -> Vec<usize> {
+        let reached = self.reachable_from(0);
+        (0..self.functions.len())
+            .filter(|function| reached.binary_search(function).is_err())
+            .collect()
+    }
+}
+
+/// The tree of a node, given by the paths of the eval calls leading to it.
+type Scope = Vec<Vec<usize>>;
+
+/// A call found by `PolyglotTree::call_graph`, before the functions it links are known.
+struct Call {
+    scope: Scope,
+    span: Range<usize>,
+    target: CallTarget,
+}
+
+enum CallTarget {
+    /// A function of the same tree, called by its name.
+    Name(String),
+    /// The top-level code of the tree of the given scope.
+    Tree(Scope),
+    /// The function exported under the given binding name and defined by the identifier of the given NodeId.
+    Binding(String, NodeId),
+}
+
+impl PolyglotTree {
+    /// Returns the CallGraph of the tree and of its subtrees.
+    ///
+    /// Functions call the functions of the same tree they name, the top-level code of the trees they evaluate,
+    /// and the functions exported by other trees, whatever their language, when they call a value imported from the same binding:
+    /// directly, after assigning it to a variable, or through `Value.execute` in Java. Functions calling the same function several times
+    /// are linked by several edges. As for `def_use_chains`, functions and variables are told apart by their name and the tree they belong to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::call_graph::CallKind;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\n\ndef double(x):\n    return 2 * x\n\ndef unused():\n    pass\n\npolyglot.export_value(name=\"double\", value=double)\npolyglot.eval(language=\"js\", string=\"const d = Polyglot.import('double'); d(21)\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let graph = tree.call_graph();
+    ///
+    /// let names: Vec<Option<&str>> = graph.functions.iter().map(|f| f.name.as_deref()).collect();
+    /// assert_eq!(names, vec![None, Some("double"), Some("unused"), None]);
+    /// assert_eq!(graph.functions[3].language, Language::JavaScript);
+    ///
+    /// let polyglot = graph.edges.iter().find(|e| e.kind == CallKind::Polyglot("double".to_string())).unwrap();
+    /// assert_eq!((polyglot.caller, polyglot.callee), (3, 1));
+    /// assert_eq!(&code[polyglot.span.clone()], "d(21)");
+    ///
+    /// assert_eq!(graph.reachable_from(0), vec![0, 1, 3]);
+    /// assert_eq!(graph.unreachable(), vec![2]);
+    /// ```
+    pub fn call_graph(&self) -> CallGraph {
+        let mut graph = CallGraph::default();
+        // the functions of each tree, given by the paths of its eval calls, with the NodeIds of their names
+        let mut scopes: HashMap<Scope, Vec<(usize, Option<NodeId>)>> = HashMap::new();
+        let mut calls = Vec::new();
+
+        self.visit_nodes(&mut |tree, node, id| {
+            let scope = id.paths()[..id.paths().len() - 1].to_vec();
+            let name = match node.parent() {
+                None => None,
+                Some(_) if FUNCTIONS.contains(&node.kind()) => {
+                    let Some(name) = node.child_by_field_name("name") else {
+                        return;
+                    };
+                    Some(name)
+                }
+                Some(_) => {
+                    let span = tree.host_range(node);
+                    if tree.node_to_subtrees_map.contains_key(&node.id()) {
+                        calls.push(Call {
+                            scope: scope.clone(),
+                            span: span.clone(),
+                            target: CallTarget::Tree(id.paths().to_vec()),
+                        });
+                    }
+                    let callee = match node.kind() {
+                        "call" | "call_expression" => node.child_by_field_name("function"),
+                        "method_invocation" if node.child_by_field_name("object").is_none() => {
+                            node.child_by_field_name("name")
+                        }
+                        _ => None,
+                    };
+                    if let Some(callee) = callee.filter(|c| c.kind() == "identifier") {
+                        calls.push(Call {
+                            scope,
+                            span,
+                            target: CallTarget::Name(tree.node_to_code(callee).to_string()),
+                        });
+                    }
+                    return;
+                }
+            };
+
+            let name_id = name.map(|name| {
+                let mut paths = id.paths().to_vec();
+                let mut cursor = node.walk();
+                let index = node.children(&mut cursor).position(|c| c == name);
+                if let Some(last) = paths.last_mut() {
+                    last.push(index.unwrap_or_default());
+                }
+                NodeId::from_paths(paths)
+            });
+            scopes
+                .entry(scope)
+                .or_default()
+                .push((graph.functions.len(), name_id));
+            graph.functions.push(Function {
+                name: name.map(|name| tree.node_to_code(name).to_string()),
+                language: tree.language,
+                origin: tree.origin.clone(),
+                id: id.clone(),
+                span: tree.host_range(node),
+                start_position: node.start_position(),
+            });
+        });
+
+        for chain in self.def_use_chains() {
+            let Some(definition) = &chain.definition else {
+                continue;
+            };
+            let invocations = std::iter::once(&chain.import.id)
+                .chain(chain.uses.iter().map(|u| &u.id))
+                .filter_map(|id| self.zipper_at(id))
+                .filter_map(|zip| invocation(&zip));
+            for zip in invocations {
+                let id = zip.node_id();
+                calls.push(Call {
+                    scope: id.paths()[..id.paths().len() - 1].to_vec(),
+                    span: zip.host_byte_range(),
+                    target: CallTarget::Binding(chain.name.clone(), definition.id.clone()),
+                });
+            }
+        }
+
+        let no_functions = Vec::new();
+        let mut edges = Vec::new();
+        for call in calls {
+            let functions = scopes.get(&call.scope).unwrap_or(&no_functions);
+            // the innermost function containing the call, the top-level code of the tree containing them all
+            let Some(caller) = functions
+                .iter()
+                .map(|(index, _)| *index)
+                .filter(|index| {
+                    let span = &graph.functions[*index].span;
+                    span.start <= call.span.start && call.span.end <= span.end
+                })
+                .min_by_key(|index| graph.functions[*index].span.len())
+            else {
+                continue;
+            };
+            let (callee, kind) = match call.target {
+                CallTarget::Name(name) => {
+                    let callee = functions
+                        .iter()
+                        .find(|(index, _)| graph.functions[*index].name.as_ref() == Some(&name));
+                    (callee.map(|(index, _)| *index), CallKind::Direct)
+                }
+                CallTarget::Tree(scope) => {
+                    let callee = scopes.get(&scope).and_then(|functions| functions.first());
+                    (callee.map(|(index, _)| *index), CallKind::Eval)
+                }
+                CallTarget::Binding(name, definition) => {
+                    let callee = scopes
+                        .values()
+                        .flatten()
+                        .find(|(_, name_id)| name_id.as_ref() == Some(&definition));
+                    (callee.map(|(index, _)| *index), CallKind::Polyglot(name))
+                }
+            };
+            if let Some(callee) = callee {
+                edges.push((
+                    kind.clone(),
+                    CallEdge {
+                        caller,
+                        callee,
+                        kind,
+                        span: call.span,
+                    },
+                ));
+            }
+        }
+        let rank = |kind: &CallKind| match kind {
+            CallKind::Direct => 0,
+            CallKind::Eval => 1,
+            CallKind::Polyglot(_) => 2,
+        };
+        edges.sort_by_key(|(kind, edge)| (rank(kind), edge.span.start));
+        graph.edges = edges.into_iter().map(|(_, edge)| edge).collect();
+        graph
+    }
+}
+
+/// Internal function returning the call invoking the value of the given node, if it is called directly,
+/// or through `Value.execute` in Java.
+fn invocation<'a>(zip: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+    let parent = zip.parent()?;
+    let field = zip.field_name();
+    match parent.kind() {
+        "call" | "call_expression" if field == Some("function") => Some(parent),
+        "method_invocation" if field == Some("object") => {
+            let method = std::iter::successors(parent.child(0), |c| c.next_sibling())
+                .find(|c| c.field_name() == Some("name"))?;
+            EXECUTE_METHODS.contains(&method.code()).then_some(parent)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,224 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use tree_sitter::{Node, Point};
+
+use super::node_id::NodeId;
+use super::polyglot_node::{EvalPayload, PolyglotNode};
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// Whether a polyglot eval call evaluates code written inline or read from a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalKind {
+    /// The code is written in a string literal passed to the call.
+    Inline,
+    /// The code is read from a file whose path is passed to the call.
+    File,
+}
+
+/// A description of a polyglot eval call found in a tree, as returned by `PolyglotTree::eval_sites`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalSite {
+    /// The NodeId of the call.
+    pub id: NodeId,
+    /// The Origin of the code containing the call.
+    pub origin: Origin,
+    /// The byte range of the call, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the call in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+    /// The Language the call is written in.
+    pub host_language: Language,
+    /// The Language of the evaluated code, or None if it is missing or not supported.
+    pub guest_language: Option<Language>,
+    /// Whether the evaluated code is inline or read from a file, or None if neither could be found.
+    pub kind: Option<EvalKind>,
+    /// The path of the evaluated file, resolved relative to the directory of the host file, for calls evaluating a file.
+    pub path: Option<PathBuf>,
+    /// Whether a subtree could be built for the evaluated code.
+    pub has_subtree: bool,
+}
+
+/// Whether a polyglot binding call reads a value from the polyglot bindings or writes one to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingDirection {
+    /// The call imports a value, eg. `polyglot.import_value` in Python or `getMember` in Java.
+    Import,
+    /// The call exports a value, eg. `Polyglot.export` in JavaScript or `putMember` in Java.
+    Export,
+}
+
+/// A description of a polyglot import or export call found in a tree, as returned by `PolyglotTree::bindings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingSite {
+    /// The NodeId of the call.
+    pub id: NodeId,
+    /// The name of the binding, or None if it is not given as a string literal.
+    pub name: Option<String>,
+    /// Whether the call imports or exports the binding.
+    pub direction: BindingDirection,
+    /// The Language the call is written in.
+    pub language: Language,
+    /// The Origin of the code containing the call.
+    pub origin: Origin,
+    /// The byte range of the call, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the call in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+}
+
+impl PolyglotTree {
+    /// Returns a description of every polyglot eval call of the tree, including those found in subtrees however deep they are.
+    /// Calls are given in document order, each one followed by the calls found in its own subtree.
+    ///
+    /// Calls whose arguments cannot be understood at all are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::call_sites::EvalKind;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(42)')\nPolyglot.evalFile(\"ruby\", 'script.rb')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let sites = tree.eval_sites();
+    ///
+    /// assert_eq!(sites.len(), 2);
+    /// assert_eq!(sites[0].span, 0..36);
+    /// assert_eq!(sites[0].guest_language, Some(Language::Python));
+    /// assert_eq!(sites[0].kind, Some(EvalKind::Inline));
+    /// assert!(sites[0].has_subtree);
+    ///
+    /// assert_eq!(sites[1].guest_language, None);
+    /// assert_eq!(sites[1].kind, Some(EvalKind::File));
+    /// assert_eq!(sites[1].path, Some(PathBuf::from("script.rb")));
+    /// assert!(!sites[1].has_subtree);
+    /// ```
+    pub fn eval_sites(&self) -> Vec<EvalSite> {
+        let mut result = Vec::new();
+        self.eval_sites_impl(&mut result);
+        result
+    }
+
+    fn eval_sites_impl(&self, result: &mut Vec<EvalSite>) {
+        self.visit_nodes(&mut |tree, node, id| {
+            if let Some(PolyglotNode::EvalCall {
+                lang,
+                payload,
+                span,
+            }) = tree.polyglot_nodes.get(&node.id())
+            {
+                let (kind, path) = match payload {
+                    Some(EvalPayload::Inline { .. }) => (Some(EvalKind::Inline), None),
+                    Some(EvalPayload::File(p)) => (Some(EvalKind::File), Some(p.clone())),
+                    None => (None, None),
+                };
+
+                result.push(EvalSite {
+                    id: id.clone(),
+                    origin: tree.origin.clone(),
+                    span: span.clone(),
+                    start_position: node.start_position(),
+                    host_language: tree.language,
+                    guest_language: *lang,
+                    kind,
+                    path,
+                    has_subtree: tree.node_to_subtrees_map.contains_key(&node.id()),
+                });
+            }
+        });
+    }
+
+    /// Returns a description of every polyglot import and export call of the tree, including those found in subtrees however deep they are.
+    /// Calls are given in document order, the calls of a subtree coming right after the eval call it belongs to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::call_sites::BindingDirection;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\npolyglot.export_value(name=\"x\", value=42)\npolyglot.eval(language=\"js\", string=\"Polyglot.import('x')\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let bindings = tree.bindings();
+    ///
+    /// assert_eq!(bindings.len(), 2);
+    /// assert_eq!(bindings[0].name.as_deref(), Some("x"));
+    /// assert_eq!(bindings[0].direction, BindingDirection::Export);
+    /// assert_eq!(bindings[0].language, Language::Python);
+    ///
+    /// assert_eq!(bindings[1].name.as_deref(), Some("x"));
+    /// assert_eq!(bindings[1].direction, BindingDirection::Import);
+    /// assert_eq!(bindings[1].language, Language::JavaScript);
+    /// assert_eq!(&code[bindings[1].span.clone()], "Polyglot.import('x')");
+    /// ```
+    pub fn bindings(&self) -> Vec<BindingSite> {
+        let mut result = Vec::new();
+        self.visit_nodes(&mut |tree, node, id| {
+            let (direction, name) = match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ImportCall { name }) => (BindingDirection::Import, name),
+                Some(PolyglotNode::ExportCall { name }) => (BindingDirection::Export, name),
+                _ => return,
+            };
+
+            result.push(BindingSite {
+                id: id.clone(),
+                name: name.clone(),
+                direction,
+                language: tree.language,
+                origin: tree.origin.clone(),
+                span: tree.host_range(node),
+                start_position: node.start_position(),
+            });
+        });
+        result
+    }
+
+    /// Internal function calling `f` on every node of the tree in document order, along with the tree the node belongs to and the node's NodeId.
+    /// The subtree of an eval call is visited right after the call itself, whereas the arguments of the call are not visited.
+    pub(crate) fn visit_nodes(&self, f: &mut dyn FnMut(&PolyglotTree, Node, &NodeId)) {
+        let mut id = NodeId::from_paths(Vec::new());
+        self.visit_nodes_impl(&mut id, f);
+    }
+
+    fn visit_nodes_impl(&self, id: &mut NodeId, f: &mut dyn FnMut(&PolyglotTree, Node, &NodeId)) {
+        id.paths_mut().push(Vec::new());
+        let mut cursor = self.root_node().walk();
+        loop {
+            let node = cursor.node();
+            f(self, node, id);
+
+            if let Some(subtree) = self.node_to_subtrees_map.get(&node.id()) {
+                subtree.visit_nodes_impl(id, f);
+            } else if !self.is_polyglot_eval_call(node) && cursor.goto_first_child() {
+                current_path(id).push(0);
+                continue;
+            }
+
+            loop {
+                if cursor.goto_next_sibling() {
+                    if let Some(index) = current_path(id).last_mut() {
+                        *index += 1;
+                    }
+                    break;
+                }
+                if !cursor.goto_parent() {
+                    id.paths_mut().pop();
+                    return;
+                }
+                current_path(id).pop();
+            }
+        }
+    }
+}
+
+/// Internal function to get the path of a NodeId within the innermost tree.
+fn current_path(id: &mut NodeId) -> &mut Vec<usize> {
+    id.paths_mut()
+        .last_mut()
+        .expect("a NodeId being visited always has a path")
+}
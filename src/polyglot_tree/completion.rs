@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+use tree_sitter::Point;
+
+use super::call_sites::{BindingDirection, BindingSite};
+use super::util::Language;
+use super::PolyglotTree;
+
+/// A polyglot binding that code at a given position can import, as returned by `PolyglotTree::bindings_visible_at`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisibleBinding {
+    /// The name of the binding.
+    pub name: String,
+    /// The Language of the code exporting the binding.
+    pub language: Language,
+    /// The export call defining the value an import at the position reads: the last export call of the binding before the position.
+    pub export: BindingSite,
+}
+
+impl PolyglotTree {
+    /// Returns the polyglot bindings that an import at the given position of the tree's code can read, ordered by name,
+    /// so that editors can complete binding names in import calls and member accesses.
+    ///
+    /// A binding is visible when one of its export calls, whose binding name is given as a string literal, comes before the position
+    /// in the order the code runs in when it has no loops or functions called later on: document order, the code of a subtree
+    /// running right after its eval call. Returns an empty list if the position is out of the code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    /// use tree_sitter::Point;
+    ///
+    /// let code = "import polyglot\npolyglot.export_value(name=\"x\", value=1)\npolyglot.eval(language=\"js\", string=\"Polyglot.export('y', 2); Polyglot.import('')\")\npolyglot.export_value(name=\"z\", value=3)";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    ///
+    /// // between the quotes of `Polyglot.import('')`
+    /// let visible = tree.bindings_visible_at(Point::new(2, 79));
+    /// let names: Vec<(&str, Language)> = visible.iter().map(|b| (b.name.as_str(), b.language)).collect();
+    /// assert_eq!(names, vec![("x", Language::Python), ("y", Language::JavaScript)]);
+    /// assert_eq!(visible[0].export.start_position.row, 1);
+    /// ```
+    pub fn bindings_visible_at(&self, position: Point) -> Vec<VisibleBinding> {
+        let Some(zip) = self.zipper_at_position(position) else {
+            return Vec::new();
+        };
+        let id = zip.node_id();
+
+        // bindings are ordered by name, each export replacing the previous ones of the same binding
+        let mut visible = BTreeMap::new();
+        for site in self.bindings() {
+            if site.direction != BindingDirection::Export || site.id >= id {
+                continue;
+            }
+            let Some(name) = site.name.clone() else {
+                continue;
+            };
+            visible.insert(
+                name.clone(),
+                VisibleBinding {
+                    name,
+                    language: site.language,
+                    export: site,
+                },
+            );
+        }
+        visible.into_values().collect()
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use super::data_flow::{FlowNode, DEFINITIONS};
+use super::polyglot_node::PolyglotNode;
+use super::PolyglotTree;
+
+/// Why a BindingConflict was reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictKind {
+    /// Two export calls write the binding with values of different code or languages, so the value read depends on which call runs last.
+    ConflictingExport,
+    /// A local variable of the code importing the binding is defined after the import call, with the name of the binding
+    /// or of the variable the imported value is assigned to, hiding the imported value.
+    ShadowedImport,
+}
+
+/// Two places of a tree that disagree about a polyglot binding, as returned by `PolyglotTree::binding_conflicts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingConflict {
+    pub kind: ConflictKind,
+    /// The name of the binding.
+    pub name: String,
+    /// The call the conflict is about: the first export call of the binding, or the import call whose value is hidden.
+    pub original: FlowNode,
+    /// What conflicts with it: a later export call of the binding, or the identifier of the local definition hiding the imported value.
+    pub conflicting: FlowNode,
+}
+
+/// An export call, as collected by `PolyglotTree::binding_conflicts`.
+struct Export {
+    name: String,
+    call: FlowNode,
+    /// The code of the exported value, if the call passes one.
+    value: Option<String>,
+}
+
+/// An import call, as collected by `PolyglotTree::binding_conflicts`.
+struct Import {
+    name: String,
+    call: FlowNode,
+    /// The tree of the call, given by the paths of its eval calls.
+    scope: Vec<Vec<usize>>,
+    /// The name of the variable the imported value is assigned to.
+    variable: Option<String>,
+}
+
+impl PolyglotTree {
+    /// Returns the conflicts between the polyglot bindings of the tree and of its subtrees, ordered by the place of their `conflicting` node.
+    ///
+    /// An export call conflicts with the first export call of the same binding when it is written in another language,
+    /// or passes a value whose code differs. An import call conflicts with the first definition of a local variable that comes after it
+    /// in the same file or snippet and has the name of the binding or of the variable the imported value is assigned to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::conflicts::ConflictKind;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\nx = 42\npolyglot.export_value(name=\"x\", value=x)\npolyglot.eval(language=\"js\", string=\"Polyglot.export('x', 1); let a = Polyglot.import('x'); a = 2\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let conflicts = tree.binding_conflicts();
+    ///
+    /// assert_eq!(conflicts.len(), 2);
+    /// assert_eq!(conflicts[0].kind, ConflictKind::ConflictingExport);
+    /// assert_eq!(conflicts[0].original.language, Language::Python);
+    /// assert_eq!(&code[conflicts[0].conflicting.span.clone()], "Polyglot.export('x', 1)");
+    ///
+    /// assert_eq!(conflicts[1].kind, ConflictKind::ShadowedImport);
+    /// assert_eq!(conflicts[1].name, "x");
+    /// assert_eq!(&code[conflicts[1].original.span.clone()], "Polyglot.import('x')");
+    /// assert_eq!(&code[conflicts[1].conflicting.span.clone()], "a");
+    /// ```
+    pub fn binding_conflicts(&self) -> Vec<BindingConflict> {
+        let mut exports: Vec<Export> = Vec::new();
+        let mut imports: Vec<Import> = Vec::new();
+        let mut definitions: HashMap<Vec<Vec<usize>>, Vec<(String, FlowNode)>> = HashMap::new();
+
+        self.visit_nodes(&mut |tree, node, id| {
+            let scope = id.paths()[..id.paths().len() - 1].to_vec();
+            match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ExportCall { name: Some(name) }) => exports.push(Export {
+                    name: name.clone(),
+                    call: tree.flow_node(node, id.clone()),
+                    value: tree
+                        .export_value(node)
+                        .map(|value| tree.node_to_code(value).to_string()),
+                }),
+                Some(PolyglotNode::ImportCall { name: Some(name) }) => imports.push(Import {
+                    name: name.clone(),
+                    call: tree.flow_node(node, id.clone()),
+                    scope,
+                    variable: tree
+                        .import_variable(node, id)
+                        .map(|(variable, _)| tree.node_to_code(variable).to_string()),
+                }),
+                _ => {
+                    let Some(parent) = node.parent() else { return };
+                    let defined = node.kind() == "identifier"
+                        && DEFINITIONS.contains(&parent.kind())
+                        && ["name", "left"]
+                            .iter()
+                            .any(|field| parent.child_by_field_name(field) == Some(node));
+                    if defined {
+                        definitions.entry(scope).or_default().push((
+                            tree.node_to_code(node).to_string(),
+                            tree.flow_node(node, id.clone()),
+                        ));
+                    }
+                }
+            }
+        });
+
+        let mut conflicts = Vec::new();
+        let mut first_exports: HashMap<&str, &Export> = HashMap::new();
+        for export in &exports {
+            let Some(first) = first_exports.get(export.name.as_str()) else {
+                first_exports.insert(&export.name, export);
+                continue;
+            };
+            if first.call.language != export.call.language || first.value != export.value {
+                conflicts.push(BindingConflict {
+                    kind: ConflictKind::ConflictingExport,
+                    name: export.name.clone(),
+                    original: first.call.clone(),
+                    conflicting: export.call.clone(),
+                });
+            }
+        }
+
+        let no_definitions = Vec::new();
+        for import in &imports {
+            let shadowing = definitions
+                .get(&import.scope)
+                .unwrap_or(&no_definitions)
+                .iter()
+                .find(|(name, definition)| {
+                    definition.span.start >= import.call.span.end
+                        && (*name == import.name || Some(name) == import.variable.as_ref())
+                });
+            if let Some((_, definition)) = shadowing {
+                conflicts.push(BindingConflict {
+                    kind: ConflictKind::ShadowedImport,
+                    name: import.name.clone(),
+                    original: import.call.clone(),
+                    conflicting: definition.clone(),
+                });
+            }
+        }
+
+        conflicts.sort_by(|a, b| a.conflicting.id.cmp(&b.conflicting.id));
+        conflicts
+    }
+}
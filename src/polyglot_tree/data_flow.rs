@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use tree_sitter::{Node, Point};
+
+use super::call_sites::BindingSite;
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// The kinds of the nodes defining the identifier in their `name` or `left` field, in any of the supported languages.
+pub(super) const DEFINITIONS: [&str; 8] = [
+    "assignment",
+    "assignment_expression",
+    "variable_declarator",
+    "function_definition",
+    "function_declaration",
+    "class_definition",
+    "class_declaration",
+    "method_declaration",
+];
+
+/// A node taking part in a DefUseChain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowNode {
+    /// The NodeId of the node.
+    pub id: NodeId,
+    /// The Language of the code containing the node.
+    pub language: Language,
+    /// The Origin of the code containing the node.
+    pub origin: Origin,
+    /// The byte range of the node, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the node in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+}
+
+/// The path of a value from its definition to its uses, through a polyglot export call and an import call of the same binding,
+/// as returned by `PolyglotTree::def_use_chains`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefUseChain {
+    /// The name of the binding.
+    pub name: String,
+    /// Where the exported value is defined: the identifier assigned or declared last before the export call, if the exported value
+    /// is a variable, or the exported expression otherwise. None if the variable is not defined in the code containing the call,
+    /// or if the export call passes no value.
+    pub definition: Option<FlowNode>,
+    /// The call exporting the value.
+    pub export: BindingSite,
+    /// The call importing the value.
+    pub import: BindingSite,
+    /// The identifier the imported value is assigned to, or None if the import call is used directly.
+    pub variable: Option<FlowNode>,
+    /// The identifiers reading that variable after the import call, until it is assigned again, in document order.
+    pub uses: Vec<FlowNode>,
+}
+
+/// An identifier, as collected by `PolyglotTree::def_use_chains`.
+struct Identifier {
+    name: String,
+    /// Whether the identifier is defined by its parent, as opposed to read.
+    defined: bool,
+    node: FlowNode,
+}
+
+/// What `PolyglotTree::def_use_chains` needs to know about the import and export calls of the tree.
+struct CallFlow {
+    /// The trees of the calls, given by the paths of their eval calls, with which their identifiers are looked up.
+    scope: Vec<Vec<usize>>,
+    /// The identifier passed as the value of an export call or assigned the result of an import call, with its name.
+    identifier: Option<(String, FlowNode)>,
+    /// The value of an export call, when it is not an identifier.
+    expression: Option<FlowNode>,
+}
+
+impl PolyglotTree {
+    /// Follows the values exported by the polyglot export calls of the tree and of its subtrees to the import calls of the same binding,
+    /// returning one DefUseChain for each edge of the `binding_graph`, in the same order.
+    ///
+    /// The analysis is lightweight: variables are told apart by their name and the tree they belong to, whatever their scope,
+    /// and the order of the code is taken for the order it runs in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\nx = 42\npolyglot.export_value(name=\"answer\", value=x)\npolyglot.eval(language=\"js\", string=\"let a = Polyglot.import('answer'); console.log(a + 1)\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let chains = tree.def_use_chains();
+    ///
+    /// assert_eq!(chains.len(), 1);
+    /// let chain = &chains[0];
+    /// assert_eq!(chain.name, "answer");
+    /// let definition = chain.definition.as_ref().unwrap();
+    /// assert_eq!((&code[definition.span.clone()], definition.start_position.row), ("x", 1));
+    /// let variable = chain.variable.as_ref().unwrap();
+    /// assert_eq!((&code[variable.span.clone()], variable.language), ("a", Language::JavaScript));
+    /// assert_eq!(tree.zipper_at(&variable.id).unwrap().code(), "a");
+    /// assert_eq!(chain.uses.len(), 1);
+    /// assert_eq!(&code[chain.uses[0].span.clone()], "a");
+    /// assert!(chain.uses[0].span.start > variable.span.start);
+    /// ```
+    pub fn def_use_chains(&self) -> Vec<DefUseChain> {
+        let graph = self.binding_graph();
+        let mut identifiers: HashMap<Vec<Vec<usize>>, Vec<Identifier>> = HashMap::new();
+        let mut calls: HashMap<NodeId, CallFlow> = HashMap::new();
+
+        self.visit_nodes(&mut |tree, node, id| {
+            let scope = id.paths()[..id.paths().len() - 1].to_vec();
+            // the names of keyword arguments are not variables
+            let keyword = node.parent().is_some_and(|parent| {
+                parent.kind() == "keyword_argument"
+                    && parent.child_by_field_name("name") == Some(node)
+            });
+            if node.kind() == "identifier" && !keyword {
+                let defined = node.parent().is_some_and(|parent| {
+                    DEFINITIONS.contains(&parent.kind())
+                        && ["name", "left"]
+                            .iter()
+                            .any(|field| parent.child_by_field_name(field) == Some(node))
+                });
+                identifiers
+                    .entry(scope.clone())
+                    .or_default()
+                    .push(Identifier {
+                        name: tree.node_to_code(node).to_string(),
+                        defined,
+                        node: tree.flow_node(node, id.clone()),
+                    });
+            }
+
+            let flow = match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ExportCall { name: Some(_) }) => {
+                    tree.export_value(node).map(|value| {
+                        let value_id = descendant_id(id, node, value);
+                        match value.kind() {
+                            "identifier" => (Some(tree.identifier(value, value_id)), None),
+                            _ => (None, Some(tree.flow_node(value, value_id))),
+                        }
+                    })
+                }
+                Some(PolyglotNode::ImportCall { name: Some(_) }) => tree
+                    .import_variable(node, id)
+                    .map(|(variable, variable_id)| {
+                        (Some(tree.identifier(variable, variable_id)), None)
+                    }),
+                _ => return,
+            };
+            let (identifier, expression) = flow.unwrap_or((None, None));
+            calls.insert(
+                id.clone(),
+                CallFlow {
+                    scope,
+                    identifier,
+                    expression,
+                },
+            );
+        });
+
+        let no_identifiers = Vec::new();
+        let mut chains = Vec::new();
+        for edge in &graph.edges {
+            let export = &graph.sites[edge.export];
+            let import = &graph.sites[edge.import];
+            let (Some(exported), Some(imported)) = (calls.get(&export.id), calls.get(&import.id))
+            else {
+                continue;
+            };
+
+            let definition = match &exported.identifier {
+                Some((name, value)) => identifiers
+                    .get(&exported.scope)
+                    .unwrap_or(&no_identifiers)
+                    .iter()
+                    .rev()
+                    .find(|i| i.defined && i.name == *name && i.node.span.end <= value.span.start)
+                    .map(|i| i.node.clone()),
+                None => exported.expression.clone(),
+            };
+
+            let mut uses = Vec::new();
+            if let Some((name, variable)) = &imported.identifier {
+                let later = identifiers
+                    .get(&imported.scope)
+                    .unwrap_or(&no_identifiers)
+                    .iter()
+                    .filter(|i| i.name == *name && i.node.span.start > variable.span.start);
+                for identifier in later {
+                    // the variable no longer holds the imported value once it is assigned again
+                    if identifier.defined {
+                        break;
+                    }
+                    uses.push(identifier.node.clone());
+                }
+            }
+
+            chains.push(DefUseChain {
+                name: edge.name.clone(),
+                definition,
+                export: export.clone(),
+                import: import.clone(),
+                variable: imported
+                    .identifier
+                    .as_ref()
+                    .map(|(_, variable)| variable.clone()),
+                uses,
+            });
+        }
+        chains
+    }
+
+    pub(super) fn flow_node(&self, node: Node, id: NodeId) -> FlowNode {
+        FlowNode {
+            id,
+            language: self.language,
+            origin: self.origin.clone(),
+            span: self.host_range(node),
+            start_position: node.start_position(),
+        }
+    }
+
+    fn identifier(&self, node: Node, id: NodeId) -> (String, FlowNode) {
+        (
+            self.node_to_code(node).to_string(),
+            self.flow_node(node, id),
+        )
+    }
+
+    /// Internal function returning the value passed to a polyglot export call: its `value` keyword argument in Python,
+    /// and its second positional argument otherwise.
+    pub(super) fn export_value<'t>(&self, node: Node<'t>) -> Option<Node<'t>> {
+        let args = node.child_by_field_name("arguments")?;
+        let mut cursor = args.walk();
+        let second = args
+            .named_children(&mut cursor)
+            .filter(|arg| !matches!(arg.kind(), "keyword_argument" | "comment"))
+            .nth(1);
+
+        let mut cursor = args.walk();
+        let keyword = args.named_children(&mut cursor).find_map(|arg| {
+            let name = arg.child_by_field_name("name")?;
+            match arg.kind() == "keyword_argument" && self.node_to_code(name) == "value" {
+                true => arg.child_by_field_name("value"),
+                false => None,
+            }
+        });
+        keyword.or(second)
+    }
+
+    /// Internal function returning the identifier the result of a polyglot import call is assigned to, or declared with, with its NodeId.
+    pub(super) fn import_variable<'t>(
+        &self,
+        node: Node<'t>,
+        id: &NodeId,
+    ) -> Option<(Node<'t>, NodeId)> {
+        let parent = node.parent()?;
+        let field = match parent.kind() {
+            "assignment" | "assignment_expression" => "left",
+            "variable_declarator" => "name",
+            _ => return None,
+        };
+        let value_field = match field {
+            "left" => "right",
+            _ => "value",
+        };
+        if parent.child_by_field_name(value_field) != Some(node) {
+            return None;
+        }
+        let variable = parent.child_by_field_name(field)?;
+        if variable.kind() != "identifier" {
+            return None;
+        }
+
+        let mut parent_paths = id.paths().to_vec();
+        parent_paths.last_mut()?.pop()?;
+        let parent_id = NodeId::from_paths(parent_paths);
+        Some((variable, descendant_id(&parent_id, parent, variable)))
+    }
+}
+
+/// Internal function returning the NodeId of a descendant of the node of the given NodeId, within the same tree.
+pub(super) fn descendant_id(id: &NodeId, node: Node, descendant: Node) -> NodeId {
+    let mut path = Vec::new();
+    let mut current = descendant;
+    while current != node {
+        let Some(parent) = current.parent() else {
+            break;
+        };
+        let mut cursor = parent.walk();
+        let index = parent.children(&mut cursor).position(|c| c == current);
+        path.push(index.unwrap_or_default());
+        current = parent;
+    }
+    path.reverse();
+
+    let mut paths = id.paths().to_vec();
+    if let Some(last) = paths.last_mut() {
+        last.extend(path);
+    }
+    NodeId::from_paths(paths)
+}
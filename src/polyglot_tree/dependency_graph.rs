@@ -0,0 +1,266 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::build_options::BuildOptions;
+use super::call_sites::{EvalKind, EvalSite};
+use super::project::PolyglotProject;
+use super::util::{self, Language};
+use super::PolyglotTree;
+
+/// A file evaluating another file through a polyglot eval call, eg. `Polyglot.evalFile` in JavaScript or `polyglot.eval(path=...)` in Python.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDependency {
+    /// The file containing the eval call.
+    pub from: PathBuf,
+    /// The evaluated file, resolved relative to the directory of the file containing the call.
+    pub to: PathBuf,
+    /// The Language the evaluated file is evaluated as, or None if it is missing or not supported.
+    pub language: Option<Language>,
+    /// The byte range of the eval call in the file containing it.
+    pub span: Range<usize>,
+    /// Whether the evaluated file exists. Unresolved dependencies are kept in the graph so that missing files can be reported.
+    pub resolved: bool,
+}
+
+/// A directed graph of the files of a tree or a project and of the files they evaluate.
+///
+/// Paths are normalized lexically, without accessing the file system, so that `dir/../lib.py` and `./lib.py` are the same file.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+///
+/// let project = PolyglotProject::new("TestSamples", ["*.py", "*.js"]).unwrap();
+/// let graph = project.dependency_graph();
+///
+/// assert_eq!(graph.dependencies().len(), 2);
+/// assert!(graph.unresolved().next().is_none());
+/// assert_eq!(
+///     graph.reachable_from(Path::new("TestSamples/test_pyprint_file.js")),
+///     vec![PathBuf::from("TestSamples/pyprint.py")]
+/// );
+///
+/// let order = graph.topological_order().unwrap();
+/// let position = |file: &str| order.iter().position(|p| p == Path::new(file)).unwrap();
+/// assert!(position("TestSamples/import_x.py") < position("TestSamples/export_x.py"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    files: Vec<PathBuf>,
+    dependencies: Vec<FileDependency>,
+}
+
+impl DependencyGraph {
+    /// Internal function building the graph of the given files and of the file evaluations among the given eval sites.
+    /// Evaluated files are looked for with the FileProvider of the given options.
+    pub(crate) fn from_sites<'a>(
+        files: impl IntoIterator<Item = &'a Path>,
+        sites: &[EvalSite],
+        options: &BuildOptions,
+    ) -> DependencyGraph {
+        let files: Vec<PathBuf> = files.into_iter().map(util::normalize_path).collect();
+        let mut dependencies = Vec::new();
+        for site in sites {
+            let (Some(EvalKind::File), Some(from), Some(to)) =
+                (site.kind, site.origin.file(), &site.path)
+            else {
+                continue;
+            };
+            let (from, to) = (util::normalize_path(from), util::normalize_path(to));
+            dependencies.push(FileDependency {
+                resolved: options.provider().exists(&to),
+                from,
+                to,
+                language: site.guest_language,
+                span: site.span.clone(),
+            });
+        }
+        DependencyGraph::from_dependencies(files, dependencies)
+    }
+
+    /// Internal function building the graph of the given files, whose paths are normalized, and of the given dependencies.
+    pub(crate) fn from_dependencies(
+        files: impl IntoIterator<Item = PathBuf>,
+        dependencies: Vec<FileDependency>,
+    ) -> DependencyGraph {
+        let mut nodes: BTreeSet<PathBuf> = files.into_iter().collect();
+        for dependency in &dependencies {
+            nodes.insert(dependency.from.clone());
+            nodes.insert(dependency.to.clone());
+        }
+        DependencyGraph {
+            files: nodes.into_iter().collect(),
+            dependencies,
+        }
+    }
+
+    /// Returns every file of the graph, including evaluated files that do not exist, in alphabetical order.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns every dependency of the graph, one per eval call, so that a file evaluating another one twice depends on it twice.
+    pub fn dependencies(&self) -> &[FileDependency] {
+        &self.dependencies
+    }
+
+    /// Returns the dependencies of the files evaluated by the given file.
+    pub fn dependencies_of<'a>(
+        &'a self,
+        file: &Path,
+    ) -> impl Iterator<Item = &'a FileDependency> + 'a {
+        let file = util::normalize_path(file);
+        self.dependencies.iter().filter(move |d| d.from == file)
+    }
+
+    /// Returns the dependencies of the files evaluating the given file.
+    pub fn dependents_of<'a>(
+        &'a self,
+        file: &Path,
+    ) -> impl Iterator<Item = &'a FileDependency> + 'a {
+        let file = util::normalize_path(file);
+        self.dependencies.iter().filter(move |d| d.to == file)
+    }
+
+    /// Returns the dependencies whose evaluated file does not exist.
+    pub fn unresolved(&self) -> impl Iterator<Item = &FileDependency> {
+        self.dependencies.iter().filter(|d| !d.resolved)
+    }
+
+    /// Returns the files evaluated by the given file, directly or through other files, in breadth-first order.
+    /// The given file is only part of the result if it ends up evaluating itself.
+    pub fn reachable_from(&self, file: &Path) -> Vec<PathBuf> {
+        let mut reached = Vec::new();
+        let mut queue = VecDeque::from([util::normalize_path(file)]);
+        while let Some(file) = queue.pop_front() {
+            for dependency in self.dependencies.iter().filter(|d| d.from == file) {
+                if !reached.contains(&dependency.to) {
+                    reached.push(dependency.to.clone());
+                    queue.push_back(dependency.to.clone());
+                }
+            }
+        }
+        reached
+    }
+
+    /// Returns the files evaluating the given file, directly or through other files, in breadth-first order.
+    /// The given file is only part of the result if it ends up evaluating itself.
+    pub fn reaching(&self, file: &Path) -> Vec<PathBuf> {
+        let mut reached = Vec::new();
+        let mut queue = VecDeque::from([util::normalize_path(file)]);
+        while let Some(file) = queue.pop_front() {
+            for dependency in self.dependencies.iter().filter(|d| d.to == file) {
+                if !reached.contains(&dependency.from) {
+                    reached.push(dependency.from.clone());
+                    queue.push_back(dependency.from.clone());
+                }
+            }
+        }
+        reached
+    }
+
+    /// Returns every file of the graph, each one coming after all the files it evaluates, so that files can be built or analyzed in that order.
+    /// Files that do not depend on each other are given in alphabetical order.
+    ///
+    /// Returns None if some files evaluate each other in a cycle, in which case there is no such order, see `cycles`.
+    pub fn topological_order(&self) -> Option<Vec<PathBuf>> {
+        let index: HashMap<&PathBuf, usize> =
+            self.files.iter().enumerate().map(|(i, f)| (f, i)).collect();
+        let edges: BTreeSet<(usize, usize)> = self
+            .dependencies
+            .iter()
+            .map(|d| (index[&d.to], index[&d.from]))
+            .collect();
+
+        let mut pending = vec![0; self.files.len()];
+        for (_, dependent) in &edges {
+            pending[*dependent] += 1;
+        }
+        let mut ready: BTreeSet<usize> =
+            (0..self.files.len()).filter(|&i| pending[i] == 0).collect();
+        let mut order = Vec::with_capacity(self.files.len());
+        while let Some(i) = ready.pop_first() {
+            order.push(self.files[i].clone());
+            for (_, dependent) in edges.range((i, 0)..=(i, usize::MAX)) {
+                pending[*dependent] -= 1;
+                if pending[*dependent] == 0 {
+                    ready.insert(*dependent);
+                }
+            }
+        }
+        (order.len() == self.files.len()).then_some(order)
+    }
+
+    /// Returns a cycle for each group of files evaluating each other, however indirectly, as the files of the cycle
+    /// starting and ending with the first file of the group in alphabetical order, eg. `[a.py, b.js, a.py]`.
+    /// A file evaluating itself directly is a cycle of its own, eg. `[a.py, a.py]`.
+    ///
+    /// When a group of files has several cycles, the shortest one going through its first file is returned.
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        let mut grouped: HashSet<PathBuf> = HashSet::new();
+        let mut cycles = Vec::new();
+        for file in &self.files {
+            if grouped.contains(file) {
+                continue;
+            }
+            let reached = self.reachable_from(file);
+            if !reached.contains(file) {
+                continue;
+            }
+            let reaching = self.reaching(file);
+            let group: HashSet<&PathBuf> =
+                reached.iter().filter(|f| reaching.contains(f)).collect();
+            cycles.extend(self.shortest_cycle(file, &group));
+            grouped.extend(group.into_iter().cloned());
+        }
+        cycles
+    }
+
+    /// Internal function returning the shortest cycle going from the given file back to it through the files of its group.
+    fn shortest_cycle(&self, file: &PathBuf, group: &HashSet<&PathBuf>) -> Option<Vec<PathBuf>> {
+        let mut previous: HashMap<&PathBuf, &PathBuf> = HashMap::new();
+        let mut queue = VecDeque::from([file]);
+        while let Some(current) = queue.pop_front() {
+            for dependency in self.dependencies.iter().filter(|d| d.from == *current) {
+                if dependency.to == *file {
+                    let mut cycle = vec![file.clone()];
+                    let mut node = current;
+                    while node != file {
+                        cycle.push(node.clone());
+                        node = previous[node];
+                    }
+                    cycle[1..].reverse();
+                    cycle.push(file.clone());
+                    return Some(cycle);
+                }
+                if group.contains(&dependency.to) && !previous.contains_key(&dependency.to) {
+                    previous.insert(&dependency.to, current);
+                    queue.push_back(&dependency.to);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl PolyglotTree {
+    /// Returns the graph of the files evaluated by the tree, directly or from its subtrees.
+    /// Eval calls of code that does not come from a file, such as snippets, are left out.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::from_sites(
+            self.origin.file().map(PathBuf::as_path),
+            &self.eval_sites(),
+            &BuildOptions::default(),
+        )
+    }
+}
+
+impl PolyglotProject {
+    /// Returns the graph of the files of the project and of the files they evaluate, whether they are part of the project or not.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::from_sites(self.files(), &self.eval_sites(), self.options())
+    }
+}
@@ -0,0 +1,241 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::build_options::BuildOptions;
+use super::encoding::{
+    read_option, read_path, read_u64, read_usize, write_atomically, write_option, write_path,
+    write_u64, write_usize,
+};
+use super::fingerprint::Fnv64;
+use super::owned_tree::OwnedTree;
+use super::util::{self, Language};
+use super::PolyglotTree;
+
+/// The first bytes of the files written by a DiskCache, changed whenever their format changes.
+const MAGIC: &[u8] = b"polyglot_ast tree cache 1\n";
+
+/// A cache of the trees of files stored in a directory, to be shared by successive runs of a program, such as the jobs of a CI pipeline.
+///
+/// The tree of each file is stored as an OwnedTree, keyed by a fingerprint of the file's content, path and Language,
+/// and by a stamp of the grammars compiled in. It is read back, without parsing anything, for as long as neither the file
+/// nor the files it evaluates changed; otherwise the file is built again and its tree stored anew.
+/// The trees stored for previous contents of a file are kept, as the file may be reverted, and left for `clear` to remove.
+///
+/// Trees stored by another version of this crate, or with other grammars, are never read back, as their stamp differs.
+/// Since which subtrees are built depends on the BuildOptions, a cache should only be shared by builds using the same languages and maximum depth.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+/// use polyglot_ast::polyglot_tree::disk_cache::DiskCache;
+/// use polyglot_ast::util::Language;
+///
+/// let dir = std::env::temp_dir().join(format!("polyglot_ast_disk_cache_doc_{}", std::process::id()));
+/// let cache = DiskCache::new(&dir);
+/// let file = Path::new("TestSamples/test_pyprint_file.js");
+///
+/// let built = cache.get_or_build(file, Language::JavaScript, &BuildOptions::new()).unwrap();
+/// assert_eq!(cache.len(), 1);
+///
+/// // a later run reads the tree back instead of parsing the file and the file it evaluates
+/// let cache = DiskCache::new(&dir);
+/// let read = cache.get_or_build(file, Language::JavaScript, &BuildOptions::new()).unwrap();
+/// assert_eq!(read, built);
+/// assert_eq!(cache.len(), 1);
+///
+/// cache.clear().unwrap();
+/// assert!(cache.is_empty());
+/// # std::fs::remove_dir(&dir).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Initializes a DiskCache storing its trees in the given directory, which is created when the first tree is stored.
+    pub fn new(dir: impl Into<PathBuf>) -> DiskCache {
+        DiskCache { dir: dir.into() }
+    }
+
+    /// Returns the directory the trees are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the number of stored trees, up to date or not.
+    pub fn len(&self) -> usize {
+        self.entries().count()
+    }
+
+    /// Returns true if no tree is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every stored tree.
+    pub fn clear(&self) -> std::io::Result<()> {
+        self.entries().try_for_each(fs::remove_file)
+    }
+
+    /// Returns the tree of the file at the given path, read from the cache if it is up to date,
+    /// or built as allowed by the given BuildOptions and stored otherwise.
+    ///
+    /// Returns None if the file could not be read or parsed. Failing to read or write the cache is not an error:
+    /// the tree is built instead, and a warning is printed if it cannot be stored.
+    pub fn get_or_build(
+        &self,
+        path: &Path,
+        language: Language,
+        options: &BuildOptions,
+    ) -> Option<OwnedTree> {
+        let code = options.provider().read(path).ok()?;
+        let entry = self
+            .dir
+            .join(format!("{:016x}", key(path, language, &code)));
+        if let Some(tree) = read_entry(&entry, options) {
+            return Some(tree);
+        }
+
+        let tree = PolyglotTree::from_path_with_options(path.to_path_buf(), language, options)?;
+        let owned = tree.to_owned_tree();
+        if options.is_cancelled() {
+            return Some(owned); // possibly missing subtrees, so not worth storing
+        }
+        // the evaluated files are hashed as they are now, which may be after they were built from
+        let dependencies: Vec<(PathBuf, Option<u64>)> = tree
+            .eval_sites()
+            .into_iter()
+            .filter_map(|site| site.path)
+            .map(|path| {
+                let hash = content_hash(&path, options);
+                (path, hash)
+            })
+            .collect();
+        if let Err(e) = self.write_entry(&entry, &dependencies, &owned) {
+            eprintln!(
+                "Warning: unable to store the tree of {} in {}: {e}",
+                path.display(),
+                self.dir.display()
+            );
+        }
+        Some(owned)
+    }
+
+    /// Internal function returning the paths of the stored trees.
+    fn entries(&self) -> impl Iterator<Item = PathBuf> {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_none())
+    }
+
+    /// Internal function storing a tree along with the files it evaluates and the hashes of their content.
+    fn write_entry(
+        &self,
+        entry: &Path,
+        dependencies: &[(PathBuf, Option<u64>)],
+        tree: &OwnedTree,
+    ) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        write_atomically(entry, |writer| {
+            writer.write_all(MAGIC)?;
+            write_hashes(writer, dependencies)?;
+            tree.write_to(writer)
+        })
+    }
+}
+
+/// Internal function reading a stored tree, or returning None if there is none,
+/// if it cannot be read, or if one of the files it evaluates changed since it was stored.
+fn read_entry(entry: &Path, options: &BuildOptions) -> Option<OwnedTree> {
+    let mut reader = BufReader::new(File::open(entry).ok()?);
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic).ok()?;
+    if magic != MAGIC || !hashes_unchanged(&read_hashes(&mut reader).ok()?, options) {
+        return None;
+    }
+    OwnedTree::read_from(&mut reader).ok()
+}
+
+/// Internal function writing files along with the hashes of their content, to be read back by `read_hashes`.
+pub(crate) fn write_hashes(
+    writer: &mut impl Write,
+    files: &[(PathBuf, Option<u64>)],
+) -> std::io::Result<()> {
+    write_usize(writer, files.len())?;
+    for (path, hash) in files {
+        write_path(writer, path)?;
+        write_option(writer, hash.as_ref(), |writer, hash| {
+            write_u64(writer, *hash)
+        })?;
+    }
+    Ok(())
+}
+
+/// Internal function reading files along with the hashes of their content, as written by `write_hashes`.
+pub(crate) fn read_hashes(reader: &mut impl Read) -> std::io::Result<Vec<(PathBuf, Option<u64>)>> {
+    let mut files = Vec::new();
+    for _ in 0..read_usize(reader)? {
+        files.push((read_path(reader)?, read_option(reader, read_u64)?));
+    }
+    Ok(files)
+}
+
+/// Internal function returning whether the content of the given files still has the given hashes,
+/// including whether they still cannot be read.
+pub(crate) fn hashes_unchanged(files: &[(PathBuf, Option<u64>)], options: &BuildOptions) -> bool {
+    files
+        .iter()
+        .all(|(path, hash)| content_hash(path, options) == *hash)
+}
+
+/// Internal function returning the hash of the content of a file, or None if it cannot be read.
+pub(crate) fn content_hash(path: &Path, options: &BuildOptions) -> Option<u64> {
+    let mut hash = Fnv64::new();
+    hash.write(options.provider().read(path).ok()?.as_bytes());
+    Some(hash.finish())
+}
+
+/// Internal function returning the key a file's tree is stored under.
+fn key(path: &Path, language: Language, code: &str) -> u64 {
+    let mut hash = Fnv64::new();
+    hash.write(&grammar_stamp().to_le_bytes());
+    hash.write(path.to_string_lossy().as_bytes());
+    hash.write(util::language_enum_to_string(&language).as_bytes());
+    hash.write(code.as_bytes());
+    hash.finish()
+}
+
+/// Internal function hashing the version of this crate and the node kinds and fields of the grammars compiled in,
+/// so that trees stored with another version of either are not read back.
+pub(crate) fn grammar_stamp() -> u64 {
+    static STAMP: OnceLock<u64> = OnceLock::new();
+    *STAMP.get_or_init(|| {
+        let mut hash = Fnv64::new();
+        hash.write(env!("CARGO_PKG_VERSION").as_bytes());
+        for language in [Language::Python, Language::JavaScript, Language::Java] {
+            let Some(grammar) = util::try_grammar(&language) else {
+                hash.write(&[]);
+                continue;
+            };
+            hash.write_usize(grammar.version());
+            hash.write_usize(grammar.node_kind_count());
+            for id in 0..grammar.node_kind_count() {
+                let kind = grammar.node_kind_for_id(id as u16).unwrap_or_default();
+                hash.write(kind.as_bytes());
+            }
+            hash.write_usize(grammar.field_count());
+            for id in 1..=grammar.field_count() {
+                let field = grammar.field_name_for_id(id as u16).unwrap_or_default();
+                hash.write(field.as_bytes());
+            }
+        }
+        hash.finish()
+    })
+}
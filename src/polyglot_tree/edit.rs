@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use tree_sitter::{InputEdit, Node};
+
+use super::build_options::{BuildContext, BuildOptions};
+use super::parser_pool;
+use super::source_text::SourceText;
+use super::util::InvalidArgumentError;
+use super::{Origin, PolyglotTree};
+
+impl PolyglotTree {
+    /// Updates the tree after its code was edited, `new_source` being the code after the edit.
+    ///
+    /// The syntax tree is reparsed incrementally with tree-sitter, reusing what the edit did not touch,
+    /// and the subtrees of the polyglot eval calls entirely before or after the edited range are kept as they are,
+    /// only moved to their new positions. The other eval calls have their subtrees built again.
+    /// This makes updating a tree after each keystroke cheap enough for editors.
+    ///
+    /// Returns an InvalidArgumentError if the edit's byte offsets do not fit the old and new code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    /// use tree_sitter::{InputEdit, Point};
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'x = 1')\nPolyglot.eval(\"python\", 'y = 2')";
+    /// let mut tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let first: *const PolyglotTree = tree.subtrees().next().unwrap().1;
+    ///
+    /// // replace `2` by `42`
+    /// let edit = InputEdit {
+    ///     start_byte: 62,
+    ///     old_end_byte: 63,
+    ///     new_end_byte: 64,
+    ///     start_position: Point::new(1, 29),
+    ///     old_end_position: Point::new(1, 30),
+    ///     new_end_position: Point::new(1, 31),
+    /// };
+    /// tree.apply_edit(&edit, code.replace("y = 2", "y = 42")).unwrap();
+    ///
+    /// let subtrees: Vec<&PolyglotTree> = tree.subtrees().map(|(_, t)| t).collect();
+    /// assert!(std::ptr::eq(subtrees[0], first));
+    /// assert_eq!(subtrees[1].source(), "y = 42");
+    /// ```
+    pub fn apply_edit(
+        &mut self,
+        edit: &InputEdit,
+        new_source: impl ToString,
+    ) -> Result<(), InvalidArgumentError> {
+        self.apply_edit_with_options(edit, new_source, &BuildOptions::default())
+    }
+
+    /// Same as `apply_edit`, except that the subtrees built again are only built as allowed by the given BuildOptions.
+    pub fn apply_edit_with_options(
+        &mut self,
+        edit: &InputEdit,
+        new_source: impl ToString,
+        options: &BuildOptions,
+    ) -> Result<(), InvalidArgumentError> {
+        let code = new_source.to_string();
+        if edit.start_byte > edit.old_end_byte
+            || edit.start_byte > edit.new_end_byte
+            || edit.old_end_byte > self.code.len()
+            || edit.new_end_byte > code.len()
+        {
+            return Err(InvalidArgumentError);
+        }
+
+        let mut sites = Vec::new();
+        self.collect_sites(self.root_node(), &mut sites);
+        let reuse: HashMap<Range<usize>, Arc<PolyglotTree>> = sites
+            .into_iter()
+            .filter_map(|(range, subtree)| Some((moved(&range, edit)?, subtree)))
+            .map(|(range, subtree)| (range.clone(), relocate(subtree, &range, edit)))
+            .collect();
+
+        let mut tree = self.tree.clone();
+        tree.edit(edit);
+        let tree = parser_pool::parse(self.language, code.as_str(), Some(&tree), options)
+            .ok_or(InvalidArgumentError)?;
+        self.tree = tree;
+        self.code = SourceText::from(code);
+
+        let mut ctx = BuildContext::new(options);
+        if let Some(file) = self.origin.file() {
+            ctx = ctx.in_file(file);
+        }
+        ctx.reuse = Some(&reuse);
+        (self.node_to_subtrees_map, self.polyglot_nodes) = self.build_polyglot_tree(&ctx);
+        self.bindings = OnceLock::new();
+        self.stats = OnceLock::new();
+        Ok(())
+    }
+
+    /// Internal function collecting the byte range of every eval call with a subtree, along with the subtree.
+    fn collect_sites(&self, node: Node, sites: &mut Vec<(Range<usize>, Arc<PolyglotTree>)>) {
+        if let Some(subtree) = self.node_to_subtrees_map.get(&node.id()) {
+            sites.push((node.byte_range(), subtree.clone()));
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.collect_sites(child, sites);
+        }
+    }
+}
+
+/// Returns where the given range of the old code is in the new code, or None if the edit touched it.
+fn moved(range: &Range<usize>, edit: &InputEdit) -> Option<Range<usize>> {
+    if range.end <= edit.start_byte {
+        Some(range.clone())
+    } else if range.start >= edit.old_end_byte {
+        Some(
+            range.start + edit.new_end_byte - edit.old_end_byte
+                ..range.end + edit.new_end_byte - edit.old_end_byte,
+        )
+    } else {
+        None
+    }
+}
+
+/// Returns the subtree of an eval call that moved to `range`, with its coordinates updated if it is an inline snippet.
+fn relocate(
+    subtree: Arc<PolyglotTree>,
+    range: &Range<usize>,
+    edit: &InputEdit,
+) -> Arc<PolyglotTree> {
+    match &subtree.origin {
+        Origin::Literal {
+            file,
+            range: literal,
+        } if range.start >= edit.new_end_byte && edit.new_end_byte != edit.old_end_byte => {
+            let origin = Origin::Literal {
+                file: file.clone(),
+                range: literal.start + edit.new_end_byte - edit.old_end_byte
+                    ..literal.end + edit.new_end_byte - edit.old_end_byte,
+            };
+            Arc::new(subtree.relocated(origin))
+        }
+        _ => subtree, // files and snippets before the edit did not move
+    }
+}
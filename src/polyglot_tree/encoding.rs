@@ -0,0 +1,135 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::util::{self, Language};
+
+// The compact binary form of the data stored on disk by OwnedTree, DiskCache and ProjectState:
+// integers are written as 8 little-endian bytes, strings and paths as their length followed by their UTF-8 bytes,
+// and optional values as a byte telling whether they are there followed by the value itself.
+
+/// Internal function writing a file through `write`, to a temporary file first that is then renamed,
+/// so that concurrent runs never read a partially written file.
+pub(crate) fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+    let result = File::create(&temporary).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()
+    });
+    result
+        .and_then(|_| fs::rename(&temporary, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+}
+
+/// Returns the error reported when reading data that was not written by this module, or by another version of it.
+pub(crate) fn invalid_data() -> Error {
+    Error::new(ErrorKind::InvalidData, "invalid or outdated data")
+}
+
+pub(crate) fn write_usize(writer: &mut impl Write, n: usize) -> std::io::Result<()> {
+    write_u64(writer, n as u64)
+}
+
+pub(crate) fn write_u64(writer: &mut impl Write, n: u64) -> std::io::Result<()> {
+    writer.write_all(&n.to_le_bytes())
+}
+
+pub(crate) fn write_str(writer: &mut impl Write, s: &str) -> std::io::Result<()> {
+    write_usize(writer, s.len())?;
+    writer.write_all(s.as_bytes())
+}
+
+pub(crate) fn write_path(writer: &mut impl Write, path: &Path) -> std::io::Result<()> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "path is not valid UTF-8"))?;
+    write_str(writer, path)
+}
+
+pub(crate) fn write_language(writer: &mut impl Write, language: Language) -> std::io::Result<()> {
+    write_str(writer, util::language_enum_to_string(&language))
+}
+
+pub(crate) fn write_range(writer: &mut impl Write, range: &Range<usize>) -> std::io::Result<()> {
+    write_usize(writer, range.start)?;
+    write_usize(writer, range.end)
+}
+
+pub(crate) fn write_pair(writer: &mut impl Write, (a, b): (usize, usize)) -> std::io::Result<()> {
+    write_usize(writer, a)?;
+    write_usize(writer, b)
+}
+
+pub(crate) fn write_option<W: Write, T: ?Sized>(
+    writer: &mut W,
+    value: Option<&T>,
+    write: impl FnOnce(&mut W, &T) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    match value {
+        Some(value) => {
+            writer.write_all(&[1])?;
+            write(writer, value)
+        }
+        None => writer.write_all(&[0]),
+    }
+}
+
+pub(crate) fn read_byte(reader: &mut impl Read) -> std::io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+pub(crate) fn read_usize(reader: &mut impl Read) -> std::io::Result<usize> {
+    usize::try_from(read_u64(reader)?).map_err(|_| invalid_data())
+}
+
+pub(crate) fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_string(reader: &mut impl Read) -> std::io::Result<String> {
+    let len = read_usize(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data())
+}
+
+pub(crate) fn read_path(reader: &mut impl Read) -> std::io::Result<PathBuf> {
+    read_string(reader).map(PathBuf::from)
+}
+
+pub(crate) fn read_language(reader: &mut impl Read) -> std::io::Result<Language> {
+    util::language_string_to_enum(&read_string(reader)?).map_err(|_| invalid_data())
+}
+
+pub(crate) fn read_range(reader: &mut impl Read) -> std::io::Result<Range<usize>> {
+    Ok(read_usize(reader)?..read_usize(reader)?)
+}
+
+pub(crate) fn read_pair(reader: &mut impl Read) -> std::io::Result<(usize, usize)> {
+    Ok((read_usize(reader)?, read_usize(reader)?))
+}
+
+pub(crate) fn read_option<R: Read, T>(
+    reader: &mut R,
+    read: impl FnOnce(&mut R) -> std::io::Result<T>,
+) -> std::io::Result<Option<T>> {
+    match read_byte(reader)? {
+        0 => Ok(None),
+        1 => read(reader).map(Some),
+        _ => Err(invalid_data()),
+    }
+}
@@ -0,0 +1,386 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use super::polyglot_processor::PolygotProcessor;
+use super::polyglot_zipper::PolyglotZipper;
+use super::util;
+
+/// Returns the children of the node the zipper is on, subtree roots included.
+fn children<'a>(zip: &PolyglotZipper<'a>) -> impl Iterator<Item = PolyglotZipper<'a>> {
+    std::iter::successors(zip.child(0), |child| child.next_sibling())
+}
+
+/// A value of a record written by the exporters, as JSON, YAML or CSV.
+///
+/// The exporters of this module and the command-line interface write their records through the same functions,
+/// `write_json_members`, `write_yaml_mapping` and `write_csv_record`, so that values are escaped the same way everywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportValue {
+    Str(String),
+    Int(usize),
+    Bool(bool),
+    Null,
+    /// A line and column, or a row and column.
+    Position(usize, usize),
+}
+
+impl ExportValue {
+    /// Returns the value of an optional string, or Null if there is none.
+    pub fn optional(value: Option<&str>) -> ExportValue {
+        value.map_or(ExportValue::Null, |value| {
+            ExportValue::Str(value.to_string())
+        })
+    }
+
+    /// Returns the value as JSON, which is also valid YAML.
+    pub fn to_json(&self) -> String {
+        match self {
+            ExportValue::Str(s) => util::json_string(s),
+            ExportValue::Int(i) => i.to_string(),
+            ExportValue::Bool(b) => b.to_string(),
+            ExportValue::Null => "null".to_string(),
+            ExportValue::Position(line, column) => format!("[{line},{column}]"),
+        }
+    }
+
+    /// Returns the value as a CSV field, empty for Null.
+    pub fn to_csv(&self) -> Cow<'_, str> {
+        match self {
+            ExportValue::Str(s) => util::csv_field(s),
+            ExportValue::Null => Cow::Borrowed(""),
+            ExportValue::Position(line, column) => Cow::Owned(format!("{line}:{column}")),
+            value => Cow::Owned(value.to_json()),
+        }
+    }
+}
+
+/// Writes the given fields as the members of a JSON object, separated by commas, without the braces around them.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::exporter::{write_json_members, ExportValue};
+///
+/// let mut result = String::new();
+/// write_json_members(&mut result, &[("kind", ExportValue::Str(String::from("call"))), ("start", ExportValue::Position(0, 4))]);
+/// assert_eq!(result, "\"kind\":\"call\",\"start\":[0,4]");
+/// ```
+pub fn write_json_members(result: &mut String, fields: &[(&str, ExportValue)]) {
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+        let _ = write!(result, "{}:{}", util::json_string(name), value.to_json());
+    }
+}
+
+/// Writes the given fields as a YAML mapping, one per line, the first line starting with `first` and the others with `indent`.
+/// Values are written as JSON literals, which are valid YAML flow scalars and sequences.
+pub fn write_yaml_mapping(
+    result: &mut String,
+    fields: &[(&str, ExportValue)],
+    first: &str,
+    indent: &str,
+) {
+    for (i, (name, value)) in fields.iter().enumerate() {
+        let prefix = if i == 0 { first } else { indent };
+        let _ = writeln!(result, "{prefix}{name}: {}", value.to_json());
+    }
+}
+
+/// Writes the given values as a CSV record, ending with a line break.
+pub fn write_csv_record(result: &mut String, values: &[ExportValue]) {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+        result.push_str(&value.to_csv());
+    }
+    result.push('\n');
+}
+
+/// Internal function returning the fields written for every node by the JSON and YAML exporters.
+fn node_fields(zip: &PolyglotZipper) -> Vec<(&'static str, ExportValue)> {
+    let (start, end) = zip.host_positions();
+    let mut fields = vec![
+        ("kind", ExportValue::Str(zip.kind().to_string())),
+        (
+            "language",
+            ExportValue::Str(util::language_enum_to_string(zip.get_lang()).to_string()),
+        ),
+        ("start", ExportValue::Position(start.row, start.column)),
+        ("end", ExportValue::Position(end.row, end.column)),
+    ];
+    if zip.child(0).is_none() {
+        fields.push(("text", ExportValue::Str(zip.code().to_string())));
+    }
+    fields
+}
+
+/// A processor that exports the polyglot AST as a nested JSON object.
+///
+/// Every node has a `kind`, a `language`, and `start` and `end` positions as `[row, column]`, zero-based and in the coordinates
+/// of the file its code physically lives in. Leaves have the `text` of their code, other nodes their `children`.
+/// After processing a tree, use the `get_result` method to retrieve the generated string.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::exporter::JsonExporter;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("x = 1", Language::Python).unwrap();
+/// let mut exporter = JsonExporter::new();
+/// tree.apply(&mut exporter);
+///
+/// assert!(exporter.get_result().starts_with("{\"kind\":\"module\",\"language\":\"python\",\"start\":[0,0]"));
+/// ```
+#[derive(Debug, Default)]
+pub struct JsonExporter {
+    result: String,
+}
+
+impl JsonExporter {
+    /// Initializes a new JsonExporter instance.
+    pub fn new() -> JsonExporter {
+        JsonExporter::default()
+    }
+
+    /// Returns the JSON export of the last processed polyglot tree, or an empty string if no tree was processed yet.
+    pub fn get_result(&self) -> &str {
+        self.result.as_str()
+    }
+
+    fn process_impl(&mut self, zip: PolyglotZipper) {
+        self.result.push('{');
+        write_json_members(&mut self.result, &node_fields(&zip));
+        if zip.child(0).is_none() {
+            self.result.push('}');
+            return;
+        }
+        self.result.push_str(",\"children\":[");
+        for (i, child) in children(&zip).enumerate() {
+            if i > 0 {
+                self.result.push(',');
+            }
+            self.process_impl(child);
+        }
+        self.result.push_str("]}");
+    }
+}
+
+impl PolygotProcessor for JsonExporter {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.result = String::new();
+        self.process_impl(zip);
+    }
+}
+
+/// A processor that exports the polyglot AST as a nested YAML mapping, with the same fields as the JsonExporter.
+/// After processing a tree, use the `get_result` method to retrieve the generated string.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::exporter::YamlExporter;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("x = 1", Language::Python).unwrap();
+/// let mut exporter = YamlExporter::new();
+/// tree.apply(&mut exporter);
+///
+/// assert!(exporter.get_result().starts_with("kind: \"module\"\nlanguage: \"python\"\nstart: [0,0]\n"));
+/// ```
+#[derive(Debug, Default)]
+pub struct YamlExporter {
+    result: String,
+}
+
+impl YamlExporter {
+    /// Initializes a new YamlExporter instance.
+    pub fn new() -> YamlExporter {
+        YamlExporter::default()
+    }
+
+    /// Returns the YAML export of the last processed polyglot tree, or an empty string if no tree was processed yet.
+    pub fn get_result(&self) -> &str {
+        self.result.as_str()
+    }
+
+    /// Writes a node as a mapping whose first line starts with `first` and whose other lines start with `indent`.
+    fn process_impl(&mut self, zip: PolyglotZipper, first: &str, indent: &str) {
+        write_yaml_mapping(&mut self.result, &node_fields(&zip), first, indent);
+        if zip.child(0).is_none() {
+            return;
+        }
+        let _ = writeln!(self.result, "{indent}children:");
+        let (first, indent) = (format!("{indent}  - "), format!("{indent}    "));
+        for child in children(&zip) {
+            self.process_impl(child, &first, &indent);
+        }
+    }
+}
+
+impl PolygotProcessor for YamlExporter {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.result = String::new();
+        self.process_impl(zip, "", "");
+    }
+}
+
+/// A processor that exports the polyglot AST as CSV, with a header and one record per node in depth-first order.
+///
+/// Nodes are numbered in that order, and each record gives the number of the node and of its parent, empty for the root,
+/// followed by its kind, language, zero-based positions in the coordinates of the file its code physically lives in,
+/// and the code of leaves. After processing a tree, use the `get_result` method to retrieve the generated string.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::exporter::CsvExporter;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("x = 1", Language::Python).unwrap();
+/// let mut exporter = CsvExporter::new();
+/// tree.apply(&mut exporter);
+/// let mut lines = exporter.get_result().lines();
+///
+/// assert_eq!(lines.next(), Some("node,parent,kind,language,start_row,start_column,end_row,end_column,text"));
+/// assert_eq!(lines.next(), Some("0,,module,python,0,0,0,5,"));
+/// assert!(lines.any(|line| line == "3,2,identifier,python,0,0,0,1,x"));
+/// ```
+#[derive(Debug, Default)]
+pub struct CsvExporter {
+    result: String,
+    count: usize,
+}
+
+impl CsvExporter {
+    /// The header of the CSV export.
+    pub const HEADER: &'static str =
+        "node,parent,kind,language,start_row,start_column,end_row,end_column,text";
+
+    /// Initializes a new CsvExporter instance.
+    pub fn new() -> CsvExporter {
+        CsvExporter::default()
+    }
+
+    /// Returns the CSV export of the last processed polyglot tree, or an empty string if no tree was processed yet.
+    pub fn get_result(&self) -> &str {
+        self.result.as_str()
+    }
+
+    fn process_impl(&mut self, zip: PolyglotZipper, parent: Option<usize>) {
+        let node = self.count;
+        self.count += 1;
+        let (start, end) = zip.host_positions();
+        let is_leaf = zip.child(0).is_none();
+        write_csv_record(
+            &mut self.result,
+            &[
+                ExportValue::Int(node),
+                parent.map_or(ExportValue::Null, ExportValue::Int),
+                ExportValue::Str(zip.kind().to_string()),
+                ExportValue::Str(util::language_enum_to_string(zip.get_lang()).to_string()),
+                ExportValue::Int(start.row),
+                ExportValue::Int(start.column),
+                ExportValue::Int(end.row),
+                ExportValue::Int(end.column),
+                ExportValue::Str(if is_leaf { zip.code() } else { "" }.to_string()),
+            ],
+        );
+        for child in children(&zip) {
+            self.process_impl(child, Some(node));
+        }
+    }
+}
+
+impl PolygotProcessor for CsvExporter {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.result = format!("{}\n", CsvExporter::HEADER);
+        self.count = 0;
+        self.process_impl(zip, None);
+    }
+}
+
+/// A processor that exports the polyglot AST as a Graphviz DOT digraph, to be rendered with `dot -Tsvg` for instance.
+///
+/// Nodes are labelled with their kind, and the code of leaves, and colored after their language.
+/// The edges entering the root of a subtree, whose code is evaluated by its parent, are dashed.
+/// After processing a tree, use the `get_result` method to retrieve the generated string.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::exporter::DotExporter;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("import polyglot\npolyglot.eval(language=\"js\", string=\"1\")", Language::Python).unwrap();
+/// let mut exporter = DotExporter::new();
+/// tree.apply(&mut exporter);
+///
+/// assert!(exporter.get_result().starts_with("digraph polyglot_ast {\n"));
+/// assert!(exporter.get_result().contains("[style=dashed]"));
+/// ```
+#[derive(Debug, Default)]
+pub struct DotExporter {
+    result: String,
+    count: usize,
+}
+
+impl DotExporter {
+    /// Initializes a new DotExporter instance.
+    pub fn new() -> DotExporter {
+        DotExporter::default()
+    }
+
+    /// Returns the DOT export of the last processed polyglot tree, or an empty string if no tree was processed yet.
+    pub fn get_result(&self) -> &str {
+        self.result.as_str()
+    }
+
+    fn process_impl(&mut self, zip: PolyglotZipper, parent: Option<usize>) {
+        let node = self.count;
+        self.count += 1;
+        let label = match zip.child(0) {
+            Some(_) => zip.kind().to_string(),
+            None => format!("{}: {}", zip.kind(), zip.code()),
+        };
+        let color = match zip.get_lang() {
+            util::Language::Python => "#3572a5",
+            util::Language::JavaScript => "#f1e05a",
+            util::Language::Java => "#b07219",
+        };
+        // DOT strings are quoted and escaped like JSON ones
+        let _ = writeln!(
+            self.result,
+            "  n{node} [label={}, color=\"{color}\"];",
+            util::json_string(&label)
+        );
+        match parent {
+            Some(parent) if zip.parent().is_some_and(|p| p.is_polyglot_eval_call()) => {
+                let _ = writeln!(self.result, "  n{parent} -> n{node} [style=dashed];");
+            }
+            Some(parent) => {
+                let _ = writeln!(self.result, "  n{parent} -> n{node};");
+            }
+            None => {}
+        }
+        for child in children(&zip) {
+            self.process_impl(child, Some(node));
+        }
+    }
+}
+
+impl PolygotProcessor for DotExporter {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.result = String::from("digraph polyglot_ast {\n  node [shape=box];\n");
+        self.count = 0;
+        self.process_impl(zip, None);
+        self.result.push_str("}\n");
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::build_options::BuildOptions;
+use super::sharded_map::ShardedMap;
+use super::source_text::SourceText;
+use super::util::Language;
+use super::PolyglotTree;
+
+/// A cache of the subtrees built for files evaluated by polyglot eval calls, to be shared by several builds with `BuildOptions::file_cache`.
+///
+/// When several host files evaluate the same helper script, it is read and parsed once and its subtree is shared by all of them.
+/// A cached subtree is reused as long as the file's modification time did not change; otherwise the file is read again,
+/// and the subtree is only rebuilt if its content actually changed.
+///
+/// Only the modification time of the file itself is checked: when a file it evaluates changes, its cached subtree, which contains
+/// the subtree of the evaluated file, is still reused. The subtrees of the files evaluating a changed file must then be removed
+/// with `invalidate`, as `PolyglotProject::update` does using the dependency graph of the project.
+///
+/// Since which subtrees are built depends on the BuildOptions, a cache should only be shared by builds using the same languages and maximum depth.
+///
+/// The cache only lives in memory, for the lifetime of the process, as the subtrees it shares hold tree-sitter syntax trees.
+/// To skip parsing unchanged files across runs, see DiskCache, which stores OwnedTrees instead.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::sync::Arc;
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+/// use polyglot_ast::polyglot_tree::file_cache::FileCache;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let cache = Arc::new(FileCache::new());
+/// let options = BuildOptions::new().file_cache(cache.clone());
+///
+/// let file = PathBuf::from("TestSamples/test_pyprint_file.js");
+/// let a = PolyglotTree::from_path_with_options(file.clone(), Language::JavaScript, &options).unwrap();
+/// let b = PolyglotTree::from_path_with_options(file, Language::JavaScript, &options).unwrap();
+///
+/// assert_eq!(cache.len(), 1);
+/// let (_, subtree_a) = a.subtrees().next().unwrap();
+/// let (_, subtree_b) = b.subtrees().next().unwrap();
+/// assert!(std::ptr::eq(subtree_a, subtree_b));
+/// ```
+#[derive(Default)]
+pub struct FileCache {
+    // sharded so that threads building different files do not wait for each other
+    entries: ShardedMap<CacheKey, CacheEntry>,
+}
+
+/// The file, its Language and the number of language boundaries crossed to reach it.
+type CacheKey = (PathBuf, Language, usize);
+
+struct CacheEntry {
+    modified: Option<SystemTime>,
+    tree: Arc<PolyglotTree>,
+}
+
+impl FileCache {
+    /// Initializes a new, empty FileCache.
+    pub fn new() -> FileCache {
+        FileCache::default()
+    }
+
+    /// Returns the number of cached subtrees.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no subtree is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every cached subtree.
+    pub fn clear(&self) {
+        self.entries.clear()
+    }
+
+    /// Removes the subtrees cached for the files matching `f`, whatever their Language and depth,
+    /// for when a file they evaluate changed without their own modification time changing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use std::sync::Arc;
+    /// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+    /// use polyglot_ast::polyglot_tree::file_cache::FileCache;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let cache = Arc::new(FileCache::new());
+    /// let options = BuildOptions::new().file_cache(cache.clone());
+    /// let file = PathBuf::from("TestSamples/test_pyprint_file.js");
+    /// PolyglotTree::from_path_with_options(file, Language::JavaScript, &options).unwrap();
+    /// assert_eq!(cache.len(), 1);
+    ///
+    /// cache.invalidate(|path| path.ends_with("pyprint.py"));
+    /// assert!(cache.is_empty());
+    /// ```
+    pub fn invalidate(&self, mut f: impl FnMut(&Path) -> bool) {
+        self.entries.retain(|(path, _, _), _| !f(path))
+    }
+
+    /// Internal function returning the cached subtree for the given file if it is still up to date,
+    /// or reading the file and building its subtree with `build` otherwise.
+    ///
+    /// No lock is held while building, as building the subtree can use the cache for the files it evaluates in turn.
+    pub(crate) fn get_or_build(
+        &self,
+        path: &Path,
+        language: Language,
+        depth: usize,
+        options: &BuildOptions,
+        build: impl FnOnce(SourceText) -> Option<PolyglotTree>,
+    ) -> Option<Arc<PolyglotTree>> {
+        let key = (path.to_path_buf(), language, depth);
+        let modified = options.provider().modified(path);
+        if let Some(entry) = self.entries.shard(&key).get(&key) {
+            if modified.is_some() && entry.modified == modified {
+                return Some(entry.tree.clone());
+            }
+        }
+
+        let code = PolyglotTree::read_file(path, options)?;
+        if let Some(entry) = self.entries.shard(&key).get_mut(&key) {
+            if entry.tree.source() == &*code {
+                entry.modified = modified; // only the modification time changed
+                return Some(entry.tree.clone());
+            }
+        }
+
+        let tree = Arc::new(build(code)?);
+        if options.is_cancelled() {
+            return Some(tree); // possibly missing subtrees, so not worth reusing
+        }
+        self.entries.shard(&key).insert(
+            key,
+            CacheEntry {
+                modified,
+                tree: tree.clone(),
+            },
+        );
+        Some(tree)
+    }
+
+    /// Internal function to store the subtree built for the given file, whose modification time was `modified` when it was read.
+    pub(crate) fn insert(
+        &self,
+        path: PathBuf,
+        language: Language,
+        depth: usize,
+        modified: Option<SystemTime>,
+        tree: Arc<PolyglotTree>,
+    ) {
+        let key = (path, language, depth);
+        self.entries
+            .shard(&key)
+            .insert(key, CacheEntry { modified, tree });
+    }
+}
+
+impl fmt::Debug for FileCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileCache")
+            .field("len", &self.len())
+            .finish()
+    }
+}
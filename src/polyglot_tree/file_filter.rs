@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use super::util::{self, Language};
+
+/// Which files of a directory are part of a PolyglotProject, see `PolyglotProject::with_filter`.
+///
+/// A file is part of the project if it matches one of the include patterns, or if there are none, and matches none of the exclude patterns.
+/// Files ignored by the `.gitignore` files of the directory are also left out if `gitignore` is set.
+///
+/// Include patterns are matched against the path of each file relative to the root, with `/` separators:
+/// `*` matches any part of a file or directory name, `?` matches a single character and `**` matches any number of directories.
+/// For instance, `*.py` only matches the Python files at the root, whereas `**/*.py` matches them in every directory.
+///
+/// Exclude patterns follow the rules of `.gitignore` files: a pattern without any `/` but a trailing one matches files and directories
+/// of that name at any depth, a pattern with a leading or middle `/` is relative to the root, and a pattern with a trailing `/` only matches directories.
+/// Everything inside an excluded directory is excluded, so `target/` excludes every file of every `target` directory.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::file_filter::FileFilter;
+/// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+///
+/// let filter = FileFilter::new().include(["**/*.py"]).exclude(["import_*"]);
+/// let project = PolyglotProject::with_filter("TestSamples", filter, &BuildOptions::new()).unwrap();
+/// assert_eq!(project.files().count(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    gitignore: bool,
+}
+
+impl FileFilter {
+    /// Returns a filter keeping every file, whatever the `.gitignore` files say.
+    pub fn new() -> FileFilter {
+        FileFilter::default()
+    }
+
+    /// Adds glob patterns the files must match one of.
+    pub fn include(mut self, patterns: impl IntoIterator<Item = impl ToString>) -> FileFilter {
+        self.include
+            .extend(patterns.into_iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Adds `.gitignore` patterns the files must not match, such as `vendor/` or `*.min.js`.
+    pub fn exclude(mut self, patterns: impl IntoIterator<Item = impl ToString>) -> FileFilter {
+        self.exclude
+            .extend(patterns.into_iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Sets whether the files ignored by the `.gitignore` files of the directory and of its subdirectories are left out,
+    /// along with the `.git` directory. Negated patterns, starting with `!`, are supported in `.gitignore` files.
+    pub fn gitignore(mut self, honor: bool) -> FileFilter {
+        self.gitignore = honor;
+        self
+    }
+
+    /// Returns the include patterns.
+    pub fn included(&self) -> &[String] {
+        &self.include
+    }
+
+    /// Returns the exclude patterns.
+    pub fn excluded(&self) -> &[String] {
+        &self.exclude
+    }
+
+    /// Internal function walking the root directory for the files kept by the filter, along with their Language detected from their extension.
+    /// Files of unsupported languages are left out.
+    pub(crate) fn find_files(&self, root: &Path) -> std::io::Result<Vec<(PathBuf, Language)>> {
+        let excluded: Vec<IgnoreRule> = self
+            .exclude
+            .iter()
+            .filter_map(|pattern| IgnoreRule::parse(pattern, ""))
+            .collect();
+        let mut files = Vec::new();
+        let mut directories = vec![(root.to_path_buf(), String::new(), Vec::new())];
+        while let Some((directory, prefix, mut rules)) = directories.pop() {
+            let entries = match std::fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(e) if prefix.is_empty() => return Err(e),
+                Err(e) => {
+                    eprintln!(
+                        "Warning: unable to read directory {} due to the following error: {e}",
+                        directory.display()
+                    );
+                    continue;
+                }
+            };
+            if self.gitignore {
+                if let Ok(content) = std::fs::read_to_string(directory.join(".gitignore")) {
+                    rules.extend(
+                        content
+                            .lines()
+                            .filter_map(|line| IgnoreRule::parse(line, &prefix)),
+                    );
+                }
+            }
+
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let relative = format!("{prefix}{name}");
+                let is_dir = file_type.is_dir();
+                if excluded.iter().any(|rule| rule.matches(&relative, is_dir))
+                    || (self.gitignore
+                        && (is_dir && name == ".git" || ignored(&rules, &relative, is_dir)))
+                {
+                    continue;
+                }
+                if is_dir {
+                    directories.push((entry.path(), format!("{relative}/"), rules.clone()));
+                } else if self.include.is_empty()
+                    || self.include.iter().any(|p| glob_matches(p, &relative))
+                {
+                    if let Some(language) = util::language_from_path(&entry.path()) {
+                        files.push((entry.path(), language));
+                    }
+                }
+            }
+        }
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(files)
+    }
+}
+
+/// A pattern of a `.gitignore` file, or an exclude pattern.
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// The pattern, relative to `base` if it is anchored, or else matching at any depth below `base`.
+    pattern: String,
+    /// The directory of the `.gitignore` file the rule comes from, relative to the root, with a trailing `/`.
+    base: String,
+    anchored: bool,
+    directories_only: bool,
+    negated: bool,
+}
+
+impl IgnoreRule {
+    /// Parses a line of a `.gitignore` file found in the given directory, returning None for blank lines and comments.
+    fn parse(line: &str, base: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (directories_only, line) = match line.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        Some(IgnoreRule {
+            pattern: line.strip_prefix('/').unwrap_or(line).to_string(),
+            base: base.to_string(),
+            anchored,
+            directories_only,
+            negated,
+        })
+    }
+
+    /// Returns true if the rule matches the file or directory at the given path relative to the root.
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.directories_only && !is_dir {
+            return false;
+        }
+        let Some(path) = relative.strip_prefix(self.base.as_str()) else {
+            return false;
+        };
+        match self.anchored {
+            true => glob_matches(&self.pattern, path),
+            false => glob_matches(&format!("**/{}", self.pattern), path),
+        }
+    }
+}
+
+/// Internal function returning whether the last of the rules matching a path ignores it.
+fn ignored(rules: &[IgnoreRule], relative: &str, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(relative, is_dir))
+        .is_some_and(|rule| !rule.negated)
+}
+
+/// Internal function to check whether a path relative to the root of a project, with `/` separators, matches a glob pattern.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=path.len()).any(|skipped| segments_match(rest, &path[skipped..]))
+        }
+        Some((segment, rest)) => path.split_first().is_some_and(|(name, path)| {
+            let segment: Vec<char> = segment.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            name_matches(&segment, &name) && segments_match(rest, path)
+        }),
+    }
+}
+
+fn name_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skipped| name_matches(rest, &name[skipped..])),
+        Some(('?', rest)) => !name.is_empty() && name_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && name_matches(rest, &name[1..]),
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::util;
+
+/// Where the code of files is read from, to be given to builds with `BuildOptions::file_provider`.
+///
+/// By default, files are read from the file system. Embedders can implement this trait to build trees from in-memory sources,
+/// archives or remote content instead, both for the files given to `PolyglotTree::from_path` and for the files they evaluate.
+///
+/// The directories walked by `PolyglotProject` are still read from the file system.
+pub trait FileProvider: fmt::Debug + Send + Sync {
+    /// Returns the code of the file at the given path, or an error if it cannot be read.
+    fn read(&self, path: &Path) -> std::io::Result<String>;
+
+    /// Returns true if there is a file at the given path.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Returns the canonical form of the given path, the same for every path leading to the same file,
+    /// or an error if there is no file at that path.
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf>;
+
+    /// Returns the last time the file at the given path was modified, if it is known.
+    ///
+    /// Cached trees of files with no known modification time are checked against their code each time they are used.
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        let _ = path;
+        None
+    }
+}
+
+/// The FileProvider reading files from the file system, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsFileProvider;
+
+impl FileProvider for OsFileProvider {
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// A FileProvider serving files held in memory, for instance the unsaved buffers of an editor or test fixtures.
+///
+/// Paths are compared once normalized, so that `dir/./lib.py` and `dir/sub/../lib.py` are the same file as `dir/lib.py`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::sync::Arc;
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+/// use polyglot_ast::polyglot_tree::file_provider::MemoryFileProvider;
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::util::Language;
+///
+/// let files = MemoryFileProvider::new()
+///     .with_file("src/main.js", "Polyglot.evalFile('python', 'lib.py')")
+///     .with_file("src/lib.py", "print(42)");
+/// let options = BuildOptions::new().file_provider(Arc::new(files));
+///
+/// let tree = PolyglotTree::from_path_with_options(PathBuf::from("src/main.js"), Language::JavaScript, &options).unwrap();
+/// assert_eq!(tree.subtrees().next().unwrap().1.source(), "print(42)");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileProvider {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryFileProvider {
+    /// Initializes a new provider with no file.
+    pub fn new() -> MemoryFileProvider {
+        MemoryFileProvider::default()
+    }
+
+    /// Adds a file with the given code, replacing any file at the same path.
+    pub fn with_file(mut self, path: impl AsRef<Path>, code: impl ToString) -> MemoryFileProvider {
+        self.insert(path, code);
+        self
+    }
+
+    /// Adds a file with the given code, replacing any file at the same path.
+    pub fn insert(&mut self, path: impl AsRef<Path>, code: impl ToString) {
+        self.files
+            .insert(util::normalize_path(path.as_ref()), code.to_string());
+    }
+
+    /// Removes the file at the given path, returning its code if there was one.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<String> {
+        self.files.remove(&util::normalize_path(path.as_ref()))
+    }
+}
+
+impl FileProvider for MemoryFileProvider {
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        self.files
+            .get(&util::normalize_path(path))
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(&util::normalize_path(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let path = util::normalize_path(path);
+        match self.files.contains_key(&path) {
+            true => Ok(path),
+            false => Err(not_found(&path)),
+        }
+    }
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        format!("no file at {} in memory", path.display()),
+    )
+}
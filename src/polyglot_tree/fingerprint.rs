@@ -0,0 +1,125 @@
+use super::build_options::BuildOptions;
+use super::polyglot_node::{EvalPayload, PolyglotNode};
+use super::util;
+use super::PolyglotTree;
+
+impl PolyglotTree {
+    /// Returns a hash of the structure and content of the tree, including the subtrees of its polyglot eval calls
+    /// and the content of the files they evaluate.
+    ///
+    /// The fingerprint is computed with a fixed algorithm, so it is stable across runs, platforms and versions of Rust,
+    /// and can be stored by build systems to skip analyzing code that did not change.
+    /// It only depends on what `structurally_eq` compares: two structurally equal trees have the same fingerprint,
+    /// whereas changing whitespace does not change it.
+    /// Code evaluated in a language that is not supported is hashed as it is written, or as it is read from the disk for files,
+    /// see `fingerprint_with_options` to read them through a FileProvider.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let a = PolyglotTree::from("Polyglot.eval(\"python\", 'x=1')", Language::JavaScript).unwrap();
+    /// let b = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 1')", Language::JavaScript).unwrap();
+    /// let c = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 2')", Language::JavaScript).unwrap();
+    ///
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// assert_ne!(a.fingerprint(), c.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint_with_options(&BuildOptions::default())
+    }
+
+    /// Same as `fingerprint`, except that the files evaluated in a language that is not supported are read through
+    /// the FileProvider of the given BuildOptions, which should be the options the tree was built with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    /// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+    /// use polyglot_ast::polyglot_tree::file_provider::MemoryFileProvider;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let fingerprint = |lib: &str| {
+    ///     let files = MemoryFileProvider::new()
+    ///         .with_file("main.js", "Polyglot.evalFile('ruby', 'lib.rb')")
+    ///         .with_file("lib.rb", lib);
+    ///     let options = BuildOptions::new().file_provider(Arc::new(files));
+    ///     let tree = PolyglotTree::from_path_with_options(PathBuf::from("main.js"), Language::JavaScript, &options).unwrap();
+    ///     tree.fingerprint_with_options(&options)
+    /// };
+    ///
+    /// assert_ne!(fingerprint("puts 1"), fingerprint("puts 2"));
+    /// ```
+    pub fn fingerprint_with_options(&self, options: &BuildOptions) -> u64 {
+        let mut hash = Fnv64::new();
+        self.visit_nodes(&mut |tree, node, id| {
+            if node.parent().is_none() {
+                hash.write(util::language_enum_to_string(&tree.language).as_bytes());
+            }
+
+            // the depth of each node in document order is enough to tell the shape of the tree
+            hash.write_usize(id.paths().len());
+            hash.write_usize(id.paths().last().map_or(0, Vec::len));
+            hash.write(tree.polyglot_kind(node).as_bytes());
+            if node.child_count() == 0 {
+                hash.write(tree.node_to_code(node).as_bytes());
+            }
+
+            // the arguments of eval calls are not visited, so the code they evaluate is hashed when it has no subtree
+            if tree.node_to_subtrees_map.contains_key(&node.id()) {
+                return;
+            }
+            match tree.polyglot_nodes.get(&node.id()) {
+                Some(PolyglotNode::EvalCall {
+                    payload: Some(EvalPayload::Inline { code, .. }),
+                    ..
+                }) => hash.write(code.as_bytes()),
+                Some(PolyglotNode::EvalCall {
+                    payload: Some(EvalPayload::File(path)),
+                    ..
+                }) => {
+                    if let Ok(content) = options.provider().read(path) {
+                        hash.write(content.as_bytes());
+                    }
+                }
+                _ => {}
+            }
+        });
+        hash.finish()
+    }
+}
+
+/// Internal implementation of the 64-bit FNV-1a hash, chosen for being simple and stable.
+pub(crate) struct Fnv64(u64);
+
+impl Fnv64 {
+    pub(crate) fn new() -> Fnv64 {
+        Fnv64(0xcbf29ce484222325)
+    }
+
+    /// Writes the bytes, followed by their length so that consecutive writes cannot be confused.
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        self.write_raw(bytes);
+        self.write_raw(&(bytes.len() as u64).to_le_bytes());
+    }
+
+    pub(crate) fn write_usize(&mut self, n: usize) {
+        self.write_raw(&(n as u64).to_le_bytes());
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
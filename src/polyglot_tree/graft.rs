@@ -0,0 +1,77 @@
+use std::sync::{Arc, OnceLock};
+
+use super::node_id::NodeId;
+use super::util::InvalidArgumentError;
+use super::PolyglotTree;
+
+impl PolyglotTree {
+    /// Attaches `subtree` to the polyglot eval call designated by `site`, which can be in a subtree however deep it is.
+    ///
+    /// This is meant for calls whose code cannot be resolved statically, such as files generated at build time,
+    /// but that the caller can resolve with its own logic and parse into a PolyglotTree itself.
+    /// If the call already had a subtree, it is replaced and returned.
+    ///
+    /// Subtrees shared with other trees, for instance through `PolyglotZipper::detach`, are copied before being modified,
+    /// so that grafting never affects another tree.
+    ///
+    /// Returns an InvalidArgumentError if `site` does not designate a polyglot eval call of this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let mut tree = PolyglotTree::from("Polyglot.evalFile(\"python\", 'generated.py')", Language::JavaScript).unwrap();
+    /// assert!(!tree.eval_sites()[0].has_subtree);
+    ///
+    /// let site = tree.eval_sites()[0].id.clone();
+    /// let subtree = PolyglotTree::from("print(42)", Language::Python).unwrap();
+    /// let previous = tree.graft(&site, subtree).unwrap();
+    /// assert!(previous.is_none());
+    ///
+    /// let eval = tree.zipper_at(&site).unwrap();
+    /// assert_eq!(eval.child(0).unwrap().code(), "print(42)");
+    /// ```
+    pub fn graft(
+        &mut self,
+        site: &NodeId,
+        subtree: PolyglotTree,
+    ) -> Result<Option<Arc<PolyglotTree>>, InvalidArgumentError> {
+        let (path, rest) = site.paths().split_first().ok_or(InvalidArgumentError)?;
+        self.graft_impl(path, rest, subtree)
+    }
+
+    fn graft_impl(
+        &mut self,
+        path: &[usize],
+        rest: &[Vec<usize>],
+        subtree: PolyglotTree,
+    ) -> Result<Option<Arc<PolyglotTree>>, InvalidArgumentError> {
+        let node_id = {
+            let mut node = self.root_node();
+            for index in path {
+                node = node.child(*index).ok_or(InvalidArgumentError)?;
+            }
+            if !self.is_polyglot_eval_call(node) {
+                return Err(InvalidArgumentError);
+            }
+            node.id()
+        };
+
+        let result = match rest.split_first() {
+            None => Ok(self.node_to_subtrees_map.insert(node_id, Arc::new(subtree))),
+            Some((next, rest)) => {
+                let inner = self
+                    .node_to_subtrees_map
+                    .get_mut(&node_id)
+                    .ok_or(InvalidArgumentError)?;
+                Arc::make_mut(inner).graft_impl(next, rest, subtree)
+            }
+        };
+        if result.is_ok() {
+            self.stats = OnceLock::new(); // the shape of the tree changed
+        }
+        result
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use petgraph::graph::{Graph, NodeIndex};
+
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// A node of a graph produced by `PolyglotTree::to_graph` or `PolyglotTree::to_ast_graph`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphNode {
+    /// A whole file or snippet, that is either the tree itself or one of its subtrees.
+    Tree { language: Language, origin: Origin },
+    /// A single AST node, whose span is given in the coordinates of the file its code physically lives in.
+    Ast {
+        id: NodeId,
+        kind: &'static str,
+        language: Language,
+        origin: Origin,
+        span: Range<usize>,
+    },
+}
+
+/// An edge of a graph produced by `PolyglotTree::to_graph` or `PolyglotTree::to_ast_graph`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphEdge {
+    /// Goes from an AST node to one of its children, in the same tree.
+    Contains,
+    /// Goes from a polyglot eval call, or the tree containing it, to the subtree it evaluates.
+    Eval,
+    /// Goes from a polyglot export call, or the tree containing it, to an import call of the same binding, or the tree containing it.
+    Binding(String),
+}
+
+impl PolyglotTree {
+    /// Returns a directed graph whose nodes are the tree and all of its subtrees,
+    /// linked by `Eval` edges from each host tree to its subtrees and by `Binding` edges from the trees exporting a binding to the trees importing it.
+    ///
+    /// The node at index 0 is always the tree itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::graph::GraphEdge;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\npolyglot.export_value(name=\"x\", value=1)\npolyglot.eval(language=\"js\", string=\"Polyglot.import('x')\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let graph = tree.to_graph();
+    ///
+    /// assert_eq!(graph.node_count(), 2);
+    /// let mut edges: Vec<&GraphEdge> = graph.edge_weights().collect();
+    /// edges.sort_by_key(|e| format!("{e:?}"));
+    /// assert_eq!(edges, vec![&GraphEdge::Binding(String::from("x")), &GraphEdge::Eval]);
+    /// ```
+    pub fn to_graph(&self) -> Graph<GraphNode, GraphEdge> {
+        let mut graph = Graph::new();
+        // trees are identified by the paths of their eval calls, as a subtree can be shared by several eval calls
+        let mut indices: HashMap<Vec<Vec<usize>>, NodeIndex> = HashMap::new();
+        let mut exports = Vec::new();
+        let mut imports = Vec::new();
+
+        self.visit_nodes(&mut |tree, node, id| {
+            let scope = &id.paths()[..id.paths().len() - 1];
+            let index = tree_index(&mut graph, &mut indices, scope, tree);
+            if let Some(subtree) = tree.node_to_subtrees_map.get(&node.id()) {
+                let sub_index = tree_index(&mut graph, &mut indices, id.paths(), subtree);
+                graph.add_edge(index, sub_index, GraphEdge::Eval);
+            }
+            match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ExportCall { name: Some(name) }) => {
+                    exports.push((name.clone(), index))
+                }
+                Some(PolyglotNode::ImportCall { name: Some(name) }) => {
+                    imports.push((name.clone(), index))
+                }
+                _ => {}
+            }
+        });
+
+        let mut linked = HashSet::new();
+        for (name, from) in &exports {
+            for (_, to) in imports.iter().filter(|(n, _)| n == name) {
+                // several calls of a tree can use the same binding, but the trees are only linked once
+                if linked.insert((*from, *to, name)) {
+                    graph.add_edge(*from, *to, GraphEdge::Binding(name.clone()));
+                }
+            }
+        }
+        graph
+    }
+
+    /// Returns a directed graph whose nodes are the AST nodes of the tree and all of its subtrees,
+    /// as seen by a PolyglotZipper: the arguments of polyglot eval calls are left out.
+    ///
+    /// Nodes are linked by `Contains` edges from each node to its children, by `Eval` edges from each eval call to the root of its subtree,
+    /// and by `Binding` edges from each export call to the import calls of the same binding.
+    /// The node at index 0 is always the root of the tree, and nodes are added in document order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::graph::GraphEdge;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x')", Language::JavaScript).unwrap();
+    /// let graph = tree.to_ast_graph();
+    ///
+    /// assert_eq!(graph.node_count(), 6);
+    /// assert_eq!(graph.edge_weights().filter(|e| **e == GraphEdge::Eval).count(), 1);
+    /// ```
+    pub fn to_ast_graph(&self) -> Graph<GraphNode, GraphEdge> {
+        let mut graph = Graph::new();
+        let mut indices: HashMap<NodeId, NodeIndex> = HashMap::new();
+        let mut exports = Vec::new();
+        let mut imports = Vec::new();
+
+        self.visit_nodes(&mut |tree, node, id| {
+            let index = graph.add_node(GraphNode::Ast {
+                id: id.clone(),
+                kind: tree.polyglot_kind(node),
+                language: tree.language,
+                origin: tree.origin.clone(),
+                span: tree.host_range(node),
+            });
+            indices.insert(id.clone(), index);
+
+            // the parent of the root of a subtree is the eval call whose path comes right before the root's
+            let mut paths = id.paths().to_vec();
+            let edge = match paths.last_mut().and_then(|path| path.pop()) {
+                Some(_) => GraphEdge::Contains,
+                None => {
+                    paths.pop();
+                    GraphEdge::Eval
+                }
+            };
+            if let Some(parent_index) = indices.get(&NodeId::from_paths(paths)) {
+                graph.add_edge(*parent_index, index, edge);
+            }
+            match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ExportCall { name: Some(name) }) => {
+                    exports.push((name.clone(), index))
+                }
+                Some(PolyglotNode::ImportCall { name: Some(name) }) => {
+                    imports.push((name.clone(), index))
+                }
+                _ => {}
+            }
+        });
+
+        for (name, from) in &exports {
+            for (_, to) in imports.iter().filter(|(n, _)| n == name) {
+                graph.add_edge(*from, *to, GraphEdge::Binding(name.clone()));
+            }
+        }
+        graph
+    }
+}
+
+/// Internal function returning the graph node of the tree evaluated by the eval calls at the given paths,
+/// adding it to the graph if it is not there yet.
+fn tree_index(
+    graph: &mut Graph<GraphNode, GraphEdge>,
+    indices: &mut HashMap<Vec<Vec<usize>>, NodeIndex>,
+    scope: &[Vec<usize>],
+    tree: &PolyglotTree,
+) -> NodeIndex {
+    *indices.entry(scope.to_vec()).or_insert_with(|| {
+        graph.add_node(GraphNode::Tree {
+            language: tree.language,
+            origin: tree.origin.clone(),
+        })
+    })
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use thiserror::Error;
+use tree_sitter::Node;
+use tree_sitter_graph::ast::File;
+use tree_sitter_graph::functions::Functions;
+use tree_sitter_graph::graph::{Graph, Value};
+use tree_sitter_graph::{ExecutionConfig, ExecutionError, NoCancellation, ParseError, Variables};
+
+use super::polyglot_node::PolyglotNode;
+use super::util::{self, Language};
+use super::{Origin, PolyglotTree};
+
+/// The problems met while loading or executing the graph DSL files of a GraphDsl.
+#[derive(Debug, Error)]
+pub enum GraphDslError {
+    #[error("the {} grammar is not compiled in", util::language_enum_to_string(.0))]
+    Grammar(Language),
+    #[error("invalid graph DSL file for {} code: {source}", util::language_enum_to_string(.language))]
+    Parse {
+        language: Language,
+        source: ParseError,
+    },
+    #[error("unable to execute the graph DSL file of {} code: {source}", util::language_enum_to_string(.language))]
+    Execution {
+        language: Language,
+        source: ExecutionError,
+    },
+}
+
+/// A set of tree-sitter-graph DSL files, at most one per language, building a custom graph out of the trees of that language.
+///
+/// `PolyglotTree::execute_graph_dsl` executes the file of each language on the tree and on each of its subtrees written in it,
+/// and stitches the resulting graphs together across the boundaries of polyglot calls. To be stitched, a graph node
+/// must name the syntax node it stands for with a `source_node` attribute, as in `attr (@call.node) source_node = @call`:
+/// - the nodes standing for a polyglot eval call are linked to the nodes standing for the root of its subtree by `Eval` edges;
+/// - the nodes standing for a polyglot export call are linked to the nodes standing for the import calls of the same binding by `Binding` edges.
+///
+/// Note that polyglot calls are matched by their grammar kinds in the DSL files, such as `call` in Python.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::PolyglotTree;
+/// use polyglot_ast::polyglot_tree::graph_dsl::{DslEdgeKind, DslValue, GraphDsl};
+/// use polyglot_ast::util::Language;
+///
+/// let dsl = GraphDsl::new()
+///     .with_file(Language::Python, "(call) @call {\n  node @call.node\n  attr (@call.node) source_node = @call\n}")
+///     .unwrap()
+///     .with_file(Language::JavaScript, "(program) @p {\n  node @p.node\n  attr (@p.node) name = \"program\", source_node = @p\n}")
+///     .unwrap();
+///
+/// let code = "import polyglot\npolyglot.eval(language=\"js\", string=\"1\")";
+/// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+/// let graph = tree.execute_graph_dsl(&dsl).unwrap();
+///
+/// assert_eq!(graph.nodes.len(), 2);
+/// assert_eq!(graph.nodes[1].language, Language::JavaScript);
+/// assert_eq!(graph.nodes[1].attribute("name"), Some(&DslValue::String("program".to_string())));
+/// assert_eq!(graph.edges.len(), 1);
+/// assert_eq!((graph.edges[0].from, graph.edges[0].to), (0, 1));
+/// assert_eq!(graph.edges[0].kind, DslEdgeKind::Eval);
+/// ```
+pub struct GraphDsl {
+    files: HashMap<Language, File>,
+    functions: Functions,
+}
+
+impl GraphDsl {
+    /// Returns a GraphDsl without any file, whose DSL files can call the functions of the tree-sitter-graph standard library.
+    pub fn new() -> GraphDsl {
+        GraphDsl {
+            files: HashMap::new(),
+            functions: Functions::stdlib(),
+        }
+    }
+
+    /// Parses the given DSL file, which replaces the previous file of the given language, if any.
+    pub fn with_file(
+        mut self,
+        language: Language,
+        source: &str,
+    ) -> Result<GraphDsl, GraphDslError> {
+        let grammar = util::try_grammar(&language).ok_or(GraphDslError::Grammar(language))?;
+        let file = File::from_str(*grammar, source)
+            .map_err(|source| GraphDslError::Parse { language, source })?;
+        self.files.insert(language, file);
+        Ok(self)
+    }
+
+    /// Returns the languages having a DSL file.
+    pub fn languages(&self) -> impl Iterator<Item = &Language> {
+        self.files.keys()
+    }
+}
+
+impl Default for GraphDsl {
+    fn default() -> Self {
+        GraphDsl::new()
+    }
+}
+
+/// A graph built by `PolyglotTree::execute_graph_dsl`, owning its nodes and edges.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DslGraph {
+    /// The nodes of the graphs of the tree and of its subtrees, in the order the trees are visited in, the host before its subtrees.
+    pub nodes: Vec<DslNode>,
+    /// The edges created by the DSL files and the edges stitching their graphs together, the `Binding` edges last.
+    pub edges: Vec<DslEdge>,
+}
+
+/// A node created by a DSL file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DslNode {
+    /// The language of the tree the node was created for.
+    pub language: Language,
+    /// Where the code of that tree physically comes from.
+    pub origin: Origin,
+    /// The attributes of the node, in the order given by tree-sitter-graph.
+    pub attributes: Vec<(String, DslValue)>,
+}
+
+impl DslNode {
+    /// Returns the value of the given attribute of the node, if it has it.
+    pub fn attribute(&self, name: &str) -> Option<&DslValue> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// An edge between two nodes of a DslGraph, given by their indices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DslEdge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: DslEdgeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DslEdgeKind {
+    /// An edge created by a DSL file, with its attributes.
+    Dsl(Vec<(String, DslValue)>),
+    /// Goes from a node standing for a polyglot eval call to a node standing for the root of its subtree.
+    Eval,
+    /// Goes from a node standing for a polyglot export call to a node standing for an import call of the same binding.
+    Binding(String),
+}
+
+/// The value of an attribute of a DslNode or a DslEdge.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DslValue {
+    Null,
+    Boolean(bool),
+    Integer(u32),
+    String(String),
+    List(Vec<DslValue>),
+    Set(Vec<DslValue>),
+    /// A syntax node, whose span is given in the coordinates of the file its code physically lives in.
+    SyntaxNode {
+        kind: &'static str,
+        span: Range<usize>,
+    },
+    /// The index of a node of the DslGraph.
+    GraphNode(usize),
+}
+
+impl PolyglotTree {
+    /// Executes the DSL files of the given GraphDsl on the tree and on its subtrees, and returns their graphs stitched together,
+    /// as described by GraphDsl. Trees whose language has no DSL file add no node, but their subtrees are still visited.
+    pub fn execute_graph_dsl(&self, dsl: &GraphDsl) -> Result<DslGraph, GraphDslError> {
+        let mut graph = DslGraph::default();
+        let mut exports = Vec::new();
+        let mut imports = Vec::new();
+        self.execute_graph_dsl_impl(dsl, &mut graph, &mut exports, &mut imports)?;
+
+        for (name, from) in &exports {
+            for (_, to) in imports.iter().filter(|(n, _)| n == name) {
+                graph.edges.push(DslEdge {
+                    from: *from,
+                    to: *to,
+                    kind: DslEdgeKind::Binding(name.clone()),
+                });
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Internal function adding the graph of this tree and of its subtrees to `graph`,
+    /// and returning the nodes standing for the root of this tree.
+    fn execute_graph_dsl_impl(
+        &self,
+        dsl: &GraphDsl,
+        graph: &mut DslGraph,
+        exports: &mut Vec<(String, usize)>,
+        imports: &mut Vec<(String, usize)>,
+    ) -> Result<Vec<usize>, GraphDslError> {
+        // the nodes of the graph standing for each syntax node of this tree
+        let mut anchors: HashMap<usize, Vec<usize>> = HashMap::new();
+        if let Some(file) = dsl.files.get(&self.language) {
+            let globals = Variables::new();
+            let config = ExecutionConfig::new(&dsl.functions, &globals);
+            let result = file
+                .execute(&self.tree, &self.code, &config, &NoCancellation)
+                .map_err(|source| GraphDslError::Execution {
+                    language: self.language,
+                    source,
+                })?;
+
+            let offset = graph.nodes.len();
+            for node in result.iter_nodes() {
+                let attributes =
+                    self.dsl_attributes(&result, result[node].attributes.iter(), offset);
+                let index = graph.nodes.len();
+                if let Some((_, Value::SyntaxNode(source))) = result[node]
+                    .attributes
+                    .iter()
+                    .find(|(name, _)| name.as_str() == "source_node")
+                {
+                    anchors.entry(result[*source].id()).or_default().push(index);
+                }
+                graph.nodes.push(DslNode {
+                    language: self.language,
+                    origin: self.origin.clone(),
+                    attributes,
+                });
+            }
+            for node in result.iter_nodes() {
+                for (sink, edge) in result[node].iter_edges() {
+                    graph.edges.push(DslEdge {
+                        from: offset + node.index(),
+                        to: offset + sink.index(),
+                        kind: DslEdgeKind::Dsl(self.dsl_attributes(
+                            &result,
+                            edge.attributes.iter(),
+                            offset,
+                        )),
+                    });
+                }
+            }
+        }
+
+        let anchored = |node: Node| anchors.get(&node.id()).cloned().unwrap_or_default();
+        let mut stack = vec![self.root_node()];
+        while let Some(node) = stack.pop() {
+            if let Some(subtree) = self.node_to_subtrees_map.get(&node.id()) {
+                let roots = subtree.execute_graph_dsl_impl(dsl, graph, exports, imports)?;
+                for from in anchored(node) {
+                    for to in &roots {
+                        graph.edges.push(DslEdge {
+                            from,
+                            to: *to,
+                            kind: DslEdgeKind::Eval,
+                        });
+                    }
+                }
+            }
+            match self.polyglot_node_at(node) {
+                Some(PolyglotNode::ExportCall { name: Some(name) }) => {
+                    exports.extend(anchored(node).into_iter().map(|i| (name.clone(), i)))
+                }
+                Some(PolyglotNode::ImportCall { name: Some(name) }) => {
+                    imports.extend(anchored(node).into_iter().map(|i| (name.clone(), i)))
+                }
+                _ => {}
+            }
+            let mut cursor = node.walk();
+            // children are pushed in reverse, so that the subtrees are visited in document order
+            let children: Vec<Node> = node.children(&mut cursor).collect();
+            stack.extend(children.into_iter().rev());
+        }
+        Ok(anchored(self.root_node()))
+    }
+
+    /// Internal function converting the attributes of a node or an edge of the graph of this tree,
+    /// whose graph nodes are added to the DslGraph from index `offset` on.
+    fn dsl_attributes<'v>(
+        &self,
+        graph: &Graph<'_>,
+        attributes: impl Iterator<Item = (&'v tree_sitter_graph::Identifier, &'v Value)>,
+        offset: usize,
+    ) -> Vec<(String, DslValue)> {
+        attributes
+            .map(|(name, value)| (name.to_string(), self.dsl_value(graph, value, offset)))
+            .collect()
+    }
+
+    fn dsl_value(&self, graph: &Graph<'_>, value: &Value, offset: usize) -> DslValue {
+        match value {
+            Value::Null => DslValue::Null,
+            Value::Boolean(value) => DslValue::Boolean(*value),
+            Value::Integer(value) => DslValue::Integer(*value),
+            Value::String(value) => DslValue::String(value.clone()),
+            Value::List(values) => DslValue::List(
+                values
+                    .iter()
+                    .map(|v| self.dsl_value(graph, v, offset))
+                    .collect(),
+            ),
+            Value::Set(values) => DslValue::Set(
+                values
+                    .iter()
+                    .map(|v| self.dsl_value(graph, v, offset))
+                    .collect(),
+            ),
+            Value::SyntaxNode(node) => {
+                let node = graph[*node];
+                DslValue::SyntaxNode {
+                    kind: self.polyglot_kind(node),
+                    span: self.host_range(node),
+                }
+            }
+            Value::GraphNode(node) => DslValue::GraphNode(offset + node.index()),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use tree_sitter::Point;
+
+use super::call_sites::{BindingDirection, BindingSite, EvalSite};
+use super::data_flow::{DefUseChain, FlowNode};
+use super::polyglot_node::PolyglotNode;
+use super::polyglot_zipper::PolyglotZipper;
+use super::project::PolyglotProject;
+use super::util::{self, Language};
+use super::PolyglotTree;
+
+/// What an editor shows when hovering a position of a polyglot tree, as returned by `PolyglotTree::hover`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hover {
+    /// The Language of the code at the position.
+    pub language: Language,
+    /// The kind of the innermost node at the position, as given by `PolyglotZipper::kind`.
+    pub kind: String,
+    /// The byte range of that node, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The eval calls evaluating the code at the position, directly or not, the outermost first.
+    pub evals: Vec<EvalSite>,
+    /// The name of the polyglot binding the node refers to, as found by `PolyglotZipper::binding`.
+    pub binding: Option<String>,
+    /// Whether the node is on the import or the export side of the binding, or None if it only names the binding, as Java member accesses do.
+    pub direction: Option<BindingDirection>,
+    /// The calls of the other side of the binding: the export calls writing the value the node reads, or the import calls reading the value
+    /// it writes, in document order. All the calls of the binding are given when the node has no direction.
+    pub counterparts: Vec<BindingSite>,
+}
+
+impl Hover {
+    /// Returns the hover as Markdown, the way editors display it: the language and kind of the node, the eval calls leading to it,
+    /// and the counterparts of its binding.
+    pub fn markdown(&self) -> String {
+        let mut result = format!(
+            "**{}** `{}`",
+            util::language_enum_to_string(&self.language),
+            self.kind
+        );
+        if !self.evals.is_empty() {
+            let evals: Vec<String> = self
+                .evals
+                .iter()
+                .map(|eval| {
+                    let call = match (&eval.path, eval.guest_language) {
+                        (Some(path), _) => format!(
+                            "evalFile(\"{}\")",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ),
+                        (None, Some(language)) => {
+                            format!("eval(\"{}\")", util::language_enum_to_string(&language))
+                        }
+                        (None, None) => String::from("eval"),
+                    };
+                    format!(
+                        "`{call}` in {}",
+                        util::language_enum_to_string(&eval.host_language)
+                    )
+                })
+                .collect();
+            let _ = write!(result, "\n\nEvaluated by {}", evals.join(" > "));
+        }
+        if let Some(name) = &self.binding {
+            let _ = write!(result, "\n\nBinding `{name}`");
+            if !self.counterparts.is_empty() {
+                let verb = match self.direction {
+                    Some(BindingDirection::Import) => "exported by",
+                    Some(BindingDirection::Export) => "imported by",
+                    None => "used by",
+                };
+                let _ = write!(result, ", {verb}:");
+                for site in &self.counterparts {
+                    let file = match site.origin.file() {
+                        Some(path) => path.file_name().unwrap_or_default().to_string_lossy(),
+                        None => "<snippet>".into(),
+                    };
+                    let _ = write!(
+                        result,
+                        "\n- {} at {file}:{}:{}",
+                        util::language_enum_to_string(&site.language),
+                        site.start_position.row + 1,
+                        site.start_position.column + 1
+                    );
+                }
+            }
+        }
+        result
+    }
+}
+
+impl PolyglotTree {
+    /// Returns what an editor shows when hovering the given position of the tree's code, or None if the position is out of the code.
+    /// The counterparts of the binding are looked for in the tree and in its subtrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::call_sites::BindingDirection;
+    /// use polyglot_ast::util::Language;
+    /// use tree_sitter::Point;
+    ///
+    /// let code = "import polyglot\nx = 42\npolyglot.export_value(name=\"x\", value=x)\npolyglot.eval(language=\"js\", string=\"let a = Polyglot.import('x'); a + 1\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    ///
+    /// // on the `a` of `a + 1`
+    /// let hover = tree.hover(Point::new(3, 67)).unwrap();
+    /// assert_eq!(hover.language, Language::JavaScript);
+    /// assert_eq!(hover.evals.len(), 1);
+    /// assert_eq!(hover.binding.as_deref(), Some("x"));
+    /// assert_eq!(hover.direction, Some(BindingDirection::Import));
+    /// assert_eq!(hover.counterparts.len(), 1);
+    /// assert_eq!(hover.counterparts[0].language, Language::Python);
+    /// assert_eq!(hover.markdown(), "**js** `identifier`\n\nEvaluated by `eval(\"js\")` in python\n\nBinding `x`, exported by:\n- python at <snippet>:3:1");
+    /// ```
+    pub fn hover(&self, position: Point) -> Option<Hover> {
+        let zip = self.zipper_at_position(position)?;
+        let ancestors: Vec<_> = zip.ancestors().collect();
+        let eval_sites = self.eval_sites();
+        let evals = ancestors
+            .iter()
+            .rev()
+            .filter(|ancestor| ancestor.is_polyglot_eval_call())
+            .filter_map(|ancestor| {
+                let id = ancestor.node_id();
+                eval_sites.iter().find(|site| site.id == id).cloned()
+            })
+            .collect();
+
+        let binding = zip.binding();
+        let mut direction = call_direction(&zip);
+        if direction.is_none() && binding.is_some() {
+            let id = zip.node_id();
+            direction = chain_side(&self.def_use_chains(), |node| node.id == id);
+        }
+        let counterparts = match &binding {
+            Some(name) => self
+                .bindings()
+                .into_iter()
+                .filter(|site| site.name.as_ref() == Some(name))
+                .filter(|site| direction != Some(site.direction))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Some(Hover {
+            language: *zip.get_lang(),
+            kind: zip.kind().to_string(),
+            span: zip.host_byte_range(),
+            evals,
+            binding,
+            direction,
+            counterparts,
+        })
+    }
+}
+
+impl PolyglotProject {
+    /// Returns whether the node of the given zipper is on the import or the export side of its binding, as described by `Hover::direction`,
+    /// looking for the identifiers of the file the node belongs to in the trees of the files evaluating it as well.
+    pub fn binding_direction(&self, zipper: &PolyglotZipper) -> Option<BindingDirection> {
+        if let Some(direction) = call_direction(zipper) {
+            return Some(direction);
+        }
+        let file = zipper.origin().file();
+        let span = zipper.host_byte_range();
+        self.trees().find_map(|(_, tree)| {
+            chain_side(&tree.def_use_chains(), |node| {
+                node.origin.file() == file && node.span == span
+            })
+        })
+    }
+}
+
+/// Internal function returning the direction of the import or export call containing the node of the given zipper, if there is one
+/// in the code of the node's tree.
+fn call_direction(zipper: &PolyglotZipper) -> Option<BindingDirection> {
+    std::iter::once(zipper.clone())
+        .chain(zipper.ancestors())
+        .take_while(|z| !z.is_polyglot_eval_call())
+        .find_map(|z| match z.polyglot_node() {
+            Some(PolyglotNode::ImportCall { .. }) => Some(BindingDirection::Import),
+            Some(PolyglotNode::ExportCall { .. }) => Some(BindingDirection::Export),
+            _ => None,
+        })
+}
+
+/// Internal function returning the side of the binding of the first of the given chains where a node matches `is_node`:
+/// the export side for the definition of the exported value, and the import side for the variable holding the imported value and its uses.
+fn chain_side(
+    chains: &[DefUseChain],
+    is_node: impl Fn(&FlowNode) -> bool,
+) -> Option<BindingDirection> {
+    chains.iter().find_map(|chain| {
+        if chain.definition.as_ref().is_some_and(&is_node) {
+            Some(BindingDirection::Export)
+        } else if chain.variable.iter().chain(&chain.uses).any(&is_node) {
+            Some(BindingDirection::Import)
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use tree_sitter::Point;
+
+use super::call_sites::EvalKind;
+use super::conflicts::ConflictKind;
+use super::polyglot_zipper::PolyglotZipper;
+use super::util::{self, InvalidArgumentError};
+use super::PolyglotTree;
+
+/// How serious a LintFinding is, from the least to the most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something worth knowing, which is not necessarily wrong.
+    Info,
+    /// Something that is likely wrong, or that keeps the code from being fully analyzed.
+    Warning,
+    /// Something that is wrong.
+    Error,
+}
+
+impl Severity {
+    /// Returns the name of the severity, which is also accepted by `from_str`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = InvalidArgumentError;
+
+    fn from_str(s: &str) -> Result<Severity, InvalidArgumentError> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(InvalidArgumentError),
+        }
+    }
+}
+
+/// A problem found by `PolyglotTree::lint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// The name of the rule that found the problem, eg. `syntax-error`.
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// The physical file the problem lives in, or None if it is in a snippet.
+    pub file: Option<PathBuf>,
+    /// The byte range of the problem, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the problem, in the coordinates of the file its code physically lives in.
+    pub start: Point,
+    /// The end position of the problem, in the coordinates of the file its code physically lives in.
+    pub end: Point,
+    /// The other places the problem is about, such as the first export of a binding exported twice.
+    pub related: Vec<RelatedLocation>,
+}
+
+/// Another place a LintFinding is about, with a message telling what it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedLocation {
+    pub message: String,
+    /// The physical file the place lives in, or None if it is in a snippet.
+    pub file: Option<PathBuf>,
+    /// The byte range of the place, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the place, in the coordinates of the file its code physically lives in.
+    pub start: Point,
+    /// The end position of the place, in the coordinates of the file its code physically lives in.
+    pub end: Point,
+}
+
+impl PolyglotTree {
+    /// Checks the tree and its subtrees for common problems of polyglot code, returning them ordered by file and position.
+    ///
+    /// The rules are:
+    /// - `syntax-error` (error): the code contains a syntax error, or misses a token, in any language.
+    /// - `missing-eval-target` (error): no tree could be built for a file evaluated by a polyglot eval call.
+    /// - `unsupported-language` (warning): a polyglot eval call evaluates code of a language that is not supported,
+    ///   or whose language is not given as a string literal.
+    /// - `dynamic-eval` (warning): the code evaluated by a polyglot eval call is neither a string literal nor a file path, so it cannot be analyzed.
+    /// - `dynamic-binding` (info): the name of a polyglot import or export is not given as a string literal.
+    /// - `unresolved-import` (warning): a polyglot import reads a binding that no export of the tree writes.
+    /// - `unused-export` (info): a polyglot export writes a binding that no import of the tree reads,
+    ///   which is expected when the value is read by code outside of the tree, such as the host application.
+    /// - `conflicting-export` (warning): a polyglot export writes a binding already written by another export of the tree,
+    ///   in another language or with another value. The first export is given as the related location.
+    /// - `shadowed-import` (warning): a local variable hides the value of a polyglot import, having the name of its binding
+    ///   or of the variable the value is assigned to. The import is given as the related location.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::lint::Severity;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval('python', 'print(')\nPolyglot.eval('ruby', 'puts 1')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let findings = tree.lint();
+    ///
+    /// assert_eq!(findings.len(), 2);
+    /// assert_eq!(findings[0].rule, "syntax-error");
+    /// assert_eq!(findings[0].severity, Severity::Error);
+    /// assert_eq!(findings[1].rule, "unsupported-language");
+    /// assert_eq!(findings[1].start.row, 1);
+    /// ```
+    pub fn lint(&self) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+        let finding = |zip: &PolyglotZipper, rule, severity, message| {
+            let (start, end) = zip.host_positions();
+            LintFinding {
+                rule,
+                severity,
+                message,
+                file: zip.origin().file().cloned(),
+                span: zip.host_byte_range(),
+                start,
+                end,
+                related: Vec::new(),
+            }
+        };
+
+        let root = PolyglotZipper::from(self);
+        if root.has_error() {
+            let errors = root.find_where(|zip| {
+                (zip.is_error() || zip.is_missing()) && !zip.parent().is_some_and(|p| p.is_error())
+            });
+            for zip in errors {
+                let language = util::language_enum_to_string(zip.get_lang());
+                let message = match zip.is_missing() {
+                    true => format!("missing `{}` in {language} code", zip.kind()),
+                    false => format!("syntax error in {language} code"),
+                };
+                findings.push(finding(&zip, "syntax-error", Severity::Error, message));
+            }
+        }
+
+        for site in self.eval_sites() {
+            let Some(zip) = self.zipper_at(&site.id) else {
+                continue;
+            };
+            match (site.kind, site.guest_language) {
+                (None, _) => findings.push(finding(
+                    &zip,
+                    "dynamic-eval",
+                    Severity::Warning,
+                    "the evaluated code is neither a string literal nor a file path, so it cannot be analyzed".to_string(),
+                )),
+                (Some(_), None) => findings.push(finding(
+                    &zip,
+                    "unsupported-language",
+                    Severity::Warning,
+                    "the language of the evaluated code is not supported, or not given as a string literal".to_string(),
+                )),
+                (Some(EvalKind::File), Some(_)) if !site.has_subtree => {
+                    let path = site.path.as_ref().map_or(String::new(), |p| p.display().to_string());
+                    findings.push(finding(
+                        &zip,
+                        "missing-eval-target",
+                        Severity::Error,
+                        format!("no tree could be built for the evaluated file {path}"),
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        for site in self.bindings() {
+            if site.name.is_some() {
+                continue;
+            }
+            if let Some(zip) = self.zipper_at(&site.id) {
+                findings.push(finding(
+                    &zip,
+                    "dynamic-binding",
+                    Severity::Info,
+                    "the name of the binding is not given as a string literal".to_string(),
+                ));
+            }
+        }
+
+        let graph = self.binding_graph();
+        let unmatched = graph
+            .unresolved_imports()
+            .into_iter()
+            .map(|site| {
+                (
+                    "unresolved-import",
+                    Severity::Warning,
+                    "no export writes",
+                    site,
+                )
+            })
+            .chain(
+                graph
+                    .unused_exports()
+                    .into_iter()
+                    .map(|site| ("unused-export", Severity::Info, "no import reads", site)),
+            );
+        for (rule, severity, problem, site) in unmatched {
+            if let Some(zip) = self.zipper_at(&site.id) {
+                let name = site.name.as_deref().unwrap_or_default();
+                let message = format!("{problem} the binding `{name}`");
+                findings.push(finding(&zip, rule, severity, message));
+            }
+        }
+
+        for conflict in self.binding_conflicts() {
+            let (Some(original), Some(conflicting)) = (
+                self.zipper_at(&conflict.original.id),
+                self.zipper_at(&conflict.conflicting.id),
+            ) else {
+                continue;
+            };
+            let (rule, message, related) = match conflict.kind {
+                ConflictKind::ConflictingExport => (
+                    "conflicting-export",
+                    format!(
+                        "the binding `{}` is already exported in another language or with another value",
+                        conflict.name
+                    ),
+                    "first exported here",
+                ),
+                ConflictKind::ShadowedImport => (
+                    "shadowed-import",
+                    format!(
+                        "`{}` hides the value imported from the binding `{}`",
+                        conflicting.code(),
+                        conflict.name
+                    ),
+                    "imported here",
+                ),
+            };
+            let (start, end) = original.host_positions();
+            let mut found = finding(&conflicting, rule, Severity::Warning, message);
+            found.related.push(RelatedLocation {
+                message: related.to_string(),
+                file: original.origin().file().cloned(),
+                span: original.host_byte_range(),
+                start,
+                end,
+            });
+            findings.push(found);
+        }
+
+        findings.sort_by(|a, b| (&a.file, a.span.start).cmp(&(&b.file, b.span.start)));
+        findings
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use super::node_map::NodeMap;
+use super::polyglot_node::{EvalPayload, PolyglotNode};
+use super::PolyglotTree;
+
+/// The estimated size of a node in a tree-sitter syntax tree, which does not expose its allocations:
+/// the data of an inner node, plus the pointers to its children.
+const ESTIMATED_SYNTAX_NODE_SIZE: usize = 64;
+
+/// An estimate of the memory held by a polyglot tree, in bytes, as returned by `PolyglotTree::memory_usage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The buffers holding the code of the tree and its subtrees.
+    pub source: usize,
+    /// The tree-sitter syntax trees, estimated from their number of nodes.
+    pub syntax_trees: usize,
+    /// The maps linking polyglot calls to their subtrees and meaning, and the rest of the trees' own data.
+    pub maps: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the total estimated memory, in bytes.
+    pub fn total(&self) -> usize {
+        self.source + self.syntax_trees + self.maps
+    }
+}
+
+impl PolyglotTree {
+    /// Returns an estimate of the memory held by the tree and all of its subtrees, however deep they are.
+    ///
+    /// Memory shared between several parts of the tree, such as the buffer of a host file viewed by its inline snippets
+    /// or the subtree of a file evaluated several times, is only counted once. Memory shared with other trees is counted in full,
+    /// which makes the estimate an upper bound of what dropping the tree would free.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(1)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let usage = tree.memory_usage();
+    ///
+    /// // the snippet is a view into the host's code
+    /// assert_eq!(usage.source, code.len());
+    /// assert!(usage.total() > usage.source);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        self.memory_usage_impl(&mut usage, &mut HashSet::new());
+        usage
+    }
+
+    /// Internal function adding the memory of the tree to `usage`, skipping the allocations whose address was already `seen`.
+    fn memory_usage_impl(&self, usage: &mut MemoryUsage, seen: &mut HashSet<usize>) {
+        let buffer = self.code.buffer();
+        if seen.insert(buffer.as_ptr() as usize) {
+            usage.source += buffer.len();
+        }
+        // identical snippets share their syntax tree
+        if seen.insert(self.root_node().id()) {
+            usage.syntax_trees += self.syntax_node_count() * ESTIMATED_SYNTAX_NODE_SIZE;
+        }
+
+        let bindings = self.bindings.get();
+        usage.maps += size_of::<PolyglotTree>()
+            + self.working_dir.as_os_str().len()
+            + map_size(&self.node_to_subtrees_map)
+            + map_size(&self.polyglot_nodes)
+            + bindings.map_or(0, map_size);
+        usage.maps += self
+            .polyglot_nodes
+            .values()
+            .chain(bindings.into_iter().flat_map(|bindings| bindings.values()))
+            .map(|node| match node {
+                PolyglotNode::EvalCall { payload, .. } => match payload {
+                    Some(EvalPayload::Inline { code, .. }) => code.len(),
+                    Some(EvalPayload::File(path)) => path.as_os_str().len(),
+                    None => 0,
+                },
+                PolyglotNode::ImportCall { name } | PolyglotNode::ExportCall { name } => {
+                    name.as_ref().map_or(0, String::len)
+                }
+            })
+            .sum::<usize>();
+
+        for subtree in self.node_to_subtrees_map.values() {
+            if seen.insert(Arc::as_ptr(subtree) as usize) {
+                subtree.memory_usage_impl(usage, seen);
+            }
+        }
+    }
+
+    /// Internal function to count the nodes of the tree's own syntax tree.
+    fn syntax_node_count(&self) -> usize {
+        let mut count = 0;
+        let mut cursor = self.root_node().walk();
+        loop {
+            count += 1;
+            if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return count;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the size of the table of a node map, with its entries and control bytes.
+fn map_size<V>(map: &NodeMap<V>) -> usize {
+    map.capacity() * (size_of::<(usize, V)>() + 1)
+}
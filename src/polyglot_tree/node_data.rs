@@ -0,0 +1,102 @@
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+
+use super::node_id::NodeId;
+use super::polyglot_zipper::PolyglotZipper;
+
+/// A side-table associating values to the nodes of a polyglot tree, keyed by their NodeId.
+///
+/// NodeData lets analyses annotate nodes with types, taint or metrics without wrapping or copying the tree.
+/// Since NodeIds are stable, a table filled for a tree can be used with any tree built from the same code,
+/// and it can outlive the tree it was filled from. Entries are kept in document order.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::polyglot_tree::node_data::NodeData;
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 1')", Language::JavaScript).unwrap();
+/// let mut types = NodeData::new();
+/// for zip in PolyglotZipper::from(&tree).find_all("integer") {
+///     types.set(&zip, "int");
+/// }
+///
+/// let integer = PolyglotZipper::from(&tree).find_all("integer").remove(0);
+/// assert_eq!(types.get(&integer), Some(&"int"));
+/// assert_eq!(types.get(&PolyglotZipper::from(&tree)), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NodeData<T> {
+    values: BTreeMap<NodeId, T>,
+}
+
+impl<T> Default for NodeData<T> {
+    fn default() -> Self {
+        NodeData::new()
+    }
+}
+
+impl<T> NodeData<T> {
+    /// Initializes a new, empty NodeData.
+    pub fn new() -> NodeData<T> {
+        NodeData {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the value associated with the node the zipper is located at, if there is one.
+    pub fn get(&self, zipper: &PolyglotZipper) -> Option<&T> {
+        self.values.get(&zipper.node_id())
+    }
+
+    /// Returns a mutable reference to the value associated with the node the zipper is located at, if there is one.
+    pub fn get_mut(&mut self, zipper: &PolyglotZipper) -> Option<&mut T> {
+        self.values.get_mut(&zipper.node_id())
+    }
+
+    /// Associates a value with the node the zipper is located at, and returns the value it replaces, if any.
+    pub fn set(&mut self, zipper: &PolyglotZipper, value: T) -> Option<T> {
+        self.values.insert(zipper.node_id(), value)
+    }
+
+    /// Removes the value associated with the node the zipper is located at, and returns it if there was one.
+    pub fn remove(&mut self, zipper: &PolyglotZipper) -> Option<T> {
+        self.values.remove(&zipper.node_id())
+    }
+
+    /// Returns the value associated with the node of the given NodeId, if there is one.
+    pub fn get_by_id(&self, id: &NodeId) -> Option<&T> {
+        self.values.get(id)
+    }
+
+    /// Associates a value with the node of the given NodeId, and returns the value it replaces, if any.
+    pub fn set_by_id(&mut self, id: NodeId, value: T) -> Option<T> {
+        self.values.insert(id, value)
+    }
+
+    /// Returns the number of annotated nodes.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no node is annotated.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns an iterator over the annotated nodes and their values, in document order.
+    pub fn iter(&self) -> btree_map::Iter<'_, NodeId, T> {
+        self.values.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a NodeData<T> {
+    type Item = (&'a NodeId, &'a T);
+    type IntoIter = btree_map::Iter<'a, NodeId, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
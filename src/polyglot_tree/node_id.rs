@@ -0,0 +1,110 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::polyglot_zipper::PolyglotZipper;
+use super::util::InvalidArgumentError;
+use super::PolyglotTree;
+
+/// A stable identifier for a node of a polyglot tree, made of the child indices leading to the node from the root.
+///
+/// Unlike `tree_sitter::Node::id`, which is a memory address, a NodeId only depends on the structure of the tree:
+/// the same node gets the same NodeId across runs and when the tree is built again from the same code,
+/// so NodeIds can be written out and read back through their Display and FromStr implementations.
+///
+/// A NodeId holds one path per tree crossed to reach the node: each path but the last leads to a polyglot eval call,
+/// and the next path starts from the root of that call's subtree.
+/// NodeIds are ordered in document order, an eval call coming right before the nodes of its subtree.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{NodeId, PolyglotTree, PolyglotZipper};
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x')", Language::JavaScript).unwrap();
+/// let zip = PolyglotZipper::from(&tree).find_all("identifier").remove(0);
+///
+/// let id = zip.node_id();
+/// assert_eq!(id.to_string(), "/0.0/0.0");
+///
+/// let id: NodeId = "/0.0/0.0".parse().unwrap();
+/// assert_eq!(tree.zipper_at(&id).unwrap().code(), "x");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId {
+    paths: Vec<Vec<usize>>,
+}
+
+impl NodeId {
+    /// Returns the NodeId of the root of a tree.
+    pub fn root() -> NodeId {
+        NodeId {
+            paths: vec![Vec::new()],
+        }
+    }
+
+    /// Returns true if this NodeId designates the root of a tree.
+    pub fn is_root(&self) -> bool {
+        self.paths.len() == 1 && self.paths[0].is_empty()
+    }
+
+    /// Returns the paths of child indices making up this NodeId, one per tree crossed to reach the node.
+    pub fn paths(&self) -> &[Vec<usize>] {
+        &self.paths
+    }
+
+    /// Internal function to build a NodeId from paths as computed by `PolyglotZipper::position_paths`.
+    pub(crate) fn from_paths(paths: Vec<Vec<usize>>) -> NodeId {
+        NodeId { paths }
+    }
+
+    /// Internal function giving mutable access to the paths, for traversals updating a NodeId as they go.
+    pub(crate) fn paths_mut(&mut self) -> &mut Vec<Vec<usize>> {
+        &mut self.paths
+    }
+}
+
+/// Writes the NodeId as its paths, each one preceded by a slash and made of dot-separated child indices,
+/// such as `/0.2/1.0.3` for a node in a subtree, or `/` for the root.
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.paths {
+            f.write_str("/")?;
+            for (i, index) in path.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(".")?;
+                }
+                write!(f, "{index}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a NodeId written by its Display implementation.
+impl FromStr for NodeId {
+    type Err = InvalidArgumentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix('/').ok_or(InvalidArgumentError)?;
+        let paths = rest
+            .split('/')
+            .map(|path| {
+                if path.is_empty() {
+                    return Ok(Vec::new());
+                }
+                path.split('.')
+                    .map(|index| index.parse().map_err(|_| InvalidArgumentError))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(NodeId { paths })
+    }
+}
+
+impl PolyglotTree {
+    /// Returns a zipper located at the node designated by `id`, or None if there is no such node in this tree.
+    pub fn zipper_at(&self, id: &NodeId) -> Option<PolyglotZipper<'_>> {
+        PolyglotZipper::resolve(self, &id.paths)
+    }
+}
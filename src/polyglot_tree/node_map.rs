@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// A hash map keyed by tree-sitter node ids, as used for the polyglot calls and subtrees of a tree.
+pub(crate) type NodeMap<V> = HashMap<usize, V, BuildHasherDefault<NodeIdHasher>>;
+
+/// Returns an empty NodeMap able to hold `capacity` entries without reallocating.
+pub(crate) fn with_capacity<V>(capacity: usize) -> NodeMap<V> {
+    NodeMap::with_capacity_and_hasher(capacity, Default::default())
+}
+
+/// Internal hasher for node ids, using the FxHash algorithm.
+///
+/// Node ids are addresses, which do not need the protection against collision attacks of the default hasher,
+/// so a single multiplication per key is enough and makes building trees with many polyglot calls faster.
+#[derive(Default)]
+pub(crate) struct NodeIdHasher(u64);
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl NodeIdHasher {
+    fn add(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for NodeIdHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.add(u64::from(*byte));
+        }
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        // addresses are aligned, so the low bits the map picks buckets from are moved up from the well-mixed high bits
+        self.0.rotate_left(26)
+    }
+}
@@ -0,0 +1,242 @@
+use std::io::{Read, Write};
+use std::ops::Range;
+
+use tree_sitter::Node;
+
+use super::encoding::{
+    invalid_data, read_byte, read_language, read_option, read_pair, read_path, read_range,
+    read_string, read_usize, write_language, write_option, write_pair, write_path, write_range,
+    write_str, write_usize,
+};
+use super::syntax_node::OwnedSyntaxNode;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// An owned copy of a PolyglotTree, holding its code, its nodes and the subtrees of its eval calls, obtained with `PolyglotTree::to_owned_tree`.
+///
+/// Unlike a PolyglotTree, it does not depend on tree-sitter: with the `serde` feature, it implements `Serialize` and `Deserialize`,
+/// so that trees can be cached on disk or sent to other processes, and read back without parsing their code again.
+/// Without it, `write_to` and `read_from` store a tree in a compact binary form, as the DiskCache does.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+/// use polyglot_ast::polyglot_tree::syntax_node::OwnedSyntaxNode;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 1')", Language::JavaScript).unwrap();
+/// let owned = tree.to_owned_tree();
+/// assert_eq!(owned.to_syntax_node(), OwnedSyntaxNode::from_node(&PolyglotZipper::from(&tree)));
+/// drop(tree);
+///
+/// assert_eq!(owned.root.kind, "program");
+/// let call = &owned.root.children[0].children[0];
+/// assert_eq!(call.kind, "polyglot_eval_call");
+///
+/// let subtree = call.subtree.as_ref().unwrap();
+/// assert_eq!(subtree.language, Language::Python);
+/// assert_eq!(subtree.root.kind, "module");
+/// assert_eq!(subtree.text(&subtree.root.children[0]), "x = 1");
+/// assert_eq!(subtree.root.children[0].host_range, 25..30);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedTree {
+    /// The Language the code of the tree is written in.
+    pub language: Language,
+    /// Where the code of the tree physically comes from.
+    pub origin: Origin,
+    /// The code of the tree.
+    pub code: String,
+    /// The root node of the tree.
+    pub root: OwnedNode,
+}
+
+/// A node of an OwnedTree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedNode {
+    /// The kind of the node, such as `"call_expression"` or `"polyglot_eval_call"`.
+    pub kind: String,
+    /// Whether the node is named in the grammar of its language, as opposed to punctuation and keywords.
+    pub named: bool,
+    /// The byte range of the node, within the code of its tree.
+    pub range: Range<usize>,
+    /// The byte range of the node, in the coordinates of the file its code physically lives in.
+    pub host_range: Range<usize>,
+    /// The zero-based row and column the node starts at, within the code of its tree.
+    pub start: (usize, usize),
+    /// The zero-based row and column the node ends at, within the code of its tree.
+    pub end: (usize, usize),
+    /// The children of the node, in document order.
+    pub children: Vec<OwnedNode>,
+    /// The tree of the code evaluated by the node, if it is a polyglot eval call with a subtree.
+    pub subtree: Option<Box<OwnedTree>>,
+}
+
+impl OwnedTree {
+    /// Returns the code of the given node of this tree.
+    pub fn text(&self, node: &OwnedNode) -> &str {
+        &self.code[node.range.clone()]
+    }
+
+    /// Returns the tree as an OwnedSyntaxNode, whose spans are in the coordinates of the files the code physically lives in,
+    /// and where the only child of a polyglot eval call with a subtree is the root of that subtree, as with PolyglotZipper.
+    pub fn to_syntax_node(&self) -> OwnedSyntaxNode {
+        self.syntax_node(&self.root)
+    }
+
+    /// Writes the tree to the given writer in a compact binary form, to be read back with `read_from`.
+    ///
+    /// The form only depends on the tree, not on the platform, but may change between versions of this crate.
+    /// Returns an error if the writer fails, or if a path of the tree is not valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::owned_tree::OwnedTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 1')", Language::JavaScript).unwrap();
+    /// let owned = tree.to_owned_tree();
+    ///
+    /// let mut bytes = Vec::new();
+    /// owned.write_to(&mut bytes).unwrap();
+    /// assert_eq!(OwnedTree::read_from(&mut bytes.as_slice()).unwrap(), owned);
+    /// ```
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write_language(writer, self.language)?;
+        match &self.origin {
+            Origin::Snippet => writer.write_all(&[0])?,
+            Origin::File(path) => {
+                writer.write_all(&[1])?;
+                write_path(writer, path)?;
+            }
+            Origin::Literal { file, range } => {
+                writer.write_all(&[2])?;
+                write_option(writer, file.as_deref(), write_path)?;
+                write_range(writer, range)?;
+            }
+        }
+        write_str(writer, &self.code)?;
+        self.write_node(writer, &self.root)
+    }
+
+    fn write_node(&self, writer: &mut impl Write, node: &OwnedNode) -> std::io::Result<()> {
+        write_str(writer, &node.kind)?;
+        writer.write_all(&[u8::from(node.named)])?;
+        write_range(writer, &node.range)?;
+        write_range(writer, &node.host_range)?;
+        write_pair(writer, node.start)?;
+        write_pair(writer, node.end)?;
+        write_usize(writer, node.children.len())?;
+        for child in &node.children {
+            self.write_node(writer, child)?;
+        }
+        write_option(writer, node.subtree.as_deref(), |writer, subtree| {
+            subtree.write_to(writer)
+        })
+    }
+
+    /// Reads a tree written by `write_to` from the given reader.
+    ///
+    /// Returns an error if the reader fails, or if what it reads is not a tree written by `write_to`.
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<OwnedTree> {
+        let language = read_language(reader)?;
+        let origin = match read_byte(reader)? {
+            0 => Origin::Snippet,
+            1 => Origin::File(read_path(reader)?),
+            2 => Origin::Literal {
+                file: read_option(reader, read_path)?,
+                range: read_range(reader)?,
+            },
+            _ => return Err(invalid_data()),
+        };
+        let code = read_string(reader)?;
+        let root = read_node(reader, &code)?;
+        Ok(OwnedTree {
+            language,
+            origin,
+            code,
+            root,
+        })
+    }
+
+    fn syntax_node(&self, node: &OwnedNode) -> OwnedSyntaxNode {
+        let children = match &node.subtree {
+            Some(subtree) => vec![subtree.to_syntax_node()],
+            None => node.children.iter().map(|c| self.syntax_node(c)).collect(),
+        };
+        OwnedSyntaxNode {
+            kind: node.kind.clone(),
+            span: node.host_range.clone(),
+            language: self.language,
+            children,
+        }
+    }
+}
+
+/// Internal function reading a node written by `OwnedTree::write_node`, checking that its range is within the code of its tree.
+fn read_node(reader: &mut impl Read, code: &str) -> std::io::Result<OwnedNode> {
+    let kind = read_string(reader)?;
+    let named = read_byte(reader)? != 0;
+    let range = read_range(reader)?;
+    if code.get(range.clone()).is_none() {
+        return Err(invalid_data());
+    }
+    let host_range = read_range(reader)?;
+    let start = read_pair(reader)?;
+    let end = read_pair(reader)?;
+    // the number of children is not trusted to allocate them upfront, as a corrupted count would be huge
+    let mut children = Vec::new();
+    for _ in 0..read_usize(reader)? {
+        children.push(read_node(reader, code)?);
+    }
+    let subtree = read_option(reader, |reader| OwnedTree::read_from(reader).map(Box::new))?;
+    Ok(OwnedNode {
+        kind,
+        named,
+        range,
+        host_range,
+        start,
+        end,
+        children,
+        subtree,
+    })
+}
+
+impl PolyglotTree {
+    /// Returns an owned copy of the tree and of its subtrees, which can outlive it and, with the `serde` feature, be serialized.
+    pub fn to_owned_tree(&self) -> OwnedTree {
+        OwnedTree {
+            language: self.language,
+            origin: self.origin.clone(),
+            code: self.code.to_string(),
+            root: self.owned_node(self.root_node()),
+        }
+    }
+
+    fn owned_node(&self, node: Node) -> OwnedNode {
+        let mut cursor = node.walk();
+        let children = node
+            .children(&mut cursor)
+            .map(|child| self.owned_node(child))
+            .collect();
+        OwnedNode {
+            kind: self.polyglot_kind(node).to_string(),
+            named: node.is_named(),
+            range: node.byte_range(),
+            host_range: self.to_host_offset(node.start_byte())
+                ..self.to_host_offset(node.end_byte()),
+            start: (node.start_position().row, node.start_position().column),
+            end: (node.end_position().row, node.end_position().column),
+            children,
+            subtree: self
+                .node_to_subtrees_map
+                .get(&node.id())
+                .map(|subtree| Box::new(subtree.to_owned_tree())),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use tree_sitter::{Parser, Tree};
+
+use super::build_options::BuildOptions;
+use super::util::{self, Language};
+
+thread_local! {
+    /// Parsers that are not in use, by the Language their grammar is loaded for.
+    /// They are kept per thread, as a parser can only be used by one thread at a time.
+    static PARSERS: RefCell<HashMap<Language, Vec<Parser>>> = RefCell::new(HashMap::new());
+}
+
+/// Runs `f` with a parser for the given Language, taken from the parsers of the current thread,
+/// so that building many subtrees does not allocate a parser and load a grammar for each of them.
+///
+/// The parser is returned to the pool afterwards. Calls can be nested: a new parser is created when none is available.
+///
+/// # Panics
+///
+/// This function panics if a new parser is needed and the grammar cannot be loaded into it,
+/// which can only happen if tree_sitter and the grammars are of incompatible versions.
+pub(crate) fn with_parser<R>(language: Language, f: impl FnOnce(&mut Parser) -> R) -> R {
+    let pooled = PARSERS.with(|pool| {
+        pool.borrow_mut()
+            .get_mut(&language)
+            .and_then(|parsers| parsers.pop())
+    });
+    let mut parser = pooled.unwrap_or_else(|| {
+        let mut parser = Parser::new();
+        parser
+            .set_language(util::language_enum_to_treesitter(&language))
+            .expect("Error loading the language grammar into the parser; consider verifying your versions of the grammar and tree-sitter are compatible.");
+        parser
+    });
+
+    let result = f(&mut parser);
+
+    parser.reset(); // a parse that did not finish must not be resumed by the next one
+    PARSERS.with(|pool| {
+        pool.borrow_mut().entry(language).or_default().push(parser);
+    });
+    result
+}
+
+/// Parses `code` with a parser for the given Language, reusing `old_tree` if it is given, as with `tree_sitter::Parser::parse`.
+///
+/// The parse is aborted, returning None, when the CancellationToken of the options is cancelled.
+/// None is also returned, with a warning, if the grammar of the language is not compiled in.
+pub(crate) fn parse(
+    language: Language,
+    code: &str,
+    old_tree: Option<&Tree>,
+    options: &BuildOptions,
+) -> Option<Tree> {
+    if util::try_grammar(&language).is_none() {
+        eprintln!(
+            "Warning: unable to parse {} code, as its grammar is not compiled in",
+            util::language_enum_to_string(&language)
+        );
+        return None;
+    }
+    with_parser(language, |parser| {
+        let flag = options.cancellation.as_ref().map(|token| token.flag());
+        // SAFETY: the flag outlives the parse, and is unset before the parser goes back to the pool
+        unsafe { parser.set_cancellation_flag(flag) };
+        let tree = parser.parse(code, old_tree);
+        unsafe { parser.set_cancellation_flag(None) };
+        tree
+    })
+}
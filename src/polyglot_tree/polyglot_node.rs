@@ -0,0 +1,62 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use super::util::Language;
+
+/// The code a polyglot eval call evaluates, as read from its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalPayload {
+    /// Code written in a string literal, with escape sequences left as written.
+    /// The content of the literal starts at `offset` in the code of the tree containing the call.
+    Inline { code: String, offset: usize },
+    /// Code read from the file at the given path, resolved relative to the directory of the host file.
+    File(PathBuf),
+}
+
+/// The meaning of a polyglot call, read from its arguments once when the tree is built.
+///
+/// Every polyglot eval, import and export call whose arguments could be understood has a PolyglotNode,
+/// which can be retrieved with `PolyglotZipper::polyglot_node`.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+/// use polyglot_ast::polyglot_tree::polyglot_node::{EvalPayload, PolyglotNode};
+/// use polyglot_ast::util::Language;
+///
+/// let code = "Polyglot.eval(\"python\", 'print(42)')";
+/// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+/// let zip = PolyglotZipper::from(&tree).find_all("polyglot_eval_call").remove(0);
+///
+/// match zip.polyglot_node() {
+///     Some(PolyglotNode::EvalCall { lang, payload, span }) => {
+///         assert_eq!(*lang, Some(Language::Python));
+///         assert_eq!(*payload, Some(EvalPayload::Inline { code: String::from("print(42)"), offset: 25 }));
+///         assert_eq!(*span, 0..36);
+///     }
+///     other => panic!("unexpected node {other:?}"),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolyglotNode {
+    /// A call evaluating code in another language.
+    EvalCall {
+        /// The Language of the evaluated code, or None if it is missing or not supported.
+        lang: Option<Language>,
+        /// The evaluated code, or None if neither a string nor a path argument could be found.
+        payload: Option<EvalPayload>,
+        /// The byte range of the call, in the coordinates of the file its code physically lives in.
+        span: Range<usize>,
+    },
+    /// A call reading a value from the polyglot bindings.
+    ImportCall {
+        /// The name of the binding, or None if it is not given as a string literal.
+        name: Option<String>,
+    },
+    /// A call writing a value to the polyglot bindings.
+    ExportCall {
+        /// The name of the binding, or None if it is not given as a string literal.
+        name: Option<String>,
+    },
+}
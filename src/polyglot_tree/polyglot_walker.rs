@@ -0,0 +1,183 @@
+use std::ops::Range;
+
+use tree_sitter::{Node, TreeCursor};
+
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// A lightweight, copyable view of a node visited by a `WalkEvents` iterator.
+///
+/// Unlike a PolyglotZipper, it does not track its ancestors and cannot be used to navigate the tree,
+/// which keeps walking the tree free of allocations.
+#[derive(Clone, Copy)]
+pub struct WalkNode<'a> {
+    tree: &'a PolyglotTree,
+    node: Node<'a>,
+}
+
+impl<'a> WalkNode<'a> {
+    /// Get the node's type as a string.
+    ///
+    /// For polyglot nodes, this is one of either `"polyglot_eval_call"`, `"polyglot_import_call"` or `"polyglot_export_call"`.
+    pub fn kind(&self) -> &'static str {
+        self.tree.polyglot_kind(self.node)
+    }
+
+    /// Get the node's source code as a string.
+    pub fn code(&self) -> &'a str {
+        self.tree.node_to_code(self.node)
+    }
+
+    /// Get the Language the node is written in.
+    pub fn language(&self) -> Language {
+        self.tree.language
+    }
+
+    /// Get the Origin of the node's code, which tells which file or host string literal it physically lives in.
+    pub fn origin(&self) -> &'a Origin {
+        &self.tree.origin
+    }
+
+    /// Get the node's start position in terms of rows and columns.
+    pub fn start_position(&self) -> tree_sitter::Point {
+        self.node.start_position()
+    }
+
+    /// Get the node's end position in terms of rows and columns.
+    pub fn end_position(&self) -> tree_sitter::Point {
+        self.node.end_position()
+    }
+
+    /// Get the node's byte range within the code of the tree it belongs to.
+    pub fn byte_range(&self) -> Range<usize> {
+        self.node.byte_range()
+    }
+}
+
+/// An event produced while walking a polyglot tree with `PolyglotTree::walk_events`.
+pub enum WalkEvent<'a> {
+    /// The walk reached a node; events for its descendants follow, up to the matching `Leave`.
+    Enter(WalkNode<'a>),
+    /// The walk is done with a node and all of its descendants.
+    Leave(WalkNode<'a>),
+    /// The walk is crossing a polyglot eval call into the subtree of the given Language and Origin.
+    /// This always comes right after the `Enter` event of the eval call.
+    BoundaryEnter(Language, &'a Origin),
+    /// The walk is done with the subtree of the last `BoundaryEnter`, and goes back to its host.
+    /// This always comes right before the `Leave` event of the eval call.
+    BoundaryLeave,
+}
+
+enum Pending<'a> {
+    Enter,
+    Leave,
+    BoundaryEnter(&'a PolyglotTree),
+    BoundaryLeave,
+}
+
+/// An iterator over the WalkEvents of a polyglot tree, in document order.
+/// Refer to the `PolyglotTree::walk_events` documentation for more information.
+pub struct WalkEvents<'a> {
+    stack: Vec<(&'a PolyglotTree, TreeCursor<'a>)>,
+    pending: Option<Pending<'a>>,
+}
+
+impl<'a> WalkEvents<'a> {
+    pub(crate) fn from(tree: &'a PolyglotTree) -> WalkEvents<'a> {
+        WalkEvents {
+            stack: vec![(tree, tree.root_node().walk())],
+            pending: Some(Pending::Enter),
+        }
+    }
+
+    fn current(&self) -> WalkNode<'a> {
+        let (tree, cursor) = self.stack.last().expect("the walk is not over");
+        WalkNode {
+            tree,
+            node: cursor.node(),
+        }
+    }
+}
+
+impl<'a> Iterator for WalkEvents<'a> {
+    type Item = WalkEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.pending.take()? {
+            Pending::Enter => {
+                let current = self.current();
+                let (tree, cursor) = self.stack.last_mut()?;
+
+                // Eval calls lead to their subtree instead of their own children, like zippers do.
+                self.pending = Some(if tree.is_polyglot_eval_call(current.node) {
+                    match tree.node_to_subtrees_map.get(&current.node.id()) {
+                        Some(subtree) => Pending::BoundaryEnter(subtree.as_ref()),
+                        None => Pending::Leave,
+                    }
+                } else if cursor.goto_first_child() {
+                    Pending::Enter
+                } else {
+                    Pending::Leave
+                });
+                Some(WalkEvent::Enter(current))
+            }
+
+            Pending::Leave => {
+                let current = self.current();
+                let (_, cursor) = self.stack.last_mut()?;
+
+                self.pending = if cursor.goto_next_sibling() {
+                    Some(Pending::Enter)
+                } else if cursor.goto_parent() {
+                    Some(Pending::Leave)
+                } else {
+                    // we are done with this tree, go back to the eval call in its host if there is one
+                    self.stack.pop();
+                    if self.stack.is_empty() {
+                        None
+                    } else {
+                        Some(Pending::BoundaryLeave)
+                    }
+                };
+                Some(WalkEvent::Leave(current))
+            }
+
+            Pending::BoundaryEnter(subtree) => {
+                self.stack.push((subtree, subtree.root_node().walk()));
+                self.pending = Some(Pending::Enter);
+                Some(WalkEvent::BoundaryEnter(subtree.language, &subtree.origin))
+            }
+
+            Pending::BoundaryLeave => {
+                self.pending = Some(Pending::Leave);
+                Some(WalkEvent::BoundaryLeave)
+            }
+        }
+    }
+}
+
+/// An iterator over every node of a polyglot tree and its subtrees, in document order, along with the node's Language and Origin.
+/// Refer to the `PolyglotTree::nodes` documentation for more information.
+pub struct Nodes<'a> {
+    events: WalkEvents<'a>,
+}
+
+impl<'a> Nodes<'a> {
+    pub(crate) fn from(tree: &'a PolyglotTree) -> Nodes<'a> {
+        Nodes {
+            events: WalkEvents::from(tree),
+        }
+    }
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = (WalkNode<'a>, Language, &'a Origin);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let WalkEvent::Enter(node) = self.events.next()? {
+                return Some((node, node.language(), node.origin()));
+            }
+        }
+    }
+}
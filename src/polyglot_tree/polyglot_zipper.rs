@@ -1,33 +1,233 @@
-use tree_sitter::{Node, TreeCursor};
+use std::cmp::{Ordering, Reverse};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
 
-use super::util::{InvalidArgumentError, Language};
+use tree_sitter::{Node, Query, QueryCursor, QueryError, TreeCursor};
 
-use super::PolyglotTree;
+use super::util::{self, InvalidArgumentError, Language, TextEdit};
+
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::query_set;
+use super::{Origin, PolyglotTree};
+
+/// A single capture produced by running a tree-sitter query with `PolyglotZipper::query`.
+pub struct QueryCapture<'a> {
+    /// The name of the capture in the query, without the leading `@`.
+    pub name: String,
+    /// A zipper located at the captured node.
+    pub zipper: PolyglotZipper<'a>,
+}
+
+/// An owned handle to a node of a polyglot tree, obtained with `PolyglotZipper::detach`.
+///
+/// Unlike a zipper, it does not borrow the tree it was obtained from: it keeps a shared copy of the tree the node belongs to,
+/// so it can be stored in long-lived indexes or sent to other threads.
+#[derive(Clone)]
+pub struct DetachedNode {
+    tree: Arc<PolyglotTree>,
+    path: Vec<usize>, // child indices leading from the root of the tree to the node
+}
+
+impl DetachedNode {
+    /// Returns the tree the node belongs to. This is the subtree the node was in when it was detached, not the tree navigation started from.
+    pub fn tree(&self) -> &PolyglotTree {
+        &self.tree
+    }
+
+    /// Returns a new zipper located at the node.
+    ///
+    /// As the handle does not remember how its tree was reached, the zipper's ancestors stop at the root of that tree.
+    pub fn zipper(&self) -> PolyglotZipper<'_> {
+        let mut node = self.tree.root_node();
+        for i in self.path.iter() {
+            node = node
+                .child(*i)
+                .expect("the path was computed from this same tree");
+        }
+        PolyglotZipper::from_impl(&self.tree, node, None)
+    }
+}
 
 /// A PolyglotZipper is an object based on a PolyglotTree, which contains one of the tree's nodes.
 /// Zippers allow navigation of the tree and retrieval of node properties for analysis tasks.
+///
+/// Zippers are cheap to clone, so a position can be remembered and returned to later, or several cursors can move independently over the same tree.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("x = 1\ny = 2", Language::Python).unwrap();
+/// let mut zip = PolyglotZipper::from(&tree);
+/// assert!(zip.goto_first_child());
+///
+/// let saved = zip.clone();
+/// assert!(zip.goto_first_child());
+/// assert_eq!(zip.kind(), "assignment");
+/// assert_eq!(saved.kind(), "expression_statement");
+/// ```
+#[derive(Clone)]
 pub struct PolyglotZipper<'a> {
     tree: &'a PolyglotTree,
     node: TreeCursor<'a>,
+    host: Option<Rc<PolyglotZipper<'a>>>, // the eval call this tree was reached from, if any
 }
 
-impl PolyglotZipper<'_> {
+impl<'a> PolyglotZipper<'a> {
     /// Returns a new zipper for the given tree, located at the root.
-    pub fn from(tree: &'_ PolyglotTree) -> PolyglotZipper<'_> {
-        Self::from_impl(tree, tree.root_node())
+    pub fn from(tree: &'a PolyglotTree) -> PolyglotZipper<'a> {
+        Self::from_impl(tree, tree.root_node(), None)
     }
 
-    fn from_impl<'a>(tree: &'a PolyglotTree, node: Node<'a>) -> PolyglotZipper<'a> {
+    fn from_impl(
+        tree: &'a PolyglotTree,
+        node: Node<'a>,
+        host: Option<Rc<PolyglotZipper<'a>>>,
+    ) -> PolyglotZipper<'a> {
         PolyglotZipper {
             tree,
             node: node.walk(),
+            host,
         }
     }
 
-    fn node(&self) -> Node {
+    fn node(&self) -> Node<'a> {
         self.node.node()
     }
 
+    /// Internal function to get a zipper on another node of the same tree.
+    fn at_node(&self, node: Node<'a>) -> PolyglotZipper<'a> {
+        Self::from_impl(self.tree, node, self.host.clone())
+    }
+
+    /// Returns the underlying tree-sitter node, so it can be used with other tree-sitter based tooling.
+    ///
+    /// The node belongs to the tree-sitter tree of the tree the zipper is currently in; navigating from it with tree-sitter methods
+    /// will not cross language boundaries, and its kind does not account for polyglot nodes.
+    pub fn ts_node(&self) -> Node<'a> {
+        self.node()
+    }
+
+    /// Returns an owned handle to the contained node, which outlives the borrow of the tree this zipper is based on.
+    ///
+    /// For nodes of a subtree, the handle shares that subtree with the tree it was reached from.
+    /// For nodes of the tree navigation started from, which the zipper only borrows, the handle holds its own copy of that tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'print(42)')", Language::JavaScript).unwrap();
+    /// let detached = PolyglotZipper::from(&tree).find_all("call").remove(0).detach();
+    /// let other = PolyglotZipper::from(&tree).find_all("integer").remove(0).detach();
+    /// assert!(std::ptr::eq(detached.tree(), other.tree()));
+    /// drop(tree);
+    ///
+    /// let code = std::thread::spawn(move || detached.zipper().code().to_string()).join().unwrap();
+    /// assert_eq!(code, "print(42)");
+    /// ```
+    pub fn detach(&self) -> DetachedNode {
+        let shared = self
+            .host
+            .as_ref()
+            .and_then(|host| host.tree.node_to_subtrees_map.get(&host.node().id()));
+        DetachedNode {
+            tree: match shared {
+                Some(tree) => tree.clone(),
+                None => Arc::new(self.tree.clone()),
+            },
+            path: path_from_root(self.node()),
+        }
+    }
+
+    /// Internal function returning the tree navigation started from, which the contained node may be a subtree of.
+    pub(crate) fn root_tree(&self) -> &'a PolyglotTree {
+        match &self.host {
+            Some(host) => host.root_tree(),
+            None => self.tree,
+        }
+    }
+
+    /// Returns the stable identifier of the contained node, which designates the same node in any tree built from the same code.
+    pub fn node_id(&self) -> NodeId {
+        NodeId::from_paths(self.position_paths())
+    }
+
+    /// Returns a human-readable description of where the contained node is, such as `JavaTest.java > eval("js") > function foo > call`.
+    ///
+    /// The breadcrumb starts with the file navigation started from (or `<snippet>` for code that was not read from a file),
+    /// followed by the polyglot eval calls and the named functions, methods and classes enclosing the node, and ends with the node's kind.
+    /// Eval calls are shown as `eval("<language>")` for inline code and `evalFile("<file name>")` for files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'def f():\\n  return 42')", Language::JavaScript).unwrap();
+    /// let zip = PolyglotZipper::from(&tree).find_all("integer").remove(0);
+    /// assert_eq!(zip.breadcrumb(), "<snippet> > eval(\"python\") > function f > integer");
+    ///
+    /// let file = PathBuf::from("TestSamples/test_pyprint_file.js");
+    /// let tree = PolyglotTree::from_path(file, Language::JavaScript).unwrap();
+    /// let zip = PolyglotZipper::from(&tree).find_all("call").remove(0);
+    /// assert_eq!(zip.breadcrumb(), "test_pyprint_file.js > evalFile(\"pyprint.py\") > call");
+    /// ```
+    pub fn breadcrumb(&self) -> String {
+        let ancestors: Vec<PolyglotZipper> = self.ancestors().collect();
+        let root = ancestors.last().unwrap_or(self);
+
+        let mut crumbs = vec![match root.origin().file().and_then(|f| f.file_name()) {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => String::from("<snippet>"),
+        }];
+        for ancestor in ancestors.iter().rev() {
+            if ancestor.is_polyglot_eval_call() {
+                if let Some(subtree) = ancestor.child(0) {
+                    crumbs.push(match subtree.origin() {
+                        Origin::File(path) => format!(
+                            "evalFile(\"{}\")",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ),
+                        _ => format!(
+                            "eval(\"{}\")",
+                            util::language_enum_to_string(subtree.get_lang())
+                        ),
+                    });
+                }
+            } else if let Some(definition) = ancestor.definition_label() {
+                crumbs.push(definition);
+            }
+        }
+        crumbs.push(String::from(self.kind()));
+        crumbs.join(" > ")
+    }
+
+    /// Internal function to describe the contained node as `<keyword> <name>` if it is a named function, method or class definition.
+    fn definition_label(&self) -> Option<String> {
+        let keyword = match self.node().kind() {
+            "function_definition" | "function_declaration" | "generator_function_declaration" => {
+                "function"
+            }
+            "class_definition" | "class_declaration" => "class",
+            "method_definition" | "method_declaration" | "constructor_declaration" => "method",
+            "interface_declaration" => "interface",
+            "enum_declaration" => "enum",
+            _ => return None,
+        };
+        let name = self.node().child_by_field_name("name")?;
+        Some(format!("{keyword} {}", self.tree.node_to_code(name)))
+    }
+
     /// Returns true if the contained node is a polyglot eval call.
     pub fn is_polyglot_eval_call(&self) -> bool {
         self.tree.is_polyglot_eval_call(self.node())
@@ -47,20 +247,150 @@ impl PolyglotZipper<'_> {
     ///
     /// For polyglot nodes, this is one of either `"polyglot_eval_call"`, `"polyglot_import_call"` or `"polyglot_export_call"`.
     pub fn kind(&self) -> &str {
-        if self.is_polyglot_eval_call() {
-            return "polyglot_eval_call";
-        } else if self.is_polyglot_import_call() {
-            return "polyglot_import_call";
-        } else if self.is_polyglot_export_call() {
-            return "polyglot_export_call";
+        self.tree.polyglot_kind(self.node())
+    }
+
+    /// Get the tree-sitter field name of the contained node relative to its parent, such as `"function"` or `"arguments"` for the children of a call.
+    ///
+    /// Returns None if the node is not bound to a field in its parent, or if it is the root of a tree.
+    /// The root of a subtree is never considered to be a field of the polyglot eval call it was reached from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("print(42)", Language::Python).unwrap();
+    /// let args = PolyglotZipper::from(&tree).find_all("argument_list").remove(0);
+    ///
+    /// assert_eq!(args.field_name(), Some("arguments"));
+    /// ```
+    pub fn field_name(&self) -> Option<&'static str> {
+        let node = self.node();
+        let parent = node.parent()?;
+
+        let mut cursor = parent.walk();
+        if !cursor.goto_first_child() {
+            return None;
         }
-        self.node().kind()
+        loop {
+            if cursor.node() == node {
+                return cursor.field_name();
+            }
+            if !cursor.goto_next_sibling() {
+                return None;
+            }
+        }
+    }
+
+    /// Returns true if the contained node is an error node, inserted by the parser where it could not make sense of the code.
+    pub fn is_error(&self) -> bool {
+        self.node().is_error()
     }
+
+    /// Returns true if the contained node is missing, inserted by the parser to recover from a syntax error.
+    pub fn is_missing(&self) -> bool {
+        self.node().is_missing()
+    }
+
+    /// Returns true if the contained node's subtree contains any syntax error.
+    ///
+    /// This includes errors in the subtrees of polyglot eval calls, however deep they are behind language boundaries,
+    /// so a false result means the whole branch can safely be skipped when looking for errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "let x = 1;\nPolyglot.eval(\"python\", 'print(42')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let zip = PolyglotZipper::from(&tree);
+    ///
+    /// assert!(zip.has_error());
+    /// assert!(!zip.find_all("lexical_declaration").remove(0).has_error());
+    /// ```
+    pub fn has_error(&self) -> bool {
+        self.tree.has_error_within(self.node())
+    }
+
     /// Get the contained node's source code as a string.
     pub fn code(&self) -> &str {
         self.tree.node_to_code(self.node())
     }
 
+    /// Get the runtime value of the contained node if it is a string literal, with its quotes removed and its escape sequences decoded
+    /// according to the rules of its language. This complements `code`, which returns the literal's raw source text.
+    ///
+    /// Returns None if the node is not a string literal, or if its value is only known at runtime,
+    /// as is the case for Python f-strings and JavaScript template strings with substitutions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("print('it\\'s\\n', r'\\d')", Language::Python).unwrap();
+    /// let strings = PolyglotZipper::from(&tree).find_all("string");
+    ///
+    /// assert_eq!(strings[0].code(), "'it\\'s\\n'");
+    /// assert_eq!(strings[0].string_value(), Some(String::from("it's\n")));
+    /// assert_eq!(strings[1].string_value(), Some(String::from("\\d")));
+    /// ```
+    pub fn string_value(&self) -> Option<String> {
+        let node = self.node();
+        let code = self.code();
+        let has_child = |kind: &str| {
+            let mut cursor = node.walk();
+            let found = node.named_children(&mut cursor).any(|c| c.kind() == kind);
+            found
+        };
+
+        match (self.get_lang(), node.kind()) {
+            (Language::Python, "string") => {
+                if has_child("interpolation") {
+                    return None;
+                }
+                let prefix_len = code.find(['\'', '"'])?;
+                let prefix = code[..prefix_len].to_lowercase();
+                let rest = &code[prefix_len..];
+                let quote_len = if rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+                    3
+                } else {
+                    1
+                };
+                let content = rest.get(quote_len..rest.len().checked_sub(quote_len)?)?;
+                if prefix.contains('r') {
+                    Some(String::from(content))
+                } else {
+                    Some(util::unescape_string_literal(content, self.get_lang()).into_owned())
+                }
+            }
+            (Language::JavaScript, "string") => Some(
+                util::unescape_string_literal(util::strip_quotes(code), self.get_lang())
+                    .into_owned(),
+            ),
+            (Language::JavaScript, "template_string") => {
+                if has_child("template_substitution") {
+                    return None;
+                }
+                Some(
+                    util::unescape_string_literal(util::strip_quotes(code), self.get_lang())
+                        .into_owned(),
+                )
+            }
+            (Language::Java, "string_literal") => {
+                let quote_len = if code.starts_with("\"\"\"") { 3 } else { 1 };
+                let content = code.get(quote_len..code.len().checked_sub(quote_len)?)?;
+                Some(util::unescape_string_literal(content, self.get_lang()).into_owned())
+            }
+            _ => None,
+        }
+    }
+
     /// Get the contained node's start position in terms of rows and columns.
     pub fn start_position(&self) -> tree_sitter::Point {
         self.node().start_position()
@@ -71,22 +401,150 @@ impl PolyglotZipper<'_> {
         self.node().end_position()
     }
 
-    pub fn get_binding_name(&self) -> Result<String, InvalidArgumentError> {
-        if self.is_polyglot_import_call() || self.is_polyglot_export_call() {
-            return match self.get_lang() {
-                Language::Python => match self.get_python_binding() {
-                    Some(s) => Ok(s),
-                    None => Err(InvalidArgumentError), // todo: make this into a proper error enum
-                },
-                Language::JavaScript => todo!(),
-                Language::Java => todo!(),
+    /// Get the contained node's byte range, expressed in the coordinates of the file the node's code physically lives in.
+    ///
+    /// For nodes of subtrees built from inline string literals, the range is translated through each literal's offset in its host,
+    /// so it points into the top-level host code. Nodes of subtrees built from files through `evalFile` or `path` arguments
+    /// keep the coordinates of that file, which is given by `origin`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(42)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let call = PolyglotZipper::from(&tree).find_all("call").remove(0);
+    ///
+    /// assert_eq!(call.host_byte_range(), 25..34);
+    /// assert_eq!(&code[25..34], "print(42)");
+    /// ```
+    pub fn host_byte_range(&self) -> Range<usize> {
+        self.tree.host_range(self.node())
+    }
+
+    /// Get the start and end positions of the contained node, in the coordinates of the file its code physically lives in.
+    ///
+    /// Unlike `start_position` and `end_position`, which are relative to the code of the node's own tree,
+    /// these positions can be reported directly to users, for instance by editors, even for nodes of embedded snippets.
+    ///
+    /// If the zipper was not created from the tree the node's file was read from, as is the case for detached zippers of embedded snippets,
+    /// the positions cannot be converted and are the same as `start_position` and `end_position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    /// use tree_sitter::Point;
+    ///
+    /// let code = "let x = 1;\nPolyglot.eval(\"python\", 'print(42)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let integer = PolyglotZipper::from(&tree).find_all("integer").remove(0);
+    ///
+    /// assert_eq!(integer.start_position(), Point::new(0, 6));
+    /// assert_eq!(integer.host_positions(), (Point::new(1, 31), Point::new(1, 33)));
+    /// ```
+    pub fn host_positions(&self) -> (tree_sitter::Point, tree_sitter::Point) {
+        let mut physical = self;
+        while let Origin::Literal { .. } = physical.origin() {
+            match physical.host.as_deref() {
+                Some(host) => physical = host,
+                None => return (self.start_position(), self.end_position()),
+            }
+        }
+
+        let index = util::LineIndex::new(&physical.tree.code);
+        let range = self.host_byte_range();
+        (index.point(range.start), index.point(range.end))
+    }
+
+    /// Get the Origin of the contained node's code, which tells which file or host string literal it physically lives in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::{Origin, PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let file = PathBuf::from("TestSamples/test_pyprint_file.js");
+    /// let tree = PolyglotTree::from_path(file, Language::JavaScript).unwrap();
+    /// let zip = PolyglotZipper::from(&tree);
+    /// assert_eq!(zip.origin(), &Origin::File(PathBuf::from("TestSamples/test_pyprint_file.js")));
+    ///
+    /// let call = zip.find_all("call").remove(0);
+    /// assert_eq!(call.origin(), &Origin::File(PathBuf::from("TestSamples/pyprint.py")));
+    /// ```
+    pub fn origin(&self) -> &'a Origin {
+        &self.tree.origin
+    }
+
+    /// Returns a TextEdit that would replace the contained node's code with `replacement` in the file it physically lives in.
+    ///
+    /// The edit is only recorded, and can be materialized later with `util::apply_edits`.
+    /// When the node lives inside host string literals, `replacement` is escaped for each of them in turn,
+    /// so that the value of the literal becomes the edited guest code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{util, PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(42)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let arg = PolyglotZipper::from(&tree).find_all("integer").remove(0);
+    ///
+    /// let edit = arg.edit("'x'");
+    /// assert_eq!(util::apply_edits(code, &[edit]), "Polyglot.eval(\"python\", 'print(\\'x\\')')");
+    /// ```
+    pub fn edit(&self, replacement: &str) -> TextEdit {
+        let mut replacement = String::from(replacement);
+        let mut current = self;
+
+        // escape the replacement once per string literal level, innermost first
+        while let Origin::Literal { range, .. } = current.origin() {
+            let host = match current.host.as_deref() {
+                Some(h) => h,
+                None => break,
+            };
+            let host_start = match host.origin() {
+                Origin::Literal { range, .. } => range.start,
+                _ => 0,
             };
+            if let Some(quote) = host.tree.code[..range.start - host_start]
+                .chars()
+                .next_back()
+            {
+                replacement = util::escape_string_literal(&replacement, quote);
+            }
+            current = host;
+        }
+
+        TextEdit {
+            file: self.origin().file().cloned(),
+            range: self.host_byte_range(),
+            replacement,
+        }
+    }
+
+    /// Get the name of the binding imported or exported by the contained node.
+    ///
+    /// Returns an InvalidArgumentError if the node is not a polyglot import or export call,
+    /// or if the binding name is not given as a string literal.
+    pub fn get_binding_name(&self) -> Result<String, InvalidArgumentError> {
+        match self.polyglot_node() {
+            Some(PolyglotNode::ImportCall { name: Some(name) })
+            | Some(PolyglotNode::ExportCall { name: Some(name) }) => Ok(name.clone()),
+            _ => Err(InvalidArgumentError), // todo: make this into a proper error enum
         }
-        Err(InvalidArgumentError)
     }
 
-    fn get_python_binding(&self) -> Option<String> {
-        Some(String::from(self.child(1)?.child(1)?.code()))
+    /// Returns the meaning of the contained node if it is a polyglot eval, import or export call whose arguments could be understood.
+    pub fn polyglot_node(&self) -> Option<&'a PolyglotNode> {
+        self.tree.polyglot_node_at(self.node())
     }
 
     /// Get the Language associated with the contained node.
@@ -98,10 +556,11 @@ impl PolyglotZipper<'_> {
     /// Returns `true` if there were any children, otherwise returns `false` and does not move.
     pub fn goto_first_child(&mut self) -> bool {
         let my_id = self.node().id();
-        let subtree = self.tree.node_to_subtrees_map.get(&my_id);
+        let subtree = self.tree.node_to_subtrees_map.get(&my_id).map(Arc::as_ref);
 
         match subtree {
             Some(t) => {
+                self.host = Some(Rc::new(self.clone()));
                 self.tree = t;
                 self.node = t.root_node().walk();
                 true
@@ -114,28 +573,429 @@ impl PolyglotZipper<'_> {
     /// Move this zipper to the first sibling of the contained node.
     /// Returns `true` if there were any siblings, otherwise returns `false` and does not move.
     pub fn goto_next_sibling(&mut self) -> bool {
-        self.node.goto_next_sibling()
+        // the cursor cannot leave the node it was created at, as is the case after moving to a parent
+        if self.node.goto_next_sibling() {
+            return true;
+        }
+        match self.next_sibling() {
+            Some(z) => {
+                *self = z;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move this zipper to the previous sibling of the contained node.
+    /// Returns `true` if there was a previous sibling, otherwise returns `false` and does not move.
+    pub fn goto_prev_sibling(&mut self) -> bool {
+        match self.prev_sibling() {
+            Some(z) => {
+                *self = z;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move this zipper to the parent of the contained node.
+    /// For the root of a subtree, the parent is the polyglot eval call the subtree was reached from.
+    /// Returns `true` if there was a parent, otherwise returns `false` and does not move.
+    pub fn goto_parent(&mut self) -> bool {
+        if self.node.goto_parent() {
+            return true;
+        }
+        match self.parent() {
+            Some(z) => {
+                *self = z;
+                true
+            }
+            None => false,
+        }
     }
 
     /// Get the zipper for the child at the given index, where zero represents the first child.
-    pub fn child(&self, i: usize) -> Option<PolyglotZipper> {
+    pub fn child(&self, i: usize) -> Option<PolyglotZipper<'a>> {
         if self.is_polyglot_eval_call() {
             // if we are an eval call, we actually want to jump to the corresponding subtree
             let my_id = self.node().id();
-            let subtree = self.tree.node_to_subtrees_map.get(&my_id)?;
-            return Some(Self::from(subtree));
+            let subtree = self.tree.node_to_subtrees_map.get(&my_id)?.as_ref();
+            return Some(Self::from_impl(
+                subtree,
+                subtree.root_node(),
+                Some(Rc::new(self.clone())),
+            ));
         }
 
-        Some(Self::from_impl(self.tree, self.node.node().child(i)?))
+        Some(self.at_node(self.node().child(i)?))
     }
 
     /// Get the zipper for the next sibling node.
-    pub fn next_sibling(&self) -> Option<PolyglotZipper> {
-        Some(Self::from_impl(self.tree, self.node().next_sibling()?))
+    pub fn next_sibling(&self) -> Option<PolyglotZipper<'a>> {
+        Some(self.at_node(self.node().next_sibling()?))
     }
 
     /// Get the zipper for the previous sibling node.
-    pub fn prev_sibling(&self) -> Option<PolyglotZipper> {
-        Some(Self::from_impl(self.tree, self.node().prev_sibling()?))
+    pub fn prev_sibling(&self) -> Option<PolyglotZipper<'a>> {
+        Some(self.at_node(self.node().prev_sibling()?))
+    }
+
+    /// Get the zipper for the parent node.
+    ///
+    /// Parents are tracked across language boundaries: the parent of a subtree's root is the polyglot eval call it was reached from.
+    /// Returns None for the root of the tree the navigation started from.
+    pub fn parent(&self) -> Option<PolyglotZipper<'a>> {
+        match self.node().parent() {
+            Some(p) => Some(self.at_node(p)),
+            None => Some(self.host.as_ref()?.as_ref().clone()),
+        }
+    }
+
+    /// Returns an iterator over the ancestors of the contained node, from its parent up to the root the navigation started from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'def f():\\n  return 42')", Language::JavaScript).unwrap();
+    /// let ret = PolyglotZipper::from(&tree).find_all("return_statement").remove(0);
+    ///
+    /// let function = ret.ancestors().find(|z| z.kind() == "function_definition");
+    /// assert!(function.is_some());
+    /// assert_eq!(ret.ancestors().last().unwrap().kind(), "program");
+    /// ```
+    pub fn ancestors(&self) -> impl Iterator<Item = PolyglotZipper<'a>> {
+        std::iter::successors(self.parent(), |z| z.parent())
+    }
+
+    /// Returns an iterator over the siblings of the contained node in document order, excluding the contained node itself.
+    pub fn siblings(&self) -> impl Iterator<Item = PolyglotZipper<'a>> {
+        let mut first = self.clone();
+        while let Some(z) = first.prev_sibling() {
+            first = z;
+        }
+
+        let my_id = self.node().id();
+        std::iter::successors(Some(first), |z| z.next_sibling())
+            .filter(move |z| z.node().id() != my_id)
     }
+
+    /// Move this zipper to the next polyglot eval, import or export call in document order, crossing language boundaries as needed.
+    /// Returns `true` if there was such a call, otherwise returns `false` and does not move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let file = PathBuf::from("TestSamples/export_x.py");
+    /// let tree = PolyglotTree::from_path(file, Language::Python).unwrap();
+    /// let mut zip = PolyglotZipper::from(&tree);
+    ///
+    /// let mut kinds = Vec::new();
+    /// while zip.goto_next_polyglot_call() {
+    ///     kinds.push(zip.kind().to_string());
+    /// }
+    /// assert_eq!(kinds, vec!["polyglot_export_call", "polyglot_eval_call", "polyglot_import_call"]);
+    ///
+    /// assert!(zip.goto_prev_polyglot_call());
+    /// assert_eq!(zip.kind(), "polyglot_eval_call");
+    /// ```
+    pub fn goto_next_polyglot_call(&mut self) -> bool {
+        let mut current = self.next_in_document_order();
+        while let Some(z) = current {
+            if z.is_polyglot_call() {
+                *self = z;
+                return true;
+            }
+            current = z.next_in_document_order();
+        }
+        false
+    }
+
+    /// Move this zipper to the previous polyglot eval, import or export call in document order, crossing language boundaries as needed.
+    /// Returns `true` if there was such a call, otherwise returns `false` and does not move.
+    pub fn goto_prev_polyglot_call(&mut self) -> bool {
+        let mut current = self.prev_in_document_order();
+        while let Some(z) = current {
+            if z.is_polyglot_call() {
+                *self = z;
+                return true;
+            }
+            current = z.prev_in_document_order();
+        }
+        false
+    }
+
+    fn is_polyglot_call(&self) -> bool {
+        self.is_polyglot_eval_call()
+            || self.is_polyglot_import_call()
+            || self.is_polyglot_export_call()
+    }
+
+    /// Internal function to get the zipper for the node following the contained one in a depth-first traversal.
+    fn next_in_document_order(&self) -> Option<PolyglotZipper<'a>> {
+        if let Some(child) = self.child(0) {
+            return Some(child);
+        }
+        let mut current = self.clone();
+        loop {
+            if let Some(sibling) = current.next_sibling() {
+                return Some(sibling);
+            }
+            current = current.parent()?;
+        }
+    }
+
+    /// Internal function to get the zipper for the node preceding the contained one in a depth-first traversal.
+    fn prev_in_document_order(&self) -> Option<PolyglotZipper<'a>> {
+        let mut current = match self.prev_sibling() {
+            Some(sibling) => sibling,
+            None => return self.parent(),
+        };
+        while let Some(last) = current.last_child() {
+            current = last;
+        }
+        Some(current)
+    }
+
+    fn last_child(&self) -> Option<PolyglotZipper<'a>> {
+        if self.is_polyglot_eval_call() {
+            return self.child(0);
+        }
+        self.child(self.node().child_count().checked_sub(1)?)
+    }
+
+    /// Returns zippers for every node of the contained node's subtree whose kind is `kind`, including the contained node itself.
+    ///
+    /// The search continues across language boundaries, so nodes inside the subtrees of polyglot eval calls are also returned.
+    /// Results are given in document order, as reached by a depth-first traversal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'print(42)')", Language::JavaScript).unwrap();
+    /// let zip = PolyglotZipper::from(&tree);
+    ///
+    /// let calls = zip.find_all("call");
+    /// assert_eq!(calls.len(), 1);
+    /// assert_eq!(calls[0].code(), "print(42)");
+    /// ```
+    pub fn find_all(&self, kind: &str) -> Vec<PolyglotZipper<'a>> {
+        self.find_where(|zip| zip.kind() == kind)
+    }
+
+    /// Returns zippers for every node of the contained node's subtree for which `predicate` returns true, including the contained node itself.
+    ///
+    /// Like `find_all`, the search continues across language boundaries and results are given in document order.
+    pub fn find_where(
+        &self,
+        predicate: impl Fn(&PolyglotZipper) -> bool,
+    ) -> Vec<PolyglotZipper<'a>> {
+        let mut result = Vec::new();
+        self.find_where_impl(&predicate, &mut result);
+        result
+    }
+
+    fn find_where_impl(
+        &self,
+        predicate: &impl Fn(&PolyglotZipper) -> bool,
+        result: &mut Vec<PolyglotZipper<'a>>,
+    ) {
+        if predicate(self) {
+            result.push(self.clone());
+        }
+
+        let mut child = self.child(0);
+        while let Some(z) = child {
+            z.find_where_impl(predicate, result);
+            child = z.next_sibling();
+        }
+    }
+
+    /// Compiles `query_source` as a tree-sitter query for the contained node's language, and returns the captures it produces within the node's subtree.
+    ///
+    /// If `nested` is true, the query is also run over every nested subtree written in the same language,
+    /// however deep it is behind language boundaries; captures from nested subtrees follow those of the contained node's tree.
+    ///
+    /// Returns a QueryError if `query_source` is not a valid query for the language, or one of kind `QueryErrorKind::Language`
+    /// if the grammar of the language is not compiled in; refer to the `tree_sitter::Query::new()` documentation for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "console.log(1)\nPolyglot.eval(\"python\", 'print(2)')\nPolyglot.eval(\"js\", 'alert(3)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let zip = PolyglotZipper::from(&tree);
+    ///
+    /// let captures = zip.query("(call_expression function: (identifier) @f)", false).unwrap();
+    /// assert_eq!(captures.len(), 0);
+    ///
+    /// let captures = zip.query("(call_expression function: (identifier) @f)", true).unwrap();
+    /// assert_eq!(captures.len(), 1);
+    /// assert_eq!(captures[0].name, "f");
+    /// assert_eq!(captures[0].zipper.code(), "alert");
+    /// ```
+    pub fn query(
+        &self,
+        query_source: &str,
+        nested: bool,
+    ) -> Result<Vec<QueryCapture<'a>>, QueryError> {
+        let query = query_set::compile_query(self.get_lang(), query_source)?;
+
+        let mut result = self.query_impl(&query);
+        if nested {
+            for eval in self.find_where(|z| z.is_polyglot_eval_call()) {
+                if let Some(root) = eval.child(0) {
+                    if root.get_lang() == self.get_lang() {
+                        result.extend(root.query_impl(&query));
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn query_impl(&self, query: &Query) -> Vec<QueryCapture<'a>> {
+        let mut cursor = QueryCursor::new();
+        cursor
+            .captures(query, self.node(), self.tree.code.as_bytes())
+            .map(|(m, i)| {
+                let capture = m.captures[i];
+                QueryCapture {
+                    name: query.capture_names()[capture.index as usize].clone(),
+                    zipper: self.at_node(capture.node),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Zippers are equal when they are located at the same node of the same tree, reached through the same polyglot eval calls.
+impl PartialEq for PolyglotZipper<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PolyglotZipper<'_> {}
+
+impl Hash for PolyglotZipper<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.tree, state);
+        self.node().id().hash(state);
+    }
+}
+
+impl PartialOrd for PolyglotZipper<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Zippers are ordered by the document order of their nodes, as reached by a depth-first traversal across language boundaries:
+/// a node comes before its descendants, and a polyglot eval call before the nodes of its subtree.
+///
+/// Zippers over unrelated trees are ordered by the memory addresses of their trees, which is consistent but arbitrary.
+impl Ord for PolyglotZipper<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mine = self.position_chain();
+        let theirs = other.position_chain();
+
+        for ((my_tree, my_node), (their_tree, their_node)) in mine.iter().zip(theirs.iter()) {
+            if !std::ptr::eq(*my_tree, *their_tree) {
+                let mine: *const PolyglotTree = *my_tree;
+                let theirs: *const PolyglotTree = *their_tree;
+                return mine.cmp(&theirs);
+            }
+            if my_node != their_node {
+                return document_order_key(*my_node).cmp(&document_order_key(*their_node));
+            }
+        }
+        mine.len().cmp(&theirs.len())
+    }
+}
+
+impl<'a> PolyglotZipper<'a> {
+    /// Internal function to get the child indices leading to the contained node, one path per tree from the outermost one,
+    /// where each path but the last leads to the polyglot eval call of the next tree.
+    pub(crate) fn position_paths(&self) -> Vec<Vec<usize>> {
+        self.position_chain()
+            .into_iter()
+            .map(|(_, node)| path_from_root(node))
+            .collect()
+    }
+
+    /// Internal function to find the node at the end of the given position paths in a tree, as computed by `position_paths`.
+    pub(crate) fn resolve(
+        tree: &'a PolyglotTree,
+        paths: &[Vec<usize>],
+    ) -> Option<PolyglotZipper<'a>> {
+        let mut zip = Self::from(tree);
+        for (i, path) in paths.iter().enumerate() {
+            if i > 0 {
+                if !zip.is_polyglot_eval_call() {
+                    return None;
+                }
+                zip = zip.child(0)?;
+            }
+            let mut node = zip.node();
+            for index in path {
+                node = node.child(*index)?;
+            }
+            zip = zip.at_node(node);
+        }
+        Some(zip)
+    }
+
+    /// Internal function to get the (tree, node) pairs leading to the contained node, from the outermost eval call to the node itself.
+    fn position_chain(&self) -> Vec<(&'a PolyglotTree, Node<'a>)> {
+        let mut chain = vec![(self.tree, self.node())];
+        let mut current = self;
+        while let Some(host) = current.host.as_deref() {
+            chain.push((host.tree, host.node()));
+            current = host;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// Internal function to get the child indices leading from the root of a node's tree to the node.
+pub(crate) fn path_from_root(node: Node) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut node = node;
+    while let Some(parent) = node.parent() {
+        let mut cursor = parent.walk();
+        let index = parent
+            .children(&mut cursor)
+            .position(|c| c == node)
+            .expect("a node is always one of its parent's children");
+        path.push(index);
+        node = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Internal function to get a key ordering the nodes of a same tree in document order.
+/// Distinct nodes always get distinct keys, zero-width nodes at the same place, such as MISSING siblings, being told apart by their child indices.
+fn document_order_key(node: Node) -> (usize, Reverse<usize>, usize, Vec<usize>) {
+    let path = path_from_root(node);
+    (
+        node.start_byte(),
+        Reverse(node.end_byte()),
+        path.len(),
+        path,
+    )
 }
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use super::{Origin, PolyglotTree};
+
+/// Timings recorded while building a tree, available when the `profile` feature is enabled.
+///
+/// Every tree and subtree records its own profile, so the embedded snippets and files dominating analysis time can be found
+/// with `PolyglotTree::build_profiles`. Subtrees shared with other builds, through a FileCache or because identical snippets
+/// are parsed once, keep the profile of the build that created them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildProfile {
+    /// The time spent parsing the tree's own code.
+    pub parse: Duration,
+    /// The time spent finding the tree's polyglot calls, reading their arguments and assembling its maps.
+    pub assembly: Duration,
+    /// The time spent building the tree's subtrees, including reading the files they evaluate.
+    pub subtrees: Duration,
+}
+
+impl BuildProfile {
+    /// Returns the total time spent building the tree, including its subtrees.
+    pub fn total(&self) -> Duration {
+        self.parse + self.assembly + self.subtrees
+    }
+}
+
+impl PolyglotTree {
+    /// Returns the timings recorded while building this tree.
+    pub fn build_profile(&self) -> &BuildProfile {
+        &self.profile
+    }
+
+    /// Returns the timings recorded while building this tree and each of its subtrees, however deep they are,
+    /// along with the Origin of their code. Trees are listed depth first, in document order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(1)')\nPolyglot.eval(\"js\", 'alert(2)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    ///
+    /// let profiles = tree.build_profiles();
+    /// assert_eq!(profiles.len(), 3);
+    /// let (_, slowest) = profiles[1..].iter().max_by_key(|(_, p)| p.total()).unwrap();
+    /// assert!(slowest.total() <= tree.build_profile().subtrees);
+    /// ```
+    pub fn build_profiles(&self) -> Vec<(&Origin, &BuildProfile)> {
+        let mut result = vec![(&self.origin, &self.profile)];
+        for (_, subtree) in self.subtrees() {
+            result.extend(subtree.build_profiles());
+        }
+        result
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::batch::{BatchBuild, BatchDiagnostic, BatchDiagnosticKind};
+use super::binding_graph::unmatched_bindings;
+use super::build_options::BuildOptions;
+use super::call_sites::{BindingDirection, BindingSite, EvalSite};
+use super::file_cache::FileCache;
+use super::file_filter::FileFilter;
+use super::util::{self, Language};
+use super::PolyglotTree;
+
+/// The polyglot trees of all the files of a directory, built together so that files evaluated by several others are only parsed once.
+///
+/// The files are found by walking the root directory recursively and keeping the ones matching at least one of the given glob patterns,
+/// or all of them if no pattern is given, as described by FileFilter. Their Language is detected from their extension,
+/// and files of unsupported languages are left out.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+///
+/// let project = PolyglotProject::new("TestSamples", ["**/*.py", "**/*.js"]).unwrap();
+///
+/// assert_eq!(project.files().count(), 5);
+/// assert!(project.tree(Path::new("TestSamples/export_x.py")).is_some());
+/// assert_eq!(project.eval_sites().len(), 3);
+/// assert!(project.diagnostics().is_empty());
+/// ```
+pub struct PolyglotProject {
+    root: PathBuf,
+    filter: FileFilter,
+    options: BuildOptions,
+    build: BatchBuild,
+}
+
+/// A value exported to the polyglot bindings under some name, linked to a call importing it under the same name,
+/// as returned by `PolyglotProject::binding_links`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingLink {
+    /// The name of the binding.
+    pub name: String,
+    /// The call exporting the value.
+    pub export: BindingSite,
+    /// The call importing the value.
+    pub import: BindingSite,
+}
+
+/// What changed in a project when it was brought up to date with its files, as returned by `PolyglotProject::update`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectUpdate {
+    /// The files whose tree was built again, including the files that became part of the project, in alphabetical order.
+    pub rebuilt: Vec<PathBuf>,
+    /// The files that are no longer part of the project, in alphabetical order.
+    pub removed: Vec<PathBuf>,
+}
+
+impl PolyglotProject {
+    /// Finds the files of the given root directory matching the given patterns, and builds their trees.
+    ///
+    /// Returns an error if the root directory cannot be read. Subdirectories that cannot be read are skipped with a warning,
+    /// and files that cannot be read or parsed are reported by `diagnostics`.
+    pub fn new(
+        root: impl Into<PathBuf>,
+        patterns: impl IntoIterator<Item = impl ToString>,
+    ) -> std::io::Result<PolyglotProject> {
+        Self::with_options(root, patterns, &BuildOptions::default())
+    }
+
+    /// Same as `new`, except that trees are built as allowed by the given BuildOptions.
+    /// If the options have no FileCache, one is created for the project.
+    pub fn with_options(
+        root: impl Into<PathBuf>,
+        patterns: impl IntoIterator<Item = impl ToString>,
+        options: &BuildOptions,
+    ) -> std::io::Result<PolyglotProject> {
+        Self::with_filter(root, FileFilter::new().include(patterns), options)
+    }
+
+    /// Same as `with_options`, except that the files of the project are the ones kept by the given FileFilter.
+    pub fn with_filter(
+        root: impl Into<PathBuf>,
+        filter: FileFilter,
+        options: &BuildOptions,
+    ) -> std::io::Result<PolyglotProject> {
+        let root = root.into();
+        let mut options = options.clone();
+        if options.file_cache.is_none() {
+            options.file_cache = Some(Arc::new(FileCache::new()));
+        }
+
+        let files = filter.find_files(&root)?;
+        let build = PolyglotTree::from_paths_with_options(files, &options);
+        let mut project = PolyglotProject {
+            root,
+            filter,
+            options,
+            build,
+        };
+        project.report_cycles();
+        Ok(project)
+    }
+
+    /// Returns the root directory of the project.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the glob patterns the files of the project were selected with.
+    pub fn patterns(&self) -> &[String] {
+        self.filter.included()
+    }
+
+    /// Returns the filter the files of the project were selected with.
+    pub fn filter(&self) -> &FileFilter {
+        &self.filter
+    }
+
+    /// Returns the options the trees of the project are built with.
+    pub fn options(&self) -> &BuildOptions {
+        &self.options
+    }
+
+    /// Returns the paths of the files of the project, starting with the root directory, in alphabetical order.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.build.trees.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Returns the files of the project along with their tree, leaving out the files that could not be built.
+    pub fn trees(&self) -> impl Iterator<Item = (&Path, &PolyglotTree)> {
+        self.build
+            .trees
+            .iter()
+            .filter_map(|(path, tree)| Some((path.as_path(), tree.as_ref()?)))
+    }
+
+    /// Returns the tree of the file at the given path, which starts with the root directory,
+    /// or None if the file is not part of the project or could not be built.
+    pub fn tree(&self, path: &Path) -> Option<&PolyglotTree> {
+        self.trees()
+            .find(|(file, _)| *file == path)
+            .map(|(_, tree)| tree)
+    }
+
+    /// Returns every polyglot eval call of the project, file after file, as described by `PolyglotTree::eval_sites`.
+    /// A call found both in a file of the project and in a subtree evaluating that file is only returned once.
+    pub fn eval_sites(&self) -> Vec<EvalSite> {
+        let mut seen = HashSet::new();
+        self.trees()
+            .flat_map(|(_, tree)| tree.eval_sites())
+            .filter(|site| unique(&mut seen, site.origin.file(), &site.span))
+            .collect()
+    }
+
+    /// Returns every polyglot import and export call of the project, file after file, as described by `PolyglotTree::bindings`.
+    /// A call found both in a file of the project and in a subtree evaluating that file is only returned once.
+    pub fn bindings(&self) -> Vec<BindingSite> {
+        let mut seen = HashSet::new();
+        self.trees()
+            .flat_map(|(_, tree)| tree.bindings())
+            .filter(|site| unique(&mut seen, site.origin.file(), &site.span))
+            .collect()
+    }
+
+    /// Links every export call of the project to every import call reading a binding of the same name,
+    /// whether they are written in the same file or snippet or not, in the order of the import calls.
+    /// Calls whose binding name is not given as a string literal are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["*_x.py"]).unwrap();
+    /// let links = project.binding_links();
+    ///
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].name, "x");
+    /// assert_eq!(links[0].export.origin.file().unwrap().file_name().unwrap(), "export_x.py");
+    /// assert_eq!(links[0].import.origin.file().unwrap().file_name().unwrap(), "import_x.py");
+    /// assert_eq!(links[0].import.language, Language::Python);
+    /// ```
+    pub fn binding_links(&self) -> Vec<BindingLink> {
+        let bindings = self.bindings();
+        let exports: Vec<&BindingSite> = bindings
+            .iter()
+            .filter(|site| site.direction == BindingDirection::Export)
+            .collect();
+        bindings
+            .iter()
+            .filter(|site| site.direction == BindingDirection::Import)
+            .flat_map(|import| {
+                let name = import.name.as_ref();
+                exports
+                    .iter()
+                    .filter(move |export| name.is_some() && export.name.as_ref() == name)
+                    .map(move |export| BindingLink {
+                        name: name.cloned().unwrap_or_default(),
+                        export: (*export).clone(),
+                        import: import.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the export calls of the project writing a binding that no import call of the project reads,
+    /// as described by `binding_graph::unmatched_bindings`.
+    pub fn unused_exports(&self) -> Vec<BindingSite> {
+        unmatched_bindings(&self.bindings(), BindingDirection::Export)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the import calls of the project reading a binding that no export call of the project writes,
+    /// as described by `binding_graph::unmatched_bindings`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["*_x.py"]).unwrap();
+    /// assert!(project.unresolved_imports().is_empty());
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["import_x.py"]).unwrap();
+    /// let unresolved = project.unresolved_imports();
+    /// assert_eq!(unresolved.len(), 1);
+    /// assert_eq!(unresolved[0].name.as_deref(), Some("x"));
+    /// ```
+    pub fn unresolved_imports(&self) -> Vec<BindingSite> {
+        unmatched_bindings(&self.bindings(), BindingDirection::Import)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the problems found while building the trees of the project.
+    pub fn diagnostics(&self) -> &[BatchDiagnostic] {
+        &self.build.diagnostics
+    }
+
+    /// Brings the project up to date after the given files changed, or were created or removed, and returns what changed in it.
+    ///
+    /// The root directory is walked again for the files kept by the filter of the project. Only the new files, the changed files
+    /// and the files evaluating them, however indirectly, are built again; the trees of the other files are kept,
+    /// and the subtrees of the unchanged files they evaluate are reused from the FileCache of the project.
+    /// Paths should start with the root directory, the way the files of the project do.
+    ///
+    /// Returns an error if the root directory cannot be read anymore, in which case the project is left as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    ///
+    /// let mut project = PolyglotProject::new("src", ["**/*.py", "**/*.js"]).unwrap();
+    /// std::fs::write("src/lib.py", "x = 2").unwrap();
+    ///
+    /// let update = project.update(&[PathBuf::from("src/lib.py")]).unwrap();
+    /// for file in update.rebuilt {
+    ///     println!("rebuilt {}", file.display());
+    /// }
+    /// ```
+    pub fn update(&mut self, changed: &[PathBuf]) -> std::io::Result<ProjectUpdate> {
+        let files = self.filter.find_files(&self.root)?;
+        let graph = self.dependency_graph();
+        let mut affected: HashSet<PathBuf> = HashSet::new();
+        for path in changed {
+            affected.extend(graph.reaching(path));
+            affected.insert(util::normalize_path(path));
+        }
+        if let Some(cache) = &self.options.file_cache {
+            // the cached subtrees of the files evaluating a changed file are outdated, though these files did not change
+            cache.invalidate(|path| affected.contains(&util::normalize_path(path)));
+        }
+
+        let mut old: HashMap<PathBuf, Option<PolyglotTree>> =
+            std::mem::take(&mut self.build.trees).into_iter().collect();
+        let current: HashSet<&PathBuf> = files.iter().map(|(path, _)| path).collect();
+        let mut removed: Vec<PathBuf> = old
+            .keys()
+            .filter(|path| !current.contains(path))
+            .cloned()
+            .collect();
+        removed.sort();
+        let to_build: Vec<(PathBuf, Language)> = files
+            .iter()
+            .filter(|(path, _)| {
+                !old.contains_key(path) || affected.contains(&util::normalize_path(path))
+            })
+            .cloned()
+            .collect();
+        let rebuilt: Vec<PathBuf> = to_build.iter().map(|(path, _)| path.clone()).collect();
+
+        let built = PolyglotTree::from_paths_with_options(to_build, &self.options);
+        let mut built_trees: HashMap<PathBuf, Option<PolyglotTree>> =
+            built.trees.into_iter().collect();
+        self.build.trees = files
+            .into_iter()
+            .map(|(path, _)| {
+                let tree = match built_trees.remove(&path) {
+                    Some(tree) => tree,
+                    None => old.remove(&path).flatten(),
+                };
+                (path, tree)
+            })
+            .collect();
+
+        self.build.diagnostics.retain(|diagnostic| {
+            !rebuilt.contains(&diagnostic.path) && !removed.contains(&diagnostic.path)
+        });
+        self.build.diagnostics.extend(built.diagnostics);
+        self.report_cycles();
+
+        Ok(ProjectUpdate { rebuilt, removed })
+    }
+
+    /// Internal function reporting the files evaluating each other in a cycle, replacing the diagnostics of the missing subtrees
+    /// of the evaluations closing the cycles, which were not built on purpose.
+    fn report_cycles(&mut self) {
+        let graph = self.dependency_graph();
+        self.build
+            .diagnostics
+            .retain(|diagnostic| match &diagnostic.kind {
+                BatchDiagnosticKind::Cycle(_) => false,
+                BatchDiagnosticKind::MissingSubtree(site) => match (site.origin.file(), &site.path)
+                {
+                    (Some(from), Some(to)) => !graph
+                        .reachable_from(to)
+                        .contains(&util::normalize_path(from)),
+                    _ => true,
+                },
+                _ => true,
+            });
+        for cycle in graph.cycles() {
+            self.build.diagnostics.push(BatchDiagnostic {
+                path: cycle[0].clone(),
+                kind: BatchDiagnosticKind::Cycle(cycle),
+            });
+        }
+        // the sort is stable, so the diagnostics of each file stay in order
+        self.build.diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+}
+
+/// Internal function returning whether a call of the given file and span is seen for the first time.
+/// Calls of snippets are never considered as seen, as nothing tells them apart.
+fn unique(
+    seen: &mut HashSet<(PathBuf, Range<usize>)>,
+    file: Option<&PathBuf>,
+    span: &Range<usize>,
+) -> bool {
+    match file {
+        Some(file) => seen.insert((file.clone(), span.clone())),
+        None => true,
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::build_options::BuildOptions;
+use super::dependency_graph::{DependencyGraph, FileDependency};
+use super::disk_cache::{content_hash, grammar_stamp, hashes_unchanged, read_hashes, write_hashes};
+use super::encoding::{
+    invalid_data, read_byte, read_language, read_option, read_path, read_range, read_u64,
+    read_usize, write_atomically, write_language, write_option, write_path, write_range, write_u64,
+    write_usize,
+};
+use super::file_cache::FileCache;
+use super::file_filter::FileFilter;
+use super::owned_tree::OwnedTree;
+use super::project::{PolyglotProject, ProjectUpdate};
+use super::util::{self, Language};
+use super::PolyglotTree;
+
+/// The first bytes of the files written by `ProjectState::save`, changed whenever their format changes.
+const MAGIC: &[u8] = b"polyglot_ast project state 2\n";
+
+/// The name of the file a ProjectState is saved to, in its cache directory.
+const STATE_FILE: &str = "project";
+
+/// The parsed state of the files of a project: their trees, the files they evaluate and their fingerprints,
+/// which can be saved to a cache directory and loaded back on the next run, for instance by the jobs of a CI pipeline.
+///
+/// Trees are kept as OwnedTrees, so loading a state does not parse anything: only the files whose content changed since
+/// the state was saved, or whose evaluated files changed, are built again. Files are compared by the hash of their content,
+/// and a state saved by another version of this crate, or with other grammars, is ignored as a whole.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use polyglot_ast::polyglot_tree::build_options::BuildOptions;
+/// use polyglot_ast::polyglot_tree::file_filter::FileFilter;
+/// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+/// use polyglot_ast::polyglot_tree::project_state::ProjectState;
+///
+/// let dir = std::env::temp_dir().join(format!("polyglot_ast_state_doc_{}", std::process::id()));
+/// let project = PolyglotProject::new("TestSamples", ["**/*.py"]).unwrap();
+/// project.save_state(&dir).unwrap();
+///
+/// // a later run only builds the files that were not part of the saved state
+/// let filter = FileFilter::new().include(["**/*.py", "**/*.js"]);
+/// let (state, update) = ProjectState::load(&dir, "TestSamples", &filter, &BuildOptions::new()).unwrap();
+/// std::fs::remove_dir_all(&dir).unwrap();
+///
+/// assert_eq!(update.rebuilt.len(), 2);
+/// assert_eq!(state.trees().count(), 5);
+/// let tree = state.tree(Path::new("TestSamples/test_pyprint_file.js")).unwrap();
+/// assert_eq!(tree.root.kind, "program");
+/// assert_eq!(state.dependency_graph().dependencies().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProjectState {
+    files: Vec<(PathBuf, FileState)>,
+}
+
+/// The state of a file of a project.
+#[derive(Debug, Clone)]
+struct FileState {
+    language: Language,
+    /// The file and the files it evaluates, however indirectly, with the hash of their content,
+    /// or None for the files that could not be read.
+    sources: Vec<(PathBuf, Option<u64>)>,
+    /// The fingerprint of the tree, as given by `PolyglotTree::fingerprint_with_options`.
+    fingerprint: Option<u64>,
+    /// The files evaluated by the tree and its subtrees.
+    dependencies: Vec<FileDependency>,
+    /// The tree of the file, or None if it could not be read or parsed.
+    tree: Option<OwnedTree>,
+}
+
+impl FileState {
+    /// Internal function returning the state of a file, given its tree if it could be built.
+    fn new(
+        path: &Path,
+        language: Language,
+        tree: Option<&PolyglotTree>,
+        options: &BuildOptions,
+    ) -> FileState {
+        let mut sources = vec![(path.to_path_buf(), content_hash(path, options))];
+        let Some(tree) = tree else {
+            return FileState {
+                language,
+                sources,
+                fingerprint: None,
+                dependencies: Vec::new(),
+                tree: None,
+            };
+        };
+
+        let dependencies = DependencyGraph::from_sites(
+            tree.origin.file().map(PathBuf::as_path),
+            &tree.eval_sites(),
+            options,
+        )
+        .dependencies()
+        .to_vec();
+        let mut seen: HashSet<&PathBuf> = HashSet::new();
+        for dependency in &dependencies {
+            if seen.insert(&dependency.to) {
+                sources.push((dependency.to.clone(), content_hash(&dependency.to, options)));
+            }
+        }
+        FileState {
+            language,
+            sources,
+            fingerprint: Some(tree.fingerprint_with_options(options)),
+            dependencies,
+            tree: Some(tree.to_owned_tree()),
+        }
+    }
+
+    fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write_language(writer, self.language)?;
+        write_hashes(writer, &self.sources)?;
+        write_option(writer, self.fingerprint.as_ref(), |writer, fingerprint| {
+            write_u64(writer, *fingerprint)
+        })?;
+        write_usize(writer, self.dependencies.len())?;
+        for dependency in &self.dependencies {
+            write_path(writer, &dependency.from)?;
+            write_path(writer, &dependency.to)?;
+            write_option(writer, dependency.language.as_ref(), |writer, language| {
+                write_language(writer, *language)
+            })?;
+            write_range(writer, &dependency.span)?;
+            writer.write_all(&[u8::from(dependency.resolved)])?;
+        }
+        write_option(writer, self.tree.as_ref(), |writer, tree| {
+            tree.write_to(writer)
+        })
+    }
+
+    fn read_from(reader: &mut impl Read) -> std::io::Result<FileState> {
+        let language = read_language(reader)?;
+        let sources = read_hashes(reader)?;
+        let fingerprint = read_option(reader, read_u64)?;
+        let mut dependencies = Vec::new();
+        for _ in 0..read_usize(reader)? {
+            dependencies.push(FileDependency {
+                from: read_path(reader)?,
+                to: read_path(reader)?,
+                language: read_option(reader, read_language)?,
+                span: read_range(reader)?,
+                resolved: read_byte(reader)? != 0,
+            });
+        }
+        let tree = read_option(reader, OwnedTree::read_from)?;
+        Ok(FileState {
+            language,
+            sources,
+            fingerprint,
+            dependencies,
+            tree,
+        })
+    }
+}
+
+impl ProjectState {
+    /// Loads the state saved in the given cache directory by `save` or `PolyglotProject::save_state`, and brings it up to date
+    /// with the files of the given root directory kept by the given FileFilter, returning it along with what changed in it.
+    ///
+    /// The new files, and the files whose content or whose evaluated files' content changed, are built as allowed by the given
+    /// BuildOptions and reported as `rebuilt`; the others are read back from the saved state. Every file is built if there is
+    /// no saved state, or if it was saved by another version of this crate. The state is not saved back, see `save`.
+    ///
+    /// Returns an error if the root directory cannot be read, or if the saved state cannot be read or is corrupted.
+    pub fn load(
+        dir: &Path,
+        root: impl Into<PathBuf>,
+        filter: &FileFilter,
+        options: &BuildOptions,
+    ) -> std::io::Result<(ProjectState, ProjectUpdate)> {
+        let mut saved: HashMap<PathBuf, FileState> = Self::read(dir)?.files.into_iter().collect();
+        let files = filter.find_files(&root.into())?;
+
+        let mut to_build = Vec::new();
+        let mut state = ProjectState::default();
+        for (path, language) in files {
+            match saved.remove(&path) {
+                Some(file)
+                    if file.language == language && hashes_unchanged(&file.sources, options) =>
+                {
+                    state.files.push((path, file))
+                }
+                _ => to_build.push((path, language)),
+            }
+        }
+
+        let mut options = options.clone();
+        if options.file_cache.is_none() {
+            options.file_cache = Some(Arc::new(FileCache::new()));
+        }
+        let mut update = ProjectUpdate {
+            rebuilt: to_build.iter().map(|(path, _)| path.clone()).collect(),
+            removed: saved.into_keys().collect(),
+        };
+        update.rebuilt.sort();
+        update.removed.sort();
+        let languages: HashMap<PathBuf, Language> = to_build.iter().cloned().collect();
+        let built = PolyglotTree::from_paths_with_options(to_build, &options);
+        for (path, tree) in built.trees {
+            let file = FileState::new(&path, languages[&path], tree.as_ref(), &options);
+            state.files.push((path, file));
+        }
+        state.files.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok((state, update))
+    }
+
+    /// Saves the state to the given cache directory, creating it if needed, to be loaded back by `load`.
+    ///
+    /// Returns an error if the state cannot be written, or if one of its paths is not valid UTF-8.
+    pub fn save(&self, dir: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(dir)?;
+        write_atomically(&dir.join(STATE_FILE), |writer| {
+            writer.write_all(MAGIC)?;
+            write_u64(writer, grammar_stamp())?;
+            write_usize(writer, self.files.len())?;
+            for (path, file) in &self.files {
+                write_path(writer, path)?;
+                file.write_to(writer)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Internal function reading the state saved in the given cache directory as is, without checking whether its files changed.
+    /// Returns an empty state if there is none, or if it was saved by another version of this crate.
+    fn read(dir: &Path) -> std::io::Result<ProjectState> {
+        let mut reader = match File::open(dir.join(STATE_FILE)) {
+            Ok(file) => BufReader::new(file),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(ProjectState::default()),
+            Err(e) => return Err(e),
+        };
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC || read_u64(&mut reader)? != grammar_stamp() {
+            return Ok(ProjectState::default());
+        }
+        let mut state = ProjectState::default();
+        for _ in 0..read_usize(&mut reader)? {
+            let path = read_path(&mut reader)?;
+            state.files.push((path, FileState::read_from(&mut reader)?));
+        }
+        match reader.read(&mut [0])? {
+            0 => Ok(state),
+            _ => Err(invalid_data()),
+        }
+    }
+
+    /// Returns the paths of the files of the project, in alphabetical order.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Returns the files of the project along with their tree, leaving out the files that could not be built.
+    pub fn trees(&self) -> impl Iterator<Item = (&Path, &OwnedTree)> {
+        self.files
+            .iter()
+            .filter_map(|(path, file)| Some((path.as_path(), file.tree.as_ref()?)))
+    }
+
+    /// Returns the tree of the file at the given path, or None if the file is not part of the project or could not be built.
+    pub fn tree(&self, path: &Path) -> Option<&OwnedTree> {
+        self.file(path)?.tree.as_ref()
+    }
+
+    /// Returns the fingerprint of the tree of the file at the given path, as given by `PolyglotTree::fingerprint_with_options`,
+    /// or None if the file is not part of the project or could not be built.
+    pub fn fingerprint(&self, path: &Path) -> Option<u64> {
+        self.file(path)?.fingerprint
+    }
+
+    /// Returns the graph of the files of the project and of the files they evaluate, as `PolyglotProject::dependency_graph` does.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        // a call of a file evaluated by several files of the project is part of each of their trees, but only counts once
+        let mut seen: HashSet<(&PathBuf, &Range<usize>)> = HashSet::new();
+        let dependencies = self
+            .files
+            .iter()
+            .flat_map(|(_, file)| &file.dependencies)
+            .filter(|dependency| seen.insert((&dependency.from, &dependency.span)))
+            .cloned()
+            .collect();
+        let files = self.files().map(util::normalize_path);
+        DependencyGraph::from_dependencies(files, dependencies)
+    }
+
+    fn file(&self, path: &Path) -> Option<&FileState> {
+        self.files
+            .iter()
+            .find(|(file, _)| file == path)
+            .map(|(_, file)| file)
+    }
+}
+
+impl PolyglotProject {
+    /// Returns the parsed state of the files of the project, to be saved to a cache directory.
+    pub fn state(&self) -> ProjectState {
+        let files = self
+            .files()
+            .map(|path| {
+                let tree = self.tree(path);
+                // files that could not be built have no tree to tell their language
+                let language = tree
+                    .map(|tree| tree.language)
+                    .or_else(|| util::language_from_path(path))
+                    .unwrap_or(Language::Python);
+                let file = FileState::new(path, language, tree, self.options());
+                (path.to_path_buf(), file)
+            })
+            .collect();
+        ProjectState { files }
+    }
+
+    /// Saves the parsed state of the files of the project to the given cache directory, creating it if needed,
+    /// so that a later run can load it back with `ProjectState::load` instead of parsing every file again,
+    /// or tell which files changed since with `changes_since`.
+    ///
+    /// Returns an error if the state cannot be written, or if one of its paths is not valid UTF-8.
+    pub fn save_state(&self, dir: &Path) -> std::io::Result<()> {
+        self.state().save(dir)
+    }
+
+    /// Returns the files of the project whose tree changed since its state was saved to the given cache directory by `save_state`,
+    /// as `rebuilt`, and the files that were part of the project then but are not anymore, as `removed`.
+    ///
+    /// Files are compared by the fingerprint of their tree, which includes the content of the files they evaluate,
+    /// so a file evaluating a changed file changed as well, while a file whose whitespace changed did not.
+    /// Every file is considered changed if there is no saved state, or if it was saved by another version of this crate.
+    ///
+    /// Returns an error if the saved state cannot be read or is corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("polyglot_ast_changes_doc_{}", std::process::id()));
+    /// let project = PolyglotProject::new("TestSamples", ["**/*.py"]).unwrap();
+    /// project.save_state(&dir).unwrap();
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["**/*.py", "**/*.js"]).unwrap();
+    /// let update = project.changes_since(&dir).unwrap();
+    /// std::fs::remove_dir_all(&dir).unwrap();
+    ///
+    /// // only the JavaScript files are new
+    /// assert_eq!(update.rebuilt.len(), 2);
+    /// assert!(update.removed.is_empty());
+    /// ```
+    pub fn changes_since(&self, dir: &Path) -> std::io::Result<ProjectUpdate> {
+        let saved = ProjectState::read(dir)?;
+        let mut update = ProjectUpdate::default();
+        for file in self.files() {
+            let fingerprint = self
+                .tree(file)
+                .map(|tree| tree.fingerprint_with_options(self.options()));
+            if fingerprint.is_none() || saved.fingerprint(file) != fingerprint {
+                update.rebuilt.push(file.to_path_buf());
+            }
+        }
+        update.removed = saved
+            .files()
+            .filter(|file| self.files().all(|f| f != *file))
+            .map(Path::to_path_buf)
+            .collect();
+        Ok(update)
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use tree_sitter::{Query, QueryError, QueryErrorKind};
+
+use super::polyglot_zipper::PolyglotZipper;
+use super::util::{self, Language};
+use super::{Origin, PolyglotTree};
+
+/// A set of tree-sitter queries, at most one per Language, to be run over a whole polyglot tree with `PolyglotTree::query_all`.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{LanguageQuerySet, PolyglotTree};
+/// use polyglot_ast::util::Language;
+///
+/// let mut queries = LanguageQuerySet::new();
+/// queries.insert(Language::JavaScript, "(call_expression function: (identifier) @call)").unwrap();
+/// queries.insert(Language::Python, "(call function: (identifier) @call)").unwrap();
+///
+/// let code = "alert(1)\nPolyglot.eval(\"python\", 'print(2)')";
+/// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+/// let captures = tree.query_all(&queries);
+///
+/// let found: Vec<(Language, &str)> = captures.iter().map(|c| (c.language, c.zipper.code())).collect();
+/// assert_eq!(found, vec![(Language::JavaScript, "alert"), (Language::Python, "print")]);
+/// ```
+#[derive(Default)]
+pub struct LanguageQuerySet {
+    queries: HashMap<Language, Query>,
+}
+
+impl LanguageQuerySet {
+    /// Initializes a new, empty LanguageQuerySet.
+    pub fn new() -> LanguageQuerySet {
+        LanguageQuerySet {
+            queries: HashMap::new(),
+        }
+    }
+
+    /// Compiles `query_source` as a tree-sitter query for the given Language, and adds it to the set,
+    /// replacing the query previously set for that Language if there was one.
+    ///
+    /// Returns a QueryError if `query_source` is not a valid query for the language, or one of kind `QueryErrorKind::Language`
+    /// if the grammar of the language is not compiled in; refer to the `tree_sitter::Query::new()` documentation for more information.
+    pub fn insert(&mut self, language: Language, query_source: &str) -> Result<(), QueryError> {
+        let query = compile_query(&language, query_source)?;
+        self.queries.insert(language, query);
+        Ok(())
+    }
+
+    /// Returns the query set for the given Language, if there is one.
+    pub fn get(&self, language: &Language) -> Option<&Query> {
+        self.queries.get(language)
+    }
+
+    /// Returns true if the set contains no query.
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
+
+/// Internal function compiling `query_source` as a tree-sitter query for the given Language,
+/// returning a QueryError of kind `QueryErrorKind::Language` rather than panicking if the grammar of the language is not compiled in.
+pub(crate) fn compile_query(language: &Language, query_source: &str) -> Result<Query, QueryError> {
+    let Some(grammar) = util::try_grammar(language) else {
+        return Err(QueryError {
+            row: 0,
+            column: 0,
+            offset: 0,
+            message: format!(
+                "the {} grammar is not compiled in",
+                util::language_enum_to_string(language)
+            ),
+            kind: QueryErrorKind::Language,
+        });
+    };
+    Query::new(*grammar, query_source)
+}
+
+/// A capture produced by `PolyglotTree::query_all`, tagged with where it was found.
+#[derive(Clone)]
+pub struct LanguageCapture<'a> {
+    /// The name of the capture in the query that produced it.
+    pub name: String,
+    /// The Language of the tree the capture was found in, which is also the Language of the query that produced it.
+    pub language: Language,
+    /// The Origin of the code the capture was found in.
+    pub origin: &'a Origin,
+    /// A zipper located at the captured node.
+    pub zipper: PolyglotZipper<'a>,
+}
+
+/// A host-side capture designating a polyglot eval call, joined with the captures found in the code the call evaluates,
+/// as returned by `PolyglotTree::query_joined`.
+#[derive(Clone)]
+pub struct JoinedMatch<'a> {
+    /// The capture of the eval call, in the host's code.
+    pub host: LanguageCapture<'a>,
+    /// The captures found in the subtree of the eval call, in the guest's code.
+    pub guest: Vec<LanguageCapture<'a>>,
+}
+
+impl PolyglotTree {
+    /// Runs the queries of `queries` over this tree and every subtree, however deep it is behind language boundaries,
+    /// each tree being queried with the query set for its own Language, if any.
+    ///
+    /// Captures are grouped by tree, in the document order of the trees: those of a host tree come before the captures of its subtrees.
+    pub fn query_all(&self, queries: &LanguageQuerySet) -> Vec<LanguageCapture<'_>> {
+        let mut result = Vec::new();
+        if queries.is_empty() {
+            return result;
+        }
+
+        let root = PolyglotZipper::from(self);
+        let nested = root
+            .find_where(|z| z.is_polyglot_eval_call())
+            .into_iter()
+            .filter_map(|eval| eval.child(0));
+
+        for tree_root in std::iter::once(root.clone()).chain(nested) {
+            let query = match queries.get(tree_root.get_lang()) {
+                Some(q) => q,
+                None => continue,
+            };
+            let language = *tree_root.get_lang();
+            let origin = tree_root.origin();
+            result.extend(
+                tree_root
+                    .query_impl(query)
+                    .into_iter()
+                    .map(|capture| LanguageCapture {
+                        name: capture.name,
+                        language,
+                        origin,
+                        zipper: capture.zipper,
+                    }),
+            );
+        }
+        result
+    }
+
+    /// Runs the queries of `host` and `guest` so that their captures are correlated across language boundaries,
+    /// for instance to find a Java eval call together with the function definitions of the JavaScript code it evaluates.
+    ///
+    /// The queries of `host` are run as with `query_all`, and every capture designating a polyglot eval call with a subtree
+    /// is joined with the captures of the `guest` query for the subtree's Language, run over that subtree only.
+    /// Host captures that are not eval calls, or whose subtree produces no guest capture, are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{LanguageQuerySet, PolyglotTree};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let mut host = LanguageQuerySet::new();
+    /// host.insert(Language::JavaScript, "(call_expression) @eval").unwrap();
+    /// let mut guest = LanguageQuerySet::new();
+    /// guest.insert(Language::Python, "(function_definition name: (identifier) @function)").unwrap();
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'def f(): pass')\nPolyglot.eval(\"python\", 'x = 1')\nalert(1)";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// let matches = tree.query_joined(&host, &guest);
+    ///
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].host.zipper.host_byte_range(), 0..40);
+    /// assert_eq!(matches[0].guest[0].zipper.code(), "f");
+    /// ```
+    pub fn query_joined(
+        &self,
+        host: &LanguageQuerySet,
+        guest: &LanguageQuerySet,
+    ) -> Vec<JoinedMatch<'_>> {
+        let mut result = Vec::new();
+        for capture in self.query_all(host) {
+            if !capture.zipper.is_polyglot_eval_call() {
+                continue;
+            }
+            let root = match capture.zipper.child(0) {
+                Some(root) => root,
+                None => continue, // the call has no subtree
+            };
+            let query = match guest.get(root.get_lang()) {
+                Some(q) => q,
+                None => continue,
+            };
+
+            let language = *root.get_lang();
+            let origin = root.origin();
+            let guest_captures: Vec<LanguageCapture> = root
+                .query_impl(query)
+                .into_iter()
+                .map(|c| LanguageCapture {
+                    name: c.name,
+                    language,
+                    origin,
+                    zipper: c.zipper,
+                })
+                .collect();
+            if !guest_captures.is_empty() {
+                result.push(JoinedMatch {
+                    host: capture,
+                    guest: guest_captures,
+                });
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use tree_sitter::Point;
+
+use super::call_sites::BindingDirection;
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::polyglot_zipper::PolyglotZipper;
+use super::project::PolyglotProject;
+use super::util::{self, Language};
+use super::{Origin, PolyglotTree};
+
+/// How a Reference refers to a polyglot binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    /// The name of the binding in an export call.
+    Export,
+    /// The name of the binding in an import call.
+    Import,
+    /// The name of the binding passed to `hasMember` or `removeMember` on the bindings of a Java Context.
+    Member,
+    /// Where the exported value is defined, as described by `DefUseChain::definition`.
+    Definition,
+    /// The identifier the imported value is assigned to.
+    Variable,
+    /// An identifier reading the imported value.
+    Use,
+}
+
+/// A node referring to a polyglot binding, as returned by `PolyglotZipper::references`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    /// The NodeId of the node, from the root of the tree it was found in.
+    pub id: NodeId,
+    /// The Language of the code containing the node.
+    pub language: Language,
+    /// The Origin of the code containing the node.
+    pub origin: Origin,
+    /// The byte range of the node, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the node in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+}
+
+impl<'a> PolyglotZipper<'a> {
+    /// Returns the name of the polyglot binding the contained node refers to: the binding of the import or export call containing it,
+    /// or the binding whose value an identifier defines or reads, as found by `PolyglotTree::def_use_chains`.
+    /// The binding name passed to a Java member access is recognized as well.
+    ///
+    /// Returns None if the node does not refer to a binding whose name is given as a string literal.
+    pub fn binding(&self) -> Option<String> {
+        for zip in std::iter::once(self.clone()).chain(self.ancestors()) {
+            match zip.polyglot_node() {
+                Some(PolyglotNode::ImportCall { name })
+                | Some(PolyglotNode::ExportCall { name }) => return name.clone(),
+                Some(PolyglotNode::EvalCall { .. }) => break,
+                None => {}
+            }
+        }
+
+        let root = self.root_tree();
+        let id = self.node_id();
+        if matches!(self.kind(), "string" | "string_literal") {
+            let name = util::strip_quotes(self.code());
+            if root.binding_literals(name).iter().any(|(i, _)| *i == id) {
+                return Some(name.to_string());
+            }
+        }
+        root.def_use_chains()
+            .into_iter()
+            .find(|chain| {
+                let variable = chain.variable.iter();
+                let definition = chain.definition.iter();
+                variable
+                    .chain(definition)
+                    .chain(chain.uses.iter())
+                    .any(|node| node.id == id)
+            })
+            .map(|chain| chain.name)
+    }
+
+    /// Returns every reference to the binding the contained node refers to, as found by `binding`, in the tree navigation started from
+    /// and in its subtrees, whatever their language, in document order. The contained node is included.
+    ///
+    /// The names of the binding in import and export calls and in Java member accesses are returned, along with the identifiers
+    /// defining the exported values and the identifiers the imported values are assigned to and read from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{PolyglotTree, PolyglotZipper};
+    /// use polyglot_ast::polyglot_tree::references::ReferenceKind;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\nx = 42\npolyglot.export_value(name=\"answer\", value=x)\npolyglot.eval(language=\"js\", string=\"let a = Polyglot.import('answer'); console.log(a)\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let x = PolyglotZipper::from(&tree).find_all("identifier").into_iter().find(|z| z.code() == "x").unwrap();
+    ///
+    /// let references = x.references();
+    /// let kinds: Vec<ReferenceKind> = references.iter().map(|r| r.kind).collect();
+    /// assert_eq!(kinds, vec![ReferenceKind::Definition, ReferenceKind::Export, ReferenceKind::Variable, ReferenceKind::Import, ReferenceKind::Use]);
+    /// assert_eq!(&code[references[3].span.clone()], "'answer'");
+    /// assert_eq!(references[3].language, Language::JavaScript);
+    /// ```
+    pub fn references(&self) -> Vec<Reference> {
+        match self.binding() {
+            Some(name) => self.root_tree().binding_references(&name),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl PolyglotTree {
+    /// Returns the references to the binding of the given name in the tree and in its subtrees, in document order,
+    /// as described by `PolyglotZipper::references`.
+    pub fn binding_references(&self, name: &str) -> Vec<Reference> {
+        let mut ids: Vec<(NodeId, ReferenceKind)> = self
+            .binding_literals(name)
+            .into_iter()
+            .map(|(id, direction)| match direction {
+                Some(BindingDirection::Export) => (id, ReferenceKind::Export),
+                Some(BindingDirection::Import) => (id, ReferenceKind::Import),
+                None => (id, ReferenceKind::Member),
+            })
+            .collect();
+        for chain in self.def_use_chains() {
+            if chain.name != name {
+                continue;
+            }
+            let definition = chain.definition.map(|n| (n.id, ReferenceKind::Definition));
+            let variable = chain.variable.map(|n| (n.id, ReferenceKind::Variable));
+            let uses = chain.uses.into_iter().map(|n| (n.id, ReferenceKind::Use));
+            ids.extend(definition.into_iter().chain(variable).chain(uses));
+        }
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+        // chains share their definitions with the other chains of the same export call, and their uses with the ones of the same import call
+        ids.dedup_by(|a, b| a.0 == b.0);
+
+        ids.into_iter()
+            .filter_map(|(id, kind)| {
+                let zip = self.zipper_at(&id)?;
+                Some(Reference {
+                    kind,
+                    language: *zip.get_lang(),
+                    origin: zip.origin().clone(),
+                    span: zip.host_byte_range(),
+                    start_position: zip.start_position(),
+                    id,
+                })
+            })
+            .collect()
+    }
+}
+
+impl PolyglotProject {
+    /// Returns every reference to the binding the node of the given zipper refers to, as described by `PolyglotZipper::references`,
+    /// in every file of the project, file after file. A reference found both in a file of the project and in a subtree
+    /// evaluating that file is only returned once, with the NodeId it has in the first tree it was found in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use polyglot_ast::PolyglotZipper;
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    /// use polyglot_ast::polyglot_tree::references::ReferenceKind;
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["*_x.py"]).unwrap();
+    /// let tree = project.tree(Path::new("TestSamples/import_x.py")).unwrap();
+    /// let y = PolyglotZipper::from(tree).find_all("identifier").into_iter().find(|z| z.code() == "y").unwrap();
+    ///
+    /// let references = project.references(&y);
+    /// let mut exports = references.iter().filter(|r| r.kind == ReferenceKind::Export);
+    /// assert_eq!(exports.next().unwrap().origin.file().unwrap().file_name().unwrap(), "export_x.py");
+    /// assert!(references.iter().any(|r| r.kind == ReferenceKind::Use));
+    /// ```
+    pub fn references(&self, zipper: &PolyglotZipper) -> Vec<Reference> {
+        match self.binding(zipper) {
+            Some(name) => self.binding_references(&name),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the name of the polyglot binding the node of the given zipper refers to, as described by `PolyglotZipper::binding`,
+    /// looking for the identifiers of the file the node belongs to in the trees of the files evaluating it as well.
+    pub fn binding(&self, zipper: &PolyglotZipper) -> Option<String> {
+        // the chains of an identifier may only be found in the trees evaluating the file it belongs to
+        let file = zipper.origin().file();
+        let span = zipper.host_byte_range();
+        zipper.binding().or_else(|| {
+            self.trees().find_map(|(_, tree)| {
+                let chains = tree.def_use_chains();
+                chains
+                    .into_iter()
+                    .find(|chain| {
+                        let variable = chain.variable.iter();
+                        let definition = chain.definition.iter();
+                        variable
+                            .chain(definition)
+                            .chain(chain.uses.iter())
+                            .any(|node| node.origin.file() == file && node.span == span)
+                    })
+                    .map(|chain| chain.name)
+            })
+        })
+    }
+
+    /// Returns the references to the binding of the given name in every file of the project, as described by
+    /// `PolyglotTree::binding_references`, file after file, each reference being only returned once.
+    pub fn binding_references(&self, name: &str) -> Vec<Reference> {
+        let mut seen = HashSet::new();
+        self.trees()
+            .flat_map(|(_, tree)| tree.binding_references(name))
+            .filter(|reference| match reference.origin.file() {
+                Some(file) => seen.insert((file.clone(), reference.span.clone())),
+                None => true,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use tree_sitter::Node;
+
+use super::call_sites::BindingDirection;
+use super::data_flow::descendant_id;
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::project::PolyglotProject;
+use super::util::{self, Language, TextEdit};
+use super::PolyglotTree;
+
+/// The methods of the Java bindings object, as returned by `Context.getBindings`, that take a binding name
+/// without importing or exporting it.
+const MEMBER_METHODS: [&str; 2] = ["hasMember", "removeMember"];
+
+impl PolyglotTree {
+    /// Returns the TextEdits renaming the polyglot binding `old` to `new` in the tree and in its subtrees, in document order.
+    ///
+    /// Every import and export call whose binding name is given as a string literal equal to `old` has that literal replaced,
+    /// as well as the string literals passed to `hasMember` and `removeMember` on the bindings of a Java Context.
+    /// The new literals keep the quotes of the old ones, and are escaped once more for each string literal embedding
+    /// the code they belong to, so that the edits can be applied as they are with `util::apply_edits`, after grouping them by file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::{util, PolyglotTree};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = r#"polyglot.export_value(name="x", value=1)
+    /// polyglot.eval(language="js", string="Polyglot.import('x')")"#;
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let edits = tree.rename_binding("x", "it's");
+    ///
+    /// assert_eq!(edits.len(), 2);
+    /// assert_eq!(util::apply_edits(code, &edits), r#"polyglot.export_value(name="it's", value=1)
+    /// polyglot.eval(language="js", string="Polyglot.import('it\\'s')")"#);
+    /// ```
+    pub fn rename_binding(&self, old: &str, new: &str) -> Vec<TextEdit> {
+        self.binding_literals(old)
+            .into_iter()
+            .filter_map(|(id, _)| {
+                let literal = self.zipper_at(&id)?;
+                Some(literal.edit(&string_literal(literal.code(), new)))
+            })
+            .collect()
+    }
+
+    /// Internal function returning the NodeIds of the string literals naming the given binding, in document order,
+    /// with the direction of their import or export call, or None for the arguments of Java member accesses.
+    pub(super) fn binding_literals(&self, name: &str) -> Vec<(NodeId, Option<BindingDirection>)> {
+        let mut literals = Vec::new();
+        self.visit_nodes(&mut |tree, node, id| {
+            let literal = match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ImportCall { name: Some(n) }) if n == name => tree
+                    .binding_name_node(node)
+                    .map(|literal| (literal, Some(BindingDirection::Import))),
+                Some(PolyglotNode::ExportCall { name: Some(n) }) if n == name => tree
+                    .binding_name_node(node)
+                    .map(|literal| (literal, Some(BindingDirection::Export))),
+                _ => tree
+                    .member_name_node(node, name)
+                    .map(|literal| (literal, None)),
+            };
+            if let Some((literal, direction)) = literal {
+                literals.push((descendant_id(id, node, literal), direction));
+            }
+        });
+        literals
+    }
+
+    /// Internal function returning the string literal passed to `hasMember` or `removeMember` on the bindings of a Java Context,
+    /// if the node is such a call and the literal names the given binding.
+    pub(super) fn member_name_node<'t>(&self, node: Node<'t>, name: &str) -> Option<Node<'t>> {
+        if self.language != Language::Java || node.kind() != "method_invocation" {
+            return None;
+        }
+        let method = node.child_by_field_name("name")?;
+        let object = node.child_by_field_name("object")?;
+        if !MEMBER_METHODS.contains(&self.node_to_code(method))
+            || object.kind() != "method_invocation"
+            || self.node_to_code(object.child_by_field_name("name")?) != "getBindings"
+        {
+            return None;
+        }
+        let args = node.child_by_field_name("arguments")?;
+        let literal = args.named_child(0)?;
+        match literal.kind() == "string_literal"
+            && util::strip_quotes(self.node_to_code(literal)) == name
+        {
+            true => Some(literal),
+            false => None,
+        }
+    }
+}
+
+impl PolyglotProject {
+    /// Returns the TextEdits renaming the polyglot binding `old` to `new` in every file of the project, as described by
+    /// `PolyglotTree::rename_binding`, file after file. A literal found both in a file of the project and in a subtree
+    /// evaluating that file is only renamed once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["*_x.py"]).unwrap();
+    /// let edits = project.rename_binding("x", "answer");
+    ///
+    /// let mut files: Vec<_> = edits.iter().map(|e| e.file.as_ref().unwrap().file_name().unwrap()).collect();
+    /// files.sort();
+    /// assert_eq!(files, ["export_x.py", "import_x.py"]);
+    /// assert!(edits.iter().all(|e| e.replacement == "\"answer\""));
+    /// ```
+    pub fn rename_binding(&self, old: &str, new: &str) -> Vec<TextEdit> {
+        let mut seen = HashSet::new();
+        self.trees()
+            .flat_map(|(_, tree)| tree.rename_binding(old, new))
+            .filter(|edit| match &edit.file {
+                Some(file) => seen.insert((file.clone(), edit.range.clone())),
+                None => true,
+            })
+            .collect()
+    }
+}
+
+/// Internal function returning a string literal whose value is `value`, written with the prefix and quotes of the literal `code`.
+/// Raw string prefixes are left out, as the value is escaped.
+fn string_literal(code: &str, value: &str) -> String {
+    let start = code.find(['\'', '"', '`']).unwrap_or(0);
+    let prefix: String = code[..start]
+        .chars()
+        .filter(|c| !matches!(c, 'r' | 'R'))
+        .collect();
+    let rest = &code[start..];
+    let quotes = match rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+        true => &rest[..3],
+        false => rest.get(..1).unwrap_or("\""),
+    };
+    let quote = quotes.chars().next().unwrap_or('"');
+    format!(
+        "{prefix}{quotes}{}{quotes}",
+        util::escape_string_literal(value, quote)
+    )
+}
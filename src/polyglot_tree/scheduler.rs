@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::SystemTime;
+
+use super::build_options::{BuildContext, BuildOptions, PolyglotFreeFiles};
+use super::polyglot_node::{EvalPayload, PolyglotNode};
+use super::sharded_map::lock;
+use super::util::{self, Language};
+use super::{Origin, PolyglotTree};
+
+/// What became of one of the inputs of a batch.
+pub(crate) enum BuiltFile {
+    Tree(Box<PolyglotTree>),
+    /// The file could not be read or parsed.
+    Unbuilt,
+    /// The file contains no polyglot call, and was skipped as allowed by `BuildOptions::polyglot_free_files`.
+    Skipped,
+}
+
+/// A file to build, as one of the inputs of a batch or as a file evaluated by another file of the batch.
+struct FileJob {
+    path: PathBuf,
+    language: Language,
+    /// The number of language boundaries crossed to reach the file, 0 for the inputs.
+    depth: usize,
+    modified: Option<SystemTime>,
+    /// The tree of the file's own code, without its subtrees, until they are built.
+    host: Option<PolyglotTree>,
+    /// Whether a scan of the file's code showed it contains no polyglot call, in which case its host is already complete.
+    polyglot_free: bool,
+    skipped: bool,
+    /// The number of files it evaluates that are not built yet.
+    pending: usize,
+    /// The files evaluating it, which can only be built once it is.
+    dependents: Vec<usize>,
+    /// The complete tree, for the inputs.
+    built: Option<PolyglotTree>,
+}
+
+/// The files of a batch and the dependencies between them, which form a DAG as every dependency is one boundary deeper than its dependent.
+#[derive(Default)]
+struct FileGraph {
+    jobs: Vec<FileJob>,
+    index: HashMap<(PathBuf, Language), usize>,
+}
+
+impl FileGraph {
+    /// Returns the job building the given file, and whether it was just added.
+    /// A file that is already part of the graph at a different depth is not added again, and will be built when it is needed instead.
+    fn job(&mut self, path: PathBuf, language: Language, depth: usize) -> (usize, bool) {
+        if let Some(&i) = self.index.get(&(path.clone(), language)) {
+            return (i, false);
+        }
+        let i = self.jobs.len();
+        self.index.insert((path.clone(), language), i);
+        self.jobs.push(FileJob {
+            path,
+            language,
+            depth,
+            modified: None,
+            host: None,
+            polyglot_free: false,
+            skipped: false,
+            pending: 0,
+            dependents: Vec::new(),
+            built: None,
+        });
+        (i, true)
+    }
+}
+
+/// Builds the given files on up to `threads` threads, returning what became of them in the order of the inputs.
+///
+/// The files are first parsed on their own, which reveals the files they evaluate. Evaluated files are then built before the files evaluating them,
+/// and stored into the cache of the options, so that each of them is only built once, however many files evaluate it,
+/// while the files that do not depend on each other are built in parallel.
+pub(crate) fn build_files(
+    inputs: Vec<(PathBuf, Language)>,
+    options: &BuildOptions,
+    threads: usize,
+) -> Vec<BuiltFile> {
+    let graph = Mutex::new(FileGraph::default());
+    let roots: Vec<usize> = inputs
+        .into_iter()
+        .map(|(path, language)| lock(&graph).job(path, language, 0).0)
+        .collect();
+    let mut unique = roots.clone();
+    unique.sort_unstable();
+    unique.dedup();
+
+    // discover the files to build, parsing the code of each of them
+    run_tasks(threads, unique, |i| {
+        if options.is_cancelled() {
+            return Vec::new();
+        }
+        let (path, language, depth) = {
+            let graph = lock(&graph);
+            let job = &graph.jobs[i];
+            (job.path.clone(), job.language, job.depth)
+        };
+        let modified = options.provider().modified(&path);
+        let code = PolyglotTree::read_file(&path, options);
+        let polyglot_free = options.polyglot_free_files != PolyglotFreeFiles::Build
+            && code
+                .as_ref()
+                .is_some_and(|code| !util::may_contain_polyglot_calls(code, &language));
+        if polyglot_free && depth == 0 && options.polyglot_free_files == PolyglotFreeFiles::Skip {
+            lock(&graph).jobs[i].skipped = true;
+            return Vec::new();
+        }
+
+        let host = code.and_then(|code| {
+            if polyglot_free {
+                let working_dir = path.parent()?.to_path_buf();
+                let origin = Origin::File(path);
+                return PolyglotTree::parse_only(code, language, working_dir, origin, options);
+            }
+            // subtrees are left out for now, the host is completed once the files it evaluates are built
+            let host_options = options.clone().max_depth(depth);
+            let ctx = BuildContext::at_depth(&host_options, depth);
+            PolyglotTree::from_file_code(code, path, language, &ctx)
+        });
+
+        let evaluated: HashSet<(PathBuf, Language)> = host
+            .iter()
+            .flat_map(|host| host.polyglot_nodes.values())
+            .filter_map(|node| match node {
+                PolyglotNode::EvalCall {
+                    lang: Some(lang),
+                    payload: Some(EvalPayload::File(path)),
+                    ..
+                } if options.allows(*lang, depth + 1) => Some((path.clone(), *lang)),
+                _ => None,
+            })
+            .collect();
+
+        let mut graph = lock(&graph);
+        graph.jobs[i].modified = modified;
+        graph.jobs[i].host = host;
+        graph.jobs[i].polyglot_free = polyglot_free;
+        let mut discovered = Vec::new();
+        for (path, language) in evaluated {
+            let (dependency, added) = graph.job(path, language, depth + 1);
+            if added {
+                discovered.push(dependency);
+            }
+            if graph.jobs[dependency].depth == depth + 1 {
+                graph.jobs[dependency].dependents.push(i);
+                graph.jobs[i].pending += 1;
+            }
+        }
+        discovered
+    });
+
+    // build the files once the files they evaluate are built
+    let ready: Vec<usize> = {
+        let graph = lock(&graph);
+        (0..graph.jobs.len())
+            .filter(|&i| graph.jobs[i].pending == 0)
+            .collect()
+    };
+    run_tasks(threads, ready, |i| {
+        let (host, path, depth, polyglot_free) = {
+            let mut graph = lock(&graph);
+            let job = &mut graph.jobs[i];
+            (
+                job.host.take(),
+                job.path.clone(),
+                job.depth,
+                job.polyglot_free,
+            )
+        };
+        let built = host.filter(|_| !options.is_cancelled()).map(|mut tree| {
+            if !polyglot_free {
+                let ctx = BuildContext::at_depth(options, depth).in_file(&path);
+                (tree.node_to_subtrees_map, tree.polyglot_nodes) = tree.build_polyglot_tree(&ctx);
+            }
+            tree
+        });
+
+        let mut graph = lock(&graph);
+        let job = &mut graph.jobs[i];
+        match (built, &options.file_cache) {
+            (Some(tree), Some(cache)) if depth > 0 => {
+                cache.insert(
+                    job.path.clone(),
+                    job.language,
+                    depth,
+                    job.modified,
+                    Arc::new(tree),
+                );
+            }
+            (built, _) => job.built = built,
+        }
+        // a file that could not be built is done all the same, its dependents will report it
+        let dependents = std::mem::take(&mut job.dependents);
+        dependents
+            .into_iter()
+            .filter(|&d| {
+                graph.jobs[d].pending -= 1;
+                graph.jobs[d].pending == 0
+            })
+            .collect()
+    });
+
+    let graph = lock(&graph);
+    roots
+        .into_iter()
+        .map(|i| match &graph.jobs[i] {
+            job if job.skipped => BuiltFile::Skipped,
+            FileJob {
+                built: Some(tree), ..
+            } => BuiltFile::Tree(Box::new(tree.clone())),
+            _ => BuiltFile::Unbuilt,
+        })
+        .collect()
+}
+
+/// Runs `work` on the given tasks on up to `threads` threads, as well as on the tasks it returns, until there are none left.
+fn run_tasks(threads: usize, tasks: Vec<usize>, work: impl Fn(usize) -> Vec<usize> + Sync) {
+    // the tasks waiting to be run, and the number of tasks running, which can still add tasks
+    let queue = Mutex::new((tasks, 0));
+    let changed = Condvar::new();
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let task = {
+                    let mut queue = lock(&queue);
+                    loop {
+                        if let Some(task) = queue.0.pop() {
+                            queue.1 += 1;
+                            break task;
+                        }
+                        if queue.1 == 0 {
+                            return;
+                        }
+                        queue = changed.wait(queue).unwrap_or_else(|e| e.into_inner());
+                    }
+                };
+                let added = panic::catch_unwind(AssertUnwindSafe(|| work(task)));
+                let mut queue = lock(&queue);
+                queue.1 -= 1;
+                changed.notify_all();
+                match added {
+                    Ok(added) => queue.0.extend(added),
+                    Err(payload) => {
+                        drop(queue); // let the other threads finish before propagating the panic
+                        panic::resume_unwind(payload);
+                    }
+                }
+            });
+        }
+    });
+}
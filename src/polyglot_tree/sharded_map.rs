@@ -0,0 +1,60 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Mutex, MutexGuard};
+
+/// The number of shards of a ShardedMap, enough for threads working on different keys to rarely wait for each other.
+const SHARDS: usize = 16;
+
+/// A map shared between threads, split into shards locked independently of each other,
+/// so that threads accessing different keys do not wait on a single lock.
+pub(crate) struct ShardedMap<K, V> {
+    shards: Box<[Mutex<HashMap<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq, V> ShardedMap<K, V> {
+    pub(crate) fn new() -> ShardedMap<K, V> {
+        ShardedMap {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Locks and returns the shard holding the given key.
+    pub(crate) fn shard(&self, key: &K) -> MutexGuard<'_, HashMap<K, V>> {
+        let index = self.hasher.hash_one(key) as usize % SHARDS;
+        lock(&self.shards[index])
+    }
+
+    /// Returns the number of entries in all the shards, locking them one after another.
+    pub(crate) fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).len()).sum()
+    }
+
+    /// Only keeps the entries for which `f` returns true, locking the shards one after another.
+    pub(crate) fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        for shard in self.shards.iter() {
+            lock(shard).retain(&mut f);
+        }
+    }
+
+    /// Removes every entry, locking the shards one after another.
+    pub(crate) fn clear(&self) {
+        for shard in self.shards.iter() {
+            lock(shard).clear();
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Default for ShardedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Internal function locking a mutex even if another thread panicked while holding it.
+/// The data the crate guards with mutexes is never left in an inconsistent state by a panic, so it can still be used.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
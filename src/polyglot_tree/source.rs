@@ -0,0 +1,98 @@
+use super::polyglot_node::{EvalPayload, PolyglotNode};
+use super::PolyglotTree;
+
+impl PolyglotTree {
+    /// Regenerates the source text of the tree, the code of every inline polyglot eval call being regenerated from its subtree.
+    ///
+    /// For a tree that was not modified, the result is the code the tree was built from,
+    /// which makes this method a round-trip check as well as the base for rewriting features.
+    /// Code evaluated from other files is not included: only the path passed to the call is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(42)')";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// assert_eq!(tree.to_source(), code);
+    /// ```
+    pub fn to_source(&self) -> String {
+        self.to_source_impl(false)
+    }
+
+    /// Same as `to_source`, except that the code of embedded snippets is normalized:
+    /// blank lines at its start and end and whitespace at the end of its lines are removed, and its common indentation is stripped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "Polyglot.eval(\"python\", `\n    x = 1\n    if x:  \n        print(x)\n`)";
+    /// let tree = PolyglotTree::from(code, Language::JavaScript).unwrap();
+    /// assert_eq!(tree.to_normalized_source(), "Polyglot.eval(\"python\", `x = 1\nif x:\n    print(x)`)");
+    /// ```
+    pub fn to_normalized_source(&self) -> String {
+        self.to_source_impl(true)
+    }
+
+    fn to_source_impl(&self, normalize: bool) -> String {
+        let mut literals: Vec<(usize, usize, &PolyglotTree)> = self
+            .polyglot_nodes
+            .iter()
+            .filter_map(|(id, node)| match node {
+                PolyglotNode::EvalCall {
+                    payload: Some(EvalPayload::Inline { code, offset }),
+                    ..
+                } => Some((
+                    *offset,
+                    code.len(),
+                    self.node_to_subtrees_map.get(id)?.as_ref(),
+                )),
+                _ => None,
+            })
+            .collect();
+        literals.sort_by_key(|(offset, _, _)| *offset);
+
+        let mut result = String::with_capacity(self.code.len());
+        let mut last = 0;
+        for (offset, len, subtree) in literals {
+            result.push_str(&self.code[last..offset]);
+            let code = subtree.to_source_impl(normalize);
+            if normalize {
+                result.push_str(&normalize_snippet(&code));
+            } else {
+                result.push_str(&code);
+            }
+            last = offset + len;
+        }
+        result.push_str(&self.code[last..]);
+        result
+    }
+}
+
+/// Internal function to normalize the code of an embedded snippet, as described in `PolyglotTree::to_normalized_source`.
+fn normalize_snippet(code: &str) -> String {
+    let lines: Vec<&str> = code.lines().map(str::trim_end).collect();
+    let first = lines.iter().position(|l| !l.is_empty());
+    let last = lines.iter().rposition(|l| !l.is_empty());
+    let lines = match (first, last) {
+        (Some(first), Some(last)) => &lines[first..=last],
+        _ => return String::new(),
+    };
+
+    let indent = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
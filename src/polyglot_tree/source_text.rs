@@ -0,0 +1,102 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+/// The code of a PolyglotTree, as a view into a shared, reference counted buffer.
+///
+/// The code of an inline snippet is a view into the buffer of its host's code rather than a copy of it,
+/// so a whole tree only stores the code of each file once, however many snippets it contains.
+#[derive(Clone)]
+pub(crate) struct SourceText {
+    buffer: Buffer,
+    range: Range<usize>,
+}
+
+#[derive(Clone)]
+enum Buffer {
+    Owned(Arc<str>),
+    /// A memory-mapped file, whose content was checked to be valid UTF-8 when it was mapped.
+    #[cfg(feature = "mmap")]
+    Mapped(Arc<memmap2::Mmap>),
+}
+
+impl SourceText {
+    /// Returns a view of the given range of this code, sharing its buffer,
+    /// or None if the range is out of bounds or does not fall on character boundaries.
+    pub(crate) fn slice(&self, range: Range<usize>) -> Option<SourceText> {
+        self.get(range.clone())?;
+        Some(SourceText {
+            buffer: self.buffer.clone(),
+            range: self.range.start + range.start..self.range.start + range.end,
+        })
+    }
+
+    /// Returns the whole buffer this code is a view into.
+    pub(crate) fn buffer(&self) -> &str {
+        match &self.buffer {
+            Buffer::Owned(buffer) => buffer,
+            // SAFETY: the content was validated as UTF-8 when the file was mapped
+            #[cfg(feature = "mmap")]
+            Buffer::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.buffer()[self.range.clone()]
+    }
+
+    /// Maps the file at the given path into memory, rather than reading it into a String.
+    ///
+    /// The file must not be modified while the tree built from it is in use, as its content would change behind the tree's back.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn map(path: &std::path::Path) -> std::io::Result<SourceText> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the map is only read, and changing mapped files is documented as unsupported
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        std::str::from_utf8(&map)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(SourceText {
+            range: 0..map.len(),
+            buffer: Buffer::Mapped(Arc::new(map)),
+        })
+    }
+}
+
+impl From<String> for SourceText {
+    fn from(code: String) -> Self {
+        let range = 0..code.len();
+        SourceText {
+            buffer: Buffer::Owned(Arc::from(code)),
+            range,
+        }
+    }
+}
+
+impl Deref for SourceText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for SourceText {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SourceText {}
+
+impl Hash for SourceText {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
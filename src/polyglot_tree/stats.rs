@@ -0,0 +1,81 @@
+use super::util::Language;
+use super::PolyglotTree;
+
+/// Statistics about the shape of a polyglot tree, as returned by `PolyglotTree::stats`.
+///
+/// Nodes are counted the way a PolyglotZipper sees them: the root of a subtree is a child of its polyglot eval call,
+/// and the arguments of the call are not part of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeStats {
+    /// The number of edges on the longest path from the root to a leaf, across language boundaries.
+    pub depth: usize,
+    /// The number of nodes in the tree and all of its subtrees.
+    pub node_count: usize,
+    /// The number of subtrees, however deep they are.
+    pub subtree_count: usize,
+    /// The languages of the longest chain of nested polyglot eval calls, starting with the language of the tree itself.
+    pub deepest_chain: Vec<Language>,
+}
+
+impl PolyglotTree {
+    /// Returns statistics about the shape of the tree and its subtrees.
+    ///
+    /// The statistics are computed on the first call and cached, so that later calls are free.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x')", Language::JavaScript).unwrap();
+    /// let stats = tree.stats();
+    ///
+    /// // program > expression_statement > eval call > module > expression_statement > identifier
+    /// assert_eq!(stats.depth, 5);
+    /// assert_eq!(stats.node_count, 6);
+    /// assert_eq!(stats.subtree_count, 1);
+    /// assert_eq!(stats.deepest_chain, vec![Language::JavaScript, Language::Python]);
+    /// ```
+    pub fn stats(&self) -> &TreeStats {
+        self.stats.get_or_init(|| self.compute_stats())
+    }
+
+    fn compute_stats(&self) -> TreeStats {
+        let mut result = TreeStats {
+            depth: 0,
+            node_count: 0,
+            subtree_count: self.node_to_subtrees_map.len(),
+            deepest_chain: Vec::new(),
+        };
+
+        let mut cursor = self.root_node().walk();
+        let mut depth = 0;
+        loop {
+            let node = cursor.node();
+            result.node_count += 1;
+            result.depth = result.depth.max(depth);
+
+            if let Some(subtree) = self.node_to_subtrees_map.get(&node.id()) {
+                let sub = subtree.stats();
+                result.node_count += sub.node_count;
+                result.subtree_count += sub.subtree_count;
+                result.depth = result.depth.max(depth + 1 + sub.depth);
+                if sub.deepest_chain.len() > result.deepest_chain.len() {
+                    result.deepest_chain = sub.deepest_chain.clone();
+                }
+            } else if !self.is_polyglot_eval_call(node) && cursor.goto_first_child() {
+                depth += 1;
+                continue;
+            }
+
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    result.deepest_chain.insert(0, self.language);
+                    return result;
+                }
+                depth -= 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+
+use super::build_options::{BuildContext, BuildOptions};
+use super::polyglot_processor::PolygotProcessor;
+use super::source_text::SourceText;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+impl PolyglotTree {
+    /// Parses the given code and every subtree it evaluates, applying the processor to each tree as soon as it is parsed,
+    /// and dropping each subtree once it and its own subtrees were processed. Nothing is kept in the end.
+    ///
+    /// This suits one-shot exporters over large polyglot programs: only the trees on the path from the root to the tree being processed
+    /// are in memory at once, however many subtrees there are. The processor is applied to each tree on its own, host trees before the subtrees
+    /// they evaluate and subtrees in the order of their eval calls; the eval calls of the trees it is given have no subtree.
+    ///
+    /// Returns the number of trees given to the processor, or None if the code could not be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::polyglot_tree::polyglot_processor::PolygotProcessor;
+    /// use polyglot_ast::polyglot_tree::polyglot_zipper::PolyglotZipper;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// struct Languages(Vec<Language>);
+    ///
+    /// impl PolygotProcessor for Languages {
+    ///     fn process(&mut self, zip: PolyglotZipper) {
+    ///         self.0.push(*zip.get_lang());
+    ///     }
+    /// }
+    ///
+    /// let code = "Polyglot.eval(\"python\", 'print(1)')\nPolyglot.eval(\"js\", 'Polyglot.eval(\"python\", \"print(2)\")')";
+    /// let mut languages = Languages(Vec::new());
+    /// assert_eq!(PolyglotTree::stream(code, Language::JavaScript, &mut languages), Some(4));
+    /// assert_eq!(
+    ///     languages.0,
+    ///     vec![Language::JavaScript, Language::Python, Language::JavaScript, Language::Python]
+    /// );
+    /// ```
+    pub fn stream(
+        code: impl ToString,
+        language: Language,
+        processor: &mut impl PolygotProcessor,
+    ) -> Option<usize> {
+        Self::stream_with_options(code, language, &BuildOptions::default(), processor)
+    }
+
+    /// Same as `stream`, except that subtrees are only parsed as allowed by the given BuildOptions.
+    ///
+    /// Streaming builds are sequential and keep no subtree, so the threads and file cache of the options are not used.
+    pub fn stream_with_options(
+        code: impl ToString,
+        language: Language,
+        options: &BuildOptions,
+        processor: &mut impl PolygotProcessor,
+    ) -> Option<usize> {
+        Self::stream_impl(options, processor, |ctx| {
+            Self::from_directory(
+                SourceText::from(code.to_string()),
+                language,
+                PathBuf::new(),
+                Origin::Snippet,
+                ctx,
+            )
+        })
+    }
+
+    /// Same as `stream`, except that the code is read from the file at the given path, as with `from_path`.
+    pub fn stream_path(
+        path: PathBuf,
+        language: Language,
+        processor: &mut impl PolygotProcessor,
+    ) -> Option<usize> {
+        Self::stream_path_with_options(path, language, &BuildOptions::default(), processor)
+    }
+
+    /// Same as `stream_path`, except that subtrees are only parsed as allowed by the given BuildOptions.
+    pub fn stream_path_with_options(
+        path: PathBuf,
+        language: Language,
+        options: &BuildOptions,
+        processor: &mut impl PolygotProcessor,
+    ) -> Option<usize> {
+        Self::stream_impl(options, processor, |ctx| {
+            Self::from_path_impl(path, language, ctx)
+        })
+    }
+
+    /// Internal function running `build` with a context streaming every tree it builds to the processor.
+    fn stream_impl(
+        options: &BuildOptions,
+        processor: &mut impl PolygotProcessor,
+        build: impl FnOnce(&BuildContext) -> Option<PolyglotTree>,
+    ) -> Option<usize> {
+        let count = Cell::new(0);
+        let sink = RefCell::new(|tree: &PolyglotTree| {
+            count.set(count.get() + 1);
+            tree.apply(processor);
+        });
+        let mut ctx = BuildContext::new(options);
+        ctx.stream = Some(&sink);
+        build(&ctx)?;
+        Some(count.get())
+    }
+}
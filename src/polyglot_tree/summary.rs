@@ -0,0 +1,217 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use super::batch::BatchDiagnosticKind;
+use super::call_sites::BindingDirection;
+use super::project::PolyglotProject;
+use super::util::{self, Language};
+
+/// An overview of a PolyglotProject, as returned by `PolyglotProject::summary`, which can be rendered as JSON or Markdown for reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectSummary {
+    /// The number of files of the project.
+    pub file_count: usize,
+    /// The languages used in the project, in the order of the Language enum, leaving out the ones never used.
+    pub languages: Vec<LanguageUsage>,
+    /// The number of polyglot eval calls.
+    pub eval_count: usize,
+    /// The number of polyglot import calls.
+    pub import_count: usize,
+    /// The number of polyglot export calls.
+    pub export_count: usize,
+    /// The number of links between the code of two different languages: eval calls evaluating code of another language
+    /// than their own, and export calls linked to an import call written in another language.
+    pub cross_language_edges: usize,
+    /// The number of eval calls for which no subtree could be built, for instance because the evaluated file is missing.
+    pub unresolved_evals: usize,
+    /// The number of import calls reading a binding no export call of the project writes,
+    /// leaving out the calls whose binding name is not given as a string literal.
+    pub unresolved_imports: usize,
+    /// The files whose tree, or one of its subtrees, contains syntax errors, in alphabetical order.
+    pub files_with_errors: Vec<PathBuf>,
+}
+
+/// How much a language is used in a project, as part of a ProjectSummary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageUsage {
+    pub language: Language,
+    /// The number of files of the project written in the language.
+    pub files: usize,
+    /// The number of eval calls evaluating code of the language, whether inline or read from a file.
+    pub evals: usize,
+}
+
+impl PolyglotProject {
+    /// Returns an overview of the project: the languages it uses, how many polyglot calls it contains and how they link to each other,
+    /// and what is wrong with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let project = PolyglotProject::new("TestSamples", ["**/*.py", "**/*.js"]).unwrap();
+    /// let summary = project.summary();
+    ///
+    /// assert_eq!(summary.file_count, 5);
+    /// assert_eq!(summary.languages[0].language, Language::Python);
+    /// assert_eq!(summary.eval_count, 3);
+    /// assert_eq!(summary.unresolved_evals, 0);
+    /// assert!(summary.files_with_errors.is_empty());
+    /// assert!(summary.to_json().starts_with("{\"file_count\":5,"));
+    /// assert!(summary.to_markdown().starts_with("# Project summary"));
+    /// ```
+    pub fn summary(&self) -> ProjectSummary {
+        let mut languages: Vec<LanguageUsage> =
+            [Language::Python, Language::JavaScript, Language::Java]
+                .into_iter()
+                .map(|language| LanguageUsage {
+                    language,
+                    files: 0,
+                    evals: 0,
+                })
+                .collect();
+        let mut file_count = 0;
+        for path in self.files() {
+            file_count += 1;
+            let language = self
+                .tree(path)
+                .map(|tree| tree.language)
+                .or_else(|| util::language_from_path(path));
+            if let Some(usage) = language.and_then(|l| usage_of(&mut languages, l)) {
+                usage.files += 1;
+            }
+        }
+
+        let eval_sites = self.eval_sites();
+        let mut cross_language_edges = 0;
+        for site in &eval_sites {
+            if let Some(language) = site.guest_language {
+                if let Some(usage) = usage_of(&mut languages, language) {
+                    usage.evals += 1;
+                }
+                if language != site.host_language {
+                    cross_language_edges += 1;
+                }
+            }
+        }
+
+        let bindings = self.bindings();
+        let links = self.binding_links();
+        cross_language_edges += links
+            .iter()
+            .filter(|link| link.export.language != link.import.language)
+            .count();
+        let count = |direction| bindings.iter().filter(|b| b.direction == direction).count();
+        let unresolved_imports = bindings
+            .iter()
+            .filter(|b| b.direction == BindingDirection::Import && b.name.is_some())
+            .filter(|b| !links.iter().any(|link| link.import == **b))
+            .count();
+
+        let mut files_with_errors: Vec<PathBuf> = self
+            .diagnostics()
+            .iter()
+            .filter(|d| d.kind == BatchDiagnosticKind::SyntaxErrors)
+            .map(|d| d.path.clone())
+            .collect();
+        files_with_errors.sort();
+        files_with_errors.dedup();
+
+        languages.retain(|usage| usage.files > 0 || usage.evals > 0);
+        ProjectSummary {
+            file_count,
+            languages,
+            eval_count: eval_sites.len(),
+            import_count: count(BindingDirection::Import),
+            export_count: count(BindingDirection::Export),
+            cross_language_edges,
+            unresolved_evals: eval_sites.iter().filter(|s| !s.has_subtree).count(),
+            unresolved_imports,
+            files_with_errors,
+        }
+    }
+}
+
+impl ProjectSummary {
+    /// Renders the summary as a compact JSON object, with the same fields as the struct and languages given by their name, eg. `"python"`.
+    pub fn to_json(&self) -> String {
+        let languages: Vec<String> = self
+            .languages
+            .iter()
+            .map(|usage| {
+                format!(
+                    "{{\"language\":{},\"files\":{},\"evals\":{}}}",
+                    util::json_string(util::language_enum_to_string(&usage.language)),
+                    usage.files,
+                    usage.evals
+                )
+            })
+            .collect();
+        let files: Vec<String> = self
+            .files_with_errors
+            .iter()
+            .map(|path| util::json_string(&path.to_string_lossy()))
+            .collect();
+        format!(
+            "{{\"file_count\":{},\"languages\":[{}],\"eval_count\":{},\"import_count\":{},\"export_count\":{},\
+             \"cross_language_edges\":{},\"unresolved_evals\":{},\"unresolved_imports\":{},\"files_with_errors\":[{}]}}",
+            self.file_count,
+            languages.join(","),
+            self.eval_count,
+            self.import_count,
+            self.export_count,
+            self.cross_language_edges,
+            self.unresolved_evals,
+            self.unresolved_imports,
+            files.join(",")
+        )
+    }
+
+    /// Renders the summary as a Markdown document, with a table of the languages used and a list of the files with syntax errors.
+    pub fn to_markdown(&self) -> String {
+        let mut result = String::from("# Project summary\n\n");
+        let _ = writeln!(result, "- Files: {}", self.file_count);
+        let _ = writeln!(result, "- Eval calls: {}", self.eval_count);
+        let _ = writeln!(result, "- Import calls: {}", self.import_count);
+        let _ = writeln!(result, "- Export calls: {}", self.export_count);
+        let _ = writeln!(
+            result,
+            "- Cross-language edges: {}",
+            self.cross_language_edges
+        );
+        let _ = writeln!(result, "- Unresolved eval calls: {}", self.unresolved_evals);
+        let _ = writeln!(
+            result,
+            "- Unresolved import calls: {}",
+            self.unresolved_imports
+        );
+
+        result.push_str("\n## Languages\n\n| Language | Files | Evaluated snippets and files |\n| --- | --- | --- |\n");
+        for usage in &self.languages {
+            let _ = writeln!(
+                result,
+                "| {} | {} | {} |",
+                util::language_enum_to_string(&usage.language),
+                usage.files,
+                usage.evals
+            );
+        }
+
+        result.push_str("\n## Files with syntax errors\n\n");
+        if self.files_with_errors.is_empty() {
+            result.push_str("None\n");
+        }
+        for path in &self.files_with_errors {
+            let _ = writeln!(result, "- `{}`", path.display());
+        }
+        result
+    }
+}
+
+fn usage_of(languages: &mut [LanguageUsage], language: Language) -> Option<&mut LanguageUsage> {
+    languages
+        .iter_mut()
+        .find(|usage| usage.language == language)
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use tree_sitter::{Node, Point};
+
+use super::node_id::NodeId;
+use super::polyglot_node::PolyglotNode;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// What a Symbol of a SymbolTable stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// A value written to the polyglot bindings by an export call.
+    Export,
+    /// A value read from the polyglot bindings by an import call.
+    Import,
+    /// A function defined at the top level of a file or snippet.
+    Function,
+    /// A class, interface or enum defined at the top level of a file or snippet.
+    Class,
+}
+
+/// A named entity of a tree, as listed by a SymbolTable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The name of the binding or of the definition.
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The Language of the code defining the symbol.
+    pub language: Language,
+    /// The Origin of the code defining the symbol.
+    pub origin: Origin,
+    /// The NodeId of the call or of the definition.
+    pub id: NodeId,
+    /// The byte range of the call or of the definition, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the call or of the definition in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+}
+
+/// The symbols of a tree and of its subtrees, as returned by `PolyglotTree::symbol_table`, keyed by name and language.
+///
+/// It lists the polyglot import and export calls whose binding name is given as a string literal,
+/// and the functions and classes defined at the top level of each file or snippet, so that the symbols
+/// of all the languages of a polyglot program can be looked up in one place.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+    by_key: HashMap<(String, Language), Vec<usize>>,
+}
+
+impl SymbolTable {
+    /// Returns every symbol, in document order, the symbols of a subtree coming right after the eval call it belongs to.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Returns the symbols of the given name defined by code of the given language, in document order.
+    pub fn get(&self, name: &str, language: Language) -> Vec<&Symbol> {
+        self.by_key
+            .get(&(name.to_string(), language))
+            .map_or_else(Vec::new, |indices| {
+                indices.iter().map(|i| &self.symbols[*i]).collect()
+            })
+    }
+
+    /// Returns the symbols of the given name, whatever the language defining them, in document order.
+    pub fn named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Symbol> {
+        self.symbols
+            .iter()
+            .filter(move |symbol| symbol.name == name)
+    }
+
+    /// Returns the export calls of the binding of the given name, whatever their language.
+    pub fn exports<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Symbol> {
+        self.named(name)
+            .filter(|symbol| symbol.kind == SymbolKind::Export)
+    }
+
+    /// Returns the import calls of the binding of the given name, whatever their language.
+    pub fn imports<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Symbol> {
+        self.named(name)
+            .filter(|symbol| symbol.kind == SymbolKind::Import)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    fn insert(&mut self, symbol: Symbol) {
+        self.by_key
+            .entry((symbol.name.clone(), symbol.language))
+            .or_default()
+            .push(self.symbols.len());
+        self.symbols.push(symbol);
+    }
+}
+
+impl PolyglotTree {
+    /// Returns the SymbolTable of the tree and of its subtrees, however deep they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::symbols::SymbolKind;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = "import polyglot\n\ndef f():\n    pass\n\npolyglot.export_value(name=\"x\", value=f)\npolyglot.eval(language=\"js\", string=\"class C {}\\nPolyglot.import('x')\")";
+    /// let tree = PolyglotTree::from(code, Language::Python).unwrap();
+    /// let table = tree.symbol_table();
+    ///
+    /// assert_eq!(table.len(), 4);
+    /// assert_eq!(table.get("f", Language::Python)[0].kind, SymbolKind::Function);
+    /// assert_eq!(table.get("x", Language::Python)[0].kind, SymbolKind::Export);
+    /// assert_eq!(table.get("x", Language::JavaScript)[0].kind, SymbolKind::Import);
+    /// assert_eq!(table.get("C", Language::JavaScript)[0].kind, SymbolKind::Class);
+    /// assert!(table.get("C", Language::Python).is_empty());
+    /// assert_eq!(table.exports("x").count(), 1);
+    /// ```
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut table = SymbolTable::default();
+        self.visit_nodes(&mut |tree, node, id| {
+            let (name, kind) = match tree.polyglot_node_at(node) {
+                Some(PolyglotNode::ExportCall { name: Some(name) }) => {
+                    (name.clone(), SymbolKind::Export)
+                }
+                Some(PolyglotNode::ImportCall { name: Some(name) }) => {
+                    (name.clone(), SymbolKind::Import)
+                }
+                _ => match top_level_definition(tree, node) {
+                    Some(definition) => definition,
+                    None => return,
+                },
+            };
+            table.insert(Symbol {
+                name,
+                kind,
+                language: tree.language,
+                origin: tree.origin.clone(),
+                id: id.clone(),
+                span: tree.host_range(node),
+                start_position: node.start_position(),
+            });
+        });
+        table
+    }
+}
+
+/// Internal function returning the name and kind of the given node if it is a function or class defined at the top level of its tree.
+fn top_level_definition(tree: &PolyglotTree, node: Node) -> Option<(String, SymbolKind)> {
+    let kind = match node.kind() {
+        "function_definition" | "function_declaration" | "generator_function_declaration" => {
+            SymbolKind::Function
+        }
+        "class_definition" | "class_declaration" | "interface_declaration" | "enum_declaration" => {
+            SymbolKind::Class
+        }
+        _ => return None,
+    };
+    let mut parent = node.parent()?;
+    // decorators in Python and `export` in JavaScript wrap the definitions they apply to
+    if matches!(parent.kind(), "decorated_definition" | "export_statement") {
+        parent = parent.parent()?;
+    }
+    if parent.parent().is_some() {
+        return None;
+    }
+    let name = node.child_by_field_name("name")?;
+    Some((tree.node_to_code(name).to_string(), kind))
+}
@@ -0,0 +1,141 @@
+use std::ops::Range;
+
+use super::polyglot_zipper::PolyglotZipper;
+use super::util::Language;
+
+/// A minimal, object-safe view of a syntax node that does not expose tree-sitter types.
+///
+/// Consumers written against `dyn SyntaxNode` work the same over a tree parsed with tree-sitter, through PolyglotZipper,
+/// and over trees coming from elsewhere, such as OwnedSyntaxNode trees built from serialized data.
+/// Children are listed the way zippers navigate: the only child of a polyglot eval call with a subtree is the root of that subtree.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper, SyntaxNode};
+/// use polyglot_ast::util::Language;
+///
+/// fn count_leaves(node: &dyn SyntaxNode, language: Language) -> usize {
+///     let own = usize::from(node.language() == language && node.children().is_empty());
+///     own + node.children().iter().map(|c| count_leaves(c.as_ref(), language)).sum::<usize>()
+/// }
+///
+/// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 1')", Language::JavaScript).unwrap();
+/// let root = PolyglotZipper::from(&tree);
+/// assert_eq!(count_leaves(&root, Language::Python), 3);
+/// ```
+pub trait SyntaxNode {
+    /// Returns the kind of the node, such as `"call_expression"` or `"polyglot_eval_call"`.
+    fn kind(&self) -> &str;
+
+    /// Returns the byte range of the node, in the coordinates of the file its code physically lives in.
+    fn span(&self) -> Range<usize>;
+
+    /// Returns the Language the node is written in.
+    fn language(&self) -> Language;
+
+    /// Returns the children of the node, in document order.
+    fn children(&self) -> Vec<Box<dyn SyntaxNode + '_>>;
+}
+
+impl SyntaxNode for PolyglotZipper<'_> {
+    fn kind(&self) -> &str {
+        PolyglotZipper::kind(self)
+    }
+
+    fn span(&self) -> Range<usize> {
+        self.host_byte_range()
+    }
+
+    fn language(&self) -> Language {
+        *self.get_lang()
+    }
+
+    fn children(&self) -> Vec<Box<dyn SyntaxNode + '_>> {
+        std::iter::successors(self.child(0), |z| z.next_sibling())
+            .map(|z| Box::new(z) as Box<dyn SyntaxNode>)
+            .collect()
+    }
+}
+
+/// A syntax node owning its data and its children, independent from any parser.
+///
+/// It can be built by hand, for instance when deserializing a tree, or copied from any other SyntaxNode with `OwnedSyntaxNode::from_node`.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper, SyntaxNode};
+/// use polyglot_ast::polyglot_tree::syntax_node::OwnedSyntaxNode;
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 1')", Language::JavaScript).unwrap();
+/// let owned = OwnedSyntaxNode::from_node(&PolyglotZipper::from(&tree));
+/// drop(tree);
+///
+/// let statement = &owned.children[0];
+/// assert_eq!(statement.kind, "expression_statement");
+/// assert_eq!(statement.children[0].children[0].language(), Language::Python);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSyntaxNode {
+    pub kind: String,
+    pub span: Range<usize>,
+    pub language: Language,
+    pub children: Vec<OwnedSyntaxNode>,
+}
+
+impl OwnedSyntaxNode {
+    /// Copies the given node and all of its descendants.
+    pub fn from_node(node: &dyn SyntaxNode) -> OwnedSyntaxNode {
+        OwnedSyntaxNode {
+            kind: String::from(node.kind()),
+            span: node.span(),
+            language: node.language(),
+            children: node
+                .children()
+                .iter()
+                .map(|c| OwnedSyntaxNode::from_node(c.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+impl SyntaxNode for OwnedSyntaxNode {
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn children(&self) -> Vec<Box<dyn SyntaxNode + '_>> {
+        self.children
+            .iter()
+            .map(|c| Box::new(c) as Box<dyn SyntaxNode>)
+            .collect()
+    }
+}
+
+impl<T: SyntaxNode + ?Sized> SyntaxNode for &T {
+    fn kind(&self) -> &str {
+        (**self).kind()
+    }
+
+    fn span(&self) -> Range<usize> {
+        (**self).span()
+    }
+
+    fn language(&self) -> Language {
+        (**self).language()
+    }
+
+    fn children(&self) -> Vec<Box<dyn SyntaxNode + '_>> {
+        (**self).children()
+    }
+}
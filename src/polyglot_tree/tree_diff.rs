@@ -0,0 +1,294 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use tree_sitter::{Node, Point};
+
+use super::node_id::NodeId;
+use super::polyglot_zipper::path_from_root;
+use super::util::Language;
+use super::{Origin, PolyglotTree};
+
+/// A node involved in a change reported by `PolyglotTree::diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffNode {
+    /// The NodeId of the node, in the tree it belongs to.
+    pub id: NodeId,
+    /// The type of the node, accounting for polyglot nodes.
+    pub kind: &'static str,
+    /// The Language the node is written in.
+    pub language: Language,
+    /// The Origin of the code containing the node.
+    pub origin: Origin,
+    /// The byte range of the node, in the coordinates of the file its code physically lives in.
+    pub span: Range<usize>,
+    /// The start position of the node in terms of rows and columns, within the code of the tree containing it.
+    pub start_position: Point,
+}
+
+/// A difference between two trees, as returned by `PolyglotTree::diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeChange {
+    /// The node only exists in the new tree.
+    Added(DiffNode),
+    /// The node only exists in the old tree.
+    Removed(DiffNode),
+    /// The node was replaced, or its text changed, between the old and the new tree.
+    Changed { old: DiffNode, new: DiffNode },
+}
+
+impl PolyglotTree {
+    /// Returns true if both trees have the same structure, including the subtrees of their polyglot eval calls.
+    ///
+    /// Two trees are structurally equal if their nodes have the same types and their leaves the same text,
+    /// regardless of whitespace and of where the trees come from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let a = PolyglotTree::from("Polyglot.eval(\"python\", 'x=1')", Language::JavaScript).unwrap();
+    /// let b = PolyglotTree::from("Polyglot.eval( \"python\" , 'x = 1' )", Language::JavaScript).unwrap();
+    /// let c = PolyglotTree::from("Polyglot.eval(\"python\", 'x = 2')", Language::JavaScript).unwrap();
+    ///
+    /// assert!(a.structurally_eq(&b));
+    /// assert!(!a.structurally_eq(&c));
+    /// ```
+    pub fn structurally_eq(&self, other: &PolyglotTree) -> bool {
+        self.language == other.language
+            && nodes_eq(
+                (self, self.root_node(), &[]),
+                (other, other.root_node(), &[]),
+            )
+    }
+
+    /// Returns the changes turning this tree into the other one, including changes confined to the subtrees of polyglot eval calls.
+    ///
+    /// Children are matched by structural equality first, then by type, so that an inserted or deleted node
+    /// is reported as such rather than as a change of every following sibling.
+    /// Changes are reported at the deepest node that differs, and are given in document order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::polyglot_tree::tree_diff::TreeChange;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let old = PolyglotTree::from("Polyglot.eval(\"python\", 'print(1)')", Language::JavaScript).unwrap();
+    /// let new = PolyglotTree::from("Polyglot.eval(\"python\", 'print(2)')\nlet x = 3;", Language::JavaScript).unwrap();
+    /// let changes = old.diff(&new);
+    ///
+    /// assert_eq!(changes.len(), 2);
+    /// match &changes[0] {
+    ///     TreeChange::Changed { old, new } => {
+    ///         assert_eq!(old.language, Language::Python);
+    ///         assert_eq!(old.kind, "integer");
+    ///         assert_eq!(new.span, 31..32);
+    ///     }
+    ///     other => panic!("unexpected change {other:?}"),
+    /// }
+    /// match &changes[1] {
+    ///     TreeChange::Added(node) => assert_eq!(node.kind, "lexical_declaration"),
+    ///     other => panic!("unexpected change {other:?}"),
+    /// }
+    /// ```
+    pub fn diff(&self, other: &PolyglotTree) -> Vec<TreeChange> {
+        let mut result = Vec::new();
+        diff_pair(
+            (self, self.root_node(), &[]),
+            (other, other.root_node(), &[]),
+            &mut result,
+        );
+        result
+    }
+}
+
+/// A node along with the tree it belongs to and the paths of the eval calls leading to that tree, as in a NodeId.
+type Located<'t> = (&'t PolyglotTree, Node<'t>, &'t [Vec<usize>]);
+
+fn describe((tree, node, evals): Located) -> DiffNode {
+    let mut paths = evals.to_vec();
+    paths.push(path_from_root(node));
+    DiffNode {
+        id: NodeId::from_paths(paths),
+        kind: tree.polyglot_kind(node),
+        language: tree.language,
+        origin: tree.origin.clone(),
+        span: tree.host_range(node),
+        start_position: node.start_position(),
+    }
+}
+
+fn subtree<'t>((tree, node, _): Located<'t>) -> Option<&'t PolyglotTree> {
+    tree.node_to_subtrees_map.get(&node.id()).map(AsRef::as_ref)
+}
+
+fn children<'t>((tree, node, evals): Located<'t>) -> Vec<Located<'t>> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .map(|n| (tree, n, evals))
+        .collect()
+}
+
+/// Internal function checking two nodes are structurally equal, looking through eval calls into their subtrees.
+fn nodes_eq(a: Located, b: Located) -> bool {
+    if a.0.polyglot_kind(a.1) != b.0.polyglot_kind(b.1) {
+        return false;
+    }
+
+    match (subtree(a), subtree(b)) {
+        (Some(x), Some(y)) => x.structurally_eq(y),
+        (None, None) => {
+            if a.1.child_count() == 0 || b.1.child_count() == 0 {
+                return a.0.node_to_code(a.1) == b.0.node_to_code(b.1);
+            }
+            a.1.child_count() == b.1.child_count()
+                && children(a)
+                    .into_iter()
+                    .zip(children(b))
+                    .all(|(x, y)| nodes_eq(x, y))
+        }
+        _ => false,
+    }
+}
+
+/// Internal function reporting the changes between two nodes occupying the same place in their trees.
+fn diff_pair(a: Located, b: Located, result: &mut Vec<TreeChange>) {
+    if nodes_eq(a, b) {
+        return;
+    }
+    let changed = || TreeChange::Changed {
+        old: describe(a),
+        new: describe(b),
+    };
+    if a.0.polyglot_kind(a.1) != b.0.polyglot_kind(b.1) {
+        result.push(changed());
+        return;
+    }
+
+    match (subtree(a), subtree(b)) {
+        (Some(x), Some(y)) if x.language == y.language => {
+            let evals_a = [a.2, &[path_from_root(a.1)]].concat();
+            let evals_b = [b.2, &[path_from_root(b.1)]].concat();
+            diff_pair(
+                (x, x.root_node(), &evals_a),
+                (y, y.root_node(), &evals_b),
+                result,
+            )
+        }
+        (None, None) if a.1.child_count() > 0 && b.1.child_count() > 0 => {
+            diff_children(&children(a), &children(b), result)
+        }
+        _ => result.push(changed()),
+    }
+}
+
+/// Internal function hashing the types of a node and of its descendants and the text of its leaves, as `nodes_eq` compares them,
+/// so that aligning siblings does not compare whole subtrees again and again.
+fn structural_hash(a: Located) -> u64 {
+    fn hash_node(a: Located, state: &mut DefaultHasher) {
+        a.0.polyglot_kind(a.1).hash(state);
+        match subtree(a) {
+            Some(tree) => {
+                tree.language.hash(state);
+                hash_node((tree, tree.root_node(), a.2), state);
+            }
+            None if a.1.child_count() == 0 => a.0.node_to_code(a.1).hash(state),
+            None => {
+                state.write_usize(a.1.child_count());
+                for child in children(a) {
+                    hash_node(child, state);
+                }
+            }
+        }
+    }
+    let mut state = DefaultHasher::new();
+    hash_node(a, &mut state);
+    state.finish()
+}
+
+/// Internal function aligning two lists of sibling nodes and reporting their changes.
+fn diff_children(old: &[Located], new: &[Located], result: &mut Vec<TreeChange>) {
+    // nodes are aligned by their hashes, each pair of aligned nodes being diffed in case their hashes collide
+    let old_hashes: Vec<u64> = old.iter().map(|&a| structural_hash(a)).collect();
+    let new_hashes: Vec<u64> = new.iter().map(|&b| structural_hash(b)).collect();
+    let prefix = old_hashes
+        .iter()
+        .zip(&new_hashes)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_hashes[prefix..]
+        .iter()
+        .rev()
+        .zip(new_hashes[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    for (&a, &b) in old[..prefix].iter().zip(&new[..prefix]) {
+        diff_pair(a, b, result);
+    }
+
+    // longest common subsequence of the remaining nodes, computed from the end
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    let old_hashes = &old_hashes[prefix..old_end];
+    let new_hashes = &new_hashes[prefix..new_end];
+    let mut lcs = vec![vec![0usize; new_hashes.len() + 1]; old_hashes.len() + 1];
+    for i in (0..old_hashes.len()).rev() {
+        for j in (0..new_hashes.len()).rev() {
+            lcs[i][j] = if old_hashes[i] == new_hashes[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // walk the alignment, and diff the unmatched nodes found between two anchors
+    let (old_rest, new_rest) = (&old[prefix..old_end], &new[prefix..new_end]);
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_old, mut gap_new) = (i, j);
+    while i < old_rest.len() || j < new_rest.len() {
+        if i < old_rest.len() && j < new_rest.len() && old_hashes[i] == new_hashes[j] {
+            diff_gap(&old_rest[gap_old..i], &new_rest[gap_new..j], result);
+            diff_pair(old_rest[i], new_rest[j], result);
+            i += 1;
+            j += 1;
+            (gap_old, gap_new) = (i, j);
+        } else if j < new_rest.len() && (i == old_rest.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    diff_gap(&old_rest[gap_old..], &new_rest[gap_new..], result);
+
+    for (&a, &b) in old[old_end..].iter().zip(&new[new_end..]) {
+        diff_pair(a, b, result);
+    }
+}
+
+/// Internal function pairing up the nodes of the same type between two anchors, the others being added or removed.
+fn diff_gap(old: &[Located], new: &[Located], result: &mut Vec<TreeChange>) {
+    let mut next_new = 0;
+    for &a in old {
+        let kind = a.0.polyglot_kind(a.1);
+        match new[next_new..]
+            .iter()
+            .position(|b| b.0.polyglot_kind(b.1) == kind)
+        {
+            Some(offset) => {
+                for &b in &new[next_new..next_new + offset] {
+                    result.push(TreeChange::Added(describe(b)));
+                }
+                diff_pair(a, new[next_new + offset], result);
+                next_new += offset + 1;
+            }
+            None => result.push(TreeChange::Removed(describe(a))),
+        }
+    }
+    for &b in &new[next_new..] {
+        result.push(TreeChange::Added(describe(b)));
+    }
+}
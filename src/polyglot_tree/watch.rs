@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use notify::{Event, RecursiveMode, Watcher};
+
+use super::project::{PolyglotProject, ProjectUpdate};
+use super::sharded_map::lock;
+
+/// How long to wait for more file system events before updating the project, as saving a file usually triggers several of them.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Keeps a PolyglotProject up to date as the files of its root directory change, are created or are removed.
+///
+/// File system notifications are handled on a background thread: only the trees of the changed files and of the files evaluating them,
+/// however indirectly, are built again, after which the given callback is called with the project and what changed in it,
+/// for instance to report its new diagnostics.
+///
+/// The project can be queried at any time with `project`, which waits for any update in progress.
+/// The background thread stops once the ProjectWatcher is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use polyglot_ast::polyglot_tree::project::PolyglotProject;
+/// use polyglot_ast::polyglot_tree::watch::ProjectWatcher;
+///
+/// let project = PolyglotProject::new("src", ["**/*.py", "**/*.js"]).unwrap();
+/// let watcher = ProjectWatcher::new(project, |project, update| {
+///     println!("rebuilt {} files", update.rebuilt.len());
+///     for diagnostic in project.diagnostics() {
+///         println!("{}: {:?}", diagnostic.path.display(), diagnostic.kind);
+///     }
+/// })
+/// .unwrap();
+///
+/// // the project is kept up to date for as long as the watcher lives
+/// std::thread::sleep(std::time::Duration::from_secs(60));
+/// println!("{} eval calls", watcher.project().eval_sites().len());
+/// ```
+pub struct ProjectWatcher {
+    project: Arc<Mutex<PolyglotProject>>,
+    watcher: Option<notify::RecommendedWatcher>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ProjectWatcher {
+    /// Starts watching the root directory of the given project, calling `on_update` each time the project is updated.
+    ///
+    /// Returns an error if the root directory cannot be watched.
+    pub fn new(
+        project: PolyglotProject,
+        mut on_update: impl FnMut(&PolyglotProject, &ProjectUpdate) + Send + 'static,
+    ) -> notify::Result<ProjectWatcher> {
+        let root = project.root().to_path_buf();
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        let project = Arc::new(Mutex::new(project));
+        let shared = project.clone();
+        let thread = std::thread::spawn(move || {
+            // notifications may give absolute paths, while the project uses paths starting with its root as given
+            let canonical_root = std::fs::canonicalize(&root).unwrap_or_else(|_| root.clone());
+            while let Some(changed) = next_changes(&receiver, &root, &canonical_root) {
+                if changed.is_empty() {
+                    continue;
+                }
+                let mut project = lock(&shared);
+                match project.update(&changed) {
+                    Ok(update) if update.rebuilt.is_empty() && update.removed.is_empty() => {}
+                    Ok(update) => on_update(&project, &update),
+                    Err(e) => eprintln!(
+                        "Warning: unable to update project at {} due to the following error: {e}",
+                        root.display()
+                    ),
+                }
+            }
+        });
+
+        Ok(ProjectWatcher {
+            project,
+            watcher: Some(watcher),
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the project, waiting for any update in progress. The project is not updated while the returned guard is held.
+    pub fn project(&self) -> MutexGuard<'_, PolyglotProject> {
+        lock(&self.project)
+    }
+}
+
+impl Drop for ProjectWatcher {
+    fn drop(&mut self) {
+        // dropping the watcher closes the channel, which stops the background thread
+        self.watcher.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Internal function waiting for the next burst of file system events, returning the paths they concern,
+/// or None once the watcher is dropped.
+fn next_changes(
+    receiver: &Receiver<notify::Result<Event>>,
+    root: &Path,
+    canonical_root: &Path,
+) -> Option<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    let mut event = receiver.recv().ok()?;
+    loop {
+        match event {
+            Ok(event) if event.kind.is_access() => {}
+            Ok(event) => changed.extend(event.paths.into_iter().map(|path| {
+                match path.strip_prefix(canonical_root) {
+                    Ok(relative) => root.join(relative),
+                    Err(_) => path,
+                }
+            })),
+            Err(e) => {
+                eprintln!("Warning: file system notification failed with the following error: {e}")
+            }
+        }
+        event = match receiver.recv_timeout(DEBOUNCE) {
+            Ok(event) => event,
+            Err(_) => break,
+        };
+    }
+    changed.sort();
+    changed.dedup();
+    Some(changed)
+}
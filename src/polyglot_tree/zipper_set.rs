@@ -0,0 +1,130 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use super::polyglot_zipper::PolyglotZipper;
+use super::PolyglotTree;
+
+/// A set of saved positions in a polyglot tree, kept in document order and without duplicates.
+///
+/// ZipperSets are meant for features handling many positions at once, such as editor multi-selections or incremental analyses:
+/// positions can be filtered and mapped in batches, and re-resolved against a new version of the tree after the code was parsed again.
+///
+/// # Examples
+///
+/// ```
+/// use polyglot_ast::{PolyglotTree, PolyglotZipper, ZipperSet};
+/// use polyglot_ast::util::Language;
+///
+/// let tree = PolyglotTree::from("print(1)\nprint(2)", Language::Python).unwrap();
+/// let set: ZipperSet = PolyglotZipper::from(&tree).find_all("integer").into_iter().collect();
+/// assert_eq!(set.spans(), vec![(None, 6..7), (None, 15..16)]);
+///
+/// let new_tree = PolyglotTree::from("print(3)\nprint(4)", Language::Python).unwrap();
+/// let moved = set.re_resolve(&new_tree);
+/// let codes: Vec<&str> = moved.iter().map(|z| z.code()).collect();
+/// assert_eq!(codes, vec!["3", "4"]);
+/// ```
+#[derive(Clone, Default)]
+pub struct ZipperSet<'a> {
+    zippers: BTreeSet<PolyglotZipper<'a>>,
+}
+
+impl<'a> ZipperSet<'a> {
+    /// Initializes a new, empty ZipperSet.
+    pub fn new() -> ZipperSet<'a> {
+        ZipperSet {
+            zippers: BTreeSet::new(),
+        }
+    }
+
+    /// Adds a position to the set. Returns `true` if it was not already in the set.
+    pub fn insert(&mut self, zipper: PolyglotZipper<'a>) -> bool {
+        self.zippers.insert(zipper)
+    }
+
+    /// Removes a position from the set. Returns `true` if it was in the set.
+    pub fn remove(&mut self, zipper: &PolyglotZipper<'a>) -> bool {
+        self.zippers.remove(zipper)
+    }
+
+    /// Returns `true` if the set contains the given position.
+    pub fn contains(&self, zipper: &PolyglotZipper<'a>) -> bool {
+        self.zippers.contains(zipper)
+    }
+
+    /// Returns the number of positions in the set.
+    pub fn len(&self) -> usize {
+        self.zippers.len()
+    }
+
+    /// Returns `true` if the set contains no positions.
+    pub fn is_empty(&self) -> bool {
+        self.zippers.is_empty()
+    }
+
+    /// Returns an iterator over the positions of the set, in document order.
+    pub fn iter(&self) -> btree_set::Iter<'_, PolyglotZipper<'a>> {
+        self.zippers.iter()
+    }
+
+    /// Returns a new set with the positions for which `predicate` returns true.
+    pub fn filter(&self, predicate: impl Fn(&PolyglotZipper<'a>) -> bool) -> ZipperSet<'a> {
+        self.iter().filter(|z| predicate(z)).cloned().collect()
+    }
+
+    /// Applies `f` to every position of the set, in document order, and returns the results.
+    pub fn map<T>(&self, f: impl Fn(&PolyglotZipper<'a>) -> T) -> Vec<T> {
+        self.iter().map(f).collect()
+    }
+
+    /// Returns the span of every position of the set, in document order, as the file it physically lives in
+    /// (None if the code was not read from a file) and its byte range in that file, as given by `PolyglotZipper::host_byte_range`.
+    pub fn spans(&self) -> Vec<(Option<&'a PathBuf>, Range<usize>)> {
+        self.map(|z| (z.origin().file(), z.host_byte_range()))
+    }
+
+    /// Returns a new set with the positions found at the same place in `tree`, typically a new version of the tree
+    /// built after the code was edited.
+    ///
+    /// Positions are matched structurally, by following the same children and polyglot eval calls as in the original tree;
+    /// positions that do not exist in `tree` anymore are left out of the result.
+    pub fn re_resolve<'b>(&self, tree: &'b PolyglotTree) -> ZipperSet<'b> {
+        self.iter()
+            .filter_map(|z| PolyglotZipper::resolve(tree, &z.position_paths()))
+            .collect()
+    }
+}
+
+impl<'a> FromIterator<PolyglotZipper<'a>> for ZipperSet<'a> {
+    fn from_iter<I: IntoIterator<Item = PolyglotZipper<'a>>>(iter: I) -> Self {
+        ZipperSet {
+            zippers: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> Extend<PolyglotZipper<'a>> for ZipperSet<'a> {
+    fn extend<I: IntoIterator<Item = PolyglotZipper<'a>>>(&mut self, iter: I) {
+        self.zippers.extend(iter)
+    }
+}
+
+impl<'a> IntoIterator for ZipperSet<'a> {
+    type Item = PolyglotZipper<'a>;
+    type IntoIter = btree_set::IntoIter<PolyglotZipper<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.zippers.into_iter()
+    }
+}
+
+impl<'s, 'a> IntoIterator for &'s ZipperSet<'a> {
+    type Item = &'s PolyglotZipper<'a>;
+    type IntoIter = btree_set::Iter<'s, PolyglotZipper<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.zippers.iter()
+    }
+}
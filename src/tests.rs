@@ -1,5 +1,5 @@
 use super::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn assert_ast_eq(test_file: &str, expected_file: &str, lang: util::Language) {
     let file = PathBuf::from(test_file);
@@ -17,6 +17,28 @@ fn assert_ast_eq(test_file: &str, expected_file: &str, lang: util::Language) {
     assert_eq!(expected, actual);
 }
 
+/// A directory created in the temporary directory of the system for a test, removed with its content when dropped,
+/// even if the test fails.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("polyglot_ast_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[test]
 fn python_test() {
     let file_test = "TestSamples/export_x.py";
@@ -48,3 +70,477 @@ fn java_test() {
 
     assert_ast_eq(file_test, file_expect, util::Language::Java)
 }
+
+#[test]
+fn zipper_document_order() {
+    let file = PathBuf::from("TestSamples/export_x.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+    let zip = PolyglotZipper::from(&tree);
+
+    let all = zip.find_where(|_| true);
+    let mut sorted = all.clone();
+    sorted.reverse();
+    sorted.sort();
+    assert!(all == sorted);
+
+    let mut deduped = all.clone();
+    deduped.extend(all.clone());
+    deduped.sort();
+    deduped.dedup();
+    assert_eq!(deduped.len(), all.len());
+}
+
+#[test]
+fn zipper_moves_to_siblings_after_moving_up() {
+    let tree = PolyglotTree::from("x = 1\ny = 2", util::Language::Python).unwrap();
+    let mut zip = PolyglotZipper::from(&tree)
+        .find_all("expression_statement")
+        .remove(0);
+    assert!(zip.goto_first_child());
+    assert!(zip.goto_parent());
+    assert!(zip.goto_next_sibling());
+    assert_eq!(zip.code(), "y = 2");
+    assert!(!zip.goto_next_sibling());
+
+    assert!(zip.goto_first_child());
+    assert!(zip.goto_parent());
+    assert!(zip.goto_prev_sibling());
+    assert_eq!(zip.code(), "x = 1");
+    assert!(!zip.goto_prev_sibling());
+}
+
+#[test]
+fn node_ids_are_stable() {
+    let file = PathBuf::from("TestSamples/test_pyprint.js");
+    let tree = PolyglotTree::from_path(file.clone(), util::Language::JavaScript)
+        .expect("This test file exists");
+    let other =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+
+    for zip in PolyglotZipper::from(&tree).find_where(|_| true) {
+        let id: NodeId = zip.node_id().to_string().parse().unwrap();
+        let found = other
+            .zipper_at(&id)
+            .expect("the same node exists in the other tree");
+        assert_eq!(found.kind(), zip.kind());
+        assert_eq!(found.code(), zip.code());
+    }
+
+    for site in tree.eval_sites() {
+        let zip = tree.zipper_at(&site.id).unwrap();
+        assert!(zip.is_polyglot_eval_call());
+    }
+}
+
+#[test]
+fn identical_snippets_keep_their_positions() {
+    let snippet = "Polyglot.eval('js', 'Polyglot.eval(\"python\", \"x = 1\")')";
+    let code = format!("{snippet}\n{snippet}\n{snippet}");
+    let tree = PolyglotTree::from(code.as_str(), util::Language::JavaScript).unwrap();
+
+    let all = PolyglotZipper::from(&tree).find_where(|_| true);
+    assert_eq!(all.iter().filter(|z| z.kind() == "integer").count(), 3);
+    for zip in all {
+        assert_eq!(&code[zip.host_byte_range()], zip.code());
+    }
+    for site in tree.eval_sites() {
+        let call = tree.zipper_at(&site.id).unwrap();
+        assert_eq!(&code[site.span], call.code());
+    }
+}
+
+#[test]
+#[cfg(feature = "petgraph")]
+fn graphs_keep_shared_subtrees_apart() {
+    let code = "Polyglot.evalFile('python', 'TestSamples/pyprint.py')\nPolyglot.evalFile('python', 'TestSamples/pyprint.py')";
+    let cache = std::sync::Arc::new(polyglot_tree::file_cache::FileCache::new());
+    let options = polyglot_tree::build_options::BuildOptions::new().file_cache(cache);
+    let tree = PolyglotTree::from_with_options(code, util::Language::JavaScript, &options).unwrap();
+    let (_, first) = tree.subtrees().next().unwrap();
+    assert!(tree
+        .subtrees()
+        .all(|(_, subtree)| std::ptr::eq(subtree, first)));
+
+    assert_eq!(tree.to_graph().node_count(), 3);
+    let graph = tree.to_ast_graph();
+    let evals: Vec<_> = graph
+        .edge_indices()
+        .filter(|e| graph[*e] == polyglot_tree::graph::GraphEdge::Eval)
+        .map(|e| graph.edge_endpoints(e).unwrap())
+        .collect();
+    assert_eq!(evals.len(), 2);
+    assert_ne!(evals[0].0, evals[1].0);
+    assert_ne!(evals[0].1, evals[1].1);
+}
+
+#[test]
+fn edited_tree_matches_rebuilt_tree() {
+    let code = "Polyglot.eval('python', 'x = 1')\nPolyglot.eval('js', 'Polyglot.eval(\"python\", \"y = 2\")')";
+    let mut tree = PolyglotTree::from(code, util::Language::JavaScript).unwrap();
+
+    // insert a statement before every eval call
+    let new_code = format!("alert(0);\n{code}");
+    let edit = tree_sitter::InputEdit {
+        start_byte: 0,
+        old_end_byte: 0,
+        new_end_byte: 10,
+        start_position: tree_sitter::Point::new(0, 0),
+        old_end_position: tree_sitter::Point::new(0, 0),
+        new_end_position: tree_sitter::Point::new(1, 0),
+    };
+    tree.apply_edit(&edit, new_code.as_str()).unwrap();
+
+    let rebuilt = PolyglotTree::from(new_code.as_str(), util::Language::JavaScript).unwrap();
+    assert!(tree.structurally_eq(&rebuilt));
+    assert_eq!(tree.eval_sites(), rebuilt.eval_sites());
+    for zip in PolyglotZipper::from(&tree).find_where(|_| true) {
+        assert_eq!(&new_code[zip.host_byte_range()], zip.code());
+    }
+}
+
+#[test]
+fn threaded_build_matches_sequential_build() {
+    let code = (0..8)
+        .map(|i| format!("Polyglot.eval('python', 'x = {i}')\nPolyglot.evalFile('python', 'TestSamples/pyprint.py')\n"))
+        .collect::<String>();
+    let options = polyglot_tree::build_options::BuildOptions::new().threads(4);
+    let threaded =
+        PolyglotTree::from_with_options(code.as_str(), util::Language::JavaScript, &options)
+            .unwrap();
+    let sequential = PolyglotTree::from(code.as_str(), util::Language::JavaScript).unwrap();
+
+    assert_eq!(threaded.subtrees().count(), 16);
+    assert!(threaded.structurally_eq(&sequential));
+    assert_eq!(threaded.eval_sites(), sequential.eval_sites());
+}
+
+#[test]
+fn batch_builds_shared_evaluated_files_once() {
+    let temp = TempDir::new("batch");
+    let dir = temp.path();
+    std::fs::write(dir.join("lib.py"), "print(1)").unwrap();
+    let inputs: Vec<(PathBuf, util::Language)> = (0..4)
+        .map(|i| {
+            let path = dir.join(format!("host_{i}.js"));
+            std::fs::write(
+                &path,
+                format!("let x = {i}\nPolyglot.evalFile('python', 'lib.py')"),
+            )
+            .unwrap();
+            (path, util::Language::JavaScript)
+        })
+        .collect();
+
+    let cache = std::sync::Arc::new(polyglot_tree::file_cache::FileCache::new());
+    let options = polyglot_tree::build_options::BuildOptions::new()
+        .file_cache(cache.clone())
+        .threads(2);
+    let batch = PolyglotTree::from_paths_with_options(inputs, &options);
+
+    assert!(batch.diagnostics.is_empty());
+    assert_eq!(cache.len(), 1);
+    let subtrees: Vec<&PolyglotTree> = batch
+        .trees
+        .iter()
+        .map(|(_, tree)| tree.as_ref().unwrap().subtrees().next().unwrap().1)
+        .collect();
+    assert!(subtrees
+        .iter()
+        .all(|subtree| std::ptr::eq(*subtree, subtrees[0])));
+}
+
+#[test]
+fn dependency_graph_reports_missing_files_and_cycles() {
+    let temp = TempDir::new("deps");
+    let dir = temp.path();
+    std::fs::write(
+        dir.join("a.py"),
+        "import polyglot\npolyglot.eval(language=\"js\", path=\"b.js\")\npolyglot.eval(language=\"js\", path=\"missing.js\")",
+    )
+    .unwrap();
+    std::fs::write(dir.join("b.js"), "Polyglot.evalFile('python', './a.py')").unwrap();
+    let project = polyglot_tree::project::PolyglotProject::new(dir, ["*"]).unwrap();
+
+    let graph = project.dependency_graph();
+    assert_eq!(graph.files().len(), 3);
+    let unresolved: Vec<_> = graph.unresolved().collect();
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].to, dir.join("missing.js"));
+    assert_eq!(graph.dependents_of(&dir.join("a.py")).count(), 1);
+    assert!(graph
+        .reachable_from(&dir.join("a.py"))
+        .contains(&dir.join("a.py")));
+    assert!(graph.topological_order().is_none());
+    let cycle = vec![dir.join("a.py"), dir.join("b.js"), dir.join("a.py")];
+    assert_eq!(graph.cycles(), vec![cycle.clone()]);
+    // the evaluation closing the cycle is reported as such, rather than as a missing subtree
+    use polyglot_tree::batch::BatchDiagnosticKind;
+    assert!(project.diagnostics().iter().all(|d| match &d.kind {
+        BatchDiagnosticKind::Cycle(files) => d.path == dir.join("a.py") && *files == cycle,
+        BatchDiagnosticKind::MissingSubtree(site) => site.path == Some(dir.join("missing.js")),
+        _ => false,
+    }));
+    assert!(project
+        .diagnostics()
+        .iter()
+        .any(|d| matches!(d.kind, BatchDiagnosticKind::Cycle(_))));
+}
+
+#[test]
+fn project_update_rebuilds_changed_files_and_their_dependents() {
+    let temp = TempDir::new("update");
+    let dir = temp.path();
+    std::fs::write(dir.join("lib.py"), "print(1)").unwrap();
+    std::fs::write(dir.join("host.js"), "Polyglot.evalFile('python', 'lib.py')").unwrap();
+    std::fs::write(dir.join("other.js"), "console.log(1)").unwrap();
+    let mut project = polyglot_tree::project::PolyglotProject::new(dir, ["*"]).unwrap();
+
+    std::fs::write(dir.join("lib.py"), "print(2)").unwrap();
+    std::fs::remove_file(dir.join("other.js")).unwrap();
+    let update = project.update(&[dir.join("lib.py")]).unwrap();
+    let host = project.tree(&dir.join("host.js")).unwrap().clone();
+
+    assert_eq!(
+        update.rebuilt,
+        vec![dir.join("host.js"), dir.join("lib.py")]
+    );
+    assert_eq!(update.removed, vec![dir.join("other.js")]);
+    assert_eq!(host.subtrees().next().unwrap().1.source(), "print(2)");
+}
+
+#[test]
+fn project_state_only_rebuilds_files_whose_evaluated_files_changed() {
+    use polyglot_tree::build_options::BuildOptions;
+    use polyglot_tree::project_state::ProjectState;
+    let temp = TempDir::new("state");
+    let dir = temp.path().join("project");
+    let cache = temp.path().join("cache");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("a.py"),
+        "import polyglot\npolyglot.eval(language=\"js\", path=\"b.js\")",
+    )
+    .unwrap();
+    std::fs::write(dir.join("b.js"), "let x = 1").unwrap();
+    std::fs::write(dir.join("c.js"), "console.log(1)").unwrap();
+    let filter = polyglot_tree::file_filter::FileFilter::new().include(["*.py"]);
+    let project = polyglot_tree::project::PolyglotProject::with_filter(
+        &dir,
+        filter.clone(),
+        &BuildOptions::new(),
+    )
+    .unwrap();
+    project.save_state(&cache).unwrap();
+
+    let (state, update) = ProjectState::load(&cache, &dir, &filter, &BuildOptions::new()).unwrap();
+    assert!(update.rebuilt.is_empty() && update.removed.is_empty());
+    assert_eq!(
+        state.dependency_graph().dependencies(),
+        project.dependency_graph().dependencies()
+    );
+
+    std::fs::write(dir.join("b.js"), "let x = 2").unwrap();
+    let (state, update) = ProjectState::load(&cache, &dir, &filter, &BuildOptions::new()).unwrap();
+    assert_eq!(update.rebuilt, vec![dir.join("a.py")]);
+    let a = dir.join("a.py");
+    assert!(state.tree(&a).unwrap().code.contains("b.js"));
+    assert_ne!(state.fingerprint(&a), project.state().fingerprint(&a));
+    state.save(&cache).unwrap();
+
+    let (_, update) = ProjectState::load(&cache, &dir, &filter, &BuildOptions::new()).unwrap();
+    assert!(update.rebuilt.is_empty());
+}
+
+#[test]
+fn memory_files_evaluating_themselves_are_built_once() {
+    let files = polyglot_tree::file_provider::MemoryFileProvider::new()
+        .with_file(
+            "app/a.py",
+            "import polyglot\npolyglot.eval(language=\"js\", path=\"lib/b.js\")",
+        )
+        .with_file("app/lib/b.js", "Polyglot.evalFile('python', '../a.py')");
+    let options =
+        polyglot_tree::build_options::BuildOptions::new().file_provider(std::sync::Arc::new(files));
+    let tree = PolyglotTree::from_path_with_options(
+        PathBuf::from("app/a.py"),
+        util::Language::Python,
+        &options,
+    )
+    .unwrap();
+
+    let sites = tree.eval_sites();
+    assert_eq!(sites.len(), 2);
+    assert!(sites[0].has_subtree);
+    assert_eq!(sites[1].path, Some(PathBuf::from("app/lib/../a.py")));
+    assert!(!sites[1].has_subtree);
+}
+
+#[test]
+fn bounded_memory_files_evaluating_themselves_are_built_once() {
+    let files = polyglot_tree::file_provider::MemoryFileProvider::new()
+        .with_file(
+            "app/a.py",
+            "import polyglot\npolyglot.eval(language=\"js\", path=\"lib/b.js\")",
+        )
+        .with_file("app/lib/b.js", "Polyglot.evalFile('python', '../a.py')");
+    let options =
+        polyglot_tree::build_options::BuildOptions::new().file_provider(std::sync::Arc::new(files));
+    let tree = polyglot_tree::bounded_tree::BoundedTree::from_path_with_options(
+        PathBuf::from("app/a.py"),
+        util::Language::Python,
+        &options,
+        1,
+    )
+    .unwrap();
+
+    let mut languages = Vec::new();
+    tree.visit(|_, subtree| languages.push(subtree.language()));
+    assert_eq!(
+        languages,
+        vec![util::Language::Python, util::Language::JavaScript]
+    );
+}
+
+#[test]
+fn disk_cache_rebuilds_trees_whose_evaluated_files_changed() {
+    fn subtree(
+        node: &polyglot_tree::owned_tree::OwnedNode,
+    ) -> Option<&polyglot_tree::owned_tree::OwnedTree> {
+        node.subtree
+            .as_deref()
+            .or_else(|| node.children.iter().find_map(subtree))
+    }
+    let options = |lib: &str| {
+        let files = polyglot_tree::file_provider::MemoryFileProvider::new()
+            .with_file(
+                "main.py",
+                "import polyglot\npolyglot.eval(language=\"js\", path=\"lib.js\")",
+            )
+            .with_file("lib.js", lib);
+        polyglot_tree::build_options::BuildOptions::new().file_provider(std::sync::Arc::new(files))
+    };
+    let temp = TempDir::new("disk_cache");
+    let cache = polyglot_tree::disk_cache::DiskCache::new(temp.path());
+    let main = Path::new("main.py");
+
+    let old = cache
+        .get_or_build(main, util::Language::Python, &options("let x = 1"))
+        .unwrap();
+    let same = cache
+        .get_or_build(main, util::Language::Python, &options("let x = 1"))
+        .unwrap();
+    assert_eq!(same, old);
+    assert_eq!(cache.len(), 1);
+
+    let new = cache
+        .get_or_build(main, util::Language::Python, &options("let x = 2"))
+        .unwrap();
+    assert_eq!(subtree(&new.root).unwrap().code, "let x = 2");
+    // the content of the file itself did not change, so its outdated tree was replaced
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn evaluated_files_without_language_are_detected() {
+    let files = polyglot_tree::file_provider::MemoryFileProvider::new()
+        .with_file(
+            "main.py",
+            "import polyglot\npolyglot.eval(path=\"lib.js\")\npolyglot.eval(path=\"tool\")",
+        )
+        .with_file("lib.js", "console.log(1)")
+        .with_file("tool", "#!/usr/bin/env python3\nprint(2)");
+    let options =
+        polyglot_tree::build_options::BuildOptions::new().file_provider(std::sync::Arc::new(files));
+    let tree =
+        PolyglotTree::from_path_auto_with_options(PathBuf::from("main.py"), &options).unwrap();
+
+    let languages: Vec<_> = tree.eval_sites().iter().map(|s| s.guest_language).collect();
+    assert_eq!(
+        languages,
+        vec![
+            Some(util::Language::JavaScript),
+            Some(util::Language::Python)
+        ]
+    );
+    assert_eq!(tree.subtrees().count(), 2);
+}
+
+#[test]
+fn project_filters_skip_excluded_and_ignored_files() {
+    let temp = TempDir::new("filter");
+    let dir = temp.path();
+    for sub in ["src", "vendor", "build", ".git"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join(".gitignore"), "# outputs\nbuild/\n*.min.js\n").unwrap();
+    std::fs::write(dir.join("src/.gitignore"), "!keep.min.js\n").unwrap();
+    for file in [
+        "src/main.js",
+        "src/app.min.js",
+        "src/keep.min.js",
+        "vendor/lib.js",
+        "build/out.js",
+        ".git/hook.py",
+    ] {
+        std::fs::write(dir.join(file), "console.log(1)").unwrap();
+    }
+
+    let filter = polyglot_tree::file_filter::FileFilter::new()
+        .exclude(["vendor/"])
+        .gitignore(true);
+    let project = polyglot_tree::project::PolyglotProject::with_filter(
+        dir,
+        filter,
+        &polyglot_tree::build_options::BuildOptions::new(),
+    )
+    .unwrap();
+    let all = polyglot_tree::project::PolyglotProject::new(dir, ["**/*"]).unwrap();
+
+    let files: Vec<_> = project.files().collect();
+    assert_eq!(
+        files,
+        vec![&dir.join("src/keep.min.js"), &dir.join("src/main.js")]
+    );
+    assert_eq!(all.files().count(), 6);
+}
+
+#[test]
+fn project_summary_counts_edges_and_problems() {
+    let temp = TempDir::new("summary");
+    let dir = temp.path();
+    std::fs::write(
+        dir.join("main.js"),
+        "Polyglot.export('x', 1)\nPolyglot.eval('python', 'print(')\nPolyglot.evalFile('python', 'missing.py')",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("lib.py"),
+        "import polyglot\nx = polyglot.import_value(name=\"x\")\ny = polyglot.import_value(name=\"y\")",
+    )
+    .unwrap();
+    let project = polyglot_tree::project::PolyglotProject::new(dir, ["*"]).unwrap();
+
+    let summary = project.summary();
+    assert_eq!(summary.file_count, 2);
+    assert_eq!(summary.eval_count, 2);
+    assert_eq!(summary.import_count, 2);
+    assert_eq!(summary.export_count, 1);
+    assert_eq!(summary.cross_language_edges, 3);
+    assert_eq!(summary.unresolved_evals, 1);
+    assert_eq!(summary.unresolved_imports, 1);
+    assert_eq!(summary.files_with_errors, vec![dir.join("main.js")]);
+    let languages: Vec<_> = summary
+        .languages
+        .iter()
+        .map(|u| (u.language, u.files, u.evals))
+        .collect();
+    assert_eq!(
+        languages,
+        vec![
+            (util::Language::Python, 1, 2),
+            (util::Language::JavaScript, 1, 0)
+        ]
+    );
+    assert!(summary.to_markdown().contains("| js | 1 | 0 |"));
+}
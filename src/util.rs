@@ -1,10 +1,32 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 #[error("Invalid argument received")]
 pub struct InvalidArgumentError;
 
+/// The error returned when a build is aborted through the CancellationToken set in its BuildOptions.
+#[derive(Error, Debug)]
+#[error("The build was cancelled")]
+pub struct Cancelled;
+
+/// A pending replacement of part of a file's code, as produced by `PolyglotZipper::edit()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The file the edit applies to, or None if the code was not read from a file.
+    pub file: Option<PathBuf>,
+    /// The byte range of the code to replace in that file.
+    pub range: Range<usize>,
+    /// The text to write in place of the range, already escaped as needed.
+    pub replacement: String,
+}
+
 /// An enumeration that represents all languages supported by this crate. Current options are Python, JavaScript and Java.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Language {
     Python,
     JavaScript,
@@ -12,7 +34,7 @@ pub enum Language {
     Java,
 }
 
-/// Returns a String identical to the provided slice but with leading and trailing characters removed.
+/// Returns the provided slice with its leading and trailing characters removed, without copying it.
 /// In practice, this is mostly used to remove quotes from string literals, but the function does not actually check which characters it removes.
 ///
 /// # Examples
@@ -21,17 +43,350 @@ pub enum Language {
 ///
 /// let s = "\'Hello!\'";
 /// let stripped = util::strip_quotes(&s);
-/// assert_eq!(stripped, String::from("Hello!"));
+/// assert_eq!(stripped, "Hello!");
 ///
-/// let stripped_again = util::strip_quotes(stripped.as_str());
-/// assert_eq!(stripped_again, String::from("ello"));
+/// let stripped_again = util::strip_quotes(stripped);
+/// assert_eq!(stripped_again, "ello");
 ///
 /// ```
-pub fn strip_quotes(s: &str) -> String {
+pub fn strip_quotes(s: &str) -> &str {
     let mut tmp = s.chars();
     tmp.next();
     tmp.next_back();
-    String::from(tmp.as_str())
+    tmp.as_str()
+}
+
+/// Returns a String that can be placed between `quote` characters to form a string literal whose value is `s`.
+/// Backslashes, `quote` characters and line breaks are escaped in a way shared by Python, JavaScript and Java;
+/// for JavaScript template literals (when `quote` is a backtick), template substitutions are escaped as well.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util;
+///
+/// let escaped = util::escape_string_literal("print('a\\b')\n", '\'');
+/// assert_eq!(escaped, String::from("print(\\'a\\\\b\\')\\n"));
+/// ```
+pub fn escape_string_literal(s: &str, quote: char) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '$' if quote == '`' && chars.peek() == Some(&'{') => result.push_str("\\$"),
+            c if c == quote => {
+                result.push('\\');
+                result.push(c);
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Returns the given string as a JSON string literal, with its quotes, escaping the characters JSON does not allow as they are.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util;
+///
+/// assert_eq!(util::json_string("say \"hi\"\n"), String::from("\"say \\\"hi\\\"\\n\""));
+/// ```
+pub fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Returns the given string as a CSV field, quoted if it contains a comma, a quote or a line break, with its quotes doubled.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util;
+///
+/// assert_eq!(util::csv_field("module"), "module");
+/// assert_eq!(util::csv_field("f(a, \"b\")"), "\"f(a, \"\"b\"\")\"");
+/// ```
+pub fn csv_field(s: &str) -> Cow<'_, str> {
+    match s.contains([',', '"', '\n', '\r']) {
+        true => Cow::Owned(format!("\"{}\"", s.replace('"', "\"\""))),
+        false => Cow::Borrowed(s),
+    }
+}
+
+/// Returns the runtime value of the content of a string literal written in the given language, with its escape sequences decoded.
+/// The content should not include the literal's quotes or prefixes. It is returned as is, without being copied, if it contains no escape sequence.
+///
+/// Escape sequences follow the rules of the language: unknown escapes keep their backslash in Python and Java, but not in JavaScript,
+/// escaped line breaks are removed in Python and JavaScript, and the escaped surrogate pairs of JavaScript and Java are combined into one character.
+/// Escape sequences that cannot be decoded, such as lone surrogates, are kept as they are.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util;
+/// use util::Language;
+///
+/// let value = util::unescape_string_literal("a\\tb\\x41\\u00e9\\q", &Language::Python);
+/// assert_eq!(value, String::from("a\tbA\u{e9}\\q"));
+///
+/// let value = util::unescape_string_literal("\\u{1F600}\\q", &Language::JavaScript);
+/// assert_eq!(value, String::from("\u{1F600}q"));
+///
+/// let value = util::unescape_string_literal("\\uD83D\\uDE00 \\uD83D", &Language::JavaScript);
+/// assert_eq!(value, String::from("\u{1F600} \\uD83D"));
+/// ```
+pub fn unescape_string_literal<'a>(content: &'a str, language: &Language) -> Cow<'a, str> {
+    if !content.contains('\\') {
+        return Cow::Borrowed(content);
+    }
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some((_, e)) => e,
+            None => {
+                result.push(c);
+                break;
+            }
+        };
+
+        let mut unknown = false;
+        let decoded = match escaped {
+            '\\' | '\'' | '"' => Some(escaped),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'b' => Some('\u{8}'),
+            'f' => Some('\u{c}'),
+            'v' if !matches!(language, Language::Java) => Some('\u{b}'),
+            '\n' if !matches!(language, Language::Java) => {
+                continue; // escaped line break
+            }
+            'x' if !matches!(language, Language::Java) => {
+                take_hex(&mut chars, 2).and_then(char::from_u32)
+            }
+            'u' => {
+                if matches!(language, Language::JavaScript)
+                    && chars.peek().map(|(_, c)| *c) == Some('{')
+                {
+                    chars.next();
+                    let code = take_hex(&mut chars, 6);
+                    if chars.peek().map(|(_, c)| *c) == Some('}') {
+                        chars.next();
+                        code.and_then(char::from_u32)
+                    } else {
+                        None
+                    }
+                } else {
+                    match take_hex(&mut chars, 4) {
+                        // JavaScript and Java strings are UTF-16, so characters out of the BMP are escaped as surrogate pairs
+                        Some(high @ 0xD800..=0xDBFF) if !matches!(language, Language::Python) => {
+                            let rest = &content[next_index(&mut chars, content)..];
+                            match low_surrogate(rest) {
+                                Some(low) => {
+                                    chars.nth(5);
+                                    char::from_u32(
+                                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                                    )
+                                }
+                                None => None,
+                            }
+                        }
+                        code => code.and_then(char::from_u32),
+                    }
+                }
+            }
+            'U' if matches!(language, Language::Python) => {
+                take_hex(&mut chars, 8).and_then(char::from_u32)
+            }
+            '0'..='7' => {
+                let mut code = escaped.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|(_, d)| d.to_digit(8)) {
+                        Some(d) => {
+                            code = code * 8 + d;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                char::from_u32(code)
+            }
+            _ => {
+                unknown = true;
+                None
+            }
+        };
+
+        match decoded {
+            Some(d) => result.push(d),
+            None if unknown && matches!(language, Language::JavaScript) => result.push(escaped),
+            None => result.push_str(&content[start..next_index(&mut chars, content)]),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Internal function to read up to `max` hexadecimal digits into a number.
+fn take_hex(chars: &mut std::iter::Peekable<std::str::CharIndices>, max: usize) -> Option<u32> {
+    let mut code = 0;
+    let mut read = 0;
+    while read < max {
+        match chars.peek().and_then(|(_, d)| d.to_digit(16)) {
+            Some(d) => {
+                code = code * 16 + d;
+                chars.next();
+                read += 1;
+            }
+            None => break,
+        }
+    }
+    if read == 0 {
+        None
+    } else {
+        Some(code)
+    }
+}
+
+/// Internal function returning the byte index of the next character to be read from `content`, or its length if all of it was read.
+fn next_index(chars: &mut std::iter::Peekable<std::str::CharIndices>, content: &str) -> usize {
+    chars.peek().map_or(content.len(), |(i, _)| *i)
+}
+
+/// Internal function returning the code of the low surrogate escaped by the `\uXXXX` escape sequence `rest` starts with, if there is one.
+fn low_surrogate(rest: &str) -> Option<u32> {
+    let digits = rest.strip_prefix("\\u")?.get(..4)?;
+    if !digits.chars().all(|d| d.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|code| (0xDC00..=0xDFFF).contains(code))
+}
+
+/// Returns false if a quick scan of `code` shows it contains no polyglot call that this crate recognizes in the given language, without parsing it.
+///
+/// The scan looks for the names polyglot calls are made through, such as `polyglot` in Python, `Polyglot` in JavaScript
+/// and the `eval`, `getMember` and `putMember` methods in Java. It can return true for code that contains no polyglot call, for instance
+/// when these names only appear in comments, but never returns false for code that contains one.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util::{self, Language};
+///
+/// assert!(util::may_contain_polyglot_calls("Polyglot.eval('python', 'print(1)')", &Language::JavaScript));
+/// assert!(!util::may_contain_polyglot_calls("console.log(42)", &Language::JavaScript));
+/// assert!(util::may_contain_polyglot_calls("context.eval(\"python\", \"print(1)\");", &Language::Java));
+/// ```
+pub fn may_contain_polyglot_calls(code: &str, language: &Language) -> bool {
+    let markers: &[&str] = match language {
+        Language::Python => &["polyglot"],
+        Language::JavaScript => &["Polyglot"],
+        Language::Java => &["eval", "getMember", "putMember"],
+    };
+    markers.iter().any(|marker| code.contains(marker))
+}
+
+/// Returns a copy of `code` with all the given edits applied.
+/// Edit ranges are byte ranges into `code`, and must not overlap; the order in which they are given does not matter.
+///
+/// This is used to materialize the TextEdits obtained from `PolyglotZipper::edit()`, once grouped by file.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util::{self, TextEdit};
+///
+/// let edits = vec![
+///     TextEdit { file: None, range: 6..8, replacement: String::from("43") },
+///     TextEdit { file: None, range: 0..5, replacement: String::from("alert") },
+/// ];
+/// assert_eq!(util::apply_edits("print(42)", &edits), String::from("alert(43)"));
+/// ```
+pub fn apply_edits(code: &str, edits: &[TextEdit]) -> String {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|e| e.range.start);
+
+    let mut result = String::with_capacity(code.len());
+    let mut last = 0;
+    for edit in sorted {
+        result.push_str(&code[last..edit.range.start]);
+        result.push_str(&edit.replacement);
+        last = edit.range.end;
+    }
+    result.push_str(&code[last..]);
+    result
+}
+
+/// An index of the line starts of a text, converting between byte offsets and tree-sitter Points in that text.
+///
+/// Points use the same conventions as tree-sitter: rows and columns start at 0, and columns are counted in bytes.
+/// Building the index is linear in the length of the text, after which every conversion is logarithmic in its number of lines.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util::LineIndex;
+/// use tree_sitter::Point;
+///
+/// let index = LineIndex::new("x = 1\nprint(x)\n");
+/// assert_eq!(index.point(8), Point::new(1, 2));
+/// assert_eq!(index.offset(Point::new(1, 2)), Some(8));
+/// assert_eq!(index.offset(Point::new(1, 42)), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    /// Indexes the lines of the given text.
+    pub fn new(text: &str) -> LineIndex {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex {
+            line_starts,
+            len: text.len(),
+        }
+    }
+
+    /// Returns the Point of the given byte offset. Offsets past the end of the text are clamped to its end.
+    pub fn point(&self, offset: usize) -> tree_sitter::Point {
+        let offset = offset.min(self.len);
+        let row = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        tree_sitter::Point::new(row, offset - self.line_starts[row])
+    }
+
+    /// Returns the byte offset of the given Point, or None if it is not within the text.
+    pub fn offset(&self, point: tree_sitter::Point) -> Option<usize> {
+        let start = *self.line_starts.get(point.row)?;
+        let end = self
+            .line_starts
+            .get(point.row + 1)
+            .map_or(self.len, |next| next - 1);
+        let offset = start + point.column;
+        (offset <= end).then_some(offset)
+    }
 }
 
 /// Returns the treesitter language corresponding to the string slice passed.
@@ -76,10 +431,78 @@ pub fn language_string_to_treesitter(
 /// assert_eq!(language, tree_sitter_python::language());
 /// ```
 pub fn language_enum_to_treesitter(lang: &Language) -> tree_sitter::Language {
+    *grammar(lang)
+}
+
+/// Returns the treesitter language corresponding to the Language enum reference passed, loaded once and shared by every caller.
+///
+/// Embedders creating their own parsers or queries can use this handle rather than loading the grammar themselves.
+///
+/// # Panics
+///
+/// This function panics if the grammar of the language is not compiled in, see `try_grammar`.
+///
+/// # Example
+/// ```
+/// use polyglot_ast::util;
+/// use util::Language;
+///
+/// let grammar: &'static tree_sitter::Language = util::grammar(&Language::Python);
+///
+/// assert_eq!(*grammar, tree_sitter_python::language());
+/// assert!(std::ptr::eq(grammar, util::grammar(&Language::Python)));
+/// ```
+pub fn grammar(lang: &Language) -> &'static tree_sitter::Language {
+    try_grammar(lang).unwrap_or_else(|| {
+        panic!(
+            "the {} grammar is not compiled in; consider enabling the {} feature",
+            language_enum_to_string(lang),
+            grammar_feature(lang)
+        )
+    })
+}
+
+/// Returns the treesitter language corresponding to the Language enum reference passed, as `grammar` does,
+/// or None if its grammar is not compiled in.
+///
+/// Each grammar is behind the feature of the same name, `python`, `javascript` or `java`, all of them enabled by default,
+/// so that builds for constrained targets such as WebAssembly can leave out the grammars they do not need.
+/// Code in a language whose grammar is left out cannot be parsed: building a tree from it fails,
+/// and the eval calls evaluating it are left without a subtree.
+///
+/// # Example
+/// ```
+/// use polyglot_ast::util;
+/// use util::Language;
+///
+/// assert_eq!(util::try_grammar(&Language::Python).is_some(), cfg!(feature = "python"));
+/// ```
+pub fn try_grammar(lang: &Language) -> Option<&'static tree_sitter::Language> {
+    #[cfg(feature = "python")]
+    static PYTHON: std::sync::OnceLock<tree_sitter::Language> = std::sync::OnceLock::new();
+    #[cfg(feature = "javascript")]
+    static JAVASCRIPT: std::sync::OnceLock<tree_sitter::Language> = std::sync::OnceLock::new();
+    #[cfg(feature = "java")]
+    static JAVA: std::sync::OnceLock<tree_sitter::Language> = std::sync::OnceLock::new();
+
     match lang {
-        Language::Python => tree_sitter_python::language(),
-        Language::JavaScript => tree_sitter_javascript::language(),
-        Language::Java => tree_sitter_java::language(),
+        #[cfg(feature = "python")]
+        Language::Python => Some(PYTHON.get_or_init(tree_sitter_python::language)),
+        #[cfg(feature = "javascript")]
+        Language::JavaScript => Some(JAVASCRIPT.get_or_init(tree_sitter_javascript::language)),
+        #[cfg(feature = "java")]
+        Language::Java => Some(JAVA.get_or_init(tree_sitter_java::language)),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Returns the name of the feature compiling in the grammar of the given language.
+fn grammar_feature(lang: &Language) -> &'static str {
+    match lang {
+        Language::Python => "python",
+        Language::JavaScript => "javascript",
+        Language::Java => "java",
     }
 }
 
@@ -114,3 +537,97 @@ pub fn language_string_to_enum(lang: &str) -> Result<Language, InvalidArgumentEr
         _ => Err(InvalidArgumentError),
     }
 }
+
+/// Returns the string identifying the passed Language in polyglot calls, which is also accepted by `language_string_to_enum`.
+///
+/// # Example
+/// ```
+/// use polyglot_ast::util;
+/// use util::Language;
+///
+/// assert_eq!(util::language_enum_to_string(&Language::JavaScript), "js");
+/// assert!(matches!(util::language_string_to_enum("js"), Ok(Language::JavaScript)));
+/// ```
+pub fn language_enum_to_string(lang: &Language) -> &'static str {
+    match lang {
+        Language::Python => "python",
+        Language::JavaScript => "js",
+        Language::Java => "java",
+    }
+}
+
+/// Returns the Language a file is written in according to the extension of its path, or None if it is not the extension of a supported language.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use polyglot_ast::util::{self, Language};
+///
+/// assert_eq!(util::language_from_path(Path::new("src/main.py")), Some(Language::Python));
+/// assert_eq!(util::language_from_path(Path::new("README.md")), None);
+/// ```
+pub fn language_from_path(path: &Path) -> Option<Language> {
+    match path.extension()?.to_str()? {
+        "py" | "pyw" => Some(Language::Python),
+        "js" | "mjs" | "cjs" => Some(Language::JavaScript),
+        "java" => Some(Language::Java),
+        _ => None,
+    }
+}
+
+/// Returns the Language a script is written in according to its shebang line, eg. `#!/usr/bin/env python3`,
+/// or None if it has no shebang line or its interpreter is not one of a supported language.
+///
+/// # Example
+/// ```
+/// use polyglot_ast::util::{self, Language};
+///
+/// assert_eq!(util::language_from_shebang("#!/usr/bin/env python3\nprint(1)"), Some(Language::Python));
+/// assert_eq!(util::language_from_shebang("#!/usr/local/bin/node\nconsole.log(1)"), Some(Language::JavaScript));
+/// assert_eq!(util::language_from_shebang("print(1)"), None);
+/// ```
+pub fn language_from_shebang(code: &str) -> Option<Language> {
+    let line = code.lines().next()?.strip_prefix("#!")?;
+    // the interpreter is either the program itself, or its first argument when run through env
+    line.split(|c: char| c.is_whitespace() || c == '/')
+        .filter(|word| !word.is_empty() && !word.starts_with('-'))
+        .find_map(|word| match word {
+            _ if word.starts_with("python") => Some(Language::Python),
+            "node" | "nodejs" | "deno" | "bun" | "js" => Some(Language::JavaScript),
+            "java" => Some(Language::Java),
+            _ => None,
+        })
+}
+
+/// Returns the Language of a file according to the extension of its path, or else to the shebang line of its code,
+/// or None if neither is recognized.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use polyglot_ast::util::{self, Language};
+///
+/// assert_eq!(util::detect_language(Path::new("lib.mjs"), ""), Some(Language::JavaScript));
+/// assert_eq!(util::detect_language(Path::new("bin/tool"), "#!/usr/bin/python\n"), Some(Language::Python));
+/// assert_eq!(util::detect_language(Path::new("bin/tool"), "#!/bin/sh\n"), None);
+/// ```
+pub fn detect_language(path: &Path, code: &str) -> Option<Language> {
+    language_from_path(path).or_else(|| language_from_shebang(code))
+}
+
+/// Internal function removing the `.` components of a path, as well as the `..` components following a directory.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(result.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+    result
+}
@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::polyglot_tree::build_options::BuildOptions;
+use crate::polyglot_tree::call_sites::{BindingDirection, EvalKind};
+use crate::polyglot_tree::exporter::JsonExporter;
+use crate::polyglot_tree::file_provider::MemoryFileProvider;
+use crate::util::{self, Language};
+use crate::PolyglotTree;
+
+/// A polyglot AST, as seen from JavaScript, where it is named `PolyglotTree`.
+///
+/// Trees are built from code given as a string, or from files given as an object mapping their paths to their code,
+/// which lets the files evaluated by polyglot eval calls be followed without a file system.
+#[wasm_bindgen(js_name = PolyglotTree)]
+pub struct WasmTree {
+    tree: PolyglotTree,
+}
+
+#[wasm_bindgen(js_class = PolyglotTree)]
+impl WasmTree {
+    /// Builds the tree of the given code, written in the given language (python, js or java).
+    #[wasm_bindgen(constructor)]
+    pub fn new(code: &str, language: &str) -> Result<WasmTree, JsError> {
+        let language = parse_language(language)?;
+        PolyglotTree::from(code, language)
+            .map(|tree| WasmTree { tree })
+            .ok_or_else(|| JsError::new("unable to build a tree for the code"))
+    }
+
+    /// Builds the tree of the file at the given path of `files`, an object mapping paths to code,
+    /// its language being detected from its extension. The files it evaluates are read from `files` too.
+    #[wasm_bindgen(js_name = fromFiles)]
+    pub fn from_files(files: &js_sys::Object, path: &str) -> Result<WasmTree, JsError> {
+        let mut provider = MemoryFileProvider::new();
+        for entry in js_sys::Object::entries(files).iter() {
+            let entry = js_sys::Array::from(&entry);
+            match (entry.get(0).as_string(), entry.get(1).as_string()) {
+                (Some(file), Some(code)) => provider.insert(file, code),
+                _ => return Err(JsError::new("files must map paths to code")),
+            }
+        }
+        let path = PathBuf::from(path);
+        let language = util::language_from_path(&path).ok_or_else(|| {
+            JsError::new(&format!(
+                "unable to detect the language of {}",
+                path.display()
+            ))
+        })?;
+        let options = BuildOptions::new().file_provider(Arc::new(provider));
+        PolyglotTree::from_path_with_options(path.clone(), language, &options)
+            .map(|tree| WasmTree { tree })
+            .ok_or_else(|| JsError::new(&format!("unable to build a tree for {}", path.display())))
+    }
+
+    /// Returns the language of the code of the root of the tree.
+    pub fn language(&self) -> String {
+        util::language_enum_to_string(&self.tree.language()).to_string()
+    }
+
+    /// Returns the polyglot eval, import and export calls of the tree and of its subtrees, as an array of objects.
+    ///
+    /// Each call has a `call` (eval, import or export), the `language` it is written in, its zero-based `start` position
+    /// as `[row, column]` within the code of its tree, and its `file` if that code comes from one.
+    /// Eval calls also have the `guest` language, whether the code is `inline`, the `path` of the evaluated file,
+    /// and whether a subtree could be built for it (`hasSubtree`); import and export calls have the `name` of their binding.
+    #[wasm_bindgen(js_name = callSites)]
+    pub fn call_sites(&self) -> Result<JsValue, JsError> {
+        let mut calls = Vec::new();
+        for site in self.tree.eval_sites() {
+            calls.push(format!(
+                "{{\"call\":\"eval\",\"language\":{},\"start\":[{},{}],\"file\":{},\"guest\":{},\"inline\":{},\"path\":{},\"hasSubtree\":{}}}",
+                language_json(&site.host_language),
+                site.start_position.row,
+                site.start_position.column,
+                optional_json(site.origin.file().map(|file| file.to_string_lossy())),
+                site.guest_language.as_ref().map_or("null".to_string(), language_json),
+                site.kind.map_or("null", |kind| match kind {
+                    EvalKind::Inline => "true",
+                    EvalKind::File => "false",
+                }),
+                optional_json(site.path.as_ref().map(|path| path.to_string_lossy())),
+                site.has_subtree
+            ));
+        }
+        for site in self.tree.bindings() {
+            calls.push(format!(
+                "{{\"call\":{},\"language\":{},\"start\":[{},{}],\"file\":{},\"name\":{}}}",
+                match site.direction {
+                    BindingDirection::Import => "\"import\"",
+                    BindingDirection::Export => "\"export\"",
+                },
+                language_json(&site.language),
+                site.start_position.row,
+                site.start_position.column,
+                optional_json(site.origin.file().map(|file| file.to_string_lossy())),
+                optional_json(site.name.as_deref())
+            ));
+        }
+        js_sys::JSON::parse(&format!("[{}]", calls.join(",")))
+            .map_err(|_| JsError::new("unable to read the call sites"))
+    }
+
+    /// Returns the tree as JSON text, as written by `JsonExporter`.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        let mut exporter = JsonExporter::new();
+        self.tree.apply(&mut exporter);
+        exporter.get_result().to_string()
+    }
+}
+
+fn parse_language(language: &str) -> Result<Language, JsError> {
+    let language = util::language_string_to_enum(language).map_err(|_| {
+        JsError::new(&format!(
+            "unsupported language {language}, expected one of python, js or java"
+        ))
+    })?;
+    match util::try_grammar(&language) {
+        Some(_) => Ok(language),
+        None => Err(JsError::new(&format!(
+            "the {} grammar is not compiled in",
+            util::language_enum_to_string(&language)
+        ))),
+    }
+}
+
+fn language_json(language: &Language) -> String {
+    util::json_string(util::language_enum_to_string(language))
+}
+
+fn optional_json(value: Option<impl AsRef<str>>) -> String {
+    value.map_or("null".to_string(), |value| {
+        util::json_string(value.as_ref())
+    })
+}